@@ -1,9 +1,28 @@
 //! DHT entry types.
 
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+/// Domain separation tag for entry signatures.
+const ENTRY_SIGNING_TAG: &[u8] = b"CITADEL-DHT-ENTRY-V1";
+
+/// Domain separation tag for keys in an author's namespace.
+const OWNED_KEY_TAG: &[u8] = b"CITADEL-DHT-OWNED-V1";
+
+/// Domain separation tag for shared keys.
+const SHARED_KEY_TAG: &[u8] = b"CITADEL-DHT-SHARED-V1";
+
+/// Current Unix time in milliseconds.
+pub(crate) fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
+}
+
 /// A 32-byte DHT key (Blake3 hash).
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct DhtKey(pub [u8; 32]);
 
 impl DhtKey {
@@ -33,6 +52,30 @@ impl DhtKey {
         Ok(Self(arr))
     }
 
+    /// The key `name` maps to in `author`'s namespace.
+    ///
+    /// Signed entries live at these keys, so a key has exactly one possible
+    /// owner whichever entry a replica happens to see first.
+    pub fn owned(author: &[u8; 32], name: &DhtKey) -> Self {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(OWNED_KEY_TAG);
+        hasher.update(author);
+        hasher.update(&name.0);
+        Self(*hasher.finalize().as_bytes())
+    }
+
+    /// The key `name` maps to in the shared namespace.
+    ///
+    /// Shared entries can be written by several authors, as far as the
+    /// policy of the nodes storing them allows (see
+    /// [`DhtState::with_shared_policy`](crate::DhtState::with_shared_policy)).
+    pub fn shared(name: &DhtKey) -> Self {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(SHARED_KEY_TAG);
+        hasher.update(&name.0);
+        Self(*hasher.finalize().as_bytes())
+    }
+
     /// Get the first 8 bytes as u64 (for slot mapping).
     pub fn prefix_u64(&self) -> u64 {
         u64::from_be_bytes(self.0[..8].try_into().unwrap())
@@ -47,8 +90,8 @@ impl std::fmt::Display for DhtKey {
 }
 
 /// DHT value - arbitrary bytes.
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct DhtValue(pub Vec<u8>);
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DhtValue(#[serde(with = "serde_bytes")] pub Vec<u8>);
 
 impl DhtValue {
     /// Create from bytes.
@@ -90,39 +133,218 @@ impl From<String> for DhtValue {
     }
 }
 
+/// Why an entry was refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryRejection {
+    /// Signature does not verify against the claimed author.
+    BadSignature,
+    /// The key is owned by a different author (or the entry is unsigned).
+    NotOwner,
+    /// A shared entry the node's policy doesn't allow.
+    Unauthorized,
+    /// Timestamp is further in the future than the allowed clock skew.
+    FutureTimestamp,
+}
+
+impl std::fmt::Display for EntryRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::BadSignature => write!(f, "invalid entry signature"),
+            Self::NotOwner => write!(f, "key is owned by another author"),
+            Self::Unauthorized => write!(f, "shared entry not allowed by policy"),
+            Self::FutureTimestamp => write!(f, "entry timestamp is too far in the future"),
+        }
+    }
+}
+
+impl std::error::Error for EntryRejection {}
+
+/// Author signature over an entry.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntrySignature {
+    /// Author's ed25519 public key.
+    pub author: [u8; 32],
+    /// Name the entry was written under; its key is [`DhtKey::owned`] of the
+    /// author and this name.
+    pub name: DhtKey,
+    /// Signature over [`DhtEntry::signing_bytes`].
+    #[serde(with = "serde_bytes")]
+    pub signature: Vec<u8>,
+}
+
 /// A DHT entry with value and timestamp.
-#[derive(Debug, Clone)]
+///
+/// Entries may be signed by an author, which moves them into the author's
+/// namespace (see [`DhtKey::owned`]): only that author can write the key,
+/// including deleting it, which is done by writing a tombstone so the
+/// deletion wins over stale copies on merge. Entries signed into the shared
+/// namespace instead ([`DhtKey::shared`]) may be written by any author the
+/// storing node's policy accepts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DhtEntry {
     /// The key.
     pub key: DhtKey,
-    /// The value.
+    /// The value (empty for tombstones).
     pub value: DhtValue,
     /// Unix timestamp in milliseconds (for last-write-wins).
     pub timestamp: u64,
+    /// True if this entry records a deletion.
+    pub tombstone: bool,
+    /// Author signature, if the entry is owned.
+    pub signature: Option<EntrySignature>,
 }
 
 impl DhtEntry {
     /// Create a new entry with current timestamp.
     pub fn new(key: DhtKey, value: DhtValue) -> Self {
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as u64;
-        Self { key, value, timestamp }
+        Self::with_timestamp(key, value, now_ms())
     }
 
     /// Create an entry with explicit timestamp.
     pub fn with_timestamp(key: DhtKey, value: DhtValue, timestamp: u64) -> Self {
-        Self { key, value, timestamp }
+        Self { key, value, timestamp, tombstone: false, signature: None }
+    }
+
+    /// Create a tombstone recording the deletion of `key` at `timestamp`.
+    pub fn tombstone(key: DhtKey, timestamp: u64) -> Self {
+        Self {
+            key,
+            value: DhtValue::new(Vec::new()),
+            timestamp,
+            tombstone: true,
+            signature: None,
+        }
+    }
+
+    /// Create an entry for `name` signed by `signing_key`, stored at the
+    /// author's key for that name.
+    pub fn signed(name: DhtKey, value: DhtValue, timestamp: u64, signing_key: &SigningKey) -> Self {
+        let mut entry = Self::with_timestamp(name, value, timestamp);
+        entry.sign(signing_key);
+        entry
+    }
+
+    /// Bytes covered by the author signature.
+    pub fn signing_bytes(&self, author: &[u8; 32]) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(ENTRY_SIGNING_TAG.len() + 32 + 32 + 9 + self.value.0.len());
+        bytes.extend_from_slice(ENTRY_SIGNING_TAG);
+        bytes.extend_from_slice(author);
+        bytes.extend_from_slice(&self.key.0);
+        bytes.extend_from_slice(&self.timestamp.to_be_bytes());
+        bytes.push(self.tombstone as u8);
+        bytes.extend_from_slice(&self.value.0);
+        bytes
+    }
+
+    /// Sign this entry, making `signing_key` its author.
+    ///
+    /// The entry's key is taken as its name and replaced by the author's key
+    /// for that name.
+    pub fn sign(&mut self, signing_key: &SigningKey) {
+        let author = signing_key.verifying_key().to_bytes();
+        let name = self.name();
+        self.key = DhtKey::owned(&author, &name);
+        let signature = signing_key.sign(&self.signing_bytes(&author));
+        self.signature = Some(EntrySignature {
+            author,
+            name,
+            signature: signature.to_bytes().to_vec(),
+        });
+    }
+
+    /// Sign this entry into the shared namespace, with `signing_key` as its
+    /// author.
+    ///
+    /// The entry's key is taken as its name and replaced by the shared key
+    /// for that name.
+    pub fn sign_shared(&mut self, signing_key: &SigningKey) {
+        let author = signing_key.verifying_key().to_bytes();
+        let name = self.name();
+        self.key = DhtKey::shared(&name);
+        let signature = signing_key.sign(&self.signing_bytes(&author));
+        self.signature = Some(EntrySignature {
+            author,
+            name,
+            signature: signature.to_bytes().to_vec(),
+        });
+    }
+
+    /// Check if this entry is signed into the shared namespace.
+    pub fn is_shared(&self) -> bool {
+        self.signature.as_ref().is_some_and(|sig| self.key == DhtKey::shared(&sig.name))
+    }
+
+    /// The name the entry was written under: its key, unless it is signed.
+    pub fn name(&self) -> DhtKey {
+        self.signature.as_ref().map_or(self.key, |s| s.name)
+    }
+
+    /// The author's public key, if the entry is signed.
+    pub fn author(&self) -> Option<&[u8; 32]> {
+        self.signature.as_ref().map(|s| &s.author)
+    }
+
+    /// Check the signature, and that a signed entry is stored at its
+    /// author's key or the shared key. Unsigned entries pass.
+    pub fn verify(&self) -> Result<(), EntryRejection> {
+        let Some(sig) = &self.signature else {
+            return Ok(());
+        };
+        if self.key != DhtKey::owned(&sig.author, &sig.name) && !self.is_shared() {
+            return Err(EntryRejection::NotOwner);
+        }
+        let key = VerifyingKey::from_bytes(&sig.author).map_err(|_| EntryRejection::BadSignature)?;
+        let signature = Signature::from_slice(&sig.signature).map_err(|_| EntryRejection::BadSignature)?;
+        key.verify(&self.signing_bytes(&sig.author), &signature)
+            .map_err(|_| EntryRejection::BadSignature)
+    }
+
+    /// Check if this entry may replace `existing` under the ownership rules.
+    /// A signed entry can only be replaced by its author; since verified
+    /// entries sit at their author's key, that is any signed entry there.
+    /// A shared entry can be replaced by any shared entry, subject to the
+    /// storing node's policy.
+    pub fn may_replace(&self, existing: &Self) -> bool {
+        match existing.author() {
+            Some(_) if existing.is_shared() => self.is_shared(),
+            Some(owner) => self.author() == Some(owner),
+            None => true,
+        }
+    }
+
+    /// Check if this entry records a deletion.
+    pub fn is_tombstone(&self) -> bool {
+        self.tombstone
+    }
+
+    /// Hash of the entry's contents, used to order entries with equal
+    /// timestamps.
+    pub fn hash(&self) -> [u8; 32] {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&self.signing_bytes(self.author().unwrap_or(&[0u8; 32])));
+        if let Some(sig) = &self.signature {
+            hasher.update(&sig.signature);
+        }
+        *hasher.finalize().as_bytes()
     }
 
     /// Check if this entry is newer than another.
+    ///
+    /// Signed entries take precedence over unsigned ones at their key, and
+    /// equal timestamps are ordered by [`hash`](Self::hash), so every replica
+    /// picks the same winner whatever order it sees entries in.
     pub fn is_newer_than(&self, other: &Self) -> bool {
-        self.timestamp > other.timestamp
+        let rank = |e: &Self| (e.signature.is_some(), e.timestamp);
+        match rank(self).cmp(&rank(other)) {
+            std::cmp::Ordering::Equal => self.hash() > other.hash(),
+            ordering => ordering.is_gt(),
+        }
     }
 
     /// Merge with another entry, keeping the newer one.
     /// Returns true if self was updated.
+    ///
+    /// Does not check signatures or ownership - see `DhtState::put_entry`.
     pub fn merge(&mut self, other: Self) -> bool {
         if other.is_newer_than(self) {
             *self = other;
@@ -166,6 +388,101 @@ mod tests {
         assert_eq!(entry.timestamp, 200);
     }
 
+    #[test]
+    fn signed_entry_verifies() {
+        let key = DhtKey::from_bytes([0u8; 32]);
+        let author = SigningKey::from_bytes(&[7u8; 32]);
+        let entry = DhtEntry::signed(key, "value".into(), 100, &author);
+        assert_eq!(entry.verify(), Ok(()));
+        assert_eq!(entry.author(), Some(&author.verifying_key().to_bytes()));
+
+        let mut tampered = entry.clone();
+        tampered.value = "other".into();
+        assert_eq!(tampered.verify(), Err(EntryRejection::BadSignature));
+
+        // Turning a signed write into a tombstone invalidates it too
+        let mut tampered = entry;
+        tampered.tombstone = true;
+        assert_eq!(tampered.verify(), Err(EntryRejection::BadSignature));
+    }
+
+    #[test]
+    fn ownership() {
+        let name = DhtKey::from_bytes([0u8; 32]);
+        let alice = SigningKey::from_bytes(&[1u8; 32]);
+        let bob = SigningKey::from_bytes(&[2u8; 32]);
+
+        // Each author writes the name at their own key
+        let owned = DhtEntry::signed(name, "a".into(), 100, &alice);
+        let bobs = DhtEntry::signed(name, "b".into(), 200, &bob);
+        assert_eq!(owned.key, DhtKey::owned(&alice.verifying_key().to_bytes(), &name));
+        assert_ne!(bobs.key, owned.key);
+        assert_eq!((owned.name(), bobs.name()), (name, name));
+
+        // Moving Bob's entry to Alice's key doesn't verify
+        let mut moved = bobs.clone();
+        moved.key = owned.key;
+        assert_eq!(moved.verify(), Err(EntryRejection::NotOwner));
+
+        assert!(DhtEntry::signed(name, "a2".into(), 200, &alice).may_replace(&owned));
+        assert!(!moved.may_replace(&owned));
+        assert!(!DhtEntry::with_timestamp(owned.key, "x".into(), 200).may_replace(&owned));
+
+        let open = DhtEntry::with_timestamp(owned.key, "x".into(), 100);
+        assert!(owned.may_replace(&open));
+    }
+
+    #[test]
+    fn shared_entries() {
+        let name = DhtKey::from_bytes([0u8; 32]);
+        let alice = SigningKey::from_bytes(&[1u8; 32]);
+        let bob = SigningKey::from_bytes(&[2u8; 32]);
+
+        let mut shared = DhtEntry::with_timestamp(name, "a".into(), 100);
+        shared.sign_shared(&alice);
+        assert_eq!(shared.verify(), Ok(()));
+        assert!(shared.is_shared());
+        assert_eq!((shared.key, shared.name()), (DhtKey::shared(&name), name));
+
+        // Any shared entry may replace it; owned and unsigned entries may not
+        let mut tombstone = DhtEntry::tombstone(name, 200);
+        tombstone.sign_shared(&bob);
+        assert!(tombstone.may_replace(&shared));
+        let owned = DhtEntry::signed(name, "b".into(), 200, &alice);
+        assert!(!owned.may_replace(&shared));
+        assert!(!DhtEntry::with_timestamp(shared.key, "x".into(), 200).may_replace(&shared));
+
+        // An owned signature moved to the shared key doesn't verify
+        let mut moved = owned;
+        moved.key = shared.key;
+        assert_eq!(moved.verify(), Err(EntryRejection::BadSignature));
+
+        // A shared entry can't be moved into an author's namespace
+        let mut squat = shared.clone();
+        squat.key = DhtKey::owned(&alice.verifying_key().to_bytes(), &name);
+        assert!(squat.verify().is_err());
+    }
+
+    #[test]
+    fn merge_order_is_deterministic() {
+        let key = DhtKey::from_bytes([0u8; 32]);
+        let a = DhtEntry::with_timestamp(key, "a".into(), 100);
+        let b = DhtEntry::with_timestamp(key, "b".into(), 100);
+
+        // Equal timestamps: both replicas settle on the same entry
+        let (mut left, mut right) = (a.clone(), b.clone());
+        left.merge(b);
+        right.merge(a);
+        assert_eq!(left.value, right.value);
+
+        // A signed entry wins its key over an unsigned write timestamped later
+        let author = SigningKey::from_bytes(&[1u8; 32]);
+        let signed = DhtEntry::signed(key, "mine".into(), 100, &author);
+        let mut squatter = DhtEntry::with_timestamp(signed.key, "squat".into(), 900);
+        assert!(squatter.merge(signed));
+        assert_eq!(squatter.value.as_str(), Some("mine"));
+    }
+
     #[test]
     fn entry_merge_keeps_newer() {
         let key = DhtKey::from_bytes([0u8; 32]);
@@ -12,16 +12,39 @@
 //! # Conflict Resolution
 //!
 //! Uses timestamps for last-write-wins semantics. When merging state from
-//! multiple peers, the entry with the highest timestamp wins.
+//! multiple peers, the entry with the highest timestamp wins, ties going to
+//! the higher entry hash. Signed entries are stored under their author's
+//! namespace ([`DhtKey::owned`]), so only that author can write the key
+//! whichever entry a replica sees first; entries in the shared namespace
+//! ([`DhtKey::shared`]) are admitted by the node's [`SharedPolicy`] instead.
+//! Deletions are tombstones, garbage-collected once they are older than a
+//! configurable horizon, and entries timestamped too far in the future are
+//! refused.
+//!
+//! # Replication
+//!
+//! Each key is held by the `R` occupied slots nearest its target slot (see
+//! [`MeshView::replicas`]). When slots change hands, [`MeshView::handoff`]
+//! says which nodes a key must be pushed to so it follows its slot.
+//!
+//! # Storage
+//!
+//! [`DhtState`] is generic over a [`DhtStore`]. [`MemoryStore`] is the
+//! default; nodes that need their shard to survive a restart plug in a
+//! persistent backend.
 
 mod entry;
 mod state;
+mod store;
 mod routing;
+mod replica;
 pub mod peer;
 
-pub use entry::{DhtKey, DhtEntry, DhtValue};
-pub use state::DhtState;
-pub use routing::{key_to_slot, route_to_key};
+pub use entry::{DhtKey, DhtEntry, DhtValue, EntryRejection, EntrySignature};
+pub use state::{DhtError, DhtState, SharedPolicy, DEFAULT_MAX_CLOCK_SKEW, DEFAULT_TOMBSTONE_HORIZON};
+pub use store::{DhtStore, MemoryStore};
+pub use routing::{key_to_coord, key_to_slot, route_to_key};
+pub use replica::{MeshView, DEFAULT_REPLICATION_FACTOR};
 pub use peer::{PeerId, PeerInfo, PeerKnowledge, PeerSpore, KnowledgeMode, NeighborType};
 
 /// Hash a string key to a DHT key using Blake3.
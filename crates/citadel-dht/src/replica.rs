@@ -0,0 +1,252 @@
+//! Replica placement and handoff.
+//!
+//! A key is stored on the `R` occupied slots nearest its target slot, found by
+//! walking outward from the target through the 20-neighbor topology
+//! ([`Neighbors::of`]). With all slots occupied that is the target plus the
+//! first `R - 1` of its neighbors; when slots are vacant the walk skips them.
+//!
+//! When the mesh changes (a node leaves, joins, or two nodes swap slots via
+//! Proof of Latency), each key's replica set may move. The first surviving
+//! holder from the old set pushes the key to the nodes that joined the set,
+//! so keys follow their slots and survive as long as fewer than `R` holders
+//! fail at once.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+
+use citadel_topology::{HexCoord, Neighbors};
+
+use crate::{key_to_coord, DhtKey};
+
+/// Default number of nodes holding each key.
+pub const DEFAULT_REPLICATION_FACTOR: usize = 3;
+
+/// Coordinates visited looking for occupied slots before giving up.
+const MAX_SEARCH: usize = 4096;
+
+/// A node's view of which slots are occupied, and by whom.
+///
+/// `T` identifies a node (e.g. its peer ID), so a slot changing hands is
+/// distinguishable from a slot staying put.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MeshView<T> {
+    /// Number of slots keys are spread over (see [`key_to_slot`](crate::key_to_slot)).
+    pub mesh_size: u64,
+    /// Occupant of each live slot.
+    pub occupants: HashMap<HexCoord, T>,
+}
+
+impl<T> Default for MeshView<T> {
+    fn default() -> Self {
+        Self { mesh_size: 0, occupants: HashMap::new() }
+    }
+}
+
+impl<T> MeshView<T> {
+    /// Create a view.
+    pub fn new(mesh_size: u64, occupants: HashMap<HexCoord, T>) -> Self {
+        Self { mesh_size, occupants }
+    }
+
+    /// The nodes that should hold `key`, nearest to its target slot first.
+    /// Returns fewer than `replication_factor` only if the mesh is that small.
+    pub fn replicas(&self, key: &DhtKey, replication_factor: usize) -> Vec<(HexCoord, &T)> {
+        let mut replicas = Vec::new();
+        if self.occupants.is_empty() || replication_factor == 0 {
+            return replicas;
+        }
+
+        let target = key_to_coord(key, self.mesh_size);
+        let mut seen = HashSet::from([target]);
+        let mut queue = VecDeque::from([target]);
+        while let Some(coord) = queue.pop_front() {
+            if let Some(node) = self.occupants.get(&coord) {
+                replicas.push((coord, node));
+                if replicas.len() == replication_factor || replicas.len() == self.occupants.len() {
+                    break;
+                }
+            }
+            if seen.len() >= MAX_SEARCH {
+                continue;
+            }
+            for neighbor in Neighbors::of(coord) {
+                if seen.insert(neighbor) {
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+        replicas
+    }
+}
+
+impl<T: Eq + Hash> MeshView<T> {
+    /// Nodes `me` must send `key` to now that the mesh changed from `old` to
+    /// this view. Empty unless `me` is the first holder in `old`'s replica set
+    /// still present, so every key is handed off by exactly one node.
+    pub fn handoff(&self, old: &MeshView<T>, key: &DhtKey, me: &T, replication_factor: usize) -> Vec<&T> {
+        let old_holders: Vec<&T> = old.replicas(key, replication_factor)
+            .into_iter()
+            .map(|(_, node)| node)
+            .collect();
+        let alive: HashSet<&T> = self.occupants.values().collect();
+        match old_holders.iter().find(|node| alive.contains(*node)) {
+            Some(sender) if *sender == me => {}
+            _ => return Vec::new(),
+        }
+
+        self.replicas(key, replication_factor)
+            .into_iter()
+            .map(|(_, node)| node)
+            .filter(|node| !old_holders.contains(node))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash_key;
+    use citadel_topology::{spiral3d_to_coord, Spiral3DIndex};
+
+    const R: usize = DEFAULT_REPLICATION_FACTOR;
+
+    fn full_view(nodes: u64) -> MeshView<u64> {
+        let occupants = (0..nodes)
+            .map(|slot| (spiral3d_to_coord(Spiral3DIndex(slot)), slot))
+            .collect();
+        MeshView::new(nodes, occupants)
+    }
+
+    /// Tiny deterministic PRNG (xorshift64) so the simulation is reproducible
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+    }
+
+    #[test]
+    fn replicas_start_at_target() {
+        let view = full_view(100);
+        let key = hash_key(b"release:abc");
+        let replicas = view.replicas(&key, R);
+
+        assert_eq!(replicas.len(), R);
+        assert_eq!(replicas[0].0, key_to_coord(&key, 100));
+        let neighbors = Neighbors::of(replicas[0].0);
+        assert!(replicas[1..].iter().all(|(coord, _)| neighbors.contains(coord)));
+    }
+
+    #[test]
+    fn replicas_skip_vacant_slots() {
+        let mut view = full_view(100);
+        let key = hash_key(b"release:abc");
+        let target = key_to_coord(&key, 100);
+        view.occupants.remove(&target);
+
+        let replicas = view.replicas(&key, R);
+        assert_eq!(replicas.len(), R);
+        assert!(replicas.iter().all(|(coord, _)| *coord != target));
+
+        // Small meshes replicate everywhere
+        assert_eq!(full_view(2).replicas(&key, R).len(), 2);
+        assert!(MeshView::<u64>::default().replicas(&key, R).is_empty());
+    }
+
+    #[test]
+    fn handoff_has_one_sender() {
+        let old = full_view(100);
+        let key = hash_key(b"release:abc");
+        let holders: Vec<u64> = old.replicas(&key, R).into_iter().map(|(_, n)| *n).collect();
+
+        let mut new = old.clone();
+        new.occupants.retain(|_, node| *node != holders[0]);
+
+        // The next holder hands off to exactly the one node that joined the set
+        let sent = new.handoff(&old, &key, &holders[1], R);
+        assert_eq!(sent.len(), 1);
+        assert!(!holders.contains(sent[0]));
+        assert!(new.handoff(&old, &key, &holders[2], R).is_empty());
+
+        // Nothing moves when the view is unchanged
+        assert!(old.handoff(&old, &key, &holders[0], R).is_empty());
+    }
+
+    /// Remove random nodes, fewer than R at a time, handing off after each
+    /// round. Every key must still be held by a live node, and after handoff
+    /// by its full replica set.
+    #[test]
+    fn simulation_survives_churn() {
+        const NODES: u64 = 80;
+        const KEYS: usize = 300;
+
+        let keys: Vec<DhtKey> = (0..KEYS).map(|i| hash_key(format!("key:{}", i).as_bytes())).collect();
+        let mut view = full_view(NODES);
+        let mut stores: HashMap<u64, HashSet<usize>> = HashMap::new();
+        for (i, key) in keys.iter().enumerate() {
+            for (_, node) in view.replicas(key, R) {
+                stores.entry(*node).or_default().insert(i);
+            }
+        }
+
+        let mut rng = Rng(0x5eed);
+        for round in 0..25 {
+            let old = view.clone();
+
+            // Fail R - 1 nodes at once, or swap two nodes' slots
+            if round % 5 == 4 {
+                let coords: Vec<HexCoord> = view.occupants.keys().copied().collect();
+                let a = coords[rng.next() as usize % coords.len()];
+                let b = coords[rng.next() as usize % coords.len()];
+                let node_a = view.occupants[&a];
+                let node_b = view.occupants.insert(b, node_a).unwrap();
+                view.occupants.insert(a, node_b);
+            } else {
+                for _ in 0..R - 1 {
+                    let live: Vec<u64> = view.occupants.values().copied().collect();
+                    let victim = live[rng.next() as usize % live.len()];
+                    view.occupants.retain(|_, node| *node != victim);
+                    stores.remove(&victim);
+                }
+            }
+
+            for i in 0..KEYS {
+                assert!(
+                    stores.values().any(|held| held.contains(&i)),
+                    "round {}: key {} lost",
+                    round,
+                    i
+                );
+            }
+
+            // Every node hands off what it holds
+            let mut transfers = Vec::new();
+            for (node, held) in &stores {
+                for &i in held {
+                    for target in view.handoff(&old, &keys[i], node, R) {
+                        transfers.push((*target, i));
+                    }
+                }
+            }
+            for (target, i) in transfers {
+                stores.entry(target).or_default().insert(i);
+            }
+
+            for (i, key) in keys.iter().enumerate() {
+                for (_, node) in view.replicas(key, R) {
+                    assert!(
+                        stores.get(node).is_some_and(|held| held.contains(&i)),
+                        "round {}: replica {} missing key {}",
+                        round,
+                        node,
+                        i
+                    );
+                }
+            }
+        }
+    }
+}
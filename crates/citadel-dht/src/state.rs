@@ -1,110 +1,282 @@
 //! DHT state management.
 
-use std::collections::HashMap;
-use crate::{DhtKey, DhtEntry, DhtValue};
+use std::sync::Arc;
+use std::time::Duration;
+
+use ed25519_dalek::SigningKey;
+
+use crate::entry::now_ms;
+use crate::store::{DhtStore, MemoryStore};
+use crate::{DhtKey, DhtEntry, DhtValue, EntryRejection};
+
+/// Default allowance for peers whose clocks run ahead of ours.
+pub const DEFAULT_MAX_CLOCK_SKEW: Duration = Duration::from_secs(5 * 60);
+
+/// Default age after which tombstones are garbage-collected.
+pub const DEFAULT_TOMBSTONE_HORIZON: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Error from a write to DHT state.
+#[derive(Debug)]
+pub enum DhtError<E> {
+    /// The backing store failed.
+    Store(E),
+    /// The entry was refused.
+    Rejected(EntryRejection),
+}
+
+impl<E: std::fmt::Display> std::fmt::Display for DhtError<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Store(e) => write!(f, "store error: {}", e),
+            Self::Rejected(r) => write!(f, "entry rejected: {}", r),
+        }
+    }
+}
+
+impl<E: std::fmt::Debug + std::fmt::Display> std::error::Error for DhtError<E> {}
+
+/// Decides whether a shared entry may be stored, given the entry stored at
+/// its key (tombstones included), if any.
+#[derive(Clone)]
+pub struct SharedPolicy(Arc<PolicyFn>);
+
+type PolicyFn = dyn Fn(&DhtEntry, Option<&DhtEntry>) -> bool + Send + Sync;
+
+impl SharedPolicy {
+    /// Wrap a policy function.
+    pub fn new(allow: impl Fn(&DhtEntry, Option<&DhtEntry>) -> bool + Send + Sync + 'static) -> Self {
+        Self(Arc::new(allow))
+    }
+
+    fn allows(&self, entry: &DhtEntry, existing: Option<&DhtEntry>) -> bool {
+        (self.0)(entry, existing)
+    }
+}
+
+impl std::fmt::Debug for SharedPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SharedPolicy")
+    }
+}
 
 /// Local DHT state.
 ///
 /// Stores entries that this node is responsible for based on its
-/// position in the hexagonal mesh.
-#[derive(Debug, Default)]
-pub struct DhtState {
+/// position in the hexagonal mesh. Entries live in a [`DhtStore`];
+/// the default keeps them in memory.
+///
+/// Deleted keys are kept as tombstones so the deletion survives merges.
+/// A tombstone is dropped by [`gc_tombstones`](Self::gc_tombstones) once it
+/// is older than the tombstone horizon, so the horizon must be longer than
+/// any peer is expected to stay partitioned.
+#[derive(Debug)]
+pub struct DhtState<S: DhtStore = MemoryStore> {
     /// Entries stored locally.
-    entries: HashMap<DhtKey, DhtEntry>,
+    store: S,
+    /// Entries timestamped more than this far ahead of now are refused.
+    max_clock_skew_ms: u64,
+    /// Tombstones older than this are garbage-collected.
+    tombstone_horizon_ms: u64,
+    /// Admits shared entries; without one they are refused.
+    shared_policy: Option<SharedPolicy>,
 }
 
 impl DhtState {
-    /// Create empty state.
+    /// Create empty in-memory state.
     pub fn new() -> Self {
+        Self::with_store(MemoryStore::new())
+    }
+}
+
+impl<S: DhtStore + Default> Default for DhtState<S> {
+    fn default() -> Self {
+        Self::with_store(S::default())
+    }
+}
+
+impl<S: DhtStore> DhtState<S> {
+    /// Create state backed by the given store.
+    pub fn with_store(store: S) -> Self {
         Self {
-            entries: HashMap::new(),
+            store,
+            max_clock_skew_ms: DEFAULT_MAX_CLOCK_SKEW.as_millis() as u64,
+            tombstone_horizon_ms: DEFAULT_TOMBSTONE_HORIZON.as_millis() as u64,
+            shared_policy: None,
         }
     }
 
-    /// Get an entry by key.
-    pub fn get(&self, key: &DhtKey) -> Option<&DhtEntry> {
-        self.entries.get(key)
+    /// Set how far ahead of local time an entry's timestamp may be.
+    pub fn with_max_clock_skew(mut self, skew: Duration) -> Self {
+        self.max_clock_skew_ms = skew.as_millis() as u64;
+        self
+    }
+
+    /// Set how long tombstones are kept before garbage collection.
+    pub fn with_tombstone_horizon(mut self, horizon: Duration) -> Self {
+        self.tombstone_horizon_ms = horizon.as_millis() as u64;
+        self
+    }
+
+    /// Accept shared entries (see [`DhtKey::shared`]) that `policy` allows.
+    pub fn with_shared_policy(mut self, policy: SharedPolicy) -> Self {
+        self.shared_policy = Some(policy);
+        self
+    }
+
+    /// Get the backing store.
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Get a live entry by key. Deleted keys return `None`.
+    pub fn get(&self, key: &DhtKey) -> Result<Option<DhtEntry>, S::Error> {
+        Ok(self.store.get(key)?.filter(|e| !e.is_tombstone()))
+    }
+
+    /// Get the stored entry for a key, including tombstones.
+    pub fn get_entry(&self, key: &DhtKey) -> Result<Option<DhtEntry>, S::Error> {
+        self.store.get(key)
     }
 
     /// Put an entry (with current timestamp).
-    pub fn put(&mut self, key: DhtKey, value: DhtValue) -> bool {
+    pub fn put(&mut self, key: DhtKey, value: DhtValue) -> Result<bool, DhtError<S::Error>> {
         let entry = DhtEntry::new(key, value);
         self.put_entry(entry)
     }
 
     /// Put an entry with explicit timestamp.
-    pub fn put_with_timestamp(&mut self, key: DhtKey, value: DhtValue, timestamp: u64) -> bool {
+    pub fn put_with_timestamp(
+        &mut self,
+        key: DhtKey,
+        value: DhtValue,
+        timestamp: u64,
+    ) -> Result<bool, DhtError<S::Error>> {
         let entry = DhtEntry::with_timestamp(key, value, timestamp);
         self.put_entry(entry)
     }
 
     /// Put a pre-constructed entry.
     /// Returns true if the entry was newer and stored.
-    pub fn put_entry(&mut self, entry: DhtEntry) -> bool {
-        let key = entry.key;
-        match self.entries.get_mut(&key) {
-            Some(existing) => existing.merge(entry),
-            None => {
-                self.entries.insert(key, entry);
-                true
-            }
+    ///
+    /// The entry is refused if its signature is invalid, if the key is owned
+    /// by a different author, if it is a shared entry the shared policy
+    /// doesn't allow, or if its timestamp is beyond the allowed clock skew.
+    pub fn put_entry(&mut self, entry: DhtEntry) -> Result<bool, DhtError<S::Error>> {
+        if entry.timestamp > now_ms().saturating_add(self.max_clock_skew_ms) {
+            return Err(DhtError::Rejected(EntryRejection::FutureTimestamp));
+        }
+        entry.verify().map_err(DhtError::Rejected)?;
+
+        let existing = self.store.get(&entry.key).map_err(DhtError::Store)?;
+        if entry.is_shared() && !self.shared_policy.as_ref().is_some_and(|p| p.allows(&entry, existing.as_ref())) {
+            return Err(DhtError::Rejected(EntryRejection::Unauthorized));
         }
+        let merged = match existing {
+            Some(mut existing) => {
+                if !entry.may_replace(&existing) {
+                    return Err(DhtError::Rejected(EntryRejection::NotOwner));
+                }
+                if !existing.merge(entry) {
+                    return Ok(false);
+                }
+                existing
+            }
+            None => entry,
+        };
+        self.store.insert(merged).map_err(DhtError::Store)?;
+        Ok(true)
+    }
+
+    /// Delete an unowned entry by writing a tombstone.
+    /// Returns true if the tombstone was stored.
+    pub fn delete(&mut self, key: &DhtKey) -> Result<bool, DhtError<S::Error>> {
+        self.put_entry(DhtEntry::tombstone(*key, now_ms()))
     }
 
-    /// Delete an entry.
-    pub fn delete(&mut self, key: &DhtKey) -> Option<DhtEntry> {
-        self.entries.remove(key)
+    /// Delete `name` from the author's namespace by writing a tombstone
+    /// signed by `signing_key`.
+    pub fn delete_signed(
+        &mut self,
+        name: &DhtKey,
+        signing_key: &SigningKey,
+    ) -> Result<bool, DhtError<S::Error>> {
+        let mut tombstone = DhtEntry::tombstone(*name, now_ms());
+        tombstone.sign(signing_key);
+        self.put_entry(tombstone)
+    }
+
+    /// Drop tombstones older than the tombstone horizon.
+    /// Returns number of tombstones removed.
+    pub fn gc_tombstones(&mut self) -> Result<usize, S::Error> {
+        let cutoff = now_ms().saturating_sub(self.tombstone_horizon_ms);
+        let mut removed = 0;
+        for entry in self.store.entries()? {
+            if entry.is_tombstone() && entry.timestamp < cutoff {
+                self.store.remove(&entry.key)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
     }
 
     /// Merge another state into this one.
     /// Uses last-write-wins semantics.
     /// Returns number of entries updated.
-    pub fn merge(&mut self, other: DhtState) -> usize {
+    pub fn merge(&mut self, other: DhtState) -> Result<usize, S::Error> {
+        self.merge_entries(other.store.into_entries())
+    }
+
+    /// Merge entries received from a peer.
+    /// Entries refused by [`put_entry`](Self::put_entry) are skipped.
+    /// Returns number of entries updated.
+    pub fn merge_entries(
+        &mut self,
+        entries: impl IntoIterator<Item = DhtEntry>,
+    ) -> Result<usize, S::Error> {
         let mut updated = 0;
-        for (_key, entry) in other.entries {
-            if self.put_entry(entry) {
-                updated += 1;
+        for entry in entries {
+            match self.put_entry(entry) {
+                Ok(true) => updated += 1,
+                Ok(false) | Err(DhtError::Rejected(_)) => {}
+                Err(DhtError::Store(e)) => return Err(e),
             }
         }
-        updated
+        Ok(updated)
     }
 
-    /// Get all entries (for synchronization).
-    pub fn entries(&self) -> impl Iterator<Item = &DhtEntry> {
-        self.entries.values()
+    /// Get all entries, including tombstones (for synchronization).
+    pub fn entries(&self) -> Result<Vec<DhtEntry>, S::Error> {
+        self.store.entries()
     }
 
-    /// Get entries newer than a timestamp.
-    pub fn entries_since(&self, timestamp: u64) -> impl Iterator<Item = &DhtEntry> {
-        self.entries.values().filter(move |e| e.timestamp > timestamp)
+    /// Get entries (including tombstones) newer than a timestamp, oldest first.
+    pub fn entries_since(&self, timestamp: u64) -> Result<Vec<DhtEntry>, S::Error> {
+        self.store.entries_since(timestamp)
     }
 
-    /// Number of entries.
-    pub fn len(&self) -> usize {
-        self.entries.len()
+    /// Number of stored entries, including tombstones.
+    pub fn len(&self) -> Result<usize, S::Error> {
+        self.store.len()
     }
 
     /// Check if empty.
-    pub fn is_empty(&self) -> bool {
-        self.entries.is_empty()
+    pub fn is_empty(&self) -> Result<bool, S::Error> {
+        self.store.is_empty()
     }
 
-    /// Get all keys.
-    pub fn keys(&self) -> impl Iterator<Item = &DhtKey> {
-        self.entries.keys()
+    /// Get all stored keys, including deleted ones.
+    pub fn keys(&self) -> Result<Vec<DhtKey>, S::Error> {
+        Ok(self.store.entries()?.into_iter().map(|e| e.key).collect())
     }
 
     /// Clear all entries.
-    pub fn clear(&mut self) {
-        self.entries.clear();
+    pub fn clear(&mut self) -> Result<(), S::Error> {
+        self.store.clear()
     }
 
-    /// Iterate with prefix filter.
-    /// Note: This is O(n) - use sparingly.
-    pub fn iter_prefix<'a>(&'a self, prefix_bytes: &'a [u8]) -> impl Iterator<Item = &'a DhtEntry> {
-        self.entries.values().filter(move |e| {
-            e.key.0.starts_with(prefix_bytes)
-        })
+    /// Get entries whose key starts with `prefix_bytes`.
+    pub fn iter_prefix(&self, prefix_bytes: &[u8]) -> Result<Vec<DhtEntry>, S::Error> {
+        self.store.entries_with_prefix(prefix_bytes)
     }
 }
 
@@ -118,9 +290,9 @@ mod tests {
         let mut state = DhtState::new();
         let key = hash_key(b"test");
 
-        state.put(key, "value".into());
+        state.put(key, "value".into()).unwrap();
 
-        let entry = state.get(&key).unwrap();
+        let entry = state.get(&key).unwrap().unwrap();
         assert_eq!(entry.value.as_str(), Some("value"));
     }
 
@@ -129,10 +301,10 @@ mod tests {
         let mut state = DhtState::new();
         let key = hash_key(b"test");
 
-        state.put_with_timestamp(key, "old".into(), 100);
-        state.put_with_timestamp(key, "new".into(), 200);
+        state.put_with_timestamp(key, "old".into(), 100).unwrap();
+        state.put_with_timestamp(key, "new".into(), 200).unwrap();
 
-        let entry = state.get(&key).unwrap();
+        let entry = state.get(&key).unwrap().unwrap();
         assert_eq!(entry.value.as_str(), Some("new"));
     }
 
@@ -141,11 +313,11 @@ mod tests {
         let mut state = DhtState::new();
         let key = hash_key(b"test");
 
-        state.put_with_timestamp(key, "new".into(), 200);
-        let updated = state.put_with_timestamp(key, "old".into(), 100);
+        state.put_with_timestamp(key, "new".into(), 200).unwrap();
+        let updated = state.put_with_timestamp(key, "old".into(), 100).unwrap();
 
         assert!(!updated);
-        let entry = state.get(&key).unwrap();
+        let entry = state.get(&key).unwrap().unwrap();
         assert_eq!(entry.value.as_str(), Some("new"));
     }
 
@@ -159,33 +331,172 @@ mod tests {
         let key3 = hash_key(b"key3");
 
         // state1 has key1 (newer) and key2
-        state1.put_with_timestamp(key1, "state1-new".into(), 200);
-        state1.put_with_timestamp(key2, "state1".into(), 100);
+        state1.put_with_timestamp(key1, "state1-new".into(), 200).unwrap();
+        state1.put_with_timestamp(key2, "state1".into(), 100).unwrap();
 
         // state2 has key1 (older) and key3
-        state2.put_with_timestamp(key1, "state2-old".into(), 100);
-        state2.put_with_timestamp(key3, "state2".into(), 100);
+        state2.put_with_timestamp(key1, "state2-old".into(), 100).unwrap();
+        state2.put_with_timestamp(key3, "state2".into(), 100).unwrap();
 
         // Merge state2 into state1
-        let updated = state1.merge(state2);
+        let updated = state1.merge(state2).unwrap();
 
         // key3 should be added (1 update)
         // key1 should keep state1's value (0 updates from key1)
         assert_eq!(updated, 1);
-        assert_eq!(state1.len(), 3);
-        assert_eq!(state1.get(&key1).unwrap().value.as_str(), Some("state1-new"));
-        assert_eq!(state1.get(&key3).unwrap().value.as_str(), Some("state2"));
+        assert_eq!(state1.len().unwrap(), 3);
+        assert_eq!(state1.get(&key1).unwrap().unwrap().value.as_str(), Some("state1-new"));
+        assert_eq!(state1.get(&key3).unwrap().unwrap().value.as_str(), Some("state2"));
     }
 
     #[test]
     fn entries_since() {
         let mut state = DhtState::new();
 
-        state.put_with_timestamp(hash_key(b"old"), "old".into(), 100);
-        state.put_with_timestamp(hash_key(b"new"), "new".into(), 200);
-        state.put_with_timestamp(hash_key(b"newer"), "newer".into(), 300);
+        state.put_with_timestamp(hash_key(b"old"), "old".into(), 100).unwrap();
+        state.put_with_timestamp(hash_key(b"new"), "new".into(), 200).unwrap();
+        state.put_with_timestamp(hash_key(b"newer"), "newer".into(), 300).unwrap();
 
-        let recent: Vec<_> = state.entries_since(150).collect();
+        let recent = state.entries_since(150).unwrap();
         assert_eq!(recent.len(), 2);
     }
+
+    #[test]
+    fn delete_survives_merge() {
+        let mut state = DhtState::new();
+        let mut stale = DhtState::new();
+        let key = hash_key(b"test");
+
+        state.put_with_timestamp(key, "value".into(), 100).unwrap();
+        stale.put_with_timestamp(key, "value".into(), 100).unwrap();
+        assert!(state.delete(&key).unwrap());
+        assert!(state.get(&key).unwrap().is_none());
+
+        // A peer that missed the delete cannot resurrect the key
+        assert_eq!(state.merge(stale).unwrap(), 0);
+        assert!(state.get(&key).unwrap().is_none());
+        assert!(state.get_entry(&key).unwrap().unwrap().is_tombstone());
+    }
+
+    #[test]
+    fn owned_keys_reject_other_authors() {
+        let mut state = DhtState::new();
+        let name = hash_key(b"release:abc");
+        let owner = SigningKey::from_bytes(&[1u8; 32]);
+        let other = SigningKey::from_bytes(&[2u8; 32]);
+        let key = DhtKey::owned(&owner.verifying_key().to_bytes(), &name);
+
+        assert!(state.put_entry(DhtEntry::signed(name, "v1".into(), 100, &owner)).unwrap());
+
+        let mut hijack = DhtEntry::signed(name, "evil".into(), 200, &other);
+        hijack.key = key;
+        assert!(matches!(state.put_entry(hijack), Err(DhtError::Rejected(EntryRejection::NotOwner))));
+        let unsigned = DhtEntry::with_timestamp(key, "evil".into(), 200);
+        assert!(matches!(state.put_entry(unsigned), Err(DhtError::Rejected(EntryRejection::NotOwner))));
+        assert!(matches!(state.delete(&key), Err(DhtError::Rejected(EntryRejection::NotOwner))));
+
+        let mut forged = DhtEntry::signed(name, "v2".into(), 200, &owner);
+        forged.value = "evil".into();
+        assert!(matches!(state.put_entry(forged), Err(DhtError::Rejected(EntryRejection::BadSignature))));
+
+        assert!(state.put_entry(DhtEntry::signed(name, "v2".into(), 200, &owner)).unwrap());
+        assert_eq!(state.get(&key).unwrap().unwrap().value.as_str(), Some("v2"));
+
+        // Another author's entry for the same name lands at their own key
+        assert!(state.put_entry(DhtEntry::signed(name, "theirs".into(), 300, &other)).unwrap());
+        assert_eq!(state.get(&key).unwrap().unwrap().value.as_str(), Some("v2"));
+
+        assert!(state.delete_signed(&name, &owner).unwrap());
+        assert!(state.get(&key).unwrap().is_none());
+    }
+
+    #[test]
+    fn ownership_independent_of_arrival_order() {
+        let name = hash_key(b"release:abc");
+        let alice = DhtEntry::signed(name, "a".into(), 100, &SigningKey::from_bytes(&[1u8; 32]));
+        let bob = DhtEntry::signed(name, "b".into(), 100, &SigningKey::from_bytes(&[2u8; 32]));
+
+        let mut first = DhtState::new();
+        let mut second = DhtState::new();
+        assert_eq!(first.merge_entries(vec![alice.clone(), bob.clone()]).unwrap(), 2);
+        assert_eq!(second.merge_entries(vec![bob, alice]).unwrap(), 2);
+        assert_eq!(first.entries().unwrap().len(), 2);
+        for entry in first.entries().unwrap() {
+            assert_eq!(second.get(&entry.key).unwrap().unwrap().value, entry.value);
+        }
+    }
+
+    #[test]
+    fn merge_skips_rejected_entries() {
+        let mut state = DhtState::new();
+        let owner = SigningKey::from_bytes(&[1u8; 32]);
+        let mine = DhtEntry::signed(hash_key(b"owned"), "mine".into(), 100, &owner);
+        let owned = mine.key;
+        state.put_entry(mine).unwrap();
+
+        let updated = state.merge_entries(vec![
+            DhtEntry::with_timestamp(owned, "theirs".into(), 200),
+            DhtEntry::with_timestamp(hash_key(b"open"), "ok".into(), 200),
+        ]).unwrap();
+
+        assert_eq!(updated, 1);
+        assert_eq!(state.get(&owned).unwrap().unwrap().value.as_str(), Some("mine"));
+    }
+
+    #[test]
+    fn shared_entries_follow_policy() {
+        let name = hash_key(b"release:abc");
+        let alice = SigningKey::from_bytes(&[1u8; 32]);
+        let mallory = SigningKey::from_bytes(&[2u8; 32]);
+        let shared = |value: &str, timestamp, key: &SigningKey| {
+            let mut entry = DhtEntry::with_timestamp(name, value.into(), timestamp);
+            entry.sign_shared(key);
+            entry
+        };
+
+        // Refused without a policy
+        let mut state = DhtState::new();
+        assert!(matches!(
+            state.put_entry(shared("a", 100, &alice)),
+            Err(DhtError::Rejected(EntryRejection::Unauthorized))
+        ));
+
+        let allowed = alice.verifying_key().to_bytes();
+        let mut state = DhtState::new().with_shared_policy(SharedPolicy::new(move |entry, _| entry.author() == Some(&allowed)));
+        assert!(state.put_entry(shared("a", 100, &alice)).unwrap());
+        assert!(state.put_entry(shared("m", 200, &mallory)).is_err());
+
+        let mut tombstone = DhtEntry::tombstone(name, 300);
+        tombstone.sign_shared(&alice);
+        assert!(state.put_entry(tombstone).unwrap());
+        assert!(state.get(&DhtKey::shared(&name)).unwrap().is_none());
+    }
+
+    #[test]
+    fn rejects_future_timestamps() {
+        let mut state = DhtState::new().with_max_clock_skew(Duration::from_secs(60));
+        let key = hash_key(b"test");
+
+        let far = now_ms() + 3_600_000;
+        assert!(matches!(
+            state.put_with_timestamp(key, "future".into(), far),
+            Err(DhtError::Rejected(EntryRejection::FutureTimestamp))
+        ));
+        assert!(state.put_with_timestamp(key, "soon".into(), now_ms() + 1_000).unwrap());
+    }
+
+    #[test]
+    fn tombstone_gc() {
+        let mut state = DhtState::new().with_tombstone_horizon(Duration::from_secs(60));
+        let old = hash_key(b"old");
+        let recent = hash_key(b"recent");
+
+        state.put_entry(DhtEntry::tombstone(old, 100)).unwrap();
+        state.put_with_timestamp(recent, "value".into(), 100).unwrap();
+        state.delete(&recent).unwrap();
+
+        assert_eq!(state.gc_tombstones().unwrap(), 1);
+        assert!(state.get_entry(&old).unwrap().is_none());
+        assert!(state.get_entry(&recent).unwrap().unwrap().is_tombstone());
+    }
 }
@@ -0,0 +1,185 @@
+//! Storage backends for DHT state.
+//!
+//! [`DhtState`](crate::DhtState) owns the merge rules; a [`DhtStore`] only
+//! has to hold entries and answer the queries sync needs. Backends keep a
+//! timestamp index so `entries_since` does not scan the whole shard.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::Infallible;
+use std::ops::Bound;
+
+use crate::{DhtEntry, DhtKey};
+
+/// A place to keep the entries of a DHT shard.
+///
+/// Writes are unconditional - last-write-wins is decided by `DhtState`
+/// before `insert` is called.
+pub trait DhtStore {
+    /// Error returned by the backend.
+    type Error: std::fmt::Debug;
+
+    /// Get an entry by key.
+    fn get(&self, key: &DhtKey) -> Result<Option<DhtEntry>, Self::Error>;
+
+    /// Insert or replace an entry.
+    fn insert(&mut self, entry: DhtEntry) -> Result<(), Self::Error>;
+
+    /// Remove an entry, returning it if present.
+    fn remove(&mut self, key: &DhtKey) -> Result<Option<DhtEntry>, Self::Error>;
+
+    /// Number of entries.
+    fn len(&self) -> Result<usize, Self::Error>;
+
+    /// Check if empty.
+    fn is_empty(&self) -> Result<bool, Self::Error> {
+        Ok(self.len()? == 0)
+    }
+
+    /// All entries, ordered by key.
+    fn entries(&self) -> Result<Vec<DhtEntry>, Self::Error>;
+
+    /// Entries with a timestamp strictly greater than `timestamp`,
+    /// ordered by timestamp.
+    fn entries_since(&self, timestamp: u64) -> Result<Vec<DhtEntry>, Self::Error>;
+
+    /// Entries whose key starts with `prefix`, ordered by key.
+    fn entries_with_prefix(&self, prefix: &[u8]) -> Result<Vec<DhtEntry>, Self::Error>;
+
+    /// Remove all entries.
+    fn clear(&mut self) -> Result<(), Self::Error>;
+}
+
+/// In-memory store (the default).
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    /// Entries by key.
+    entries: BTreeMap<DhtKey, DhtEntry>,
+    /// Secondary index: (timestamp, key).
+    by_time: BTreeSet<(u64, DhtKey)>,
+}
+
+impl MemoryStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Consume the store, yielding its entries.
+    pub fn into_entries(self) -> impl Iterator<Item = DhtEntry> {
+        self.entries.into_values()
+    }
+}
+
+impl DhtStore for MemoryStore {
+    type Error = Infallible;
+
+    fn get(&self, key: &DhtKey) -> Result<Option<DhtEntry>, Self::Error> {
+        Ok(self.entries.get(key).cloned())
+    }
+
+    fn insert(&mut self, entry: DhtEntry) -> Result<(), Self::Error> {
+        let index = (entry.timestamp, entry.key);
+        if let Some(old) = self.entries.insert(entry.key, entry) {
+            self.by_time.remove(&(old.timestamp, old.key));
+        }
+        self.by_time.insert(index);
+        Ok(())
+    }
+
+    fn remove(&mut self, key: &DhtKey) -> Result<Option<DhtEntry>, Self::Error> {
+        let removed = self.entries.remove(key);
+        if let Some(entry) = &removed {
+            self.by_time.remove(&(entry.timestamp, entry.key));
+        }
+        Ok(removed)
+    }
+
+    fn len(&self) -> Result<usize, Self::Error> {
+        Ok(self.entries.len())
+    }
+
+    fn entries(&self) -> Result<Vec<DhtEntry>, Self::Error> {
+        Ok(self.entries.values().cloned().collect())
+    }
+
+    fn entries_since(&self, timestamp: u64) -> Result<Vec<DhtEntry>, Self::Error> {
+        let start = Bound::Excluded((timestamp, DhtKey([0xff; 32])));
+        Ok(self
+            .by_time
+            .range((start, Bound::Unbounded))
+            .map(|(_, key)| self.entries[key].clone())
+            .collect())
+    }
+
+    fn entries_with_prefix(&self, prefix: &[u8]) -> Result<Vec<DhtEntry>, Self::Error> {
+        let mut start = [0u8; 32];
+        let len = prefix.len().min(32);
+        start[..len].copy_from_slice(&prefix[..len]);
+        Ok(self
+            .entries
+            .range(DhtKey(start)..)
+            .take_while(|(key, _)| key.0.starts_with(prefix))
+            .map(|(_, entry)| entry.clone())
+            .collect())
+    }
+
+    fn clear(&mut self) -> Result<(), Self::Error> {
+        self.entries.clear();
+        self.by_time.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash_key;
+
+    #[test]
+    fn time_index_follows_overwrites() {
+        let mut store = MemoryStore::new();
+        let key = hash_key(b"test");
+
+        store.insert(DhtEntry::with_timestamp(key, "old".into(), 100)).unwrap();
+        store.insert(DhtEntry::with_timestamp(key, "new".into(), 300)).unwrap();
+
+        assert_eq!(store.len().unwrap(), 1);
+        assert!(store.entries_since(200).unwrap().iter().all(|e| e.timestamp == 300));
+        assert_eq!(store.entries_since(50).unwrap().len(), 1);
+
+        store.remove(&key).unwrap();
+        assert!(store.entries_since(0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn entries_since_is_ordered_and_exclusive() {
+        let mut store = MemoryStore::new();
+        store.insert(DhtEntry::with_timestamp(DhtKey([3; 32]), "c".into(), 300)).unwrap();
+        store.insert(DhtEntry::with_timestamp(DhtKey([1; 32]), "a".into(), 100)).unwrap();
+        store.insert(DhtEntry::with_timestamp(DhtKey([2; 32]), "b".into(), 200)).unwrap();
+
+        let since: Vec<u64> = store.entries_since(100).unwrap().iter().map(|e| e.timestamp).collect();
+        assert_eq!(since, vec![200, 300]);
+        assert!(store.entries_since(u64::MAX).unwrap().is_empty());
+    }
+
+    #[test]
+    fn prefix_range() {
+        let mut store = MemoryStore::new();
+        let mut a = [0u8; 32];
+        a[0] = 0xab;
+        let mut b = a;
+        b[1] = 0x01;
+        let mut c = [0u8; 32];
+        c[0] = 0xac;
+
+        for key in [a, b, c] {
+            store.insert(DhtEntry::with_timestamp(DhtKey(key), "v".into(), 1)).unwrap();
+        }
+
+        assert_eq!(store.entries_with_prefix(&[0xab]).unwrap().len(), 2);
+        assert_eq!(store.entries_with_prefix(&[0xab, 0x01]).unwrap().len(), 1);
+        assert_eq!(store.entries_with_prefix(&[]).unwrap().len(), 3);
+        assert!(store.entries_with_prefix(&[0xad]).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,661 @@
+//! Signed admin and permission operations.
+//!
+//! Admin and upload rights are not set directly: every change is an
+//! [`AdminOp`] signed by an admin key, kept in an append-only log and
+//! replicated through SPORE like any other record. Each node replays the log
+//! to derive who is an admin and who may upload:
+//!
+//! ```text
+//! admins = root admins (LensConfig::admin_public_key)
+//! for op in log, parents first, then ordered by (timestamp, hash):
+//!     skip unless op.author is an admin at this point
+//!     skip if op.author was removed by an op that doesn't descend from it
+//!     AddAdmin / RemoveAdmin / GrantUpload / RevokeUpload op.subject
+//! ```
+//!
+//! Operations are causal: each names the log heads its author had seen
+//! ([`AdminOp::deps`]), and takes effect only once those are present.
+//! Timestamps just break ties. When an admin is removed, their operations
+//! count only if the removal descends from them - it was signed by someone
+//! who had already seen them. Anything else they signed, whatever its
+//! timestamp, is ignored, so a revoked admin can't undo the revocation by
+//! backdating.
+//!
+//! Nodes holding the same log derive the same rights, whatever order the
+//! operations arrived in, so a revocation on one node sticks everywhere.
+//! An operation that lands at the end of the log and descends from every
+//! removal of its author is applied on top of the stored rights; anything
+//! else replays the log (see [`apply`] and [`apply_all`]).
+//! Root admins come from configuration and cannot be removed. Operations
+//! signed by keys that were never admins are stored but have no effect.
+
+use crate::error::{Error, Result};
+use crate::storage::Storage;
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, BinaryHeap, HashMap};
+
+/// Domain separation tag for admin operation signatures.
+pub const ADMIN_OP_SIGNING_TAG: &[u8] = b"CITADEL-LENS-ADMIN-OP-V1";
+
+/// How far in the future an operation's timestamp may be.
+pub const MAX_OP_CLOCK_SKEW_MS: u64 = 5 * 60 * 1000;
+
+/// A change to admin or upload rights.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AdminAction {
+    AddAdmin,
+    RemoveAdmin,
+    GrantUpload,
+    RevokeUpload,
+}
+
+impl AdminAction {
+    fn tag(self) -> u8 {
+        match self {
+            AdminAction::AddAdmin => 0,
+            AdminAction::RemoveAdmin => 1,
+            AdminAction::GrantUpload => 2,
+            AdminAction::RevokeUpload => 3,
+        }
+    }
+}
+
+/// A signed admin operation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AdminOp {
+    pub action: AdminAction,
+    /// Hex public key the operation applies to
+    pub subject: String,
+    /// Milliseconds since the Unix epoch
+    pub timestamp_ms: u64,
+    /// Hex public key of the admin who signed it
+    pub author: String,
+    /// Hex ed25519 signature over [`AdminOp::signing_bytes`]
+    pub signature: String,
+    /// Hex hashes of the log heads the author had seen, sorted
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deps: Vec<String>,
+}
+
+/// Decode lowercase hex of exactly `N` bytes. Other spellings of the same
+/// bytes are rejected, so each operation has one encoding and one hash.
+fn canonical_hex<const N: usize>(value: &str) -> Option<[u8; N]> {
+    if value.len() != 2 * N || !value.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')) {
+        return None;
+    }
+    hex::decode(value).ok()?.try_into().ok()
+}
+
+fn key_bytes(hex_key: &str) -> Result<[u8; 32]> {
+    canonical_hex(hex_key).ok_or_else(|| Error::InvalidInput(format!("Invalid public key: {}", hex_key)))
+}
+
+impl AdminOp {
+    /// Create an operation signed by `signing_key` with no dependencies.
+    ///
+    /// Such an operation is concurrent with the whole log; use
+    /// [`AdminOp::signed_after`] with [`heads`] for operations made on a node.
+    pub fn signed(action: AdminAction, subject: &str, timestamp_ms: u64, signing_key: &SigningKey) -> Result<Self> {
+        Self::signed_after(action, subject, timestamp_ms, Vec::new(), signing_key)
+    }
+
+    /// Create an operation signed by `signing_key` that follows `deps`.
+    pub fn signed_after(
+        action: AdminAction,
+        subject: &str,
+        timestamp_ms: u64,
+        mut deps: Vec<String>,
+        signing_key: &SigningKey,
+    ) -> Result<Self> {
+        deps.sort();
+        deps.dedup();
+        let mut op = Self {
+            action,
+            subject: subject.to_lowercase(),
+            timestamp_ms,
+            author: hex::encode(signing_key.verifying_key().as_bytes()),
+            signature: String::new(),
+            deps,
+        };
+        op.signature = hex::encode(signing_key.sign(&op.signing_bytes()?).to_bytes());
+        Ok(op)
+    }
+
+    /// Bytes covered by the signature:
+    /// tag ‖ author ‖ action ‖ subject ‖ timestamp (BE) ‖ deps.
+    pub fn signing_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(ADMIN_OP_SIGNING_TAG.len() + 73 + 32 * self.deps.len());
+        bytes.extend_from_slice(ADMIN_OP_SIGNING_TAG);
+        bytes.extend_from_slice(&key_bytes(&self.author)?);
+        bytes.push(self.action.tag());
+        bytes.extend_from_slice(&key_bytes(&self.subject)?);
+        bytes.extend_from_slice(&self.timestamp_ms.to_be_bytes());
+        for dep in &self.deps {
+            let dep: [u8; 32] = canonical_hex(dep)
+                .ok_or_else(|| Error::InvalidInput(format!("Invalid admin operation dependency: {}", dep)))?;
+            bytes.extend_from_slice(&dep);
+        }
+        Ok(bytes)
+    }
+
+    /// Check the encoding and the signature against the author key.
+    ///
+    /// Keys, signature and deps must be lowercase hex and deps sorted without
+    /// duplicates, so that variants of one operation aren't stored twice.
+    pub fn verify(&self) -> Result<()> {
+        let author = VerifyingKey::from_bytes(&key_bytes(&self.author)?)
+            .map_err(|_| Error::InvalidInput(format!("Invalid author key: {}", self.author)))?;
+        let signature: [u8; 64] = canonical_hex(&self.signature)
+            .ok_or_else(|| Error::InvalidInput("Malformed admin operation signature".into()))?;
+        if self.deps.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(Error::InvalidInput("Admin operation dependencies must be sorted".into()));
+        }
+        author
+            .verify_strict(&self.signing_bytes()?, &Signature::from_bytes(&signature))
+            .map_err(|_| Error::InvalidInput("Bad admin operation signature".into()))
+    }
+
+    /// Content hash, which identifies the operation in other operations'
+    /// deps and orders operations with the same timestamp.
+    pub fn hash(&self) -> [u8; 32] {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(self.author.as_bytes());
+        hasher.update(&[self.action.tag()]);
+        hasher.update(self.subject.as_bytes());
+        hasher.update(&self.timestamp_ms.to_be_bytes());
+        hasher.update(self.signature.as_bytes());
+        for dep in &self.deps {
+            hasher.update(dep.as_bytes());
+        }
+        *hasher.finalize().as_bytes()
+    }
+}
+
+/// Rights derived from the operation log.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Acl {
+    pub admins: BTreeSet<String>,
+    pub uploaders: BTreeSet<String>,
+}
+
+/// An operation placed in causal order, with the positions of its deps.
+struct Placed<'a> {
+    op: &'a AdminOp,
+    parents: Vec<usize>,
+}
+
+/// Mark `start` and every position reachable from it along `edges`.
+fn reach<'e>(edges: impl Fn(usize) -> &'e [usize], start: usize, marked: &mut [bool]) {
+    let mut stack = vec![start];
+    while let Some(i) = stack.pop() {
+        if !std::mem::replace(&mut marked[i], true) {
+            stack.extend_from_slice(edges(i));
+        }
+    }
+}
+
+/// Order operations parents first, breaking ties by (timestamp, hash).
+///
+/// Duplicates are dropped, and so are operations whose deps (or their deps)
+/// aren't in `ops`: they wait until the missing operations arrive.
+fn causal_order<'a>(ops: impl IntoIterator<Item = &'a AdminOp>) -> Vec<Placed<'a>> {
+    let mut index = HashMap::new();
+    let mut nodes: Vec<([u8; 32], &AdminOp)> = Vec::new();
+    for op in ops {
+        let hash = op.hash();
+        index.entry(hex::encode(hash)).or_insert_with(|| {
+            nodes.push((hash, op));
+            nodes.len() - 1
+        });
+    }
+
+    let mut children = vec![Vec::new(); nodes.len()];
+    let mut waiting = vec![0usize; nodes.len()];
+    let mut ready = BinaryHeap::new();
+    for (i, (hash, op)) in nodes.iter().enumerate() {
+        for dep in &op.deps {
+            waiting[i] += 1;
+            if let Some(&parent) = index.get(dep) {
+                children[parent].push(i);
+            }
+        }
+        if waiting[i] == 0 {
+            ready.push(Reverse((op.timestamp_ms, *hash, i)));
+        }
+    }
+
+    let mut position = vec![usize::MAX; nodes.len()];
+    let mut order: Vec<Placed<'a>> = Vec::with_capacity(nodes.len());
+    while let Some(Reverse((_, _, i))) = ready.pop() {
+        let op = nodes[i].1;
+        let parents = op.deps.iter().map(|dep| position[index[dep]]).collect();
+        position[i] = order.len();
+        order.push(Placed { op, parents });
+
+        for &child in &children[i] {
+            waiting[child] -= 1;
+            if waiting[child] == 0 {
+                let (hash, op) = nodes[child];
+                ready.push(Reverse((op.timestamp_ms, hash, child)));
+            }
+        }
+    }
+    order
+}
+
+impl Acl {
+    /// Replay operations on top of the roots, in causal order.
+    ///
+    /// Operations by a removed admin that the removal doesn't descend from
+    /// are ignored. Ignoring them can void other removals' authors, so this
+    /// repeats until no removal that took effect is left to check.
+    pub fn replay<'a>(roots: &[String], ops: impl IntoIterator<Item = &'a AdminOp>) -> Self {
+        let order = causal_order(ops);
+        let mut children = vec![Vec::new(); order.len()];
+        for (i, placed) in order.iter().enumerate() {
+            for &parent in &placed.parents {
+                children[parent].push(i);
+            }
+        }
+
+        let mut ignored = vec![false; order.len()];
+        let mut checked = BTreeSet::new();
+        loop {
+            let (acl, removals) = Self::apply_in_order(roots, &order, &ignored);
+            // Ignoring only grows, so a removal needs checking once
+            let removals: Vec<usize> = removals.into_iter().filter(|&r| checked.insert(r)).collect();
+            if removals.is_empty() {
+                return acl;
+            }
+            for removal in removals {
+                let subject = &order[removal].op.subject;
+                if !order.iter().any(|placed| placed.op.author == *subject) {
+                    continue;
+                }
+                // The removal, its ancestors and its descendants
+                let mut related = vec![false; order.len()];
+                reach(|i| &order[i].parents, removal, &mut related);
+                related[removal] = false;
+                reach(|i| &children[i], removal, &mut related);
+                for (i, placed) in order.iter().enumerate() {
+                    if !related[i] && placed.op.author == *subject {
+                        ignored[i] = true;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Apply the operations not ignored, returning the rights and the
+    /// positions of the removals that took effect.
+    fn apply_in_order(roots: &[String], order: &[Placed], ignored: &[bool]) -> (Self, Vec<usize>) {
+        let mut acl = Acl {
+            admins: roots.iter().cloned().collect(),
+            uploaders: BTreeSet::new(),
+        };
+        let mut removals = Vec::new();
+        for (i, placed) in order.iter().enumerate() {
+            let op = placed.op;
+            if ignored[i] || !acl.admins.contains(&op.author) {
+                continue;
+            }
+            let subject = op.subject.clone();
+            match op.action {
+                AdminAction::AddAdmin => {
+                    acl.admins.insert(subject);
+                }
+                AdminAction::RemoveAdmin if !roots.contains(&subject) => {
+                    acl.admins.remove(&subject);
+                    removals.push(i);
+                }
+                AdminAction::RemoveAdmin => {}
+                AdminAction::GrantUpload => {
+                    acl.uploaders.insert(subject);
+                }
+                AdminAction::RevokeUpload => {
+                    acl.uploaders.remove(&subject);
+                }
+            }
+        }
+        (acl, removals)
+    }
+}
+
+/// Hashes of the stored operations that nothing depends on yet, leaving out
+/// those still waiting for deps: the deps for a new operation on this node.
+pub fn heads(storage: &Storage) -> Result<Vec<String>> {
+    let ops = storage.list_admin_ops()?;
+    let order = causal_order(&ops);
+    let referenced: BTreeSet<&str> = order
+        .iter()
+        .flat_map(|placed| placed.op.deps.iter().map(String::as_str))
+        .collect();
+    let mut heads: Vec<String> = order
+        .iter()
+        .map(|placed| hex::encode(placed.op.hash()))
+        .filter(|hash| !referenced.contains(hash.as_str()))
+        .collect();
+    heads.sort();
+    Ok(heads)
+}
+
+/// Replay the stored log and write the resulting rights to storage.
+pub fn rebuild(storage: &Storage) -> Result<Acl> {
+    let acl = Acl::replay(&storage.list_root_admins()?, &storage.list_admin_ops()?);
+    storage.set_access(&acl.admins, &acl.uploaders)?;
+    Ok(acl)
+}
+
+/// Check and store an operation without updating the rights derived from
+/// the log.
+fn append(storage: &Storage, op: &AdminOp, now_ms: u64) -> Result<bool> {
+    op.verify()?;
+    if op.timestamp_ms > now_ms.saturating_add(MAX_OP_CLOCK_SKEW_MS) {
+        return Err(Error::InvalidInput("Admin operation timestamp is in the future".into()));
+    }
+    storage.append_admin_op(op)
+}
+
+/// Update the stored rights for an operation just added to the log.
+///
+/// If the operation comes last in causal order, isn't a removal and
+/// descends from every removal of its author, replaying the log would give
+/// the stored rights plus its effect, so only that is written. Otherwise
+/// (it arrived late, completes waiting operations, or is concurrent with a
+/// revocation) the log is replayed.
+fn update(storage: &Storage, op: &AdminOp) -> Result<()> {
+    let ops = storage.list_admin_ops()?;
+    let order = causal_order(&ops);
+    let Some(last) = order.last().filter(|placed| placed.op == op) else {
+        return rebuild(storage).map(drop);
+    };
+    if op.action == AdminAction::RemoveAdmin {
+        return rebuild(storage).map(drop);
+    }
+    let mut ancestors = vec![false; order.len()];
+    for &parent in &last.parents {
+        reach(|i| &order[i].parents, parent, &mut ancestors);
+    }
+    let revoked = order.iter().enumerate().any(|(i, placed)| {
+        placed.op.action == AdminAction::RemoveAdmin && placed.op.subject == op.author && !ancestors[i]
+    });
+    if revoked {
+        return rebuild(storage).map(drop);
+    }
+
+    if !storage.is_admin(&op.author)? {
+        return Ok(());
+    }
+    match op.action {
+        AdminAction::AddAdmin => storage.set_admin(&op.subject, true),
+        AdminAction::GrantUpload => storage.grant_permission(&op.subject, "upload"),
+        AdminAction::RevokeUpload => storage.revoke_permission(&op.subject, "upload"),
+        AdminAction::RemoveAdmin => unreachable!("removals replay the log"),
+    }
+}
+
+/// Add an operation (local or replicated) to the log.
+///
+/// Returns `false` if it was already there. Operations with a bad signature
+/// or a timestamp too far in the future are rejected.
+pub fn apply(storage: &Storage, op: &AdminOp, now_ms: u64) -> Result<bool> {
+    if !append(storage, op, now_ms)? {
+        return Ok(false);
+    }
+    update(storage, op)?;
+    Ok(true)
+}
+
+/// Add operations received together (a sync batch or an archive), updating
+/// the rights once rather than per operation.
+///
+/// Returns [`apply`]'s result for each operation; the outer error is a
+/// failure to update the rights.
+pub fn apply_all<'a>(
+    storage: &Storage,
+    ops: impl IntoIterator<Item = &'a AdminOp>,
+    now_ms: u64,
+) -> Result<Vec<Result<bool>>> {
+    let mut results = Vec::new();
+    let mut added = Vec::new();
+    for op in ops {
+        let result = append(storage, op, now_ms);
+        if matches!(result, Ok(true)) {
+            added.push(op);
+        }
+        results.push(result);
+    }
+    match added[..] {
+        [] => {}
+        [op] => update(storage, op)?,
+        _ => {
+            rebuild(storage)?;
+        }
+    }
+    Ok(results)
+}
+
+/// Apply an operation made on this node. Fails unless its author is
+/// currently an admin, since otherwise it would have no effect.
+pub fn submit(storage: &Storage, op: &AdminOp, now_ms: u64) -> Result<()> {
+    if !storage.is_admin(&op.author)? {
+        return Err(Error::InvalidInput(format!("{} is not an admin", op.author)));
+    }
+    apply(storage, op, now_ms)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    fn public(key: &SigningKey) -> String {
+        hex::encode(key.verifying_key().as_bytes())
+    }
+
+    fn op(action: AdminAction, subject: &SigningKey, ts: u64, author: &SigningKey) -> AdminOp {
+        AdminOp::signed(action, &public(subject), ts, author).unwrap()
+    }
+
+    fn op_after(action: AdminAction, subject: &SigningKey, ts: u64, author: &SigningKey, deps: &[&AdminOp]) -> AdminOp {
+        let deps = deps.iter().map(|dep| hex::encode(dep.hash())).collect();
+        AdminOp::signed_after(action, &public(subject), ts, deps, author).unwrap()
+    }
+
+    fn node_with_root(root: &SigningKey) -> (tempfile::TempDir, Storage) {
+        let dir = tempdir().unwrap();
+        let storage = Storage::open(dir.path()).unwrap();
+        storage.add_root_admin(&public(root)).unwrap();
+        rebuild(&storage).unwrap();
+        (dir, storage)
+    }
+
+    #[test]
+    fn test_signature() {
+        let op = op(AdminAction::AddAdmin, &key(2), 1000, &key(1));
+        op.verify().unwrap();
+
+        let mut tampered = op.clone();
+        tampered.subject = public(&key(3));
+        assert!(tampered.verify().is_err());
+
+        let mut tampered = op;
+        tampered.action = AdminAction::RemoveAdmin;
+        assert!(tampered.verify().is_err());
+    }
+
+    #[test]
+    fn test_only_admins_change_rights() {
+        let (root, alice, mallory) = (key(1), key(2), key(3));
+        let (_dir, storage) = node_with_root(&root);
+
+        // Mallory signs her own promotion: stored, but without effect
+        assert!(apply(&storage, &op(AdminAction::AddAdmin, &mallory, 100, &mallory), 1000).unwrap());
+        assert!(!storage.is_admin(&public(&mallory)).unwrap());
+        assert!(submit(&storage, &op(AdminAction::GrantUpload, &mallory, 110, &mallory), 1000).is_err());
+
+        apply(&storage, &op(AdminAction::AddAdmin, &alice, 200, &root), 1000).unwrap();
+        apply(&storage, &op(AdminAction::GrantUpload, &mallory, 300, &alice), 1000).unwrap();
+        assert!(storage.is_admin(&public(&alice)).unwrap());
+        assert!(storage.has_permission(&public(&mallory), "upload").unwrap());
+
+        // Roots can't be removed, and duplicates are ignored
+        let remove_root = op(AdminAction::RemoveAdmin, &root, 400, &alice);
+        assert!(apply(&storage, &remove_root, 1000).unwrap());
+        assert!(!apply(&storage, &remove_root, 1000).unwrap());
+        assert!(storage.is_admin(&public(&root)).unwrap());
+
+        let future = op(AdminAction::AddAdmin, &key(4), 1000 + MAX_OP_CLOCK_SKEW_MS + 1, &root);
+        assert!(apply(&storage, &future, 1000).is_err());
+    }
+
+    #[test]
+    fn test_revocation_wins_in_any_order() {
+        let (root, alice, bob) = (key(1), key(2), key(3));
+        let ops = [
+            op(AdminAction::AddAdmin, &alice, 100, &root),
+            op(AdminAction::GrantUpload, &bob, 200, &alice),
+            op(AdminAction::RemoveAdmin, &alice, 300, &root),
+            // Made after Alice lost admin - no effect
+            op(AdminAction::AddAdmin, &bob, 400, &alice),
+            op(AdminAction::RevokeUpload, &bob, 500, &root),
+        ];
+
+        let mut results = Vec::new();
+        for order in [[0, 1, 2, 3, 4], [4, 3, 2, 1, 0], [3, 0, 4, 2, 1]] {
+            let (_dir, storage) = node_with_root(&root);
+            for i in order {
+                apply(&storage, &ops[i], 1000).unwrap();
+            }
+            results.push((storage.list_admins().unwrap(), storage.has_permission(&public(&bob), "upload").unwrap()));
+        }
+        assert!(results.iter().all(|r| *r == results[0]));
+        assert_eq!(results[0], (vec![public(&root)], false));
+    }
+
+    #[test]
+    fn test_backdating_cannot_undo_revocation() {
+        let (root, alice, bob, mallory, carol) = (key(1), key(2), key(3), key(4), key(5));
+        let add_alice = op_after(AdminAction::AddAdmin, &alice, 100, &root, &[]);
+        let grant_bob = op_after(AdminAction::GrantUpload, &bob, 200, &alice, &[&add_alice]);
+        let remove_alice = op_after(AdminAction::RemoveAdmin, &alice, 300, &root, &[&grant_bob]);
+        let ops = [
+            add_alice.clone(),
+            grant_bob,
+            remove_alice.clone(),
+            // Signed by Alice after her removal, dated before it
+            op_after(AdminAction::AddAdmin, &mallory, 150, &alice, &[&add_alice]),
+            op_after(AdminAction::AddAdmin, &alice, 50, &alice, &[]),
+        ];
+
+        for order in [[0, 1, 2, 3, 4], [4, 3, 2, 1, 0], [3, 0, 4, 2, 1]] {
+            let (_dir, storage) = node_with_root(&root);
+            for i in order {
+                apply(&storage, &ops[i], 1000).unwrap();
+            }
+            // The grant was known to the removal, so it stands
+            assert_eq!(storage.list_admins().unwrap(), vec![public(&root)]);
+            assert!(storage.has_permission(&public(&bob), "upload").unwrap());
+            assert!(!storage.is_admin(&public(&mallory)).unwrap());
+        }
+
+        // Made an admin again, Alice's new operations count
+        let (_dir, storage) = node_with_root(&root);
+        for op in &ops {
+            apply(&storage, op, 1000).unwrap();
+        }
+        let readd = op_after(AdminAction::AddAdmin, &alice, 400, &root, &[&remove_alice]);
+        apply(&storage, &readd, 1000).unwrap();
+        let heads = heads(&storage).unwrap();
+        assert!(heads.contains(&hex::encode(readd.hash())));
+        assert!(!heads.contains(&hex::encode(remove_alice.hash())));
+
+        let grant_carol = AdminOp::signed_after(AdminAction::GrantUpload, &public(&carol), 500, heads, &alice).unwrap();
+        submit(&storage, &grant_carol, 1000).unwrap();
+        assert!(storage.has_permission(&public(&carol), "upload").unwrap());
+        assert!(!storage.is_admin(&public(&mallory)).unwrap());
+    }
+
+    #[test]
+    fn test_updates_match_replay() {
+        let (root, alice, bob, carol) = (key(1), key(2), key(3), key(4));
+        let add_alice = op_after(AdminAction::AddAdmin, &alice, 100, &root, &[]);
+        let grant_bob = op_after(AdminAction::GrantUpload, &bob, 200, &alice, &[&add_alice]);
+        let grant_carol = op_after(AdminAction::GrantUpload, &carol, 300, &alice, &[&grant_bob]);
+        let remove_alice = op_after(AdminAction::RemoveAdmin, &alice, 250, &root, &[&grant_bob]);
+        let revoke_bob = op_after(AdminAction::RevokeUpload, &bob, 400, &root, &[&remove_alice]);
+        let ops = [add_alice, grant_bob, grant_carol, remove_alice, revoke_bob];
+
+        let expected = Acl::replay(&[public(&root)], &ops);
+        assert_eq!(expected.admins, BTreeSet::from([public(&root)]));
+        assert!(expected.uploaders.is_empty());
+        for order in [[0, 1, 2, 3, 4], [0, 1, 3, 4, 2], [4, 3, 2, 1, 0]] {
+            let (_dir, one_by_one) = node_with_root(&root);
+            let (_dir, batch) = node_with_root(&root);
+            for i in order {
+                apply(&one_by_one, &ops[i], 1000).unwrap();
+            }
+            let results = apply_all(&batch, order.iter().map(|&i| &ops[i]), 1000).unwrap();
+            assert!(results.into_iter().all(|added| added.unwrap()));
+            for storage in [&one_by_one, &batch] {
+                assert_eq!(storage.list_admins().unwrap(), vec![public(&root)]);
+                assert!(!storage.has_permission(&public(&bob), "upload").unwrap());
+                assert!(!storage.has_permission(&public(&carol), "upload").unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_waits_for_missing_deps() {
+        let (root, alice, bob) = (key(1), key(2), key(3));
+        let (_dir, storage) = node_with_root(&root);
+        let add_alice = op(AdminAction::AddAdmin, &alice, 100, &root);
+        let grant_bob = op_after(AdminAction::GrantUpload, &bob, 200, &alice, &[&add_alice]);
+
+        apply(&storage, &grant_bob, 1000).unwrap();
+        assert!(!storage.has_permission(&public(&bob), "upload").unwrap());
+        assert!(heads(&storage).unwrap().is_empty());
+
+        apply(&storage, &add_alice, 1000).unwrap();
+        assert!(storage.has_permission(&public(&bob), "upload").unwrap());
+    }
+
+    #[test]
+    fn test_rejects_non_canonical_encodings() {
+        let (root, alice) = (key(1), key(2));
+        let (_dir, storage) = node_with_root(&root);
+        let add_alice = op(AdminAction::AddAdmin, &alice, 100, &root);
+
+        let mut upper = add_alice.clone();
+        upper.signature = upper.signature.to_uppercase();
+        assert!(upper.verify().is_err());
+        assert!(apply(&storage, &upper, 1000).is_err());
+
+        let mut upper = add_alice.clone();
+        upper.author = upper.author.to_uppercase();
+        assert!(upper.verify().is_err());
+
+        let mut upper = add_alice.clone();
+        upper.subject = upper.subject.to_uppercase();
+        assert!(upper.verify().is_err());
+
+        let (a, b) = (op(AdminAction::GrantUpload, &alice, 1, &root), op(AdminAction::GrantUpload, &alice, 2, &root));
+        let mut deps = vec![hex::encode(a.hash()), hex::encode(b.hash())];
+        deps.sort();
+        deps.reverse();
+        let mut unsorted = AdminOp::signed_after(AdminAction::GrantUpload, &public(&alice), 3, Vec::new(), &root).unwrap();
+        unsorted.deps = deps;
+        assert!(unsorted.verify().is_err());
+
+        assert!(apply(&storage, &add_alice, 1000).unwrap());
+        assert_eq!(storage.list_admin_ops().unwrap().len(), 1);
+    }
+}
@@ -9,8 +9,22 @@
 //!   lens-admin revoke-upload <public_key>
 //!   lens-admin list-admins
 //!   lens-admin is-admin <public_key>
+//!   lens-admin export <file>
+//!   lens-admin import <file> [skip|overwrite|newest] [--signer <public_key>]
+//!   lens-admin node-key
 //!   lens-admin ping
+//!
+//! Changes are signed admin operations. With `LENS_ADMIN_KEY` set (a hex
+//! ed25519 secret key) they are signed here, after the node's current admin
+//! log heads, and submitted; otherwise the node signs them with its own key,
+//! which must have been made an admin.
+//!
+//! Archives are written and read by the node itself, so paths are resolved
+//! here before being sent. An archive is only imported if it was signed by
+//! the key given with `--signer`, or else by an admin or the node itself.
 
+use citadel_lens::acl::{AdminAction, AdminOp};
+use ed25519_dalek::SigningKey;
 use serde::{Deserialize, Serialize};
 use std::io::{BufRead, BufReader, Write};
 use std::os::unix::net::UnixStream;
@@ -24,6 +38,16 @@ enum AdminCommand {
     RemoveAdmin { public_key: String },
     GrantUpload { public_key: String },
     RevokeUpload { public_key: String },
+    Submit { op: AdminOp },
+    Heads,
+    Export { path: String },
+    Import {
+        path: String,
+        policy: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        signer: Option<String>,
+    },
+    NodeKey,
     ListAdmins,
     IsAdmin { public_key: String },
     Ping,
@@ -50,10 +74,53 @@ fn print_usage() {
     eprintln!("  lens-admin revoke-upload <public_key>  Revoke upload permission");
     eprintln!("  lens-admin list-admins                 List all admins");
     eprintln!("  lens-admin is-admin <public_key>       Check if key is admin");
+    eprintln!("  lens-admin export <file>               Write a signed catalog archive");
+    eprintln!("  lens-admin import <file> [policy]      Import an archive; on conflicts skip (default),");
+    eprintln!("                                         overwrite, or keep the newest");
+    eprintln!("         [--signer <public_key>]          Accept archives signed by this key (default:");
+    eprintln!("                                         admins and the node key)");
+    eprintln!("  lens-admin node-key                    Show the node's public key");
     eprintln!("  lens-admin ping                        Check if daemon is running");
     eprintln!();
     eprintln!("Environment:");
-    eprintln!("  LENS_SOCKET  Path to admin socket (default: ./lens-data/admin.sock)");
+    eprintln!("  LENS_SOCKET     Path to admin socket (default: ./lens-data/admin.sock)");
+    eprintln!("  LENS_ADMIN_KEY  Hex secret key to sign changes with (default: node key)");
+}
+
+/// Build a change command, signed locally if `LENS_ADMIN_KEY` is set.
+fn change(action: AdminAction, public_key: String) -> Result<AdminCommand, String> {
+    let Ok(secret) = std::env::var("LENS_ADMIN_KEY") else {
+        return Ok(match action {
+            AdminAction::AddAdmin => AdminCommand::AddAdmin { public_key },
+            AdminAction::RemoveAdmin => AdminCommand::RemoveAdmin { public_key },
+            AdminAction::GrantUpload => AdminCommand::GrantUpload { public_key },
+            AdminAction::RevokeUpload => AdminCommand::RevokeUpload { public_key },
+        });
+    };
+
+    let secret: [u8; 32] = hex::decode(secret.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or("LENS_ADMIN_KEY must be a 32-byte hex secret key")?;
+    let now_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    let deps = match send_command(AdminCommand::Heads)? {
+        AdminResponse::List { items } => items,
+        AdminResponse::Error { error } => return Err(error),
+        _ => return Err("Unexpected response to heads".to_string()),
+    };
+    let op = AdminOp::signed_after(action, &public_key, now_ms, deps, &SigningKey::from_bytes(&secret))
+        .map_err(|e| e.to_string())?;
+    Ok(AdminCommand::Submit { op })
+}
+
+/// Absolute form of a path given on the command line.
+fn absolute(path: &str) -> String {
+    std::path::absolute(path)
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|_| path.to_string())
 }
 
 fn get_socket_path() -> PathBuf {
@@ -101,8 +168,12 @@ fn main() {
                 eprintln!("Error: add-admin requires a public_key argument");
                 std::process::exit(1);
             }
-            AdminCommand::AddAdmin {
-                public_key: args[2].clone(),
+            match change(AdminAction::AddAdmin, args[2].clone()) {
+                Ok(cmd) => cmd,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
             }
         }
         "remove-admin" => {
@@ -110,8 +181,12 @@ fn main() {
                 eprintln!("Error: remove-admin requires a public_key argument");
                 std::process::exit(1);
             }
-            AdminCommand::RemoveAdmin {
-                public_key: args[2].clone(),
+            match change(AdminAction::RemoveAdmin, args[2].clone()) {
+                Ok(cmd) => cmd,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
             }
         }
         "grant-upload" => {
@@ -119,8 +194,12 @@ fn main() {
                 eprintln!("Error: grant-upload requires a public_key argument");
                 std::process::exit(1);
             }
-            AdminCommand::GrantUpload {
-                public_key: args[2].clone(),
+            match change(AdminAction::GrantUpload, args[2].clone()) {
+                Ok(cmd) => cmd,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
             }
         }
         "revoke-upload" => {
@@ -128,8 +207,12 @@ fn main() {
                 eprintln!("Error: revoke-upload requires a public_key argument");
                 std::process::exit(1);
             }
-            AdminCommand::RevokeUpload {
-                public_key: args[2].clone(),
+            match change(AdminAction::RevokeUpload, args[2].clone()) {
+                Ok(cmd) => cmd,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
             }
         }
         "list-admins" => AdminCommand::ListAdmins,
@@ -142,6 +225,47 @@ fn main() {
                 public_key: args[2].clone(),
             }
         }
+        "export" => {
+            if args.len() < 3 {
+                eprintln!("Error: export requires a file argument");
+                std::process::exit(1);
+            }
+            AdminCommand::Export {
+                path: absolute(&args[2]),
+            }
+        }
+        "import" => {
+            if args.len() < 3 {
+                eprintln!("Error: import requires a file argument");
+                std::process::exit(1);
+            }
+            let mut policy = "skip".to_string();
+            let mut signer = None;
+            let mut rest = args[3..].iter();
+            while let Some(arg) = rest.next() {
+                if arg == "--signer" {
+                    match rest.next() {
+                        Some(key) => signer = Some(key.clone()),
+                        None => {
+                            eprintln!("Error: --signer requires a public key");
+                            std::process::exit(1);
+                        }
+                    }
+                } else {
+                    policy = arg.clone();
+                }
+            }
+            if !matches!(policy.as_str(), "skip" | "overwrite" | "newest") {
+                eprintln!("Error: policy must be skip, overwrite or newest");
+                std::process::exit(1);
+            }
+            AdminCommand::Import {
+                path: absolute(&args[2]),
+                policy,
+                signer,
+            }
+        }
+        "node-key" => AdminCommand::NodeKey,
         "ping" => AdminCommand::Ping,
         "-h" | "--help" | "help" => {
             print_usage();
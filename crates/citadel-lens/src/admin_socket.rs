@@ -1,15 +1,25 @@
 //! Unix socket server for admin commands.
 //!
 //! Provides a local IPC interface for managing users, uploaders, and admins.
+//!
+//! Changes are signed admin operations (see [`crate::acl`]). Plain commands
+//! like `add_admin` are signed with the node's key, which only works once an
+//! admin has made the node an admin; `submit` takes an operation already
+//! signed by an admin key, which should follow the log `heads`.
+//!
+//! `export` and `import` read and write signed catalog archives (see
+//! [`crate::archive`]) at a path on the node's filesystem.
 
+use crate::acl::{self, AdminAction, AdminOp};
+use crate::archive::{self, ConflictPolicy};
 use crate::error::Result;
-use crate::mesh::FloodMessage;
+use crate::replication::now_ms;
 use crate::storage::Storage;
+use ed25519_dalek::SigningKey;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::{UnixListener, UnixStream};
-use tokio::sync::broadcast;
 
 /// Admin command sent over the socket.
 #[derive(Debug, Deserialize)]
@@ -23,6 +33,23 @@ pub enum AdminCommand {
     GrantUpload { public_key: String },
     /// Revoke upload permission
     RevokeUpload { public_key: String },
+    /// Apply an operation signed elsewhere by an admin key
+    Submit { op: AdminOp },
+    /// List the admin log heads, which an operation signed elsewhere should follow
+    Heads,
+    /// Write the catalog to an archive file, signed with the node key
+    Export { path: String },
+    /// Merge an archive file into the catalog
+    Import {
+        path: String,
+        #[serde(default)]
+        policy: ConflictPolicy,
+        /// Key the archive must be signed by; admins and the node key if unset
+        #[serde(default)]
+        signer: Option<String>,
+    },
+    /// Get the node's public key (which signs the commands above and archives)
+    NodeKey,
     /// List all admins
     ListAdmins,
     /// Check if a key is admin
@@ -46,23 +73,18 @@ pub enum AdminResponse {
 pub struct AdminSocket {
     storage: Arc<Storage>,
     socket_path: String,
-    flood_tx: Option<broadcast::Sender<FloodMessage>>,
+    signing_key: SigningKey,
 }
 
 impl AdminSocket {
-    /// Create a new admin socket server.
-    pub fn new(storage: Arc<Storage>, socket_path: &str) -> Self {
-        Self {
+    /// Create a new admin socket server. Commands are signed with the node key.
+    pub fn new(storage: Arc<Storage>, socket_path: &str) -> Result<Self> {
+        let signing_key = storage.get_or_create_node_key()?;
+        Ok(Self {
             storage,
             socket_path: socket_path.to_string(),
-            flood_tx: None,
-        }
-    }
-
-    /// Set the flood sender for mesh propagation.
-    pub fn with_flood_tx(mut self, tx: broadcast::Sender<FloodMessage>) -> Self {
-        self.flood_tx = Some(tx);
-        self
+            signing_key,
+        })
     }
 
     /// Run the admin socket server.
@@ -77,9 +99,9 @@ impl AdminSocket {
             match listener.accept().await {
                 Ok((stream, _)) => {
                     let storage = Arc::clone(&self.storage);
-                    let flood_tx = self.flood_tx.clone();
+                    let signing_key = self.signing_key.clone();
                     tokio::spawn(async move {
-                        if let Err(e) = handle_connection(stream, storage, flood_tx).await {
+                        if let Err(e) = handle_connection(stream, storage, signing_key).await {
                             tracing::error!("Admin connection error: {}", e);
                         }
                     });
@@ -100,7 +122,7 @@ impl AdminSocket {
 async fn handle_connection(
     stream: UnixStream,
     storage: Arc<Storage>,
-    flood_tx: Option<broadcast::Sender<FloodMessage>>,
+    signing_key: SigningKey,
 ) -> Result<()> {
     let (reader, mut writer) = stream.into_split();
     let mut reader = BufReader::new(reader);
@@ -108,7 +130,7 @@ async fn handle_connection(
 
     while reader.read_line(&mut line).await? > 0 {
         let response = match serde_json::from_str::<AdminCommand>(&line) {
-            Ok(cmd) => execute_command(cmd, &storage, &flood_tx),
+            Ok(cmd) => execute_command(cmd, &storage, &signing_key),
             Err(e) => AdminResponse::Error {
                 error: format!("Invalid command: {}", e),
             },
@@ -122,74 +144,109 @@ async fn handle_connection(
     Ok(())
 }
 
-/// Flood the current admin list to the mesh
-fn flood_admins(storage: &Arc<Storage>, flood_tx: &Option<broadcast::Sender<FloodMessage>>) {
-    if let Some(tx) = flood_tx {
-        if let Ok(admins) = storage.list_admins() {
-            let _ = tx.send(FloodMessage::Admins(admins));
-            tracing::info!("Flooded admin list to mesh");
+/// Sign an operation with the node key and apply it.
+fn node_op(storage: &Storage, signing_key: &SigningKey, action: AdminAction, public_key: &str) -> AdminResponse {
+    let result = acl::heads(storage)
+        .and_then(|deps| AdminOp::signed_after(action, public_key, now_ms(), deps, signing_key))
+        .and_then(|op| acl::submit(storage, &op, now_ms()).map(|_| op));
+    match result {
+        Ok(op) => {
+            tracing::info!("Admin operation {:?} on {}", op.action, op.subject);
+            AdminResponse::Ok {
+                message: format!("{:?} applied to {}", op.action, op.subject),
+            }
         }
+        Err(e) => AdminResponse::Error {
+            error: format!(
+                "{} (commands are signed with the node key {}; make it an admin or submit a signed operation)",
+                e,
+                hex::encode(signing_key.verifying_key().as_bytes())
+            ),
+        },
     }
 }
 
 fn execute_command(
     cmd: AdminCommand,
     storage: &Arc<Storage>,
-    flood_tx: &Option<broadcast::Sender<FloodMessage>>,
+    signing_key: &SigningKey,
 ) -> AdminResponse {
     match cmd {
         AdminCommand::AddAdmin { public_key } => {
-            match storage.set_admin(&public_key, true) {
-                Ok(()) => {
-                    tracing::info!("Added admin: {}", public_key);
-                    // Flood updated admin list to mesh
-                    flood_admins(storage, flood_tx);
-                    AdminResponse::Ok {
-                        message: format!("Added admin: {}", public_key),
-                    }
-                }
-                Err(e) => AdminResponse::Error {
-                    error: e.to_string(),
-                },
-            }
+            node_op(storage, signing_key, AdminAction::AddAdmin, &public_key)
         }
 
         AdminCommand::RemoveAdmin { public_key } => {
-            match storage.set_admin(&public_key, false) {
-                Ok(()) => {
-                    tracing::info!("Removed admin: {}", public_key);
-                    // Flood updated admin list to mesh
-                    flood_admins(storage, flood_tx);
-                    AdminResponse::Ok {
-                        message: format!("Removed admin: {}", public_key),
-                    }
-                }
-                Err(e) => AdminResponse::Error {
-                    error: e.to_string(),
-                },
-            }
+            node_op(storage, signing_key, AdminAction::RemoveAdmin, &public_key)
         }
 
         AdminCommand::GrantUpload { public_key } => {
-            match storage.grant_permission(&public_key, "upload") {
-                Ok(()) => {
-                    tracing::info!("Granted upload to: {}", public_key);
-                    AdminResponse::Ok {
-                        message: format!("Granted upload permission to: {}", public_key),
-                    }
+            node_op(storage, signing_key, AdminAction::GrantUpload, &public_key)
+        }
+
+        AdminCommand::RevokeUpload { public_key } => {
+            node_op(storage, signing_key, AdminAction::RevokeUpload, &public_key)
+        }
+
+        AdminCommand::Submit { op } => match acl::submit(storage, &op, now_ms()) {
+            Ok(()) => {
+                tracing::info!("Admin operation {:?} on {} by {}", op.action, op.subject, op.author);
+                AdminResponse::Ok {
+                    message: format!("{:?} applied to {}", op.action, op.subject),
                 }
+            }
+            Err(e) => AdminResponse::Error {
+                error: e.to_string(),
+            },
+        },
+
+        AdminCommand::Heads => match acl::heads(storage) {
+            Ok(heads) => AdminResponse::List { items: heads },
+            Err(e) => AdminResponse::Error {
+                error: e.to_string(),
+            },
+        },
+
+        AdminCommand::Export { path } => {
+            let result = std::fs::File::create(&path)
+                .map_err(crate::Error::from)
+                .and_then(|file| archive::export(storage, signing_key, &mut std::io::BufWriter::new(file)));
+            match result {
+                Ok(manifest) => AdminResponse::Ok {
+                    message: format!(
+                        "Exported {} records and {} chunks to {} (root {})",
+                        manifest.records,
+                        manifest.chunks.len(),
+                        path,
+                        manifest.root
+                    ),
+                },
                 Err(e) => AdminResponse::Error {
                     error: e.to_string(),
                 },
             }
         }
 
-        AdminCommand::RevokeUpload { public_key } => {
-            match storage.revoke_permission(&public_key, "upload") {
-                Ok(()) => {
-                    tracing::info!("Revoked upload from: {}", public_key);
+        AdminCommand::Import { path, policy, signer } => {
+            let result = std::fs::File::open(&path)
+                .map_err(crate::Error::from)
+                .and_then(|file| {
+                    archive::import(storage, &mut std::io::BufReader::new(file), policy, signer.as_deref())
+                });
+            match result {
+                Ok(summary) => {
+                    tracing::info!("Imported archive {} signed by {}", path, summary.signer);
                     AdminResponse::Ok {
-                        message: format!("Revoked upload permission from: {}", public_key),
+                        message: format!(
+                            "Imported archive signed by {} on {}: {} added, {} replaced, {} skipped, {} blobs, {} admin operations",
+                            summary.signer,
+                            summary.created_at,
+                            summary.added,
+                            summary.replaced,
+                            summary.skipped,
+                            summary.blobs,
+                            summary.admin_ops
+                        ),
                     }
                 }
                 Err(e) => AdminResponse::Error {
@@ -198,6 +255,10 @@ fn execute_command(
             }
         }
 
+        AdminCommand::NodeKey => AdminResponse::Ok {
+            message: hex::encode(signing_key.verifying_key().as_bytes()),
+        },
+
         AdminCommand::ListAdmins => {
             match storage.list_admins() {
                 Ok(admins) => AdminResponse::List { items: admins },
@@ -1,21 +1,62 @@
 //! HTTP API for Lens.
+//!
+//! Reads are open. Writes must be signed (see [`crate::auth`]): uploads need
+//! the `upload` permission, and changing or deleting a release or content
+//! item needs admin or the key that uploaded it.
+//!
+//! Single releases and content items are content-negotiated: besides JSON
+//! they can be fetched as schema.org JSON-LD, DataCite XML or OAI Dublin Core
+//! (see [`crate::export`]).
+//!
+//! The whole catalog can also be harvested over OAI-PMH at `/oai` (see
+//! [`crate::oai`]), and admins can export or import it as a signed archive
+//! (see [`crate::archive`]).
 
-use crate::models::{Category, Release};
+use crate::archive::{self, ConflictPolicy, ImportSummary};
+use crate::auth::{require_signature, Caller, SignatureCheck};
+use crate::blob::{parse_range, BlobManifest, BlobStore, Cid, RangeRequest};
+use crate::models::{
+    Category, ContentItem, ContentType, Creator, License, MetadataContainer, Release, Resource,
+};
+use crate::export::{self, ExportFormat};
+use crate::migrations;
+use crate::oai::{self, Repository};
+use crate::search::{self, DocKind, SearchQuery, SearchResults};
+use crate::storage::{ContentQuery, Page, ReleaseQuery, ReleaseSort};
+use crate::swarm::SwarmProgress;
 use crate::node::LensState;
 use crate::ws::ws_mesh_handler;
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    routing::{delete, get, post},
-    Json, Router,
+    body::{Body, Bytes},
+    extract::{DefaultBodyLimit, Form, Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{delete, get, post, put},
+    Extension, Json, Router,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tower_http::cors::{Any, CorsLayer};
 
 type AppState = Arc<RwLock<LensState>>;
 
+/// Largest blob accepted in a single upload.
+pub const MAX_BLOB_SIZE: usize = 256 * 1024 * 1024;
+
+/// Largest body accepted by routes other than blob uploads and archive
+/// imports (axum's default extractor limit).
+pub const MAX_BODY_SIZE: usize = 2 * 1024 * 1024;
+
+/// Items per page when the client doesn't ask for a limit.
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+/// Most items returned in one page.
+pub const MAX_PAGE_SIZE: usize = 200;
+/// Search results per page when the client doesn't ask for a limit.
+pub const DEFAULT_SEARCH_RESULTS: usize = 20;
+
 /// Build the API router.
 pub fn build_router(state: AppState) -> Router {
     // CORS layer for browser access
@@ -24,6 +65,25 @@ pub fn build_router(state: AppState) -> Router {
         .allow_methods(Any)
         .allow_headers(Any);
 
+    // Signed requests are read up to their route's body limit, sharing one
+    // replay guard
+    let signatures = SignatureCheck::new(state.clone(), MAX_BODY_SIZE);
+    let uploads = Router::new()
+        // Blobs (content-addressed files)
+        .route(
+            "/api/v1/blobs/:cid",
+            put(put_blob).get(get_blob).layer(DefaultBodyLimit::max(MAX_BLOB_SIZE)),
+        )
+        // Catalog archives (admin only)
+        .route(
+            "/api/v1/archive/import",
+            post(import_archive).layer(DefaultBodyLimit::max(MAX_BLOB_SIZE)),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            signatures.with_body_limit(MAX_BLOB_SIZE),
+            require_signature,
+        ));
+
     Router::new()
         // Health (at root and under /api/v1 for compatibility)
         .route("/health", get(health))
@@ -34,6 +94,19 @@ pub fn build_router(state: AppState) -> Router {
         .route("/api/v1/releases", post(create_release))
         .route("/api/v1/releases/:id", get(get_release))
         .route("/api/v1/releases/:id", delete(delete_release))
+        // Content items
+        .route("/api/v1/content", get(list_content).post(create_content))
+        .route(
+            "/api/v1/content/:id",
+            get(get_content).put(update_content).delete(delete_content),
+        )
+        // Full-text search
+        .route("/api/v1/search", get(search_content))
+        // Blob fetches and progress (uploads are below)
+        .route("/api/v1/blobs/:cid/fetch", post(fetch_blob))
+        .route("/api/v1/blobs/:cid/progress", get(get_blob_progress))
+        // Catalog archive export (admin only; a POST so it is signed)
+        .route("/api/v1/archive/export", post(export_archive))
         // Categories
         .route("/api/v1/content-categories", get(list_categories))
         // Featured releases (for flagship home page)
@@ -46,6 +119,10 @@ pub fn build_router(state: AppState) -> Router {
         .route("/api/v1/mesh/state", get(get_mesh_state))
         // WebSocket for real-time mesh updates
         .route("/api/v1/ws/mesh", get(ws_mesh_handler))
+        .route_layer(middleware::from_fn_with_state(signatures, require_signature))
+        .merge(uploads)
+        // OAI-PMH harvesters POST unsigned forms, so this sits outside the signature check
+        .route("/oai", get(oai_get).post(oai_post))
         .layer(cors)
         .with_state(state)
 }
@@ -60,17 +137,72 @@ async fn ready() -> &'static str {
     "OK"
 }
 
+/// Check that the caller may upload content (admins always can).
+fn require_upload(state: &LensState, caller: &Caller) -> Result<(), StatusCode> {
+    let storage = &state.storage;
+    let allowed = storage.is_admin(&caller.public_key).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        || storage
+            .has_permission(&caller.public_key, "upload")
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if allowed {
+        Ok(())
+    } else {
+        Err(StatusCode::FORBIDDEN)
+    }
+}
+
+/// Check that the caller is an admin or the key that uploaded an item.
+fn require_owner(state: &LensState, caller: &Caller, uploader: Option<&str>) -> Result<(), StatusCode> {
+    if uploader == Some(caller.public_key.as_str()) {
+        return Ok(());
+    }
+    match state.storage.is_admin(&caller.public_key) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(StatusCode::FORBIDDEN),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
 // --- Release endpoints ---
 
+#[derive(Debug, Deserialize)]
+struct ReleaseListParams {
+    category: Option<String>,
+    tag: Option<String>,
+    creator: Option<String>,
+    year: Option<u32>,
+    /// `created`, `year` or `title`, prefixed with `-` for descending
+    sort: Option<String>,
+    cursor: Option<String>,
+    limit: Option<usize>,
+}
+
+/// List releases, filtered and ordered through the storage indexes. Pass
+/// `next_cursor` from a page as `cursor` (with the same filters and sort)
+/// to get the next one.
 async fn list_releases(
     State(state): State<AppState>,
-) -> Result<Json<Vec<Release>>, StatusCode> {
+    Query(params): Query<ReleaseListParams>,
+) -> Result<Json<Page<Release>>, StatusCode> {
+    let sort = match params.sort {
+        Some(ref sort) => sort.parse().map_err(|_| StatusCode::BAD_REQUEST)?,
+        None => ReleaseSort::default(),
+    };
+    let query = ReleaseQuery {
+        category: params.category,
+        tag: params.tag,
+        creator: params.creator,
+        year: params.year,
+        sort,
+        after: params.cursor,
+        limit: params.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE),
+    };
     let state = state.read().await;
-    let releases = state
-        .storage
-        .list_releases()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    Ok(Json(releases))
+    match state.storage.query_releases(&query) {
+        Ok(page) => Ok(Json(page)),
+        Err(crate::Error::InvalidInput(_)) => Err(StatusCode::BAD_REQUEST),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -85,8 +217,12 @@ struct CreateReleaseRequest {
 
 async fn create_release(
     State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
     Json(req): Json<CreateReleaseRequest>,
 ) -> Result<(StatusCode, Json<Release>), StatusCode> {
+    let state = state.read().await;
+    require_upload(&state, &caller)?;
+
     // Generate ID from title + timestamp
     let content = format!("{}:{}", req.title, std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
@@ -99,39 +235,615 @@ async fn create_release(
     release.year = req.year;
     release.description = req.description;
     release.tags = req.tags.unwrap_or_default();
+    release.uploader = Some(caller.public_key);
+    release.created_at = Some(crate::timestamp::now_utc());
 
-    let state = state.read().await;
     state
         .storage
         .put_release(&release)
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    // Publish to the DHT in the background so other nodes can look it up
+    if let (Some(mesh), Ok(value)) = (state.mesh.clone(), serde_json::to_vec(&release)) {
+        let key = release.dht_key();
+        tokio::spawn(async move {
+            if let Err(e) = mesh.dht_publish(key, value.into()).await {
+                tracing::warn!("Failed to publish release to DHT: {}", e);
+            }
+        });
+    }
+
     Ok((StatusCode::CREATED, Json(release)))
 }
 
+#[derive(Debug, Deserialize)]
+struct ExportParams {
+    /// Overrides the `Accept` header: `json`, `jsonld`, `datacite` or `oai_dc`
+    format: Option<String>,
+}
+
+/// Format requested by `?format=` or, failing that, the `Accept` header.
+fn export_format(params: &ExportParams, headers: &HeaderMap) -> Result<ExportFormat, StatusCode> {
+    match params.format {
+        Some(ref name) => ExportFormat::from_name(name).ok_or(StatusCode::BAD_REQUEST),
+        None => {
+            let accept = headers.get(header::ACCEPT).and_then(|value| value.to_str().ok());
+            ExportFormat::negotiate(accept).ok_or(StatusCode::NOT_ACCEPTABLE)
+        }
+    }
+}
+
+/// A record as JSON, or as an item rendered in a metadata format.
+fn export_response<T: Serialize>(record: &T, item: impl FnOnce() -> ContentItem, format: ExportFormat) -> Response {
+    let body = match format {
+        ExportFormat::Json => return ([(header::VARY, "Accept")], Json(record)).into_response(),
+        format => export::render(&item(), format),
+    };
+    ([(header::CONTENT_TYPE, format.media_type()), (header::VARY, "Accept")], body).into_response()
+}
+
 async fn get_release(
     State(state): State<AppState>,
     Path(id): Path<String>,
-) -> Result<Json<Release>, StatusCode> {
-    let state = state.read().await;
-    match state.storage.get_release(&id) {
-        Ok(Some(release)) => Ok(Json(release)),
+    Query(params): Query<ExportParams>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let format = export_format(&params, &headers)?;
+    let release = find_release(&*state.read().await, &id).await?;
+    Ok(export_response(&release, || export::release_item(&release), format))
+}
+
+async fn find_release(state: &LensState, id: &str) -> Result<Release, StatusCode> {
+    match state.storage.get_release(id) {
+        Ok(Some(release)) => return Ok(release),
+        Ok(None) => {}
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+
+    // Not replicated here (yet) - ask the node responsible for it
+    let Some(ref mesh) = state.mesh else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+    let key = citadel_dht::hash_prefixed_key(Release::DHT_PREFIX, id);
+    match mesh.dht_find(key).await {
+        Ok(Some(entry)) => migrations::decode(entry.value.as_bytes()).map_err(|_| StatusCode::BAD_GATEWAY),
         Ok(None) => Err(StatusCode::NOT_FOUND),
-        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+        Err(_) => Err(StatusCode::BAD_GATEWAY),
     }
 }
 
 async fn delete_release(
     State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
     Path(id): Path<String>,
 ) -> StatusCode {
     let state = state.read().await;
+    let uploader = match state.storage.get_release(&id) {
+        Ok(release) => release.and_then(|r| r.uploader),
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    if let Err(status) = require_owner(&state, &caller, uploader.as_deref()) {
+        return status;
+    }
+
     match state.storage.delete_release(&id) {
-        Ok(()) => StatusCode::NO_CONTENT,
+        Ok(()) => {
+            if let Some(mesh) = state.mesh.clone() {
+                let key = citadel_dht::hash_prefixed_key(Release::DHT_PREFIX, &id);
+                tokio::spawn(async move {
+                    if let Err(e) = mesh.dht_unpublish(key, &caller.public_key).await {
+                        tracing::warn!("Failed to delete release from DHT: {}", e);
+                    }
+                });
+            }
+            StatusCode::NO_CONTENT
+        }
         Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
     }
 }
 
+// --- Content item endpoints ---
+
+#[derive(Debug, Deserialize)]
+struct ContentListParams {
+    #[serde(rename = "type")]
+    content_type: Option<String>,
+    tag: Option<String>,
+    language: Option<String>,
+    creator: Option<String>,
+    release: Option<String>,
+    cursor: Option<String>,
+    limit: Option<usize>,
+}
+
+/// List content items, filtered and paginated by ID. Pass `next_cursor`
+/// from a page as `cursor` to get the next one.
+async fn list_content(
+    State(state): State<AppState>,
+    Query(params): Query<ContentListParams>,
+) -> Result<Json<Page<ContentItem>>, StatusCode> {
+    let query = ContentQuery {
+        content_type: params.content_type,
+        tag: params.tag,
+        language: params.language,
+        creator: params.creator,
+        release_id: params.release,
+        after: params.cursor,
+        limit: params.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE),
+    };
+    let state = state.read().await;
+    let page = state
+        .storage
+        .query_content_items(&query)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(page))
+}
+
+/// Client-editable fields of a content item. The ID, uploader and
+/// timestamps are set by the node.
+#[derive(Debug, Deserialize)]
+struct ContentItemRequest {
+    content_type: ContentType,
+    title: String,
+    release_id: String,
+    description: Option<String>,
+    #[serde(default)]
+    creators: Vec<Creator>,
+    #[serde(default)]
+    tags: Vec<String>,
+    language: Option<String>,
+    license: Option<License>,
+    #[serde(default)]
+    resources: Vec<Resource>,
+    published_at: Option<String>,
+    #[serde(default)]
+    metadata: MetadataContainer,
+    #[serde(default)]
+    type_specific: serde_json::Value,
+    #[serde(default)]
+    custom: HashMap<String, serde_json::Value>,
+}
+
+impl ContentItemRequest {
+    fn apply(self, item: &mut ContentItem) {
+        item.content_type = self.content_type;
+        item.title = self.title;
+        item.release_id = Some(self.release_id);
+        item.description = self.description;
+        item.creators = self.creators;
+        item.tags = self.tags;
+        item.language = self.language;
+        item.license = self.license;
+        item.resources = self.resources;
+        item.published_at = self.published_at;
+        item.metadata = self.metadata;
+        item.type_specific = self.type_specific;
+        item.custom = self.custom;
+    }
+}
+
+/// Content write failure: a plain status, or 422 `{"errors": [...]}`.
+enum ContentError {
+    Status(StatusCode),
+    Invalid(Vec<String>),
+}
+
+impl From<StatusCode> for ContentError {
+    fn from(status: StatusCode) -> Self {
+        Self::Status(status)
+    }
+}
+
+impl IntoResponse for ContentError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Status(status) => status.into_response(),
+            Self::Invalid(errors) => {
+                (StatusCode::UNPROCESSABLE_ENTITY, Json(serde_json::json!({ "errors": errors }))).into_response()
+            }
+        }
+    }
+}
+
+/// Validate an item and check its parent release exists.
+fn check_content(state: &LensState, item: &ContentItem) -> Result<(), ContentError> {
+    let mut errors = item.validate().err().unwrap_or_default();
+    if let Some(ref release_id) = item.release_id {
+        match state.storage.get_release(release_id) {
+            Ok(Some(_)) => {}
+            Ok(None) => errors.push(format!("release_id: no release '{}'", release_id)),
+            Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR.into()),
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ContentError::Invalid(errors))
+    }
+}
+
+/// Store an item and publish it to the DHT in the background.
+fn store_content(state: &LensState, item: &ContentItem) -> Result<(), ContentError> {
+    state
+        .storage
+        .put_content_item(item)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if let (Some(mesh), Ok(value)) = (state.mesh.clone(), serde_json::to_vec(item)) {
+        let key = item.dht_key();
+        tokio::spawn(async move {
+            if let Err(e) = mesh.dht_publish(key, value.into()).await {
+                tracing::warn!("Failed to publish content item to DHT: {}", e);
+            }
+        });
+    }
+    Ok(())
+}
+
+async fn create_content(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Json(req): Json<ContentItemRequest>,
+) -> Result<(StatusCode, Json<ContentItem>), ContentError> {
+    let state = state.read().await;
+    require_upload(&state, &caller)?;
+
+    let now = crate::replication::now_ms();
+    let id = ContentItem::generate_id(format!("{}:{}:{}", caller.public_key, req.title, now).as_bytes());
+    let mut item = ContentItem::new(id, req.content_type.clone(), req.title.clone());
+    req.apply(&mut item);
+    item.uploader = Some(caller.public_key);
+    item.created_at = Some(crate::timestamp::format_utc(now));
+    item.updated_at = item.created_at.clone();
+
+    check_content(&state, &item)?;
+    store_content(&state, &item)?;
+    Ok((StatusCode::CREATED, Json(item)))
+}
+
+async fn get_content(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(params): Query<ExportParams>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let format = export_format(&params, &headers)?;
+    let item = find_content(&*state.read().await, &id).await?;
+    Ok(export_response(&item, || item.clone(), format))
+}
+
+async fn find_content(state: &LensState, id: &str) -> Result<ContentItem, StatusCode> {
+    match state.storage.get_content_item(id) {
+        Ok(Some(item)) => return Ok(item),
+        Ok(None) => {}
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+
+    let Some(ref mesh) = state.mesh else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+    let key = citadel_dht::hash_prefixed_key(ContentItem::DHT_PREFIX, id);
+    match mesh.dht_find(key).await {
+        Ok(Some(entry)) => migrations::decode(entry.value.as_bytes()).map_err(|_| StatusCode::BAD_GATEWAY),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(_) => Err(StatusCode::BAD_GATEWAY),
+    }
+}
+
+async fn update_content(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(id): Path<String>,
+    Json(req): Json<ContentItemRequest>,
+) -> Result<Json<ContentItem>, ContentError> {
+    let state = state.read().await;
+    let mut item = match state.storage.get_content_item(&id) {
+        Ok(Some(item)) => item,
+        Ok(None) => return Err(StatusCode::NOT_FOUND.into()),
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR.into()),
+    };
+    require_owner(&state, &caller, item.uploader.as_deref())?;
+
+    req.apply(&mut item);
+    item.updated_at = Some(crate::timestamp::now_utc());
+
+    check_content(&state, &item)?;
+    store_content(&state, &item)?;
+    Ok(Json(item))
+}
+
+async fn delete_content(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(id): Path<String>,
+) -> StatusCode {
+    let state = state.read().await;
+    let uploader = match state.storage.get_content_item(&id) {
+        Ok(Some(item)) => item.uploader,
+        Ok(None) => return StatusCode::NOT_FOUND,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    if let Err(status) = require_owner(&state, &caller, uploader.as_deref()) {
+        return status;
+    }
+
+    match state.storage.delete_content_item(&id) {
+        Ok(()) => {
+            if let Some(mesh) = state.mesh.clone() {
+                let key = citadel_dht::hash_prefixed_key(ContentItem::DHT_PREFIX, &id);
+                tokio::spawn(async move {
+                    if let Err(e) = mesh.dht_unpublish(key, &caller.public_key).await {
+                        tracing::warn!("Failed to delete content item from DHT: {}", e);
+                    }
+                });
+            }
+            StatusCode::NO_CONTENT
+        }
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+// --- Search endpoint ---
+
+#[derive(Debug, Deserialize)]
+struct SearchParams {
+    q: String,
+    kind: Option<DocKind>,
+    category: Option<String>,
+    #[serde(rename = "type")]
+    content_type: Option<String>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+}
+
+/// Search releases and content items (see [`crate::search`]).
+async fn search_content(
+    State(state): State<AppState>,
+    Query(params): Query<SearchParams>,
+) -> Result<Json<SearchResults>, StatusCode> {
+    let query = SearchQuery {
+        q: params.q,
+        kind: params.kind,
+        category: params.category,
+        content_type: params.content_type,
+        offset: params.offset.unwrap_or(0),
+        limit: params.limit.unwrap_or(DEFAULT_SEARCH_RESULTS).clamp(1, MAX_PAGE_SIZE),
+    };
+    let state = state.read().await;
+    search::search(&state.storage, &query)
+        .map(Json)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+// --- Catalog archives ---
+
+/// Export the catalog as an archive signed by this node.
+async fn export_archive(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+) -> Result<Response, StatusCode> {
+    let state = state.read().await;
+    require_owner(&state, &caller, None)?;
+    let signing_key = state
+        .storage
+        .get_or_create_node_key()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let mut body = Vec::new();
+    archive::export(&state.storage, &signing_key, &mut body).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(([(header::CONTENT_TYPE, archive::ARCHIVE_MEDIA_TYPE)], body).into_response())
+}
+
+#[derive(Debug, Deserialize)]
+struct ImportParams {
+    /// `skip` (default), `overwrite` or `newest`
+    policy: Option<String>,
+    /// Key the archive must be signed by; admins and the node key if unset
+    signer: Option<String>,
+}
+
+/// Verify an archive and merge it into the catalog.
+async fn import_archive(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Query(params): Query<ImportParams>,
+    body: Bytes,
+) -> Result<Json<ImportSummary>, StatusCode> {
+    let storage = {
+        let state = state.read().await;
+        require_owner(&state, &caller, None)?;
+        state.storage.clone()
+    };
+    let policy = match params.policy {
+        Some(policy) => policy.parse::<ConflictPolicy>().map_err(|_| StatusCode::BAD_REQUEST)?,
+        None => ConflictPolicy::default(),
+    };
+    // Verifying and writing a large archive is slow, blocking work; keep it
+    // off the executor and out from under the state lock
+    let imported = tokio::task::spawn_blocking(move || {
+        archive::import(&storage, &mut body.as_ref(), policy, params.signer.as_deref())
+    })
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    match imported {
+        Ok(summary) => Ok(Json(summary)),
+        Err(crate::Error::InvalidInput(_)) => Err(StatusCode::UNPROCESSABLE_ENTITY),
+        Err(crate::Error::Unauthorized(_)) => Err(StatusCode::FORBIDDEN),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+// --- OAI-PMH ---
+
+async fn oai_get(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(args): Query<Vec<(String, String)>>,
+) -> Response {
+    oai_response(&state, &headers, &args).await
+}
+
+async fn oai_post(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Form(args): Form<Vec<(String, String)>>,
+) -> Response {
+    oai_response(&state, &headers, &args).await
+}
+
+async fn oai_response(state: &AppState, headers: &HeaderMap, args: &[(String, String)]) -> Response {
+    let state = state.read().await;
+    let config = &state.config;
+    let base_url = config.oai_base_url.clone().unwrap_or_else(|| {
+        let host = headers
+            .get(header::HOST)
+            .and_then(|host| host.to_str().ok())
+            .unwrap_or("localhost");
+        format!("http://{}/oai", host)
+    });
+    let repository = Repository {
+        name: &config.oai_repository_name,
+        admin_email: &config.oai_admin_email,
+        base_url: &base_url,
+    };
+    match oai::handle(&state.storage, &repository, args) {
+        Ok(body) => ([(header::CONTENT_TYPE, "text/xml; charset=utf-8")], body).into_response(),
+        Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+// --- Blob endpoints ---
+
+/// Stored blob summary
+#[derive(Debug, Serialize)]
+struct BlobInfo {
+    cid: String,
+    size: u64,
+    chunks: usize,
+}
+
+impl From<&BlobManifest> for BlobInfo {
+    fn from(manifest: &BlobManifest) -> Self {
+        Self {
+            cid: manifest.cid().to_hex(),
+            size: manifest.size,
+            chunks: manifest.chunks.len(),
+        }
+    }
+}
+
+/// Upload a blob. The body must hash to the CID in the path.
+async fn put_blob(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(cid): Path<String>,
+    body: Bytes,
+) -> Result<(StatusCode, Json<BlobInfo>), StatusCode> {
+    require_upload(&*state.read().await, &caller)?;
+    let cid: Cid = cid.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+    if BlobManifest::for_data(&body).cid() != cid {
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    let blobs = BlobStore::new(Arc::clone(&state.read().await.storage));
+    let existed = blobs.has(&cid).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let manifest = blobs.put(&body).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let status = if existed { StatusCode::OK } else { StatusCode::CREATED };
+    Ok((status, Json(BlobInfo::from(&manifest))))
+}
+
+/// Download a blob, or the byte range named by a `Range` header.
+async fn get_blob(
+    State(state): State<AppState>,
+    Path(cid): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    let Ok(cid) = cid.parse::<Cid>() else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    let blobs = BlobStore::new(Arc::clone(&state.read().await.storage));
+    let manifest = match blobs.manifest(&cid) {
+        Ok(Some(manifest)) => manifest,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+
+    let size = manifest.size;
+    let range = headers
+        .get(header::RANGE)
+        .and_then(|value| value.to_str().ok())
+        .map_or(RangeRequest::Full, |value| parse_range(value, size));
+    let (status, range) = match range {
+        RangeRequest::Full => (StatusCode::OK, 0..size),
+        RangeRequest::Partial(range) => (StatusCode::PARTIAL_CONTENT, range),
+        RangeRequest::Unsatisfiable => {
+            return (
+                StatusCode::RANGE_NOT_SATISFIABLE,
+                [(header::CONTENT_RANGE, format!("bytes */{}", size))],
+            )
+                .into_response();
+        }
+    };
+
+    let mut response = Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "application/octet-stream")
+        .header(header::CONTENT_LENGTH, range.end - range.start)
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::ETAG, format!("\"{}\"", cid))
+        .header(header::CACHE_CONTROL, "public, max-age=31536000, immutable");
+    if status == StatusCode::PARTIAL_CONTENT {
+        let last = range.end.saturating_sub(1);
+        response = response.header(header::CONTENT_RANGE, format!("bytes {}-{}/{}", range.start, last, size));
+    }
+
+    // Chunks are read and verified as the body streams; a corrupt chunk
+    // aborts the response rather than sending bad bytes
+    let chunks = futures::stream::iter(blobs.range_chunks(manifest, range));
+    response
+        .body(Body::from_stream(chunks))
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
+
+/// Start fetching a blob from peers (see [`crate::swarm`]).
+async fn fetch_blob(
+    State(state): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(cid): Path<String>,
+) -> Result<(StatusCode, Json<SwarmProgress>), StatusCode> {
+    let cid: Cid = cid.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+    let mesh = {
+        let state = state.read().await;
+        require_upload(&state, &caller)?;
+        state.mesh.clone()
+    };
+    let Some(mesh) = mesh else {
+        return Err(StatusCode::SERVICE_UNAVAILABLE);
+    };
+    let progress = mesh.blob_fetch(cid).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let status = if progress.complete { StatusCode::OK } else { StatusCode::ACCEPTED };
+    Ok((status, Json(progress)))
+}
+
+async fn get_blob_progress(
+    State(state): State<AppState>,
+    Path(cid): Path<String>,
+) -> Result<Json<SwarmProgress>, StatusCode> {
+    let cid: Cid = cid.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+    let state = state.read().await;
+    let progress = match state.mesh {
+        Some(ref mesh) => mesh.blob_progress(&cid).await,
+        None => BlobStore::new(Arc::clone(&state.storage))
+            .manifest(&cid)
+            .map(|manifest| manifest.as_ref().map(SwarmProgress::stored)),
+    };
+    match progress {
+        Ok(Some(progress)) => Ok(Json(progress)),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
 // --- Category endpoints ---
 
 async fn list_categories(
@@ -152,11 +864,12 @@ async fn list_featured_releases(
 ) -> Result<Json<Vec<Release>>, StatusCode> {
     let state = state.read().await;
     // For now, featured releases are just the most recent releases
-    let releases = state
+    let query = ReleaseQuery { limit: DEFAULT_PAGE_SIZE, ..Default::default() };
+    let page = state
         .storage
-        .list_releases()
+        .query_releases(&query)
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    Ok(Json(releases))
+    Ok(Json(page.items))
 }
 
 // --- Account endpoints ---
@@ -0,0 +1,613 @@
+//! Signed catalog archives, for backups and moving a catalog between nodes.
+//!
+//! An archive holds categories, releases, content items, the admin operation
+//! log and every blob a release or content item refers to, in one stream:
+//!
+//! ```text
+//! "LENSARC1" | manifest length (u32 BE) | manifest JSON | records | chunks
+//! ```
+//!
+//! Records are JSON lines ([`ArchiveRecord`]); chunks are the distinct blob
+//! chunks, back to back, in the order the manifest lists them. The manifest
+//! commits to both with a BLAKE3 Merkle root (see [`crate::blob::merkle_root`])
+//! over the hash of the records section followed by each chunk hash, and is
+//! signed by the exporting node's key:
+//!
+//! ```text
+//! signature = ed25519(CITADEL-LENS-ARCHIVE-V1 ‖ version (u32 BE) ‖ root ‖ created_at)
+//! ```
+//!
+//! A valid signature only says who made the archive, so import also requires
+//! a signer it trusts: the key the caller names, or else an admin or this
+//! node's own key. It checks the signature, signer and root before reading
+//! on, then every chunk as it arrives. Each chunk is written as soon as it
+//! matches its hash, before the rest of the archive has been read, so an
+//! import that fails part way can leave chunks behind; they are
+//! content-addressed and unreferenced. Records are only written once the
+//! whole archive has been verified. Existing records are kept or replaced per
+//! [`ConflictPolicy`]; admin operations are merged into the log, where their
+//! signatures decide whether they take effect (see [`crate::acl`]).
+
+use crate::acl::{self, AdminOp};
+use crate::blob::{merkle_root, BlobManifest, BlobStore, Cid, CHUNK_SIZE};
+use crate::error::{Error, Result};
+use crate::models::{Category, ContentItem, Release};
+use crate::replication::now_ms;
+use crate::storage::Storage;
+use crate::timestamp;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::{Read, Write};
+use std::sync::Arc;
+
+/// First bytes of every archive.
+pub const ARCHIVE_MAGIC: &[u8; 8] = b"LENSARC1";
+
+/// Domain separation tag for archive signatures.
+pub const ARCHIVE_SIGNING_TAG: &[u8] = b"CITADEL-LENS-ARCHIVE-V1";
+
+/// Archive format version.
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// Media type for archives served over HTTP.
+pub const ARCHIVE_MEDIA_TYPE: &str = "application/vnd.citadel-lens.archive";
+
+/// Largest manifest accepted on import.
+const MAX_MANIFEST_SIZE: usize = 64 * 1024 * 1024;
+
+/// One line of the records section.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "record", rename_all = "snake_case")]
+pub enum ArchiveRecord {
+    Category(Category),
+    Release(Release),
+    ContentItem(Box<ContentItem>),
+    AdminOp(AdminOp),
+    Blob(BlobManifest),
+}
+
+/// A chunk in the chunk section.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkEntry {
+    /// Hex BLAKE3 hash of the chunk
+    pub hash: String,
+    pub size: u64,
+}
+
+/// Describes and signs an archive.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    pub version: u32,
+    /// When the archive was made (`YYYY-MM-DDTHH:MM:SSZ`)
+    pub created_at: String,
+    /// Hex public key of the exporting node
+    pub signer: String,
+    /// Number of records
+    pub records: u64,
+    /// Length of the records section in bytes
+    pub records_size: u64,
+    /// Hex BLAKE3 hash of the records section
+    pub records_hash: String,
+    /// Chunk section contents, in order
+    pub chunks: Vec<ChunkEntry>,
+    /// Hex Merkle root over `records_hash` and the chunk hashes
+    pub root: String,
+    /// Hex ed25519 signature over [`ArchiveManifest::signing_bytes`]
+    pub signature: String,
+}
+
+fn hash_bytes(hex_hash: &str) -> Result<[u8; 32]> {
+    hex::decode(hex_hash)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| Error::InvalidInput(format!("Invalid archive hash: {}", hex_hash)))
+}
+
+impl ArchiveManifest {
+    /// Merkle root over the records hash and chunk hashes.
+    pub fn compute_root(&self) -> Result<[u8; 32]> {
+        let mut leaves = vec![hash_bytes(&self.records_hash)?];
+        for chunk in &self.chunks {
+            leaves.push(hash_bytes(&chunk.hash)?);
+        }
+        Ok(merkle_root(&leaves))
+    }
+
+    /// Bytes covered by the signature: tag ‖ version (BE) ‖ root ‖ created_at.
+    pub fn signing_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(ARCHIVE_SIGNING_TAG.len() + 36 + self.created_at.len());
+        bytes.extend_from_slice(ARCHIVE_SIGNING_TAG);
+        bytes.extend_from_slice(&self.version.to_be_bytes());
+        bytes.extend_from_slice(&hash_bytes(&self.root)?);
+        bytes.extend_from_slice(self.created_at.as_bytes());
+        Ok(bytes)
+    }
+
+    /// Check the root against the contents listed and the signature against the signer.
+    pub fn verify(&self) -> Result<()> {
+        if self.version != ARCHIVE_VERSION {
+            return Err(Error::InvalidInput(format!("Unsupported archive version {}", self.version)));
+        }
+        if self.compute_root()? != hash_bytes(&self.root)? {
+            return Err(Error::InvalidInput("Archive root does not match its contents".into()));
+        }
+        let signer = VerifyingKey::from_bytes(&hash_bytes(&self.signer)?)
+            .map_err(|_| Error::InvalidInput(format!("Invalid archive signer: {}", self.signer)))?;
+        let signature: [u8; 64] = hex::decode(&self.signature)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| Error::InvalidInput("Malformed archive signature".into()))?;
+        signer
+            .verify(&self.signing_bytes()?, &Signature::from_bytes(&signature))
+            .map_err(|_| Error::InvalidInput("Bad archive signature".into()))
+    }
+}
+
+/// What to do with an imported record whose ID is already stored.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// Keep the stored record
+    #[default]
+    Skip,
+    /// Replace it with the imported one
+    Overwrite,
+    /// Keep whichever was updated (or created) last. Categories have no
+    /// timestamps, so stored ones are kept.
+    Newest,
+}
+
+impl std::str::FromStr for ConflictPolicy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "skip" => Ok(ConflictPolicy::Skip),
+            "overwrite" => Ok(ConflictPolicy::Overwrite),
+            "newest" => Ok(ConflictPolicy::Newest),
+            other => Err(Error::InvalidInput(format!(
+                "Unknown conflict policy '{}' (expected skip, overwrite or newest)",
+                other
+            ))),
+        }
+    }
+}
+
+/// What an import changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportSummary {
+    /// Hex public key that signed the archive
+    pub signer: String,
+    pub created_at: String,
+    /// Records that were not stored before
+    pub added: u64,
+    /// Stored records replaced by the archive's
+    pub replaced: u64,
+    /// Stored records kept
+    pub skipped: u64,
+    /// Admin operations new to the log
+    pub admin_ops: u64,
+    /// Blobs stored
+    pub blobs: u64,
+}
+
+/// Blobs referenced by a release thumbnail or content item resource.
+fn referenced_blobs(releases: &[Release], items: &[ContentItem]) -> Vec<Cid> {
+    let ids = releases
+        .iter()
+        .filter_map(|release| release.thumbnail_cid.as_deref())
+        .chain(items.iter().flat_map(|item| item.resources.iter().map(|r| r.id.as_str())));
+    let mut seen = HashSet::new();
+    ids.filter_map(|id| id.parse::<Cid>().ok())
+        .filter(|cid| seen.insert(*cid))
+        .collect()
+}
+
+/// Write the catalog to `out` as an archive signed with `signing_key`.
+///
+/// Records are gathered first, so the manifest can go at the front; chunks
+/// are then read and written one at a time.
+pub fn export(storage: &Arc<Storage>, signing_key: &SigningKey, out: &mut impl Write) -> Result<ArchiveManifest> {
+    let blobs = BlobStore::new(Arc::clone(storage));
+    let releases = storage.list_releases()?;
+    let items = storage.list_content_items()?;
+    let mut manifests = Vec::new();
+    for cid in referenced_blobs(&releases, &items) {
+        match blobs.manifest(&cid)? {
+            Some(manifest) => manifests.push(manifest),
+            None => tracing::warn!("Blob {} is referenced but not stored; leaving it out of the archive", cid),
+        }
+    }
+
+    let records = storage
+        .list_categories()?
+        .into_iter()
+        .map(ArchiveRecord::Category)
+        .chain(releases.into_iter().map(ArchiveRecord::Release))
+        .chain(items.into_iter().map(|item| ArchiveRecord::ContentItem(Box::new(item))))
+        .chain(storage.list_admin_ops()?.into_iter().map(ArchiveRecord::AdminOp))
+        .chain(manifests.iter().cloned().map(ArchiveRecord::Blob));
+    let mut section = Vec::new();
+    let mut count = 0;
+    for record in records {
+        serde_json::to_writer(&mut section, &record)?;
+        section.push(b'\n');
+        count += 1;
+    }
+
+    // Each distinct chunk once, as (blob, index) so it can be read back verified
+    let mut seen = HashSet::new();
+    let mut chunks = Vec::new();
+    for manifest in &manifests {
+        for (index, hash) in manifest.chunks.iter().enumerate() {
+            if seen.insert(*hash) {
+                chunks.push((manifest, index));
+            }
+        }
+    }
+
+    let mut manifest = ArchiveManifest {
+        version: ARCHIVE_VERSION,
+        created_at: timestamp::now_utc(),
+        signer: hex::encode(signing_key.verifying_key().as_bytes()),
+        records: count,
+        records_size: section.len() as u64,
+        records_hash: blake3::hash(&section).to_hex().to_string(),
+        chunks: chunks
+            .iter()
+            .map(|(blob, index)| {
+                let range = blob.chunk_range(*index);
+                ChunkEntry { hash: hex::encode(blob.chunks[*index]), size: range.end - range.start }
+            })
+            .collect(),
+        root: String::new(),
+        signature: String::new(),
+    };
+    manifest.root = hex::encode(manifest.compute_root()?);
+    manifest.signature = hex::encode(signing_key.sign(&manifest.signing_bytes()?).to_bytes());
+
+    let header = serde_json::to_vec(&manifest)?;
+    out.write_all(ARCHIVE_MAGIC)?;
+    out.write_all(&(header.len() as u32).to_be_bytes())?;
+    out.write_all(&header)?;
+    out.write_all(&section)?;
+    for (blob, index) in chunks {
+        out.write_all(&blobs.read_chunk(blob, index)?)?;
+    }
+    out.flush()?;
+    Ok(manifest)
+}
+
+/// Read exactly `len` bytes, reporting a short archive as invalid input.
+fn read_section(input: &mut impl Read, len: u64, what: &str) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    input.take(len).read_to_end(&mut data)?;
+    if data.len() as u64 != len {
+        return Err(Error::InvalidInput(format!("Archive truncated in {}", what)));
+    }
+    Ok(data)
+}
+
+/// Read the manifest and check its signature.
+pub fn read_manifest(input: &mut impl Read) -> Result<ArchiveManifest> {
+    let mut magic = [0u8; 8];
+    input
+        .read_exact(&mut magic)
+        .map_err(|_| Error::InvalidInput("Not a Lens archive".into()))?;
+    if &magic != ARCHIVE_MAGIC {
+        return Err(Error::InvalidInput("Not a Lens archive".into()));
+    }
+    let mut len = [0u8; 4];
+    input
+        .read_exact(&mut len)
+        .map_err(|_| Error::InvalidInput("Archive truncated in manifest".into()))?;
+    let len = u32::from_be_bytes(len) as usize;
+    if len > MAX_MANIFEST_SIZE {
+        return Err(Error::InvalidInput(format!("Archive manifest too large ({} bytes)", len)));
+    }
+    let manifest: ArchiveManifest = serde_json::from_slice(&read_section(input, len as u64, "manifest")?)
+        .map_err(|e| Error::InvalidInput(format!("Invalid archive manifest: {}", e)))?;
+    manifest.verify()?;
+    Ok(manifest)
+}
+
+/// Decide whether an imported record replaces a stored one.
+fn replaces(policy: ConflictPolicy, imported: Option<&str>, stored: Option<&str>) -> bool {
+    match policy {
+        ConflictPolicy::Skip => false,
+        ConflictPolicy::Overwrite => true,
+        ConflictPolicy::Newest => imported > stored,
+    }
+}
+
+fn item_time(item: &ContentItem) -> Option<&str> {
+    item.updated_at.as_deref().or(item.created_at.as_deref())
+}
+
+/// Check that an archive was signed by `signer` if given, or else by an
+/// admin or this node's own key.
+pub fn check_signer(storage: &Storage, manifest: &ArchiveManifest, signer: Option<&str>) -> Result<()> {
+    let trusted = match signer {
+        Some(signer) => signer.eq_ignore_ascii_case(&manifest.signer),
+        None => {
+            let node_key = hex::encode(storage.get_or_create_node_key()?.verifying_key().as_bytes());
+            manifest.signer == node_key || storage.is_admin(&manifest.signer)?
+        }
+    };
+    if !trusted {
+        return Err(Error::Unauthorized(format!(
+            "Archive signed by untrusted key {}",
+            manifest.signer
+        )));
+    }
+    Ok(())
+}
+
+/// Verify an archive from `input` and merge it into storage.
+///
+/// The archive must be signed by `signer`, or by an admin or this node if no
+/// signer is given (see [`check_signer`]). Blob chunks are written as each
+/// one is checked against its hash, before the rest of the archive has been
+/// verified; everything else is written only once the whole archive has
+/// been read and verified.
+pub fn import(
+    storage: &Arc<Storage>,
+    input: &mut impl Read,
+    policy: ConflictPolicy,
+    signer: Option<&str>,
+) -> Result<ImportSummary> {
+    let manifest = read_manifest(input)?;
+    check_signer(storage, &manifest, signer)?;
+
+    let section = read_section(input, manifest.records_size, "records")?;
+    if blake3::hash(&section).to_hex().as_str() != manifest.records_hash {
+        return Err(Error::InvalidInput("Archive records do not match their hash".into()));
+    }
+    let records = section
+        .split(|b| *b == b'\n')
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            serde_json::from_slice::<ArchiveRecord>(line)
+                .map_err(|e| Error::InvalidInput(format!("Invalid archive record: {}", e)))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if records.len() as u64 != manifest.records {
+        return Err(Error::InvalidInput(format!(
+            "Archive lists {} records but holds {}",
+            manifest.records,
+            records.len()
+        )));
+    }
+
+    for chunk in &manifest.chunks {
+        if chunk.size > CHUNK_SIZE as u64 {
+            return Err(Error::InvalidInput(format!("Archive chunk {} is too large", chunk.hash)));
+        }
+        let data = read_section(input, chunk.size, "chunks")?;
+        let hash = hash_bytes(&chunk.hash)?;
+        if blake3::hash(&data).as_bytes() != &hash {
+            return Err(Error::InvalidInput(format!("Archive chunk {} does not match its hash", chunk.hash)));
+        }
+        storage.put_blob_chunk(&hash, &data)?;
+    }
+
+    let blobs = BlobStore::new(Arc::clone(storage));
+    let mut summary = ImportSummary {
+        signer: manifest.signer.clone(),
+        created_at: manifest.created_at.clone(),
+        ..Default::default()
+    };
+    let mut tally = |stored: bool, replace: bool| -> bool {
+        match (stored, replace) {
+            (false, _) => summary.added += 1,
+            (true, true) => summary.replaced += 1,
+            (true, false) => summary.skipped += 1,
+        }
+        !stored || replace
+    };
+    let mut ops = Vec::new();
+    let mut blob_count = 0;
+
+    // Blobs first, so records never point at a blob that failed to import
+    for record in &records {
+        if let ArchiveRecord::Blob(blob) = record {
+            if !blobs.has(&blob.cid())? {
+                blobs.finish(blob)?;
+                blob_count += 1;
+            }
+        }
+    }
+    for record in records {
+        match record {
+            ArchiveRecord::Category(category) => {
+                let stored = storage.get_category(&category.id)?.is_some();
+                if tally(stored, policy == ConflictPolicy::Overwrite) {
+                    storage.put_category(&category)?;
+                }
+            }
+            ArchiveRecord::Release(release) => {
+                let stored = storage.get_release(&release.id)?;
+                let replace = stored
+                    .as_ref()
+                    .is_some_and(|s| replaces(policy, release.created_at.as_deref(), s.created_at.as_deref()));
+                if tally(stored.is_some(), replace) {
+                    storage.put_release(&release)?;
+                }
+            }
+            ArchiveRecord::ContentItem(item) => {
+                let stored = storage.get_content_item(&item.id)?;
+                let replace = stored.as_ref().is_some_and(|s| replaces(policy, item_time(&item), item_time(s)));
+                if tally(stored.is_some(), replace) {
+                    storage.put_content_item(&item)?;
+                }
+            }
+            ArchiveRecord::AdminOp(op) => ops.push(op),
+            ArchiveRecord::Blob(_) => {}
+        }
+    }
+
+    summary.blobs = blob_count;
+    for applied in acl::apply_all(storage, &ops, now_ms())? {
+        if applied? {
+            summary.admin_ops += 1;
+        }
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::acl::AdminAction;
+    use crate::models::{ContentType, Resource};
+    use tempfile::tempdir;
+
+    fn open(dir: &tempfile::TempDir) -> Arc<Storage> {
+        Arc::new(Storage::open(dir.path()).unwrap())
+    }
+
+    /// A catalog with a category, a release with a thumbnail, a content item
+    /// with a resource blob and one admin operation.
+    fn catalog(storage: &Arc<Storage>, key: &SigningKey) {
+        let blobs = BlobStore::new(Arc::clone(storage));
+        let thumbnail = blobs.put(&vec![7u8; CHUNK_SIZE + 10]).unwrap().cid();
+        let media = blobs.put(&vec![7u8; CHUNK_SIZE * 2]).unwrap().cid();
+
+        storage.put_category(&Category::new("zines".into(), "Zines".into())).unwrap();
+        let mut release = Release::new("r1".into(), "Release".into(), "zines".into());
+        release.thumbnail_cid = Some(thumbnail.to_hex());
+        release.created_at = Some("2024-01-01T00:00:00Z".into());
+        storage.put_release(&release).unwrap();
+        let item = ContentItem::new("c1".into(), ContentType::Book, "Item".into())
+            .with_release("r1".into())
+            .with_created_at("2024-01-02T00:00:00Z".into())
+            .with_resource(Resource::new(media.to_hex()));
+        storage.put_content_item(&item).unwrap();
+
+        let admin = hex::encode(key.verifying_key().as_bytes());
+        storage.add_root_admin(&admin).unwrap();
+        acl::rebuild(storage).unwrap();
+        let op = AdminOp::signed(AdminAction::GrantUpload, &"ab".repeat(32), now_ms(), key).unwrap();
+        acl::submit(storage, &op, now_ms()).unwrap();
+    }
+
+    fn exported(storage: &Arc<Storage>, key: &SigningKey) -> Vec<u8> {
+        let mut archive = Vec::new();
+        export(storage, key, &mut archive).unwrap();
+        archive
+    }
+
+    fn signer(key: &SigningKey) -> String {
+        hex::encode(key.verifying_key().as_bytes())
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let (src_dir, dst_dir) = (tempdir().unwrap(), tempdir().unwrap());
+        let (src, dst) = (open(&src_dir), open(&dst_dir));
+        let key = SigningKey::from_bytes(&[3; 32]);
+        catalog(&src, &key);
+
+        let archive = exported(&src, &key);
+        let manifest = read_manifest(&mut archive.as_slice()).unwrap();
+        // Thumbnail and media share their first chunk
+        assert_eq!(manifest.chunks.len(), 2);
+
+        let summary = import(&dst, &mut archive.as_slice(), ConflictPolicy::Skip, Some(&signer(&key))).unwrap();
+        assert_eq!(summary.signer, signer(&key));
+        assert_eq!((summary.blobs, summary.admin_ops), (2, 1));
+        assert_eq!(dst.get_release("r1").unwrap(), src.get_release("r1").unwrap());
+        assert_eq!(dst.get_content_item("c1").unwrap(), src.get_content_item("c1").unwrap());
+        assert!(dst.get_category("zines").unwrap().is_some());
+        assert_eq!(dst.list_admin_ops().unwrap(), src.list_admin_ops().unwrap());
+
+        let thumbnail: Cid = src.get_release("r1").unwrap().unwrap().thumbnail_cid.unwrap().parse().unwrap();
+        let blobs = BlobStore::new(Arc::clone(&dst));
+        assert_eq!(blobs.read_range(&thumbnail, 0..u64::MAX).unwrap().unwrap(), vec![7u8; CHUNK_SIZE + 10]);
+
+        // Importing again changes nothing
+        let again = import(&dst, &mut archive.as_slice(), ConflictPolicy::Skip, Some(&signer(&key))).unwrap();
+        assert_eq!((again.added, again.blobs, again.admin_ops), (0, 0, 0));
+    }
+
+    #[test]
+    fn test_tampering_detected() {
+        let (src_dir, dst_dir) = (tempdir().unwrap(), tempdir().unwrap());
+        let (src, dst) = (open(&src_dir), open(&dst_dir));
+        let key = SigningKey::from_bytes(&[3; 32]);
+        catalog(&src, &key);
+        let archive = exported(&src, &key);
+        let header_len = 12 + u32::from_be_bytes(archive[8..12].try_into().unwrap()) as usize;
+
+        for offset in [header_len + 5, archive.len() - 1] {
+            let mut tampered = archive.clone();
+            tampered[offset] ^= 1;
+            assert!(import(&dst, &mut tampered.as_slice(), ConflictPolicy::Skip, Some(&signer(&key))).is_err());
+        }
+        assert!(import(&dst, &mut &archive[..archive.len() - 1], ConflictPolicy::Skip, Some(&signer(&key))).is_err());
+
+        // An edited manifest no longer matches its signature
+        let mut manifest = read_manifest(&mut archive.as_slice()).unwrap();
+        manifest.created_at = "2000-01-01T00:00:00Z".into();
+        assert!(manifest.verify().is_err());
+
+        assert!(dst.get_release("r1").unwrap().is_none());
+        assert!(dst.list_admin_ops().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_conflict_policies() {
+        let (src_dir, dst_dir) = (tempdir().unwrap(), tempdir().unwrap());
+        let (src, dst) = (open(&src_dir), open(&dst_dir));
+        let key = SigningKey::from_bytes(&[3; 32]);
+        catalog(&src, &key);
+        let archive = exported(&src, &key);
+
+        let mut local = Release::new("r1".into(), "Local edit".into(), "zines".into());
+        local.created_at = Some("2025-01-01T00:00:00Z".into());
+        dst.put_release(&local).unwrap();
+        let title = |dst: &Storage| dst.get_release("r1").unwrap().unwrap().title;
+
+        let summary = import(&dst, &mut archive.as_slice(), ConflictPolicy::Skip, Some(&signer(&key))).unwrap();
+        assert_eq!(summary.skipped, 1);
+        assert_eq!(title(&dst), "Local edit");
+
+        import(&dst, &mut archive.as_slice(), ConflictPolicy::Newest, Some(&signer(&key))).unwrap();
+        assert_eq!(title(&dst), "Local edit");
+
+        let summary = import(&dst, &mut archive.as_slice(), ConflictPolicy::Overwrite, Some(&signer(&key))).unwrap();
+        assert_eq!(title(&dst), "Release");
+        assert!(summary.replaced >= 1);
+
+        assert_eq!("newest".parse::<ConflictPolicy>().unwrap(), ConflictPolicy::Newest);
+        assert!("merge".parse::<ConflictPolicy>().is_err());
+    }
+
+    #[test]
+    fn test_untrusted_signer_rejected() {
+        let (src_dir, dst_dir) = (tempdir().unwrap(), tempdir().unwrap());
+        let (src, dst) = (open(&src_dir), open(&dst_dir));
+        let key = SigningKey::from_bytes(&[3; 32]);
+        catalog(&src, &key);
+        let archive = exported(&src, &key);
+        let other = signer(&SigningKey::from_bytes(&[4; 32]));
+
+        // Validly signed, but by a key this node has no reason to trust
+        for trusted in [None, Some(other.as_str())] {
+            let result = import(&dst, &mut archive.as_slice(), ConflictPolicy::Skip, trusted);
+            assert!(matches!(result, Err(Error::Unauthorized(_))));
+        }
+        let manifest = read_manifest(&mut archive.as_slice()).unwrap();
+        for chunk in &manifest.chunks {
+            assert!(!dst.has_blob_chunk(&hash_bytes(&chunk.hash).unwrap()).unwrap());
+        }
+        assert!(dst.get_release("r1").unwrap().is_none());
+
+        // Once the signer is an admin here, no explicit signer is needed
+        dst.add_root_admin(&signer(&key)).unwrap();
+        acl::rebuild(&dst).unwrap();
+        import(&dst, &mut archive.as_slice(), ConflictPolicy::Skip, None).unwrap();
+        assert!(dst.get_release("r1").unwrap().is_some());
+    }
+}
@@ -0,0 +1,317 @@
+//! Signed request authentication for the HTTP API.
+//!
+//! Requests that change state (anything but GET, HEAD and OPTIONS) must be
+//! signed with the caller's ed25519 key:
+//!
+//! ```text
+//! X-Lens-Public-Key: <hex public key>
+//! X-Lens-Timestamp:  <milliseconds since the Unix epoch>
+//! X-Lens-Signature:  <hex signature over the string below>
+//!
+//! CITADEL-LENS-REQUEST-V1\n<METHOD>\n<path and query>\n<timestamp>\n<hex blake3(body)>
+//! ```
+//!
+//! The timestamp must be within [`MAX_REQUEST_SKEW_MS`] of the node's clock
+//! and each signature is accepted once, so a captured request can't be
+//! replayed. Only admins and keys holding a permission may sign; the headers,
+//! timestamp and key are checked before the body is read, so anyone else is
+//! turned away without the node buffering their upload. Beyond that the
+//! middleware only establishes who is calling: verified requests carry a
+//! [`Caller`] extension and handlers decide what that key may do (see
+//! [`crate::acl`]).
+
+use crate::error::{Error, Result};
+use crate::node::LensState;
+use crate::replication::now_ms;
+use crate::storage::Storage;
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header::CONTENT_LENGTH, HeaderMap, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::RwLock;
+
+/// Domain separation tag for request signatures.
+pub const REQUEST_SIGNING_TAG: &str = "CITADEL-LENS-REQUEST-V1";
+
+/// Header carrying the caller's hex public key.
+pub const PUBLIC_KEY_HEADER: &str = "x-lens-public-key";
+/// Header carrying the signing time in milliseconds.
+pub const TIMESTAMP_HEADER: &str = "x-lens-timestamp";
+/// Header carrying the hex signature.
+pub const SIGNATURE_HEADER: &str = "x-lens-signature";
+
+/// How far a request's timestamp may be from the node's clock.
+pub const MAX_REQUEST_SKEW_MS: u64 = 5 * 60 * 1000;
+
+/// The verified key behind a signed request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Caller {
+    /// Hex public key
+    pub public_key: String,
+}
+
+/// The string a request signature covers.
+pub fn signing_string(method: &str, path: &str, timestamp_ms: u64, body: &[u8]) -> String {
+    format!(
+        "{}\n{}\n{}\n{}\n{}",
+        REQUEST_SIGNING_TAG,
+        method.to_uppercase(),
+        path,
+        timestamp_ms,
+        blake3::hash(body).to_hex()
+    )
+}
+
+/// Sign a request, returning the hex value for [`SIGNATURE_HEADER`].
+pub fn sign_request(key: &SigningKey, method: &str, path: &str, timestamp_ms: u64, body: &[u8]) -> String {
+    let message = signing_string(method, path, timestamp_ms, body);
+    hex::encode(key.sign(message.as_bytes()).to_bytes())
+}
+
+/// Signatures accepted recently, so each is accepted only once.
+///
+/// Entries are dropped once their timestamp falls outside the skew window,
+/// after which the request would be rejected as stale anyway.
+#[derive(Debug, Default)]
+pub struct ReplayGuard {
+    seen: Mutex<HashMap<[u8; 64], u64>>,
+}
+
+impl ReplayGuard {
+    /// Create an empty guard.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a signature. Returns `false` if it was already used.
+    pub fn check(&self, signature: [u8; 64], timestamp_ms: u64, now_ms: u64) -> bool {
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        seen.retain(|_, ts| ts.saturating_add(MAX_REQUEST_SKEW_MS) >= now_ms);
+        seen.insert(signature, timestamp_ms).is_none()
+    }
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Result<&'a str> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| Error::Unauthorized(format!("Missing {} header", name)))
+}
+
+/// Signature headers that parsed and carry a fresh timestamp, not yet
+/// checked against the body.
+#[derive(Debug)]
+pub struct SignedHeaders {
+    /// Hex public key, lowercased
+    pub public_key: String,
+    key: VerifyingKey,
+    timestamp_ms: u64,
+    signature: [u8; 64],
+}
+
+/// Parse a request's signature headers and check the timestamp.
+///
+/// Everything that can be checked without the body is, so a request that
+/// fails here need not be read.
+pub fn check_headers(headers: &HeaderMap, now_ms: u64) -> Result<SignedHeaders> {
+    let public_key = header(headers, PUBLIC_KEY_HEADER)?.to_lowercase();
+    let timestamp_ms: u64 = header(headers, TIMESTAMP_HEADER)?
+        .parse()
+        .map_err(|_| Error::Unauthorized("Malformed timestamp".into()))?;
+    let signature: [u8; 64] = hex::decode(header(headers, SIGNATURE_HEADER)?)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| Error::Unauthorized("Malformed signature".into()))?;
+
+    if timestamp_ms.abs_diff(now_ms) > MAX_REQUEST_SKEW_MS {
+        return Err(Error::Unauthorized("Request timestamp outside allowed skew".into()));
+    }
+
+    let key: [u8; 32] = hex::decode(&public_key)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| Error::Unauthorized("Malformed public key".into()))?;
+    let key = VerifyingKey::from_bytes(&key)
+        .map_err(|_| Error::Unauthorized("Invalid public key".into()))?;
+    Ok(SignedHeaders { public_key, key, timestamp_ms, signature })
+}
+
+impl SignedHeaders {
+    /// Check the signature over the request and record it as used.
+    pub fn verify(self, method: &str, path: &str, body: &[u8], guard: &ReplayGuard, now_ms: u64) -> Result<Caller> {
+        let message = signing_string(method, path, self.timestamp_ms, body);
+        self.key
+            .verify_strict(message.as_bytes(), &Signature::from_bytes(&self.signature))
+            .map_err(|_| Error::Unauthorized("Bad request signature".into()))?;
+
+        if !guard.check(self.signature, self.timestamp_ms, now_ms) {
+            return Err(Error::Unauthorized("Request already used".into()));
+        }
+        Ok(Caller { public_key: self.public_key })
+    }
+}
+
+/// Check a request's signature headers and record the signature as used.
+pub fn authenticate(
+    headers: &HeaderMap,
+    method: &str,
+    path: &str,
+    body: &[u8],
+    guard: &ReplayGuard,
+    now_ms: u64,
+) -> Result<Caller> {
+    check_headers(headers, now_ms)?.verify(method, path, body, guard, now_ms)
+}
+
+/// Whether a key may sign requests: an admin, or a key holding any permission.
+pub fn is_known_key(storage: &Storage, public_key: &str) -> Result<bool> {
+    Ok(storage.is_admin(public_key)? || storage.has_any_permission(public_key)?)
+}
+
+/// State for [`require_signature`], one per body limit.
+#[derive(Clone)]
+pub struct SignatureCheck {
+    guard: Arc<ReplayGuard>,
+    node: Arc<RwLock<LensState>>,
+    body_limit: usize,
+}
+
+impl SignatureCheck {
+    /// Check signatures for a node, reading bodies of up to `body_limit` bytes.
+    pub fn new(node: Arc<RwLock<LensState>>, body_limit: usize) -> Self {
+        Self { guard: Arc::new(ReplayGuard::new()), node, body_limit }
+    }
+
+    /// The same check (sharing its replay guard) with another body limit,
+    /// for routes that accept larger uploads.
+    pub fn with_body_limit(&self, body_limit: usize) -> Self {
+        Self { body_limit, ..self.clone() }
+    }
+}
+
+/// Middleware requiring a valid signature on state-changing requests.
+///
+/// Rejects missing or malformed headers, stale timestamps and unknown keys
+/// first, then reads up to the route's body limit and checks the signature
+/// over it. It consumes the body, so it must run before any extractor that
+/// does.
+pub async fn require_signature(State(check): State<SignatureCheck>, request: Request, next: Next) -> Response {
+    if matches!(*request.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+        return next.run(request).await;
+    }
+
+    let unauthorized = |e: Error| (StatusCode::UNAUTHORIZED, e.to_string()).into_response();
+    let (mut parts, body) = request.into_parts();
+    let signed = match check_headers(&parts.headers, now_ms()) {
+        Ok(signed) => signed,
+        Err(e) => return unauthorized(e),
+    };
+    let storage = check.node.read().await.storage.clone();
+    match is_known_key(&storage, &signed.public_key) {
+        Ok(true) => {}
+        Ok(false) => return unauthorized(Error::Unauthorized("Unknown public key".into())),
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+
+    let declared = parts.headers.get(CONTENT_LENGTH).and_then(|v| v.to_str().ok()?.parse::<usize>().ok());
+    if declared.is_some_and(|len| len > check.body_limit) {
+        return StatusCode::PAYLOAD_TOO_LARGE.into_response();
+    }
+    let Ok(body) = axum::body::to_bytes(body, check.body_limit).await else {
+        return StatusCode::PAYLOAD_TOO_LARGE.into_response();
+    };
+    let path = parts.uri.path_and_query().map_or(parts.uri.path(), |pq| pq.as_str());
+
+    match signed.verify(parts.method.as_str(), path, &body, &check.guard, now_ms()) {
+        Ok(caller) => {
+            parts.extensions.insert(caller);
+            next.run(Request::from_parts(parts, Body::from(body))).await
+        }
+        Err(e) => unauthorized(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000_000;
+
+    fn signed_headers(key: &SigningKey, method: &str, path: &str, ts: u64, body: &[u8]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(PUBLIC_KEY_HEADER, hex::encode(key.verifying_key().as_bytes()).parse().unwrap());
+        headers.insert(TIMESTAMP_HEADER, ts.to_string().parse().unwrap());
+        headers.insert(SIGNATURE_HEADER, sign_request(key, method, path, ts, body).parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_signed_request() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let guard = ReplayGuard::new();
+        let headers = signed_headers(&key, "POST", "/api/v1/releases", NOW, b"{}");
+
+        let caller = authenticate(&headers, "POST", "/api/v1/releases", b"{}", &guard, NOW).unwrap();
+        assert_eq!(caller.public_key, hex::encode(key.verifying_key().as_bytes()));
+
+        // The same request can't be replayed
+        assert!(authenticate(&headers, "POST", "/api/v1/releases", b"{}", &guard, NOW).is_err());
+    }
+
+    #[test]
+    fn test_rejects_altered_or_stale_requests() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let guard = ReplayGuard::new();
+        let headers = signed_headers(&key, "DELETE", "/api/v1/releases/a", NOW, b"");
+
+        assert!(authenticate(&headers, "DELETE", "/api/v1/releases/b", b"", &guard, NOW).is_err());
+        assert!(authenticate(&headers, "POST", "/api/v1/releases/a", b"", &guard, NOW).is_err());
+        assert!(authenticate(&headers, "DELETE", "/api/v1/releases/a", b"x", &guard, NOW).is_err());
+
+        let late = NOW + MAX_REQUEST_SKEW_MS + 1;
+        assert!(authenticate(&headers, "DELETE", "/api/v1/releases/a", b"", &guard, late).is_err());
+        assert!(authenticate(&HeaderMap::new(), "DELETE", "/api/v1/releases/a", b"", &guard, NOW).is_err());
+
+        // Rejections don't burn the signature
+        assert!(authenticate(&headers, "DELETE", "/api/v1/releases/a", b"", &guard, NOW).is_ok());
+    }
+
+    #[test]
+    fn test_headers_checked_without_body() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let headers = signed_headers(&key, "PUT", "/api/v1/blobs/x", NOW, b"large upload");
+        assert!(check_headers(&headers, NOW).is_ok());
+        assert!(check_headers(&headers, NOW + MAX_REQUEST_SKEW_MS + 1).is_err());
+        assert!(check_headers(&HeaderMap::new(), NOW).is_err());
+
+        let mut malformed = headers.clone();
+        malformed.insert(PUBLIC_KEY_HEADER, "not-hex".parse().unwrap());
+        assert!(check_headers(&malformed, NOW).is_err());
+
+        // The body is only needed for the signature itself
+        let signed = check_headers(&headers, NOW).unwrap();
+        assert!(signed.verify("PUT", "/api/v1/blobs/x", b"other", &ReplayGuard::new(), NOW).is_err());
+    }
+
+    #[test]
+    fn test_only_known_keys_sign() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Storage::open(dir.path()).unwrap();
+        let (admin, uploader, stranger) = ("aa".repeat(32), "bb".repeat(32), "cc".repeat(32));
+        storage.set_admin(&admin, true).unwrap();
+        storage.grant_permission(&uploader, "upload").unwrap();
+
+        assert!(is_known_key(&storage, &admin).unwrap());
+        assert!(is_known_key(&storage, &uploader).unwrap());
+        assert!(!is_known_key(&storage, &stranger).unwrap());
+
+        storage.revoke_permission(&uploader, "upload").unwrap();
+        assert!(!is_known_key(&storage, &uploader).unwrap());
+    }
+}
@@ -0,0 +1,474 @@
+//! Content-addressed blob storage.
+//!
+//! Blobs (media files, thumbnails) are split into fixed-size chunks, each
+//! stored under its BLAKE3 hash, so identical chunks are stored once. A
+//! blob's CID is the root of a BLAKE3 Merkle tree over its chunk hashes,
+//! bound to its length:
+//!
+//! ```text
+//! leaf = BLAKE3(chunk)
+//! node = BLAKE3(0x01 || left || right)     an odd node is carried up a level
+//! cid  = BLAKE3(0x02 || size (u64 BE) || root)
+//! ```
+//!
+//! Every chunk is checked against its hash when read, so a corrupted chunk
+//! is reported instead of served.
+
+use crate::error::{Error, Result};
+use crate::storage::Storage;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Size of every chunk except the last.
+pub const CHUNK_SIZE: usize = 256 * 1024;
+
+/// Content identifier of a blob.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Cid(pub [u8; 32]);
+
+impl Cid {
+    /// Convert to hex string.
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.0)
+    }
+}
+
+impl std::fmt::Display for Cid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+impl std::str::FromStr for Cid {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let bytes = hex::decode(s).map_err(|_| Error::InvalidInput(format!("Invalid CID: {}", s)))?;
+        let bytes: [u8; 32] = bytes.try_into()
+            .map_err(|_| Error::InvalidInput(format!("Invalid CID length: {}", s)))?;
+        Ok(Self(bytes))
+    }
+}
+
+/// Root of the BLAKE3 Merkle tree over chunk hashes.
+pub fn merkle_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    if leaves.is_empty() {
+        return *blake3::hash(b"").as_bytes();
+    }
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => {
+                    let mut hasher = blake3::Hasher::new();
+                    hasher.update(&[0x01]);
+                    hasher.update(left);
+                    hasher.update(right);
+                    *hasher.finalize().as_bytes()
+                }
+                [odd] => *odd,
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+    level[0]
+}
+
+/// Chunk list of a stored blob.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlobManifest {
+    /// Total size in bytes
+    pub size: u64,
+    /// BLAKE3 hash of each chunk, in order
+    pub chunks: Vec<[u8; 32]>,
+}
+
+impl BlobManifest {
+    /// Chunk and hash `data`.
+    pub fn for_data(data: &[u8]) -> Self {
+        Self {
+            size: data.len() as u64,
+            chunks: data.chunks(CHUNK_SIZE).map(|c| *blake3::hash(c).as_bytes()).collect(),
+        }
+    }
+
+    /// The blob's content identifier.
+    pub fn cid(&self) -> Cid {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&[0x02]);
+        hasher.update(&self.size.to_be_bytes());
+        hasher.update(&merkle_root(&self.chunks));
+        Cid(*hasher.finalize().as_bytes())
+    }
+
+    /// Byte range covered by chunk `index`.
+    pub fn chunk_range(&self, index: usize) -> Range<u64> {
+        let start = (index * CHUNK_SIZE) as u64;
+        start..(start + CHUNK_SIZE as u64).min(self.size)
+    }
+
+    /// Check that there is one chunk hash per [`CHUNK_SIZE`] bytes. Manifests
+    /// from peers or storage must pass this before their chunks are used.
+    pub fn check(&self) -> Result<()> {
+        if self.chunks.len() as u64 != self.size.div_ceil(CHUNK_SIZE as u64) {
+            return Err(Error::InvalidInput(format!(
+                "Blob manifest lists {} chunks for {} bytes",
+                self.chunks.len(),
+                self.size
+            )));
+        }
+        Ok(())
+    }
+
+    /// Check that `data` has the length of chunk `index`.
+    fn check_chunk_len(&self, index: usize, data: &[u8]) -> Result<()> {
+        let range = self.chunk_range(index);
+        if data.len() as u64 != range.end - range.start {
+            return Err(Error::InvalidInput(format!(
+                "Chunk {} is {} bytes, expected {}",
+                index,
+                data.len(),
+                range.end - range.start
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Result of interpreting an HTTP `Range` header against a blob's size.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RangeRequest {
+    /// Serve the whole blob (no range, or one we choose to ignore)
+    Full,
+    /// Serve this byte range
+    Partial(Range<u64>),
+    /// The range starts past the end of the blob
+    Unsatisfiable,
+}
+
+/// Parse a single-range `Range: bytes=...` header. Multiple ranges and
+/// malformed headers are ignored, which RFC 9110 allows.
+pub fn parse_range(header: &str, size: u64) -> RangeRequest {
+    let Some(spec) = header.trim().strip_prefix("bytes=") else {
+        return RangeRequest::Full;
+    };
+    if spec.contains(',') {
+        return RangeRequest::Full;
+    }
+    let Some((start, end)) = spec.trim().split_once('-') else {
+        return RangeRequest::Full;
+    };
+    let (start, end) = (start.trim(), end.trim());
+
+    if start.is_empty() {
+        // Suffix range: the last N bytes
+        return match end.parse::<u64>() {
+            Ok(0) => RangeRequest::Unsatisfiable,
+            Ok(_) if size == 0 => RangeRequest::Unsatisfiable,
+            Ok(n) => RangeRequest::Partial(size.saturating_sub(n)..size),
+            Err(_) => RangeRequest::Full,
+        };
+    }
+
+    let Ok(start) = start.parse::<u64>() else {
+        return RangeRequest::Full;
+    };
+    let end = match end {
+        "" => size,
+        end => match end.parse::<u64>() {
+            Ok(end) if end >= start => end.saturating_add(1).min(size),
+            _ => return RangeRequest::Full,
+        },
+    };
+    if start >= size {
+        return RangeRequest::Unsatisfiable;
+    }
+    RangeRequest::Partial(start..end)
+}
+
+fn encode_manifest(manifest: &BlobManifest) -> Result<Vec<u8>> {
+    bincode::serialize(manifest)
+        .map_err(|e| Error::Storage(format!("Failed to encode blob manifest: {}", e)))
+}
+
+fn decode_manifest(cid: &Cid, data: &[u8]) -> Result<BlobManifest> {
+    let manifest: BlobManifest = bincode::deserialize(data)
+        .map_err(|e| Error::Storage(format!("Invalid blob manifest {}: {}", cid, e)))?;
+    if manifest.cid() != *cid {
+        return Err(Error::Storage(format!("Blob manifest {} does not match its CID", cid)));
+    }
+    manifest.check()?;
+    Ok(manifest)
+}
+
+/// Blob storage on top of the node's RocksDB.
+#[derive(Clone)]
+pub struct BlobStore {
+    storage: Arc<Storage>,
+}
+
+impl BlobStore {
+    /// Store blobs in the given storage.
+    pub fn new(storage: Arc<Storage>) -> Self {
+        Self { storage }
+    }
+
+    /// Store a blob, returning its manifest. Chunks shared with other blobs
+    /// are stored once; storing a blob again repairs any corrupt chunks.
+    pub fn put(&self, data: &[u8]) -> Result<BlobManifest> {
+        let manifest = BlobManifest::for_data(data);
+        for (chunk, hash) in data.chunks(CHUNK_SIZE).zip(&manifest.chunks) {
+            self.storage.put_blob_chunk(hash, chunk)?;
+        }
+        self.storage.put_blob_manifest(&manifest.cid().0, &encode_manifest(&manifest)?)?;
+        Ok(manifest)
+    }
+
+    /// Get a blob's manifest.
+    pub fn manifest(&self, cid: &Cid) -> Result<Option<BlobManifest>> {
+        self.storage.get_blob_manifest(&cid.0)?
+            .map(|data| decode_manifest(cid, &data))
+            .transpose()
+    }
+
+    /// Check if a blob is stored.
+    pub fn has(&self, cid: &Cid) -> Result<bool> {
+        Ok(self.storage.get_blob_manifest(&cid.0)?.is_some())
+    }
+
+    // --- Partial blobs (downloads in progress) ---
+
+    /// Start assembling a blob chunk by chunk (see [`crate::swarm`]).
+    pub fn begin(&self, manifest: &BlobManifest) -> Result<()> {
+        manifest.check()?;
+        self.storage.put_blob_pending(&manifest.cid().0, &encode_manifest(manifest)?)
+    }
+
+    /// Manifest of a blob still being assembled.
+    pub fn pending(&self, cid: &Cid) -> Result<Option<BlobManifest>> {
+        self.storage.get_blob_pending(&cid.0)?
+            .map(|data| decode_manifest(cid, &data))
+            .transpose()
+    }
+
+    /// Manifests of all blobs still being assembled.
+    pub fn all_pending(&self) -> Result<Vec<BlobManifest>> {
+        self.storage.list_blob_pending()?
+            .iter()
+            .map(|data| {
+                let manifest: BlobManifest = bincode::deserialize(data)
+                    .map_err(|e| Error::Storage(format!("Invalid blob manifest: {}", e)))?;
+                manifest.check()?;
+                Ok(manifest)
+            })
+            .collect()
+    }
+
+    /// Store chunk `index` of a partial blob, after checking its length and
+    /// hash against the manifest.
+    pub fn put_chunk(&self, manifest: &BlobManifest, index: usize, data: &[u8]) -> Result<()> {
+        let hash = manifest.chunks.get(index)
+            .ok_or_else(|| Error::InvalidInput(format!("Chunk {} out of range", index)))?;
+        manifest.check_chunk_len(index, data)?;
+        if blake3::hash(data).as_bytes() != hash {
+            return Err(Error::InvalidInput(format!("Chunk {} does not match its hash", index)));
+        }
+        self.storage.put_blob_chunk(hash, data)
+    }
+
+    /// Check if chunk `index` of a blob is stored (without verifying it).
+    pub fn has_chunk(&self, manifest: &BlobManifest, index: usize) -> Result<bool> {
+        match manifest.chunks.get(index) {
+            Some(hash) => self.storage.has_blob_chunk(hash),
+            None => Ok(false),
+        }
+    }
+
+    /// Mark a partial blob complete once all its chunks are stored.
+    pub fn finish(&self, manifest: &BlobManifest) -> Result<()> {
+        manifest.check()?;
+        for index in 0..manifest.chunks.len() {
+            if !self.has_chunk(manifest, index)? {
+                return Err(Error::InvalidInput(format!("Chunk {} is missing", index)));
+            }
+        }
+        let cid = manifest.cid();
+        self.storage.put_blob_manifest(&cid.0, &encode_manifest(manifest)?)?;
+        self.storage.delete_blob_pending(&cid.0)
+    }
+
+    /// Read chunk `index` of a blob, verifying it against its hash and length.
+    pub fn read_chunk(&self, manifest: &BlobManifest, index: usize) -> Result<Vec<u8>> {
+        let hash = manifest.chunks.get(index)
+            .ok_or_else(|| Error::InvalidInput(format!("Chunk {} out of range", index)))?;
+        let data = self.storage.get_blob_chunk(hash)?
+            .ok_or_else(|| Error::NotFound(format!("Chunk {}", hex::encode(hash))))?;
+        if blake3::hash(&data).as_bytes() != hash {
+            return Err(Error::Storage(format!("Chunk {} is corrupt", hex::encode(hash))));
+        }
+        manifest.check_chunk_len(index, &data)
+            .map_err(|e| Error::Storage(format!("Chunk {} doesn't fit its blob: {}", hex::encode(hash), e)))?;
+        Ok(data)
+    }
+
+    /// The bytes of `range`, as a sequence of verified pieces (one per chunk
+    /// touched). Chunks are read lazily, so large blobs can be streamed.
+    pub fn range_chunks(
+        &self,
+        manifest: BlobManifest,
+        range: Range<u64>,
+    ) -> impl Iterator<Item = Result<Vec<u8>>> + Send + 'static {
+        let store = self.clone();
+        let end = range.end.min(manifest.size);
+        let start = range.start.min(end);
+        let first = (start / CHUNK_SIZE as u64) as usize;
+        let last = end.div_ceil(CHUNK_SIZE as u64) as usize;
+
+        (first..last).map(move |index| {
+            let data = store.read_chunk(&manifest, index)?;
+            let chunk = manifest.chunk_range(index);
+            let from = (start.max(chunk.start) - chunk.start) as usize;
+            let to = (end.min(chunk.end) - chunk.start) as usize;
+            Ok(data[from..to].to_vec())
+        })
+    }
+
+    /// Read a byte range of a blob. Returns `None` if the blob is not stored.
+    pub fn read_range(&self, cid: &Cid, range: Range<u64>) -> Result<Option<Vec<u8>>> {
+        let Some(manifest) = self.manifest(cid)? else {
+            return Ok(None);
+        };
+        let pieces = self.range_chunks(manifest, range).collect::<Result<Vec<_>>>()?;
+        Ok(Some(pieces.concat()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn sample(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 31 % 251) as u8).collect()
+    }
+
+    #[test]
+    fn test_cid_depends_on_content_and_length() {
+        let data = sample(CHUNK_SIZE * 2 + 10);
+        let cid = BlobManifest::for_data(&data).cid();
+        assert_eq!(cid, BlobManifest::for_data(&data).cid());
+
+        let mut changed = data.clone();
+        changed[CHUNK_SIZE + 1] ^= 1;
+        assert_ne!(cid, BlobManifest::for_data(&changed).cid());
+
+        // A chunk-aligned prefix shares leaves but not the CID
+        assert_ne!(cid, BlobManifest::for_data(&data[..CHUNK_SIZE * 2]).cid());
+        assert_ne!(BlobManifest::for_data(&[]).cid(), BlobManifest::for_data(&[0]).cid());
+
+        let parsed: Cid = cid.to_string().parse().unwrap();
+        assert_eq!(parsed, cid);
+        assert!("not-hex".parse::<Cid>().is_err());
+    }
+
+    #[test]
+    fn test_merkle_root_shape() {
+        let leaves: Vec<[u8; 32]> = (0..3u8).map(|i| [i; 32]).collect();
+        assert_eq!(merkle_root(&leaves[..1]), leaves[0]);
+
+        // Three leaves: (0, 1) paired, 2 carried up
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&[0x01]);
+        hasher.update(&leaves[0]);
+        hasher.update(&leaves[1]);
+        let pair = *hasher.finalize().as_bytes();
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&[0x01]);
+        hasher.update(&pair);
+        hasher.update(&leaves[2]);
+        assert_eq!(merkle_root(&leaves), *hasher.finalize().as_bytes());
+    }
+
+    #[test]
+    fn test_store_and_read_ranges() {
+        let dir = tempdir().unwrap();
+        let blobs = BlobStore::new(Arc::new(Storage::open(dir.path()).unwrap()));
+        let data = sample(CHUNK_SIZE * 3 + 123);
+
+        let cid = blobs.put(&data).unwrap().cid();
+        assert!(blobs.has(&cid).unwrap());
+        assert_eq!(blobs.manifest(&cid).unwrap().unwrap().chunks.len(), 4);
+
+        let size = data.len() as u64;
+        for range in [0..size, 0..1, 5..CHUNK_SIZE as u64 + 7, size - 50..size, 10..10] {
+            let read = blobs.read_range(&cid, range.clone()).unwrap().unwrap();
+            assert_eq!(read, &data[range.start as usize..range.end as usize], "range {:?}", range);
+        }
+
+        let missing = BlobManifest::for_data(b"missing").cid();
+        assert!(blobs.read_range(&missing, 0..1).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_corrupt_chunk_detected() {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(Storage::open(dir.path()).unwrap());
+        let blobs = BlobStore::new(Arc::clone(&storage));
+        let data = sample(CHUNK_SIZE + 1);
+        let manifest = blobs.put(&data).unwrap();
+
+        storage.put_blob_chunk(&manifest.chunks[1], b"tampered").unwrap();
+        // The untouched chunk still reads, the corrupt one is refused
+        assert!(blobs.read_range(&manifest.cid(), 0..10).is_ok());
+        assert!(blobs.read_range(&manifest.cid(), 0..data.len() as u64).is_err());
+    }
+
+    #[test]
+    fn test_malformed_manifest_rejected() {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(Storage::open(dir.path()).unwrap());
+        let blobs = BlobStore::new(Arc::clone(&storage));
+
+        let mut extra = BlobManifest::for_data(&sample(10));
+        extra.chunks.push([0; 32]);
+        assert!(blobs.begin(&extra).is_err());
+        assert!(blobs.finish(&extra).is_err());
+
+        // The right number of chunks, but the first hash is of a short chunk
+        let short = b"short".to_vec();
+        let manifest = BlobManifest {
+            size: CHUNK_SIZE as u64 + 1,
+            chunks: vec![*blake3::hash(&short).as_bytes(), *blake3::hash(&[0]).as_bytes()],
+        };
+        blobs.begin(&manifest).unwrap();
+        assert!(blobs.put_chunk(&manifest, 0, &short).is_err());
+        blobs.put_chunk(&manifest, 1, &[0]).unwrap();
+
+        // Stored behind the store's back, the short chunk is an error rather
+        // than a panic when a range is read
+        storage.put_blob_chunk(&manifest.chunks[0], &short).unwrap();
+        storage.put_blob_manifest(&manifest.cid().0, &encode_manifest(&manifest).unwrap()).unwrap();
+        assert!(blobs.read_range(&manifest.cid(), 0..10).is_err());
+
+        storage.put_blob_manifest(&extra.cid().0, &encode_manifest(&extra).unwrap()).unwrap();
+        assert!(blobs.manifest(&extra.cid()).is_err());
+    }
+
+    #[test]
+    fn test_parse_range() {
+        use RangeRequest::*;
+        assert_eq!(parse_range("bytes=0-499", 1000), Partial(0..500));
+        assert_eq!(parse_range("bytes=500-", 1000), Partial(500..1000));
+        assert_eq!(parse_range("bytes=-100", 1000), Partial(900..1000));
+        assert_eq!(parse_range("bytes=-5000", 1000), Partial(0..1000));
+        assert_eq!(parse_range("bytes=900-5000", 1000), Partial(900..1000));
+        assert_eq!(parse_range("bytes=1000-", 1000), Unsatisfiable);
+        assert_eq!(parse_range("bytes=-0", 1000), Unsatisfiable);
+        assert_eq!(parse_range("bytes=-10", 0), Unsatisfiable);
+        assert_eq!(parse_range("bytes=0-1,5-6", 1000), Full);
+        assert_eq!(parse_range("bytes=5-1", 1000), Full);
+        assert_eq!(parse_range("items=0-1", 1000), Full);
+    }
+}
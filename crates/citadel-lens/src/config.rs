@@ -0,0 +1,664 @@
+//! Layered configuration for lens-node.
+//!
+//! Settings come from three layers, each overriding the one before:
+//!
+//! 1. A TOML file (`--config <path>` or `LENS_CONFIG`)
+//! 2. Environment variables (`LENS_API_BIND`, `CITADEL_PEERS`, ...)
+//! 3. Command-line flags (`--api-bind`, `--peer`, ...)
+//!
+//! Anything left unset takes the built-in default. Bad values are reported
+//! as [`Error::Config`] naming the file, variable or flag they came from,
+//! and `lens-node --print-config` shows the merged result as TOML, which can
+//! be used as a config file as is.
+//!
+//! ```toml
+//! data_dir = "/data"
+//! api_bind = "0.0.0.0:8080"
+//! p2p_bind = "0.0.0.0:9000"
+//! bootstrap_peers = ["lens-bootstrap-0:9000", "lens-bootstrap-1"]
+//!
+//! [cvdf]
+//! tick_ms = 100
+//!
+//! [flood]
+//! min_rate = 100
+//! max_rate = 100000
+//!
+//! [log]
+//! format = "json"
+//! filter = "citadel_lens=info"
+//!
+//! [oai]
+//! repository_name = "Example Library Lens"
+//! admin_email = "catalog@example.org"
+//! base_url = "https://lens.example.org/oai"
+//! ```
+
+use crate::cvdf::CVDF_ITERATIONS;
+use crate::error::{Error, Result};
+use crate::vdf_race::VDF_ITERATIONS;
+use citadel_protocols::FloodRateConfig;
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Port assumed for bootstrap peers given without one.
+pub const DEFAULT_P2P_PORT: u16 = 9000;
+
+/// Log filter used when none is configured.
+pub const DEFAULT_LOG_FILTER: &str = "lens_node=info,citadel=info";
+
+/// Log output format.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable lines
+    #[default]
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            other => Err(Error::Config(format!("unknown log format '{}' (expected text or json)", other))),
+        }
+    }
+}
+
+/// Configuration for a Lens node.
+#[derive(Debug, Clone)]
+pub struct LensConfig {
+    /// Data directory for storage
+    pub data_dir: PathBuf,
+
+    /// HTTP API listen address
+    pub api_addr: SocketAddr,
+
+    /// P2P listen address (for mesh)
+    pub p2p_addr: SocketAddr,
+
+    /// Bootstrap peers
+    pub bootstrap_peers: Vec<String>,
+
+    /// Admin socket path (for lens-admin CLI)
+    pub admin_socket: PathBuf,
+
+    /// Initial admin public key (hex-encoded ed25519 public key)
+    pub admin_public_key: Option<String>,
+
+    /// Number of nodes holding each DHT key
+    pub dht_replication_factor: usize,
+
+    /// How long DHT tombstones are kept before garbage collection
+    pub dht_tombstone_horizon: Duration,
+
+    /// VDF race iterations per link (must match the rest of the mesh)
+    pub vdf_iterations: u32,
+
+    /// CVDF iterations per round (must match the rest of the mesh)
+    pub cvdf_iterations: u32,
+
+    /// Interval between CVDF coordination steps
+    pub cvdf_tick: Duration,
+
+    /// TGP flood rate limits
+    pub flood_rate: FloodRateConfig,
+
+    /// Log output format
+    pub log_format: LogFormat,
+
+    /// Log filter (tracing `EnvFilter` syntax)
+    pub log_filter: String,
+
+    /// Repository name reported by OAI-PMH Identify
+    pub oai_repository_name: String,
+
+    /// Contact address reported by OAI-PMH Identify
+    pub oai_admin_email: String,
+
+    /// Public OAI-PMH base URL; taken from the request's Host header if unset
+    pub oai_base_url: Option<String>,
+}
+
+impl Default for LensConfig {
+    fn default() -> Self {
+        let data_dir = PathBuf::from("./lens-data");
+        let flood_rate = FloodRateConfig::fast();
+        Self {
+            admin_socket: data_dir.join("admin.sock"),
+            data_dir,
+            api_addr: SocketAddr::from(([0, 0, 0, 0], 8080)),
+            p2p_addr: SocketAddr::from(([0, 0, 0, 0], DEFAULT_P2P_PORT)),
+            bootstrap_peers: Vec::new(),
+            admin_public_key: None,
+            dht_replication_factor: citadel_dht::DEFAULT_REPLICATION_FACTOR,
+            dht_tombstone_horizon: citadel_dht::DEFAULT_TOMBSTONE_HORIZON,
+            vdf_iterations: VDF_ITERATIONS,
+            cvdf_iterations: CVDF_ITERATIONS,
+            cvdf_tick: Duration::from_millis(100),
+            flood_rate,
+            log_format: LogFormat::Text,
+            log_filter: DEFAULT_LOG_FILTER.to_string(),
+            oai_repository_name: "Citadel Lens".to_string(),
+            oai_admin_email: "admin@localhost".to_string(),
+            oai_base_url: None,
+        }
+    }
+}
+
+impl LensConfig {
+    /// Defaults overridden by environment variables.
+    pub fn from_env() -> Result<Self> {
+        let mut layer = ConfigLayer::default();
+        layer.merge(ConfigLayer::from_env(|name| std::env::var(name).ok())?);
+        Self::from_layer(layer)
+    }
+
+    /// Merge the config file, environment and command line.
+    pub fn load(args: &CliArgs) -> Result<Self> {
+        let path = args
+            .config
+            .clone()
+            .or_else(|| std::env::var_os("LENS_CONFIG").map(PathBuf::from));
+        let mut layer = match path {
+            Some(path) => ConfigLayer::from_file(&path)?,
+            None => ConfigLayer::default(),
+        };
+        layer.merge(ConfigLayer::from_env(|name| std::env::var(name).ok())?);
+        layer.merge(args.layer());
+        Self::from_layer(layer)
+    }
+
+    /// Fill unset values with defaults and validate the result.
+    pub fn from_layer(layer: ConfigLayer) -> Result<Self> {
+        let defaults = Self::default();
+        let data_dir = layer.data_dir.unwrap_or(defaults.data_dir);
+        let flood_rate = FloodRateConfig {
+            min_rate: layer.flood.min_rate.unwrap_or(defaults.flood_rate.min_rate),
+            max_rate: layer.flood.max_rate.unwrap_or(defaults.flood_rate.max_rate),
+        };
+
+        let config = Self {
+            admin_socket: layer.admin_socket.unwrap_or_else(|| data_dir.join("admin.sock")),
+            data_dir,
+            api_addr: layer.api_bind.unwrap_or(defaults.api_addr),
+            p2p_addr: layer.p2p_bind.unwrap_or(defaults.p2p_addr),
+            bootstrap_peers: layer
+                .bootstrap_peers
+                .unwrap_or_default()
+                .iter()
+                .map(|peer| peer.trim())
+                .filter(|peer| !peer.is_empty())
+                .map(with_default_port)
+                .collect(),
+            admin_public_key: layer.admin_public_key.filter(|key| !key.is_empty()),
+            dht_replication_factor: layer.dht_replication_factor.unwrap_or(defaults.dht_replication_factor),
+            dht_tombstone_horizon: layer
+                .dht_tombstone_horizon_secs
+                .map_or(defaults.dht_tombstone_horizon, Duration::from_secs),
+            vdf_iterations: layer.vdf.iterations.unwrap_or(defaults.vdf_iterations),
+            cvdf_iterations: layer.cvdf.iterations.unwrap_or(defaults.cvdf_iterations),
+            cvdf_tick: layer.cvdf.tick_ms.map_or(defaults.cvdf_tick, Duration::from_millis),
+            flood_rate,
+            log_format: layer.log.format.unwrap_or(defaults.log_format),
+            log_filter: layer.log.filter.unwrap_or(defaults.log_filter),
+            oai_repository_name: layer.oai.repository_name.unwrap_or(defaults.oai_repository_name),
+            oai_admin_email: layer.oai.admin_email.unwrap_or(defaults.oai_admin_email),
+            oai_base_url: layer.oai.base_url.filter(|url| !url.is_empty()),
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Check values that parse but make no sense.
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |msg: String| Err(Error::Config(msg));
+
+        if let Some(ref key) = self.admin_public_key {
+            let valid = hex::decode(key)
+                .ok()
+                .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+                .is_some_and(|bytes| ed25519_dalek::VerifyingKey::from_bytes(&bytes).is_ok());
+            if !valid {
+                return invalid(format!("admin_public_key: '{}' is not a hex ed25519 public key", key));
+            }
+        }
+        if self.dht_replication_factor == 0 {
+            return invalid("dht_replication_factor must be at least 1".into());
+        }
+        if self.dht_tombstone_horizon.is_zero() {
+            return invalid("dht_tombstone_horizon_secs must be at least 1".into());
+        }
+        if self.vdf_iterations == 0 || self.cvdf_iterations == 0 {
+            return invalid("vdf.iterations and cvdf.iterations must be at least 1".into());
+        }
+        if self.cvdf_tick.is_zero() {
+            return invalid("cvdf.tick_ms must be at least 1".into());
+        }
+        if self.flood_rate.min_rate == 0 || self.flood_rate.min_rate > self.flood_rate.max_rate {
+            return invalid(format!(
+                "flood.min_rate ({}) must be at least 1 and at most flood.max_rate ({})",
+                self.flood_rate.min_rate, self.flood_rate.max_rate
+            ));
+        }
+        if let Err(e) = tracing_subscriber::EnvFilter::try_new(&self.log_filter) {
+            return invalid(format!("log.filter: {}", e));
+        }
+        if !self.oai_admin_email.contains('@') {
+            return invalid(format!("oai.admin_email: '{}' is not an email address", self.oai_admin_email));
+        }
+        Ok(())
+    }
+
+    /// The config as a complete layer (every value set).
+    pub fn to_layer(&self) -> ConfigLayer {
+        ConfigLayer {
+            data_dir: Some(self.data_dir.clone()),
+            api_bind: Some(self.api_addr),
+            p2p_bind: Some(self.p2p_addr),
+            bootstrap_peers: Some(self.bootstrap_peers.clone()),
+            admin_socket: Some(self.admin_socket.clone()),
+            admin_public_key: self.admin_public_key.clone(),
+            dht_replication_factor: Some(self.dht_replication_factor),
+            dht_tombstone_horizon_secs: Some(self.dht_tombstone_horizon.as_secs()),
+            vdf: VdfLayer { iterations: Some(self.vdf_iterations) },
+            cvdf: CvdfLayer {
+                iterations: Some(self.cvdf_iterations),
+                tick_ms: Some(self.cvdf_tick.as_millis() as u64),
+            },
+            flood: FloodLayer {
+                min_rate: Some(self.flood_rate.min_rate),
+                max_rate: Some(self.flood_rate.max_rate),
+            },
+            log: LogLayer {
+                format: Some(self.log_format),
+                filter: Some(self.log_filter.clone()),
+            },
+            oai: OaiLayer {
+                repository_name: Some(self.oai_repository_name.clone()),
+                admin_email: Some(self.oai_admin_email.clone()),
+                base_url: self.oai_base_url.clone(),
+            },
+        }
+    }
+
+    /// The config as TOML, in config file form.
+    pub fn to_toml(&self) -> Result<String> {
+        toml::to_string(&self.to_layer()).map_err(|e| Error::Config(e.to_string()))
+    }
+}
+
+/// Append the default P2P port to peers given without one.
+fn with_default_port(peer: &str) -> String {
+    if peer.contains(':') {
+        peer.to_string()
+    } else {
+        format!("{}:{}", peer, DEFAULT_P2P_PORT)
+    }
+}
+
+/// One layer of settings; `None` leaves the value to lower layers.
+///
+/// Deserializes from the config file format.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigLayer {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data_dir: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_bind: Option<SocketAddr>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub p2p_bind: Option<SocketAddr>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bootstrap_peers: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub admin_socket: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub admin_public_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dht_replication_factor: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dht_tombstone_horizon_secs: Option<u64>,
+    #[serde(default)]
+    pub vdf: VdfLayer,
+    #[serde(default)]
+    pub cvdf: CvdfLayer,
+    #[serde(default)]
+    pub flood: FloodLayer,
+    #[serde(default)]
+    pub log: LogLayer,
+    #[serde(default)]
+    pub oai: OaiLayer,
+}
+
+/// `[vdf]` settings.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VdfLayer {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iterations: Option<u32>,
+}
+
+/// `[cvdf]` settings.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CvdfLayer {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iterations: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tick_ms: Option<u64>,
+}
+
+/// `[flood]` settings (packets per second).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FloodLayer {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_rate: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_rate: Option<u64>,
+}
+
+/// `[log]` settings.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LogLayer {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<LogFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
+}
+
+/// `[oai]` settings for the OAI-PMH endpoint.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OaiLayer {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repository_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub admin_email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+}
+
+/// Parse an environment variable, naming it in the error.
+fn env_value<T: FromStr>(get: &impl Fn(&str) -> Option<String>, name: &str) -> Result<Option<T>>
+where
+    T::Err: std::fmt::Display,
+{
+    match get(name) {
+        Some(value) => value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|e| Error::Config(format!("{}: invalid value '{}': {}", name, value, e))),
+        None => Ok(None),
+    }
+}
+
+impl ConfigLayer {
+    /// Read a TOML config file.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| Error::Config(format!("{}: {}", path.display(), e)))?;
+        Self::from_toml(&text).map_err(|e| Error::Config(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Parse TOML config file contents.
+    pub fn from_toml(text: &str) -> Result<Self> {
+        toml::from_str(text).map_err(|e| Error::Config(e.to_string()))
+    }
+
+    /// Read settings from environment variables, looked up with `get`.
+    pub fn from_env(get: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let peers = get("CITADEL_PEERS").map(|peers| peers.split(',').map(str::to_string).collect());
+        let format = match get("LENS_LOG_FORMAT") {
+            Some(format) => Some(format.parse().map_err(|e| Error::Config(format!("LENS_LOG_FORMAT: {}", e)))?),
+            None => None,
+        };
+
+        Ok(Self {
+            data_dir: get("LENS_DATA_DIR").map(PathBuf::from),
+            api_bind: env_value(&get, "LENS_API_BIND")?,
+            p2p_bind: env_value(&get, "LENS_P2P_BIND")?,
+            bootstrap_peers: peers,
+            admin_socket: get("LENS_ADMIN_SOCKET").map(PathBuf::from),
+            admin_public_key: get("ADMIN_PUBLIC_KEY"),
+            dht_replication_factor: env_value(&get, "LENS_DHT_REPLICATION")?,
+            dht_tombstone_horizon_secs: env_value(&get, "LENS_DHT_TOMBSTONE_HORIZON_SECS")?,
+            vdf: VdfLayer { iterations: env_value(&get, "LENS_VDF_ITERATIONS")? },
+            cvdf: CvdfLayer {
+                iterations: env_value(&get, "LENS_CVDF_ITERATIONS")?,
+                tick_ms: env_value(&get, "LENS_CVDF_TICK_MS")?,
+            },
+            flood: FloodLayer {
+                min_rate: env_value(&get, "LENS_FLOOD_MIN_RATE")?,
+                max_rate: env_value(&get, "LENS_FLOOD_MAX_RATE")?,
+            },
+            log: LogLayer { format, filter: get("RUST_LOG") },
+            oai: OaiLayer {
+                repository_name: get("LENS_OAI_REPOSITORY_NAME"),
+                admin_email: get("LENS_OAI_ADMIN_EMAIL"),
+                base_url: get("LENS_OAI_BASE_URL"),
+            },
+        })
+    }
+
+    /// Override this layer's values with those set in `other`.
+    pub fn merge(&mut self, other: ConfigLayer) {
+        fn set<T>(value: &mut Option<T>, other: Option<T>) {
+            if other.is_some() {
+                *value = other;
+            }
+        }
+        set(&mut self.data_dir, other.data_dir);
+        set(&mut self.api_bind, other.api_bind);
+        set(&mut self.p2p_bind, other.p2p_bind);
+        set(&mut self.bootstrap_peers, other.bootstrap_peers);
+        set(&mut self.admin_socket, other.admin_socket);
+        set(&mut self.admin_public_key, other.admin_public_key);
+        set(&mut self.dht_replication_factor, other.dht_replication_factor);
+        set(&mut self.dht_tombstone_horizon_secs, other.dht_tombstone_horizon_secs);
+        set(&mut self.vdf.iterations, other.vdf.iterations);
+        set(&mut self.cvdf.iterations, other.cvdf.iterations);
+        set(&mut self.cvdf.tick_ms, other.cvdf.tick_ms);
+        set(&mut self.flood.min_rate, other.flood.min_rate);
+        set(&mut self.flood.max_rate, other.flood.max_rate);
+        set(&mut self.log.format, other.log.format);
+        set(&mut self.log.filter, other.log.filter);
+        set(&mut self.oai.repository_name, other.oai.repository_name);
+        set(&mut self.oai.admin_email, other.oai.admin_email);
+        set(&mut self.oai.base_url, other.oai.base_url);
+    }
+}
+
+/// lens-node command-line arguments.
+#[derive(Debug, Default, Parser)]
+#[command(name = "lens-node", version, about = "Citadel Lens node")]
+pub struct CliArgs {
+    /// TOML config file [env: LENS_CONFIG]
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Print the effective configuration and exit
+    #[arg(long)]
+    pub print_config: bool,
+
+    /// Report the storage migrations that would run and exit
+    #[arg(long)]
+    pub migrate_dry_run: bool,
+
+    /// Data directory [env: LENS_DATA_DIR]
+    #[arg(long, value_name = "PATH")]
+    pub data_dir: Option<PathBuf>,
+
+    /// HTTP API bind address [env: LENS_API_BIND]
+    #[arg(long, value_name = "ADDR")]
+    pub api_bind: Option<SocketAddr>,
+
+    /// P2P mesh bind address [env: LENS_P2P_BIND]
+    #[arg(long, value_name = "ADDR")]
+    pub p2p_bind: Option<SocketAddr>,
+
+    /// Bootstrap peer, repeatable; replaces configured peers [env: CITADEL_PEERS]
+    #[arg(long = "peer", value_name = "HOST[:PORT]")]
+    pub peers: Vec<String>,
+
+    /// Admin socket path [env: LENS_ADMIN_SOCKET]
+    #[arg(long, value_name = "PATH")]
+    pub admin_socket: Option<PathBuf>,
+
+    /// Root admin public key, hex [env: ADMIN_PUBLIC_KEY]
+    #[arg(long, value_name = "HEX")]
+    pub admin_public_key: Option<String>,
+
+    /// Nodes holding each DHT key [env: LENS_DHT_REPLICATION]
+    #[arg(long, value_name = "N")]
+    pub dht_replication: Option<usize>,
+
+    /// Seconds to keep DHT tombstones [env: LENS_DHT_TOMBSTONE_HORIZON_SECS]
+    #[arg(long, value_name = "SECS")]
+    pub dht_tombstone_horizon_secs: Option<u64>,
+
+    /// VDF race iterations per link [env: LENS_VDF_ITERATIONS]
+    #[arg(long, value_name = "N")]
+    pub vdf_iterations: Option<u32>,
+
+    /// CVDF iterations per round [env: LENS_CVDF_ITERATIONS]
+    #[arg(long, value_name = "N")]
+    pub cvdf_iterations: Option<u32>,
+
+    /// CVDF coordination interval in milliseconds [env: LENS_CVDF_TICK_MS]
+    #[arg(long, value_name = "MS")]
+    pub cvdf_tick_ms: Option<u64>,
+
+    /// Minimum TGP flood rate, packets/s [env: LENS_FLOOD_MIN_RATE]
+    #[arg(long, value_name = "N")]
+    pub flood_min_rate: Option<u64>,
+
+    /// Maximum TGP flood rate, packets/s [env: LENS_FLOOD_MAX_RATE]
+    #[arg(long, value_name = "N")]
+    pub flood_max_rate: Option<u64>,
+
+    /// Log format [env: LENS_LOG_FORMAT]
+    #[arg(long, value_name = "FORMAT")]
+    pub log_format: Option<LogFormat>,
+
+    /// Log filter, e.g. "citadel_lens=debug" [env: RUST_LOG]
+    #[arg(long, value_name = "FILTER")]
+    pub log_filter: Option<String>,
+}
+
+impl CliArgs {
+    /// The settings given as flags.
+    pub fn layer(&self) -> ConfigLayer {
+        ConfigLayer {
+            data_dir: self.data_dir.clone(),
+            api_bind: self.api_bind,
+            p2p_bind: self.p2p_bind,
+            bootstrap_peers: (!self.peers.is_empty()).then(|| self.peers.clone()),
+            admin_socket: self.admin_socket.clone(),
+            admin_public_key: self.admin_public_key.clone(),
+            dht_replication_factor: self.dht_replication,
+            dht_tombstone_horizon_secs: self.dht_tombstone_horizon_secs,
+            vdf: VdfLayer { iterations: self.vdf_iterations },
+            cvdf: CvdfLayer {
+                iterations: self.cvdf_iterations,
+                tick_ms: self.cvdf_tick_ms,
+            },
+            flood: FloodLayer {
+                min_rate: self.flood_min_rate,
+                max_rate: self.flood_max_rate,
+            },
+            log: LogLayer {
+                format: self.log_format,
+                filter: self.log_filter.clone(),
+            },
+            oai: OaiLayer::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn test_layers_override_in_order() {
+        let mut layer = ConfigLayer::from_toml(
+            r#"
+            data_dir = "/data"
+            api_bind = "127.0.0.1:8000"
+            bootstrap_peers = ["a", "b:9100"]
+
+            [flood]
+            min_rate = 5
+            "#,
+        )
+        .unwrap();
+        layer.merge(ConfigLayer::from_env(env(&[("LENS_API_BIND", "127.0.0.1:8001"), ("LENS_FLOOD_MAX_RATE", "50")])).unwrap());
+        layer.merge(CliArgs::parse_from(["lens-node", "--api-bind", "127.0.0.1:8002"]).layer());
+
+        let config = LensConfig::from_layer(layer).unwrap();
+        assert_eq!(config.api_addr, "127.0.0.1:8002".parse().unwrap());
+        assert_eq!(config.data_dir, PathBuf::from("/data"));
+        assert_eq!(config.admin_socket, PathBuf::from("/data/admin.sock"));
+        assert_eq!(config.bootstrap_peers, vec!["a:9000", "b:9100"]);
+        assert_eq!((config.flood_rate.min_rate, config.flood_rate.max_rate), (5, 50));
+        assert_eq!(config.p2p_addr, LensConfig::default().p2p_addr);
+    }
+
+    #[test]
+    fn test_errors_are_readable() {
+        let err = ConfigLayer::from_env(env(&[("LENS_API_BIND", "nonsense")])).unwrap_err();
+        assert!(err.to_string().contains("LENS_API_BIND"), "{}", err);
+
+        let err = ConfigLayer::from_toml("api_bnd = \"0.0.0.0:1\"").unwrap_err();
+        assert!(err.to_string().contains("api_bnd"), "{}", err);
+
+        let layer = ConfigLayer::from_toml("[flood]\nmin_rate = 10\nmax_rate = 1").unwrap();
+        assert!(LensConfig::from_layer(layer).is_err());
+
+        let layer = ConfigLayer { admin_public_key: Some("abc".into()), ..Default::default() };
+        assert!(LensConfig::from_layer(layer).is_err());
+
+        let layer = ConfigLayer { dht_tombstone_horizon_secs: Some(0), ..Default::default() };
+        assert!(LensConfig::from_layer(layer).is_err());
+    }
+
+    #[test]
+    fn test_printed_config_round_trips() {
+        let layer = ConfigLayer::from_env(env(&[
+            ("CITADEL_PEERS", "x, y:1"),
+            ("LENS_LOG_FORMAT", "json"),
+            ("LENS_CVDF_TICK_MS", "250"),
+            ("LENS_DHT_TOMBSTONE_HORIZON_SECS", "3600"),
+            ("LENS_OAI_BASE_URL", "https://lens.example.org/oai"),
+        ]))
+        .unwrap();
+        let config = LensConfig::from_layer(layer).unwrap();
+
+        let printed = config.to_toml().unwrap();
+        let reparsed = LensConfig::from_layer(ConfigLayer::from_toml(&printed).unwrap()).unwrap();
+        assert_eq!(reparsed.to_layer(), config.to_layer());
+        assert_eq!(reparsed.log_format, LogFormat::Json);
+        assert_eq!(reparsed.cvdf_tick, Duration::from_millis(250));
+        assert_eq!(reparsed.dht_tombstone_horizon, Duration::from_secs(3600));
+        assert_eq!(reparsed.oai_base_url.as_deref(), Some("https://lens.example.org/oai"));
+    }
+}
@@ -19,7 +19,7 @@
 //! - Heavier chain adoption: natural gravitational pull toward largest swarm
 //! - Nash equilibrium: honest participation IS optimal strategy
 
-use crate::cvdf::{CvdfChain, CvdfCoordinator, CvdfRound, RoundAttestation};
+use crate::cvdf::{CvdfChain, CvdfCoordinator, CvdfRound, RoundAttestation, CVDF_ITERATIONS};
 use ed25519_dalek::SigningKey;
 use rand::rngs::OsRng;
 use rand::seq::SliceRandom;
@@ -78,7 +78,7 @@ impl TestNode {
     /// Create genesis node
     pub fn genesis(id: usize, genesis_seed: [u8; 32], addr: SocketAddr) -> Self {
         let signing_key = SigningKey::generate(&mut OsRng);
-        let mut coordinator = CvdfCoordinator::new_genesis(genesis_seed, signing_key.clone());
+        let mut coordinator = CvdfCoordinator::new_genesis(genesis_seed, signing_key.clone(), CVDF_ITERATIONS);
         coordinator.set_slot(id as u64);
         coordinator.register_slot(id as u64, signing_key.verifying_key().to_bytes());
 
@@ -100,7 +100,7 @@ impl TestNode {
         bootstrap_slots: Vec<(u64, [u8; 32])>,
     ) -> Option<Self> {
         let signing_key = SigningKey::generate(&mut OsRng);
-        let mut coordinator = CvdfCoordinator::join(genesis_seed, bootstrap_rounds, signing_key.clone())?;
+        let mut coordinator = CvdfCoordinator::join(genesis_seed, bootstrap_rounds, signing_key.clone(), CVDF_ITERATIONS)?;
 
         // Register known slots
         for (slot, pubkey) in bootstrap_slots {
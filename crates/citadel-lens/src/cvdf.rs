@@ -60,7 +60,8 @@ use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, HashMap, HashSet};
 
-/// VDF iterations per round - tuned for ~50ms on modern hardware
+/// Default VDF iterations per round - tuned for ~50ms on modern hardware.
+/// Every node in a swarm must use the same value.
 pub const CVDF_ITERATIONS: u32 = 100_000;
 
 /// Minimum attestations for a valid round (prevents solo mining)
@@ -160,7 +161,7 @@ pub struct CvdfRound {
 
 impl CvdfRound {
     /// Create genesis round
-    pub fn genesis(seed: &[u8], signing_key: &SigningKey) -> Self {
+    pub fn genesis(seed: &[u8], signing_key: &SigningKey, iterations: u32) -> Self {
         let producer = signing_key.verifying_key().to_bytes();
 
         // Genesis has no previous output
@@ -170,7 +171,7 @@ impl CvdfRound {
         let washed_input = *blake3::hash(seed).as_bytes();
 
         // Compute VDF
-        let output = compute_cvdf(&washed_input, CVDF_ITERATIONS);
+        let output = compute_cvdf(&washed_input, iterations);
 
         // Sign the round
         let mut msg = Vec::with_capacity(96);
@@ -197,6 +198,7 @@ impl CvdfRound {
         prev_output: [u8; 32],
         attestations: Vec<RoundAttestation>,
         signing_key: &SigningKey,
+        iterations: u32,
     ) -> Option<Self> {
         // Need minimum attestations
         if attestations.len() < MIN_ATTESTATIONS {
@@ -222,7 +224,7 @@ impl CvdfRound {
         let washed_input = wash_attestations(&prev_output, &attestations);
 
         // Compute VDF
-        let output = compute_cvdf(&washed_input, CVDF_ITERATIONS);
+        let output = compute_cvdf(&washed_input, iterations);
 
         let producer = signing_key.verifying_key().to_bytes();
 
@@ -246,7 +248,7 @@ impl CvdfRound {
     }
 
     /// Verify this round is valid
-    pub fn verify(&self, expected_prev: &[u8; 32]) -> bool {
+    pub fn verify(&self, expected_prev: &[u8; 32], iterations: u32) -> bool {
         // Check previous output
         if self.round > 0 && &self.prev_output != expected_prev {
             return false;
@@ -279,7 +281,7 @@ impl CvdfRound {
         }
 
         // Verify VDF output
-        let expected_output = compute_cvdf(&self.washed_input, CVDF_ITERATIONS);
+        let expected_output = compute_cvdf(&self.washed_input, iterations);
         if self.output != expected_output {
             return false;
         }
@@ -370,19 +372,22 @@ pub struct CvdfChain {
     signing_key: SigningKey,
     /// Our public key
     our_pubkey: [u8; 32],
+    /// VDF iterations per round
+    iterations: u32,
 }
 
 impl CvdfChain {
     /// Create new chain as genesis
-    pub fn new_genesis(genesis_seed: [u8; 32], signing_key: SigningKey) -> Self {
+    pub fn new_genesis(genesis_seed: [u8; 32], signing_key: SigningKey, iterations: u32) -> Self {
         let our_pubkey = signing_key.verifying_key().to_bytes();
-        let genesis = CvdfRound::genesis(&genesis_seed, &signing_key);
+        let genesis = CvdfRound::genesis(&genesis_seed, &signing_key, iterations);
 
         Self {
             genesis_seed,
             rounds: vec![genesis],
             signing_key,
             our_pubkey,
+            iterations,
         }
     }
 
@@ -391,6 +396,7 @@ impl CvdfChain {
         genesis_seed: [u8; 32],
         rounds: Vec<CvdfRound>,
         signing_key: SigningKey,
+        iterations: u32,
     ) -> Option<Self> {
         let our_pubkey = signing_key.verifying_key().to_bytes();
 
@@ -399,6 +405,7 @@ impl CvdfChain {
             rounds,
             signing_key,
             our_pubkey,
+            iterations,
         };
 
         if chain.verify_full() {
@@ -446,6 +453,7 @@ impl CvdfChain {
             prev_output,
             attestations,
             &self.signing_key,
+            self.iterations,
         )?;
 
         self.rounds.push(round);
@@ -461,7 +469,7 @@ impl CvdfChain {
 
         // Verify round
         let prev_output = self.tip_output();
-        if !round.verify(&prev_output) {
+        if !round.verify(&prev_output, self.iterations) {
             return false;
         }
 
@@ -482,7 +490,7 @@ impl CvdfChain {
         }
 
         // Verify genesis VDF
-        let expected_genesis_output = compute_cvdf(&genesis.washed_input, CVDF_ITERATIONS);
+        let expected_genesis_output = compute_cvdf(&genesis.washed_input, self.iterations);
         if genesis.output != expected_genesis_output {
             return false;
         }
@@ -496,7 +504,7 @@ impl CvdfChain {
                 return false;
             }
 
-            if !curr.verify(&prev.output) {
+            if !curr.verify(&prev.output, self.iterations) {
                 return false;
             }
         }
@@ -515,6 +523,7 @@ impl CvdfChain {
             self.genesis_seed,
             other_rounds.to_vec(),
             self.signing_key.clone(),
+            self.iterations,
         ) {
             Some(c) => c,
             None => return false,
@@ -579,8 +588,8 @@ pub struct CvdfCoordinator {
 
 impl CvdfCoordinator {
     /// Create new coordinator as genesis
-    pub fn new_genesis(genesis_seed: [u8; 32], signing_key: SigningKey) -> Self {
-        let chain = CvdfChain::new_genesis(genesis_seed, signing_key);
+    pub fn new_genesis(genesis_seed: [u8; 32], signing_key: SigningKey, iterations: u32) -> Self {
+        let chain = CvdfChain::new_genesis(genesis_seed, signing_key, iterations);
 
         Self {
             chain,
@@ -595,8 +604,9 @@ impl CvdfCoordinator {
         genesis_seed: [u8; 32],
         rounds: Vec<CvdfRound>,
         signing_key: SigningKey,
+        iterations: u32,
     ) -> Option<Self> {
-        let chain = CvdfChain::from_rounds(genesis_seed, rounds, signing_key)?;
+        let chain = CvdfChain::from_rounds(genesis_seed, rounds, signing_key, iterations)?;
 
         Some(Self {
             chain,
@@ -746,7 +756,7 @@ mod tests {
         let genesis_seed = [42u8; 32];
         let signing_key = SigningKey::generate(&mut OsRng);
 
-        let chain = CvdfChain::new_genesis(genesis_seed, signing_key);
+        let chain = CvdfChain::new_genesis(genesis_seed, signing_key, CVDF_ITERATIONS);
 
         assert_eq!(chain.height(), 0);
         assert!(chain.verify_full());
@@ -760,7 +770,7 @@ mod tests {
         let genesis_seed = [42u8; 32];
         let signing_key = SigningKey::generate(&mut OsRng);
 
-        let chain = CvdfChain::new_genesis(genesis_seed, signing_key.clone());
+        let chain = CvdfChain::new_genesis(genesis_seed, signing_key.clone(), CVDF_ITERATIONS);
 
         // Create attestation for next round
         let attestation = chain.create_attestation(Some(0));
@@ -777,7 +787,7 @@ mod tests {
         let genesis_seed = [42u8; 32];
         let signing_key = SigningKey::generate(&mut OsRng);
 
-        let mut chain = CvdfChain::new_genesis(genesis_seed, signing_key.clone());
+        let mut chain = CvdfChain::new_genesis(genesis_seed, signing_key.clone(), CVDF_ITERATIONS);
 
         // Create attestations from multiple "nodes"
         let key2 = SigningKey::generate(&mut OsRng);
@@ -808,7 +818,7 @@ mod tests {
 
         // Chain A: Few attesters per round
         let key_a = SigningKey::generate(&mut OsRng);
-        let mut chain_a = CvdfChain::new_genesis(genesis_seed, key_a.clone());
+        let mut chain_a = CvdfChain::new_genesis(genesis_seed, key_a.clone(), CVDF_ITERATIONS);
 
         for r in 1..=10 {
             let att = chain_a.create_attestation(Some(0));
@@ -820,7 +830,7 @@ mod tests {
         let keys_b: Vec<SigningKey> = (0..5)
             .map(|_| SigningKey::generate(&mut OsRng))
             .collect();
-        let mut chain_b = CvdfChain::new_genesis(genesis_seed, key_b.clone());
+        let mut chain_b = CvdfChain::new_genesis(genesis_seed, key_b.clone(), CVDF_ITERATIONS);
 
         for r in 1..=10 {
             let attestations: Vec<RoundAttestation> = keys_b.iter()
@@ -861,7 +871,7 @@ mod tests {
 
         // Genesis coordinator
         let mut coordinators: Vec<CvdfCoordinator> = vec![
-            CvdfCoordinator::new_genesis(genesis_seed, keys[0].clone())
+            CvdfCoordinator::new_genesis(genesis_seed, keys[0].clone(), CVDF_ITERATIONS)
         ];
         coordinators[0].set_slot(0);
 
@@ -873,7 +883,7 @@ mod tests {
         // Other nodes join
         for (i, key) in keys.iter().enumerate().skip(1) {
             let rounds = coordinators[0].chain().all_rounds().to_vec();
-            let mut coord = CvdfCoordinator::join(genesis_seed, rounds, key.clone())
+            let mut coord = CvdfCoordinator::join(genesis_seed, rounds, key.clone(), CVDF_ITERATIONS)
                 .expect("Should join");
             coord.set_slot(i as u64);
 
@@ -953,7 +963,7 @@ mod tests {
             .map(|_| SigningKey::generate(&mut OsRng))
             .collect();
 
-        let mut chain_a = CvdfChain::new_genesis(genesis_seed, keys_a[0].clone());
+        let mut chain_a = CvdfChain::new_genesis(genesis_seed, keys_a[0].clone(), CVDF_ITERATIONS);
 
         // A produces 15 rounds with all 3 attesters
         for r in 1..=15 {
@@ -966,7 +976,7 @@ mod tests {
 
         // Swarm B: 1 node, solo mining
         let key_b = SigningKey::generate(&mut OsRng);
-        let mut chain_b = CvdfChain::new_genesis(genesis_seed, key_b.clone());
+        let mut chain_b = CvdfChain::new_genesis(genesis_seed, key_b.clone(), CVDF_ITERATIONS);
 
         // B produces 20 rounds but only 1 attester each
         for r in 1..=20 {
@@ -0,0 +1,249 @@
+//! DHT requests routed over the mesh.
+//!
+//! Each key maps to a SPIRAL slot ([`citadel_dht::key_to_slot`]). A request is
+//! forwarded greedily: every hop hands it to the connected peer whose slot is
+//! closest to the key's slot, until no peer is closer. That node answers, and
+//! the response retraces the request's path hop by hop. PUTs are also pushed
+//! to the rest of the key's replica set ([`citadel_dht::MeshView::replicas`]),
+//! and entries are handed off to new replicas when slots change hands.
+//!
+//! Replica sets are computed over every claimed slot, not just the ones we
+//! are connected to. Entries for a replica we have a connection to go to it
+//! directly; the rest are forwarded greedily toward the replica's slot the
+//! same way requests are.
+//!
+//! ```text
+//! A ──DhtRequest──▶ B ──DhtRequest──▶ C (responsible)
+//! A ◀─DhtResponse── B ◀─DhtResponse── C ──DhtReplicate──▶ connected replicas
+//!                                      C ──DhtForward──▶ … ──▶ other replicas
+//! ```
+//!
+//! Releases and content items are published in the shared namespace
+//! ([`DhtKey::shared`]), so any node finds a record with one lookup by its ID
+//! and any authorized node can delete it. Who may write there is checked by
+//! every node storing the key (see [`shared_policy`]).
+
+use crate::error::Result;
+use crate::storage::Storage;
+use citadel_dht::{key_to_coord, DhtEntry, DhtKey, SharedPolicy};
+use citadel_topology::HexCoord;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+
+/// Requests are answered where they are after this many hops, so routing
+/// loops between nodes with different views of the mesh terminate.
+pub const MAX_DHT_HOPS: u8 = 32;
+
+/// How long an origin waits for a response.
+pub const DHT_REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often a node checks whether slots changed hands and hands off keys.
+pub const DHT_HANDOFF_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How often a node drops tombstones older than its tombstone horizon.
+pub const DHT_GC_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Entries per `DhtReplicate` message during handoff.
+pub const DHT_HANDOFF_BATCH: usize = 256;
+
+/// Operation carried by a DHT request.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum DhtOp {
+    /// Look up the entry for a key
+    Get { key: DhtKey },
+    /// Store an entry at the key's responsible slot
+    Put { entry: DhtEntry },
+}
+
+impl DhtOp {
+    /// The key this operation routes toward.
+    pub fn key(&self) -> &DhtKey {
+        match self {
+            DhtOp::Get { key } => key,
+            DhtOp::Put { entry } => &entry.key,
+        }
+    }
+}
+
+/// Answer from the node responsible for a key.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum DhtReply {
+    /// GET result (`None` if the key is absent or deleted)
+    Value(Option<DhtEntry>),
+    /// PUT result (false if a newer entry was already stored)
+    Stored(bool),
+    /// PUT refused (bad signature, not the owner, future timestamp)
+    Rejected(String),
+    /// The responsible node failed to read or write its store
+    Failed(String),
+}
+
+/// Number of slots keys are spread over: one past the highest claimed slot.
+pub fn mesh_size<'a>(claimed: impl IntoIterator<Item = &'a u64>) -> u64 {
+    claimed.into_iter().max().map(|max| max + 1).unwrap_or(0)
+}
+
+/// Pick the connected peer to forward a request toward `target` to, or `None`
+/// if no peer is closer to it than we are.
+///
+/// Without a slot of our own we can't measure progress, so we hand the
+/// request to whichever peer is closest.
+pub fn next_hop_toward(
+    self_coord: Option<HexCoord>,
+    target: HexCoord,
+    peers: &[(String, HexCoord)],
+) -> Option<&str> {
+    let limit = self_coord.map_or(u64::MAX, |from| from.distance(&target));
+    peers
+        .iter()
+        .filter(|(_, coord)| coord.distance(&target) < limit)
+        .min_by_key(|(_, coord)| coord.distance(&target))
+        .map(|(id, _)| id.as_str())
+}
+
+/// Pick the connected peer to forward a request for `key` to, or `None` if
+/// no peer is closer to the key's slot than we are (see [`next_hop_toward`]).
+pub fn next_hop<'a>(
+    self_coord: Option<HexCoord>,
+    key: &DhtKey,
+    mesh_size: u64,
+    peers: &'a [(String, HexCoord)],
+) -> Option<&'a str> {
+    next_hop_toward(self_coord, key_to_coord(key, mesh_size), peers)
+}
+
+/// Policy for shared entries, checked against the rights derived from
+/// [`crate::acl`].
+///
+/// As for replicated records (see [`crate::replication`]), the writing
+/// node's key must be an admin or granted upload. A live value can't move a
+/// record to another uploader, and a tombstone carries the key that deleted
+/// the record, which must be the record's uploader or an admin.
+pub fn shared_policy(storage: Arc<Storage>) -> SharedPolicy {
+    SharedPolicy::new(move |entry, existing| allows_shared(&storage, entry, existing).unwrap_or(false))
+}
+
+fn allows_shared(storage: &Storage, entry: &DhtEntry, existing: Option<&DhtEntry>) -> Result<bool> {
+    let Some(author) = entry.author().map(hex::encode) else {
+        return Ok(false);
+    };
+    if !storage.is_admin(&author)? && !storage.has_permission(&author, "upload")? {
+        return Ok(false);
+    }
+    let Some(owner) = existing.filter(|e| !e.is_tombstone()).and_then(uploader) else {
+        return Ok(true);
+    };
+    if entry.is_tombstone() {
+        let deleter = entry.value.as_str().unwrap_or_default();
+        return Ok(deleter == owner || storage.is_admin(deleter)?);
+    }
+    Ok(uploader(entry).as_deref() == Some(owner.as_str()))
+}
+
+/// Uploader named in a record's JSON value.
+fn uploader(entry: &DhtEntry) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_slice(entry.value.as_bytes()).ok()?;
+    value.get("uploader")?.as_str().map(String::from)
+}
+
+/// DHT requests in flight through this node.
+#[derive(Default)]
+pub struct DhtRequests {
+    /// Requests we originated, waiting for a response
+    waiting: HashMap<u64, oneshot::Sender<DhtReply>>,
+    /// Requests we forwarded: where to send the response back to
+    forwarded: HashMap<u64, (String, Instant)>,
+}
+
+impl DhtRequests {
+    /// Register a request we originated.
+    pub fn wait(&mut self, id: u64) -> oneshot::Receiver<DhtReply> {
+        let (tx, rx) = oneshot::channel();
+        self.waiting.insert(id, tx);
+        rx
+    }
+
+    /// Give up on a request we originated.
+    pub fn cancel(&mut self, id: u64) {
+        self.waiting.remove(&id);
+    }
+
+    /// Remember which peer a forwarded request came from.
+    pub fn forwarded(&mut self, id: u64, from: String) {
+        let now = Instant::now();
+        self.forwarded.retain(|_, (_, at)| now.duration_since(*at) < DHT_REQUEST_TIMEOUT);
+        self.forwarded.insert(id, (from, now));
+    }
+
+    /// Deliver a response. Returns the peer to pass it on to if we only
+    /// forwarded the request.
+    pub fn respond(&mut self, id: u64, reply: DhtReply) -> Option<(String, DhtReply)> {
+        if let Some(tx) = self.waiting.remove(&id) {
+            let _ = tx.send(reply);
+            return None;
+        }
+        self.forwarded.remove(&id).map(|(peer, _)| (peer, reply))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use citadel_dht::hash_key;
+
+    #[test]
+    fn test_next_hop_makes_progress() {
+        let key = hash_key(b"release:abc");
+        let target = key_to_coord(&key, 100);
+        let peers: Vec<(String, HexCoord)> = HexCoord::ORIGIN
+            .planar_neighbors()
+            .iter()
+            .enumerate()
+            .map(|(i, coord)| (format!("peer-{}", i), *coord))
+            .collect();
+
+        match next_hop(Some(HexCoord::ORIGIN), &key, 100, &peers) {
+            Some(id) => {
+                let (_, coord) = peers.iter().find(|(p, _)| p == id).unwrap();
+                assert!(coord.distance(&target) < HexCoord::ORIGIN.distance(&target));
+            }
+            None => assert_eq!(target, HexCoord::ORIGIN),
+        }
+
+        // At the target there is nowhere to go
+        assert_eq!(next_hop(Some(target), &key, 100, &peers), None);
+        assert_eq!(next_hop(None, &key, 100, &[]), None);
+    }
+
+    #[test]
+    fn test_next_hop_toward_slot() {
+        let target = HexCoord::new(3, 0, 0);
+        let peers = vec![
+            ("near".to_string(), HexCoord::new(2, 0, 0)),
+            ("far".to_string(), HexCoord::new(-1, 0, 0)),
+        ];
+        assert_eq!(next_hop_toward(Some(HexCoord::ORIGIN), target, &peers), Some("near"));
+        assert_eq!(next_hop_toward(None, target, &peers), Some("near"));
+        // No peer is closer than a node next to the target
+        assert_eq!(next_hop_toward(Some(HexCoord::new(2, 1, 0)), target, &peers[1..]), None);
+        assert_eq!(next_hop_toward(Some(target), target, &peers), None);
+    }
+
+    #[test]
+    fn test_responses_retrace_path() {
+        let mut requests = DhtRequests::default();
+        let mut rx = requests.wait(1);
+        requests.forwarded(2, "b3b3/prev".into());
+
+        assert!(requests.respond(1, DhtReply::Stored(true)).is_none());
+        assert!(matches!(rx.try_recv(), Ok(DhtReply::Stored(true))));
+
+        let (peer, _) = requests.respond(2, DhtReply::Value(None)).unwrap();
+        assert_eq!(peer, "b3b3/prev");
+        // Each response is delivered once
+        assert!(requests.respond(2, DhtReply::Value(None)).is_none());
+    }
+}
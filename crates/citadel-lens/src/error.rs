@@ -28,6 +28,14 @@ pub enum Error {
     #[error("Invalid input: {0}")]
     InvalidInput(String),
 
+    /// Invalid configuration
+    #[error("Configuration error: {0}")]
+    Config(String),
+
+    /// Missing or invalid request signature
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
     /// Protocol error
     #[error("Protocol error: {0}")]
     Protocol(String),
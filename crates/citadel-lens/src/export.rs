@@ -0,0 +1,632 @@
+//! Metadata export in library formats.
+//!
+//! Renders a [`ContentItem`] (or a [`Release`], via [`release_item`]) as
+//! schema.org JSON-LD, DataCite XML (kernel 4) or OAI Dublin Core XML, so
+//! catalogues and OAI harvesters can take records from a node directly.
+//!
+//! An explicit entry in the item's [`MetadataContainer`] is used as-is; for
+//! standards the item has no entry for, metadata is derived from the item's
+//! own fields ([`dublin_core`], [`datacite`]).
+//!
+//! [`MetadataContainer`]: crate::models::MetadataContainer
+
+use crate::models::{
+    ContentItem, ContentType, Creator, DataCiteCreator, DataCiteResourceType, DataCiteTitle,
+    NameIdentifier, Release, StandardMetadata,
+};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+/// DataCite "unavailable" value for required properties we can't fill.
+const UNAVAILABLE: &str = "(:unav)";
+
+/// A representation of a record the API can return.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// The Lens JSON model
+    Json,
+    /// schema.org JSON-LD
+    JsonLd,
+    /// DataCite XML (kernel 4)
+    DataCite,
+    /// OAI Dublin Core XML
+    OaiDc,
+}
+
+impl ExportFormat {
+    const ALL: [Self; 4] = [Self::Json, Self::JsonLd, Self::DataCite, Self::OaiDc];
+
+    /// Content type of the rendered body.
+    pub fn media_type(self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::JsonLd => "application/ld+json",
+            Self::DataCite => "application/vnd.datacite.datacite+xml",
+            Self::OaiDc => "application/oai_dc+xml",
+        }
+    }
+
+    /// Parse a `?format=` value: `json`, `jsonld`, `datacite` or `oai_dc`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "jsonld" | "json-ld" | "schema.org" => Some(Self::JsonLd),
+            "datacite" => Some(Self::DataCite),
+            "oai_dc" | "dc" => Some(Self::OaiDc),
+            _ => None,
+        }
+    }
+
+    fn from_media_type(media_type: &str) -> Option<Self> {
+        match media_type {
+            "*/*" | "application/*" => Some(Self::Json),
+            // Plain XML gets the most widely harvested format
+            "application/xml" | "text/xml" => Some(Self::OaiDc),
+            "application/x-datacite+xml" => Some(Self::DataCite),
+            other => Self::ALL.into_iter().find(|f| f.media_type() == other),
+        }
+    }
+
+    /// Pick a format from an `Accept` header, honouring q-values. No header
+    /// means JSON; `None` means nothing acceptable is available.
+    pub fn negotiate(accept: Option<&str>) -> Option<Self> {
+        let Some(accept) = accept.filter(|a| !a.trim().is_empty()) else {
+            return Some(Self::Json);
+        };
+        let mut best: Option<(f32, Self)> = None;
+        for range in accept.split(',') {
+            let mut parts = range.split(';').map(str::trim);
+            let media_type = parts.next().unwrap_or_default().to_ascii_lowercase();
+            let q = parts
+                .filter_map(|p| p.strip_prefix("q="))
+                .find_map(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if q <= 0.0 {
+                continue;
+            }
+            if let Some(format) = Self::from_media_type(&media_type) {
+                if best.is_none_or(|(best_q, _)| q > best_q) {
+                    best = Some((q, format));
+                }
+            }
+        }
+        best.map(|(_, format)| format)
+    }
+}
+
+/// Vocabulary terms for a content type: schema.org type, DataCite
+/// `resourceTypeGeneral` and DCMI type.
+fn vocabulary(content_type: &ContentType) -> (&'static str, &'static str, &'static str) {
+    use ContentType::*;
+    match content_type {
+        Movie => ("Movie", "Audiovisual", "MovingImage"),
+        TvSeries => ("TVSeries", "Audiovisual", "MovingImage"),
+        TvEpisode => ("TVEpisode", "Audiovisual", "MovingImage"),
+        Video | VideoClip | Lecture | Animation => ("VideoObject", "Audiovisual", "MovingImage"),
+        MusicAlbum => ("MusicAlbum", "Sound", "Sound"),
+        MusicTrack => ("MusicRecording", "Sound", "Sound"),
+        Playlist => ("MusicPlaylist", "Collection", "Collection"),
+        Podcast => ("PodcastSeries", "Sound", "Sound"),
+        PodcastEpisode => ("PodcastEpisode", "Sound", "Sound"),
+        Audiobook => ("Audiobook", "Sound", "Sound"),
+        AudioProduction => ("AudioObject", "Sound", "Sound"),
+        Book | Ebook => ("Book", "Book", "Text"),
+        Magazine => ("Periodical", "Journal", "Text"),
+        Comic => ("ComicStory", "Text", "Text"),
+        ScientificPaper => ("ScholarlyArticle", "JournalArticle", "Text"),
+        Thesis => ("Thesis", "Dissertation", "Text"),
+        Report => ("Report", "Report", "Text"),
+        Article => ("Article", "Text", "Text"),
+        Course | Workshop => ("Course", "InteractiveResource", "InteractiveResource"),
+        Lesson | Tutorial => ("LearningResource", "Text", "Text"),
+        Dataset | Observation => ("Dataset", "Dataset", "Dataset"),
+        Experiment => ("Dataset", "Dataset", "Event"),
+        Sample | Specimen | MuseumArtifact => ("Thing", "PhysicalObject", "PhysicalObject"),
+        Model | CadModel | ThreeDModel | Blueprint => ("3DModel", "Model", "Image"),
+        Software | Library | Framework => ("SoftwareSourceCode", "Software", "Software"),
+        Application | Game => ("SoftwareApplication", "Software", "Software"),
+        AiModel | MachineLearningModel => ("SoftwareSourceCode", "Model", "Software"),
+        ContainerImage | VirtualMachine => ("SoftwareApplication", "Software", "Software"),
+        Photo => ("Photograph", "Image", "StillImage"),
+        PhotoAlbum => ("ImageGallery", "Collection", "Collection"),
+        Artwork | Drawing => ("VisualArtwork", "Image", "StillImage"),
+        HistoricalDocument | Manuscript | ArchivalRecord => ("ArchiveComponent", "Text", "Text"),
+        Collection | Archive | Backup | Snapshot => ("Collection", "Collection", "Collection"),
+        Website => ("WebSite", "InteractiveResource", "InteractiveResource"),
+        WebPage => ("WebPage", "Text", "Text"),
+        Document | Presentation => ("DigitalDocument", "Text", "Text"),
+        Spreadsheet | Database => ("Dataset", "Dataset", "Dataset"),
+        Custom(_) => ("CreativeWork", "Other", "Text"),
+    }
+}
+
+/// Content type for a release category.
+fn category_content_type(category_id: &str) -> ContentType {
+    match category_id {
+        "music" => ContentType::MusicAlbum,
+        "movies" => ContentType::Movie,
+        "tv" => ContentType::TvSeries,
+        "games" => ContentType::Game,
+        "books" => ContentType::Book,
+        "software" => ContentType::Software,
+        other => ContentType::Custom(other.to_string()),
+    }
+}
+
+/// View a release as a content item for export.
+pub fn release_item(release: &Release) -> ContentItem {
+    let mut item = ContentItem::new(
+        release.id.clone(),
+        category_content_type(&release.category_id),
+        release.title.clone(),
+    )
+    .with_tags(release.tags.clone());
+    item.description = release.description.clone();
+    item.creators = release
+        .creator
+        .iter()
+        .map(|name| Creator::new(name.clone(), Default::default()))
+        .collect();
+    item.created_at = release.created_at.clone();
+    item.published_at = release.year.map(|year| year.to_string());
+    item
+}
+
+fn type_specific_str<'a>(item: &'a ContentItem, field: &str) -> Option<&'a str> {
+    item.type_specific.get(field).and_then(Value::as_str)
+}
+
+/// Year of publication (or creation) from the item's dates.
+fn year(item: &ContentItem) -> Option<u32> {
+    [&item.published_at, &item.created_at]
+        .into_iter()
+        .flatten()
+        .find_map(|date| date.get(..4).and_then(|y| y.parse().ok()))
+}
+
+fn doi(item: &ContentItem) -> Option<&str> {
+    type_specific_str(item, "doi")
+}
+
+/// Stable identifier: the DOI if there is one, else a Lens URN.
+pub fn identifier(item: &ContentItem) -> String {
+    match doi(item) {
+        Some(doi) => format!("https://doi.org/{}", doi),
+        None => format!("urn:lens:{}", item.id),
+    }
+}
+
+fn rights(item: &ContentItem) -> Option<String> {
+    item.license.as_ref().map(|l| l.url.clone().unwrap_or_else(|| l.name.clone()))
+}
+
+/// Dublin Core for an item: its explicit entry, or one derived from its fields.
+pub fn dublin_core(item: &ContentItem) -> StandardMetadata {
+    if let Some(dc) = item.metadata.get_dublin_core() {
+        return dc.clone();
+    }
+    let names = |creators: &[Creator]| -> Option<Vec<String>> {
+        (!creators.is_empty()).then(|| creators.iter().map(|c| c.name.clone()).collect())
+    };
+    StandardMetadata::DublinCore {
+        title: Some(item.title.clone()),
+        creator: names(&item.creators),
+        subject: (!item.tags.is_empty()).then(|| item.tags.clone()),
+        description: item.description.clone(),
+        publisher: type_specific_str(item, "publisher").map(str::to_string),
+        contributor: None,
+        date: item.published_at.clone().or_else(|| item.created_at.clone()),
+        dc_type: Some(vocabulary(&item.content_type).2.to_string()),
+        format: item.primary_content().and_then(|r| r.mime_type.clone()),
+        identifier: Some(identifier(item)),
+        source: None,
+        language: item.language.clone(),
+        relation: item.release_id.as_ref().map(|id| format!("urn:lens:{}", id)),
+        coverage: None,
+        rights: rights(item),
+    }
+}
+
+/// Whether an identifier looks like an ORCID iD.
+fn is_orcid(id: &str) -> bool {
+    let id = id.trim_start_matches("https://orcid.org/");
+    id.len() == 19
+        && id.char_indices().all(|(i, c)| match i {
+            4 | 9 | 14 => c == '-',
+            18 => c.is_ascii_digit() || c == 'X',
+            _ => c.is_ascii_digit(),
+        })
+}
+
+/// DataCite metadata for an item: its explicit entry, or one derived from its fields.
+pub fn datacite(item: &ContentItem) -> StandardMetadata {
+    if let Some(dc) = item.metadata.get_datacite() {
+        return dc.clone();
+    }
+    let mut creators: Vec<DataCiteCreator> = item
+        .creators
+        .iter()
+        .map(|creator| {
+            let mut dc = DataCiteCreator::organizational(&creator.name);
+            dc.name_type = None;
+            if let Some(ref id) = creator.identifier {
+                dc.name_identifiers = Some(vec![NameIdentifier {
+                    name_identifier: id.clone(),
+                    name_identifier_scheme: if is_orcid(id) { "ORCID" } else { "Other" }.to_string(),
+                    scheme_uri: is_orcid(id).then(|| "https://orcid.org".to_string()),
+                }]);
+            }
+            dc
+        })
+        .collect();
+    if creators.is_empty() {
+        creators.push(DataCiteCreator::organizational(UNAVAILABLE));
+        creators[0].name_type = None;
+    }
+    let current_year = || crate::timestamp::now_utc()[..4].parse().unwrap_or(1970);
+    StandardMetadata::DataCite {
+        doi: doi(item).map(str::to_string),
+        creators,
+        titles: vec![DataCiteTitle::main(&item.title)],
+        publisher: type_specific_str(item, "publisher").unwrap_or(UNAVAILABLE).to_string(),
+        publication_year: year(item).unwrap_or_else(current_year),
+        resource_type: DataCiteResourceType {
+            resource_type_general: vocabulary(&item.content_type).1.to_string(),
+            resource_type: Some(item.content_type.name()),
+        },
+        subjects: (!item.tags.is_empty()).then(|| item.tags.clone()),
+        additional: HashMap::new(),
+    }
+}
+
+/// schema.org JSON-LD for an item.
+///
+/// An explicit schema.org entry supplies `@context`, `@type` and its
+/// properties; anything it leaves out is filled from the item's fields.
+pub fn json_ld(item: &ContentItem) -> Value {
+    let (schema_type, _, _) = vocabulary(&item.content_type);
+    let mut doc = Map::new();
+    doc.insert("@context".into(), json!("https://schema.org"));
+    doc.insert("@type".into(), json!(schema_type));
+    if let Some(StandardMetadata::SchemaOrg { context, schema_type, properties }) = item.metadata.get_schema_org() {
+        doc.insert("@context".into(), json!(context));
+        doc.insert("@type".into(), json!(schema_type));
+        doc.extend(properties.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+
+    let mut derived = vec![
+        ("@id", json!(identifier(item))),
+        ("identifier", json!(identifier(item))),
+        ("name", json!(item.title)),
+    ];
+    let optional = [
+        ("description", item.description.as_ref().map(|d| json!(d))),
+        ("inLanguage", item.language.as_ref().map(|l| json!(l))),
+        ("keywords", (!item.tags.is_empty()).then(|| json!(item.tags.join(", ")))),
+        ("license", rights(item).map(|r| json!(r))),
+        ("datePublished", item.published_at.as_ref().map(|d| json!(d))),
+        ("dateCreated", item.created_at.as_ref().map(|d| json!(d))),
+        ("dateModified", item.updated_at.as_ref().map(|d| json!(d))),
+        ("encodingFormat", item.primary_content().and_then(|r| r.mime_type.as_ref()).map(|m| json!(m))),
+        (
+            "creator",
+            (!item.creators.is_empty()).then(|| {
+                Value::Array(
+                    item.creators
+                        .iter()
+                        .map(|c| {
+                            let mut person = json!({ "@type": "Person", "name": c.name });
+                            if let Some(ref id) = c.identifier {
+                                person["identifier"] = json!(id);
+                            }
+                            person
+                        })
+                        .collect(),
+                )
+            }),
+        ),
+    ];
+    derived.extend(optional.into_iter().filter_map(|(k, v)| v.map(|v| (k, v))));
+    for (key, value) in derived {
+        doc.entry(key).or_insert(value);
+    }
+    Value::Object(doc)
+}
+
+pub(crate) fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            // Not allowed in XML 1.0
+            c if (c as u32) < 0x20 && !matches!(c, '\t' | '\n' | '\r') => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Minimal XML writer; attribute values and text are escaped.
+#[derive(Default)]
+pub(crate) struct Xml(pub(crate) String);
+
+impl Xml {
+    pub(crate) fn open(&mut self, name: &str, attrs: &[(&str, &str)]) {
+        self.0.push('<');
+        self.0.push_str(name);
+        for (key, value) in attrs {
+            self.0.push_str(&format!(" {}=\"{}\"", key, escape(value)));
+        }
+        self.0.push('>');
+    }
+
+    pub(crate) fn close(&mut self, name: &str) {
+        self.0.push_str(&format!("</{}>", name));
+    }
+
+    pub(crate) fn element(&mut self, name: &str, attrs: &[(&str, &str)], text: &str) {
+        self.open(name, attrs);
+        self.0.push_str(&escape(text));
+        self.close(name);
+    }
+}
+
+pub(crate) const XML_DECLARATION: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n";
+
+/// The `<oai_dc:dc>` element for an item, as embedded in OAI-PMH records.
+pub fn oai_dc_element(item: &ContentItem) -> String {
+    let StandardMetadata::DublinCore {
+        title, creator, subject, description, publisher, contributor, date, dc_type, format,
+        identifier, source, language, relation, coverage, rights,
+    } = dublin_core(item)
+    else {
+        unreachable!("dublin_core returns Dublin Core");
+    };
+
+    let mut xml = Xml::default();
+    xml.open(
+        "oai_dc:dc",
+        &[
+            ("xmlns:oai_dc", "http://www.openarchives.org/OAI/2.0/oai_dc/"),
+            ("xmlns:dc", "http://purl.org/dc/elements/1.1/"),
+            ("xmlns:xsi", "http://www.w3.org/2001/XMLSchema-instance"),
+            (
+                "xsi:schemaLocation",
+                "http://www.openarchives.org/OAI/2.0/oai_dc/ http://www.openarchives.org/OAI/2.0/oai_dc.xsd",
+            ),
+        ],
+    );
+    let one = |value: Option<String>| value.into_iter().collect::<Vec<_>>();
+    let fields = [
+        ("dc:title", one(title)),
+        ("dc:creator", creator.unwrap_or_default()),
+        ("dc:subject", subject.unwrap_or_default()),
+        ("dc:description", one(description)),
+        ("dc:publisher", one(publisher)),
+        ("dc:contributor", contributor.unwrap_or_default()),
+        ("dc:date", one(date)),
+        ("dc:type", one(dc_type)),
+        ("dc:format", one(format)),
+        ("dc:identifier", one(identifier)),
+        ("dc:source", one(source)),
+        ("dc:language", one(language)),
+        ("dc:relation", one(relation)),
+        ("dc:coverage", one(coverage)),
+        ("dc:rights", one(rights)),
+    ];
+    for (name, values) in fields {
+        for value in values {
+            xml.element(name, &[], &value);
+        }
+    }
+    xml.close("oai_dc:dc");
+    xml.0
+}
+
+/// The DataCite `<resource>` element for an item.
+pub fn datacite_element(item: &ContentItem) -> String {
+    let StandardMetadata::DataCite {
+        doi, creators, titles, publisher, publication_year, resource_type, subjects, ..
+    } = datacite(item)
+    else {
+        unreachable!("datacite returns DataCite");
+    };
+
+    let mut xml = Xml::default();
+    xml.open(
+        "resource",
+        &[
+            ("xmlns", "http://datacite.org/schema/kernel-4"),
+            ("xmlns:xsi", "http://www.w3.org/2001/XMLSchema-instance"),
+            (
+                "xsi:schemaLocation",
+                "http://datacite.org/schema/kernel-4 http://schema.datacite.org/meta/kernel-4.5/metadata.xsd",
+            ),
+        ],
+    );
+    // Records without a DOI carry their Lens ID as an alternate identifier
+    if let Some(ref doi) = doi {
+        xml.element("identifier", &[("identifierType", "DOI")], doi);
+    }
+
+    xml.open("creators", &[]);
+    for creator in &creators {
+        xml.open("creator", &[]);
+        match creator.name_type {
+            Some(ref name_type) => xml.element("creatorName", &[("nameType", name_type)], &creator.name),
+            None => xml.element("creatorName", &[], &creator.name),
+        }
+        if let Some(ref given) = creator.given_name {
+            xml.element("givenName", &[], given);
+        }
+        if let Some(ref family) = creator.family_name {
+            xml.element("familyName", &[], family);
+        }
+        for id in creator.name_identifiers.iter().flatten() {
+            let mut attrs = vec![("nameIdentifierScheme", id.name_identifier_scheme.as_str())];
+            if let Some(ref uri) = id.scheme_uri {
+                attrs.push(("schemeURI", uri));
+            }
+            xml.element("nameIdentifier", &attrs, &id.name_identifier);
+        }
+        xml.close("creator");
+    }
+    xml.close("creators");
+
+    xml.open("titles", &[]);
+    for title in &titles {
+        let mut attrs = Vec::new();
+        if let Some(ref lang) = title.lang {
+            attrs.push(("xml:lang", lang.as_str()));
+        }
+        if let Some(ref title_type) = title.title_type {
+            attrs.push(("titleType", title_type.as_str()));
+        }
+        xml.element("title", &attrs, &title.title);
+    }
+    xml.close("titles");
+
+    xml.element("publisher", &[], &publisher);
+    xml.element("publicationYear", &[], &publication_year.to_string());
+    xml.element(
+        "resourceType",
+        &[("resourceTypeGeneral", &resource_type.resource_type_general)],
+        resource_type.resource_type.as_deref().unwrap_or_default(),
+    );
+    if let Some(subjects) = subjects.filter(|s| !s.is_empty()) {
+        xml.open("subjects", &[]);
+        for subject in subjects {
+            xml.element("subject", &[], &subject);
+        }
+        xml.close("subjects");
+    }
+
+    let dates = [("Created", &item.created_at), ("Updated", &item.updated_at), ("Issued", &item.published_at)];
+    if dates.iter().any(|(_, date)| date.is_some()) {
+        xml.open("dates", &[]);
+        for (date_type, date) in dates {
+            if let Some(date) = date {
+                xml.element("date", &[("dateType", date_type)], date);
+            }
+        }
+        xml.close("dates");
+    }
+    if let Some(ref language) = item.language {
+        xml.element("language", &[], language);
+    }
+    xml.open("alternateIdentifiers", &[]);
+    xml.element("alternateIdentifier", &[("alternateIdentifierType", "Lens")], &item.id);
+    xml.close("alternateIdentifiers");
+
+    if let Some(ref license) = item.license {
+        let mut attrs = Vec::new();
+        if let Some(ref url) = license.url {
+            attrs.push(("rightsURI", url.as_str()));
+        }
+        if let Some(ref spdx) = license.spdx_id {
+            attrs.push(("rightsIdentifier", spdx.as_str()));
+            attrs.push(("rightsIdentifierScheme", "SPDX"));
+        }
+        xml.open("rightsList", &[]);
+        xml.element("rights", &attrs, &license.name);
+        xml.close("rightsList");
+    }
+    if let Some(ref description) = item.description {
+        xml.open("descriptions", &[]);
+        xml.element("description", &[("descriptionType", "Abstract")], description);
+        xml.close("descriptions");
+    }
+    xml.close("resource");
+    xml.0
+}
+
+/// Render an item in a format.
+pub fn render(item: &ContentItem, format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Json => serde_json::to_string(item).unwrap_or_default(),
+        ExportFormat::JsonLd => json_ld(item).to_string(),
+        ExportFormat::DataCite => format!("{}{}", XML_DECLARATION, datacite_element(item)),
+        ExportFormat::OaiDc => format!("{}{}", XML_DECLARATION, oai_dc_element(item)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{CreatorRole, License};
+
+    fn paper() -> ContentItem {
+        ContentItem::new("p1".into(), ContentType::ScientificPaper, "Cells & <Tissues>".into())
+            .with_description("A study".into())
+            .with_creator(Creator::with_identifier(
+                "Ada Lovelace".into(),
+                CreatorRole::Author,
+                "0000-0001-2345-678X".into(),
+            ))
+            .with_tag("biology".into())
+            .with_language("en".into())
+            .with_license(License::cc_by_4())
+            .with_published_at("2023-05-01".into())
+            .with_type_specific(json!({"doi": "10.1234/cells", "publisher": "Lens Press"}))
+    }
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(ExportFormat::negotiate(None), Some(ExportFormat::Json));
+        assert_eq!(ExportFormat::negotiate(Some("application/ld+json")), Some(ExportFormat::JsonLd));
+        assert_eq!(
+            ExportFormat::negotiate(Some("application/json;q=0.5, application/vnd.datacite.datacite+xml")),
+            Some(ExportFormat::DataCite)
+        );
+        assert_eq!(ExportFormat::negotiate(Some("text/html, */*;q=0.1")), Some(ExportFormat::Json));
+        assert_eq!(ExportFormat::negotiate(Some("text/html")), None);
+        assert_eq!(ExportFormat::from_name("oai_dc"), Some(ExportFormat::OaiDc));
+    }
+
+    #[test]
+    fn test_derived_formats() {
+        let item = paper();
+
+        let ld = json_ld(&item);
+        assert_eq!(ld["@type"], "ScholarlyArticle");
+        assert_eq!(ld["identifier"], "https://doi.org/10.1234/cells");
+        assert_eq!(ld["creator"][0]["name"], "Ada Lovelace");
+
+        let datacite = render(&item, ExportFormat::DataCite);
+        assert!(datacite.starts_with("<?xml"));
+        assert!(datacite.contains("<identifier identifierType=\"DOI\">10.1234/cells</identifier>"));
+        assert!(datacite.contains("<title>Cells &amp; &lt;Tissues&gt;</title>"));
+        assert!(datacite.contains("nameIdentifierScheme=\"ORCID\""));
+        assert!(datacite.contains("<publicationYear>2023</publicationYear>"));
+        assert!(datacite.contains("resourceTypeGeneral=\"JournalArticle\""));
+        assert!(datacite.contains("rightsIdentifier=\"CC-BY-4.0\""));
+
+        let dc = oai_dc_element(&item);
+        assert!(dc.contains("<dc:creator>Ada Lovelace</dc:creator>"));
+        assert!(dc.contains("<dc:publisher>Lens Press</dc:publisher>"));
+        assert!(dc.contains("<dc:type>Text</dc:type>"));
+    }
+
+    #[test]
+    fn test_explicit_metadata_wins() {
+        let mut item = paper();
+        item.metadata.add(StandardMetadata::SchemaOrg {
+            context: "https://schema.org".into(),
+            schema_type: "Chapter".into(),
+            properties: HashMap::from([("name".to_string(), json!("Chapter One"))]),
+        });
+        let ld = json_ld(&item);
+        assert_eq!(ld["@type"], "Chapter");
+        assert_eq!(ld["name"], "Chapter One");
+        assert_eq!(ld["inLanguage"], "en");
+
+        let release = Release::new("r1".into(), "Album".into(), "music".into());
+        let dc = oai_dc_element(&release_item(&release));
+        assert!(dc.contains("<dc:type>Sound</dc:type>"));
+        assert!(dc.contains("<dc:identifier>urn:lens:r1</dc:identifier>"));
+    }
+}
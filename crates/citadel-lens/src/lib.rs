@@ -7,8 +7,18 @@
 //!
 //! - **Models**: Content types (Release, ContentItem, etc.)
 //! - **Storage**: RocksDB-backed persistent storage
+//! - **Migrations**: Versioned upgrades of stored data and model schemas
+//! - **Search**: Full-text index over releases and content items
+//! - **Export**: schema.org JSON-LD, DataCite XML and Dublin Core renderings
+//! - **OAI-PMH**: Metadata harvesting of the catalog by libraries and archives
+//! - **Archives**: Signed catalog backups for moving a catalog between nodes
+//! - **ACL**: Admin and upload rights derived from a signed, replicated operation log
+//! - **Blobs**: Content-addressed, chunked file storage, fetched from many peers at once
 //! - **Mesh**: Integration with Citadel DHT and protocols
+//! - **DHT**: Key lookups routed across the mesh to the responsible slot
+//! - **Config**: Layered settings from a TOML file, environment and command line
 //! - **API**: HTTP endpoints for content management
+//! - **Auth**: ed25519 signed requests for API writes
 //! - **Admin Socket**: Unix socket for local admin commands (lens-admin CLI)
 //!
 //! # Example
@@ -18,7 +28,7 @@
 //!
 //! #[tokio::main]
 //! async fn main() -> Result<(), Box<dyn std::error::Error>> {
-//!     let config = LensConfig::default();
+//!     let config = LensConfig::from_env()?;
 //!     let node = LensNode::new(config).await?;
 //!     node.run().await?;
 //!     Ok(())
@@ -27,12 +37,26 @@
 
 pub mod models;
 pub mod storage;
+pub mod migrations;
+pub mod acl;
+pub mod blob;
+pub mod swarm;
+pub mod config;
 pub mod node;
 pub mod api;
+pub mod auth;
 pub mod admin_socket;
 pub mod mesh;
+pub mod dht;
+pub mod wire;
+pub mod replication;
+pub mod search;
+pub mod export;
+pub mod oai;
+pub mod archive;
 pub mod ws;
 pub mod error;
+pub mod timestamp;
 pub mod vdf_race;
 pub mod proof_of_latency;
 pub mod pvdf;
@@ -45,6 +69,7 @@ pub use models::{
     DataCiteResourceType, DataCiteTitle, License, MetadataContainer, NameIdentifier, Release,
     Resource, StandardMetadata,
 };
-pub use storage::Storage;
-pub use node::{LensNode, LensConfig};
+pub use storage::{RocksDhtStore, Storage};
+pub use config::LensConfig;
+pub use node::LensNode;
 pub use error::{Error, Result};
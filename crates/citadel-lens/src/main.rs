@@ -1,26 +1,48 @@
 //! Lens Node binary
 //!
 //! A Citadel mesh node for distributed content distribution.
+//! Run `lens-node --help` for settings (see [`citadel_lens::config`]).
 
-use citadel_lens::{LensConfig, LensNode};
+use citadel_lens::config::{CliArgs, LogFormat};
+use citadel_lens::migrations;
+use citadel_lens::{LensConfig, LensNode, Storage};
+use clap::Parser;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = CliArgs::parse();
+
+    // Load config: file, then environment, then flags
+    let config = match LensConfig::load(&args) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("lens-node: {}", e);
+            std::process::exit(2);
+        }
+    };
+
+    if args.print_config {
+        print!("{}", config.to_toml()?);
+        return Ok(());
+    }
+
+    if args.migrate_dry_run {
+        let storage = Storage::open_unmigrated(&config.data_dir)?;
+        print!("{}", migrations::run(&storage, &migrations::BUILTIN, true)?);
+        return Ok(());
+    }
+
     // Initialize tracing
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "lens_node=info,citadel=info".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
+    let filter = tracing_subscriber::EnvFilter::try_new(&config.log_filter)?;
+    let registry = tracing_subscriber::registry().with(filter);
+    match config.log_format {
+        LogFormat::Text => registry.with(tracing_subscriber::fmt::layer()).init(),
+        LogFormat::Json => registry.with(tracing_subscriber::fmt::layer().json()).init(),
+    }
 
     tracing::info!("Starting Lens Node");
 
-    // Load config (TODO: from args/file)
-    let config = LensConfig::default();
-
     // Create and run node
     let node = LensNode::new(config).await?;
     node.run().await?;
@@ -50,9 +50,13 @@
 //! ALL data transfer uses continuous flooding - no request/response patterns:
 //! - Peer discovery floods on connection
 //! - Slot announcements flood through mesh
-//! - Admin lists flood on change
+//! - Admin changes replicate as signed operations (see [`crate::acl`])
 //! - XOR cancellation: sync_cost(A,B) = O(|A ⊕ B|) → 0 at convergence
 //!
+//! DHT lookups and blob downloads are the exceptions: DHT requests are routed
+//! peer to peer toward the key's slot (see [`crate::dht`]), and blob chunks are
+//! requested from the peers that hold them (see [`crate::swarm`]).
+//!
 //! # 20-Neighbor Topology (SPIRAL)
 //!
 //! Each slot has exactly 20 theoretical neighbors:
@@ -60,21 +64,31 @@
 //! - 2 vertical (directly above/below)
 //! - 12 extended (6 above + 6 below diagonals)
 
-use crate::error::Result;
-use crate::storage::Storage;
+use crate::blob::{BlobManifest, BlobStore, Cid};
+use crate::dht::{
+    self, DhtOp, DhtReply, DhtRequests, DHT_GC_INTERVAL, DHT_HANDOFF_BATCH, DHT_HANDOFF_INTERVAL, DHT_REQUEST_TIMEOUT,
+    MAX_DHT_HOPS,
+};
+use crate::error::{Error, Result};
+use crate::replication::{self, Record, RecordBody};
+use crate::storage::{RocksDhtStore, Storage};
+use crate::swarm::{self, SwarmDownload, SwarmProgress, SWARM_BATCH, SWARM_TICK};
+use crate::wire::{self, Hello, HelloProof, WireCodec, WireMessage, SUPPORTED_WIRE_VERSIONS};
 use crate::vdf_race::{VdfRace, VdfLink, AnchoredSlotClaim, claim_has_priority};
 use crate::cvdf::{CvdfCoordinator, CvdfRound, RoundAttestation};
 use citadel_protocols::{
-    CoordinatorConfig, FloodRateConfig, KeyPair, Message as TgpMessage, MessagePayload, PeerCoordinator, PublicKey,
-    SporeSyncManager,
+    ContentBlock, CoordinatorConfig, FloodRateConfig, KeyPair, Message as TgpMessage, MessagePayload, PeerCoordinator,
+    PublicKey, SporeSyncManager,
 };
-use citadel_spore::U256;
+use citadel_dht::{key_to_coord, DhtEntry, DhtError, DhtKey, DhtState, DhtValue, MeshView, DEFAULT_REPLICATION_FACTOR};
+use citadel_spore::{Spore, SporeMessage, U256};
 use citadel_topology::{HexCoord, Neighbors, Spiral3DIndex, spiral3d_to_coord};
 use ed25519_dalek::{SigningKey, VerifyingKey};
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::net::SocketAddr;
 use std::sync::Arc;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::io::{AsyncWriteExt, BufReader};
 use tokio::net::{TcpListener, TcpStream, UdpSocket};
 use tokio::sync::{broadcast, mpsc, oneshot, Notify, RwLock};
 use tracing::{debug, error, info, warn};
@@ -301,11 +315,14 @@ impl MeshState {
 }
 
 /// Broadcast message for continuous flooding
-#[derive(Clone, Debug)]
+///
+/// Serialized as the payload of binary wire frames (see [`crate::wire`]), so new
+/// variants must be appended at the end.
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum FloodMessage {
     /// Peer discovery (id, addr, slot_index, public_key)
     Peers(Vec<(String, String, Option<u64>, Option<Vec<u8>>)>),
-    /// Admin list sync
+    /// Admin list sync (legacy, ignored: admin changes are signed operations, see [`crate::acl`])
     Admins(Vec<String>),
     /// Slot claim announcement (index, peer_id, coord as (q, r, z), public_key)
     SlotClaim { index: u64, peer_id: String, coord: (i64, i64, i64), public_key: Option<Vec<u8>> },
@@ -333,6 +350,81 @@ pub enum FloodMessage {
     CvdfSyncRequest { from_node: String, from_height: u64 },
     /// CVDF chain sync response (all rounds)
     CvdfSyncResponse { rounds: Vec<CvdfRound>, slots: Vec<(u64, [u8; 32])> },
+    /// SPORE HaveList/WantList over replicated content (see [`crate::replication`])
+    SporeSync { msg: SporeMessage },
+    /// Replicated content blocks (the XOR difference, or a new local write)
+    SporeContent { blocks: Vec<ContentBlock> },
+    /// DHT request, sent to one peer at a time along the route to the key (see [`crate::dht`])
+    DhtRequest { id: u64, hops: u8, op: DhtOp },
+    /// DHT response, sent back along the request's path
+    DhtResponse { id: u64, reply: DhtReply },
+    /// DHT entries pushed to a key's replicas (on PUT, and on handoff)
+    DhtReplicate { entries: Vec<DhtEntry> },
+    /// DHT entries for the replica at slot `coord` (q, r, z), forwarded hop by
+    /// hop when we have no connection to it
+    DhtForward { coord: (i64, i64, i64), hops: u8, entries: Vec<DhtEntry> },
+    /// Ask a peer which chunks of a blob it holds (see [`crate::swarm`])
+    BlobWant { cid: Cid },
+    /// Chunks of a blob we hold, as a SPORE over chunk indices
+    BlobHave { cid: Cid, manifest: BlobManifest, have: Spore },
+    /// Request chunks `start..end` of a blob
+    BlobRequest { cid: Cid, start: u64, end: u64 },
+    /// One chunk of a blob
+    BlobChunk { cid: Cid, index: u64, data: Vec<u8> },
+}
+
+/// Flooded peer entry (id, addr, slot_index, public_key)
+type PeerEntry = (String, String, Option<u64>, Option<Vec<u8>>);
+
+/// Target size of the block data in one `SporeContent` message
+const SPORE_CONTENT_CHUNK: usize = 1024 * 1024;
+
+/// Split blocks into `SporeContent` messages that stay well under the frame limit
+fn spore_content_messages(blocks: Vec<ContentBlock>) -> Vec<FloodMessage> {
+    let mut messages = Vec::new();
+    let mut chunk = Vec::new();
+    let mut chunk_size = 0;
+    for block in blocks {
+        if chunk_size + block.data.len() > SPORE_CONTENT_CHUNK && !chunk.is_empty() {
+            messages.push(FloodMessage::SporeContent { blocks: std::mem::take(&mut chunk) });
+            chunk_size = 0;
+        }
+        chunk_size += block.data.len();
+        chunk.push(block);
+    }
+    if !chunk.is_empty() {
+        messages.push(FloodMessage::SporeContent { blocks: chunk });
+    }
+    messages
+}
+
+/// Addresses to connect to for newly discovered peers
+fn peer_addrs(peers: &[PeerEntry]) -> Vec<(String, SocketAddr)> {
+    peers.iter()
+        .filter_map(|(peer_id, addr_str, _, _)| {
+            addr_str.parse::<SocketAddr>().ok().map(|addr| (peer_id.clone(), addr))
+        })
+        .collect()
+}
+
+/// Error for a DHT reply that doesn't answer the request successfully
+fn dht_reply_error(reply: DhtReply) -> Error {
+    match reply {
+        DhtReply::Rejected(reason) => Error::InvalidInput(format!("DHT entry rejected: {}", reason)),
+        DhtReply::Failed(reason) => Error::Storage(reason),
+        other => Error::Protocol(format!("Unexpected DHT reply: {:?}", other)),
+    }
+}
+
+/// What handling one peer message produced for its connection
+#[derive(Debug, Default)]
+struct MessageOutcome {
+    /// Real PeerID learned from hello (for re-keying)
+    real_id: Option<String>,
+    /// Discovered peers to connect to in background
+    peers_to_connect: Vec<(String, SocketAddr)>,
+    /// Messages to send back to this peer only
+    replies: Vec<FloodMessage>,
 }
 
 /// Citadel Mesh Service
@@ -355,6 +447,26 @@ pub struct MeshService {
     /// Channel for pending connections to spawn from listener
     pending_connect_tx: mpsc::Sender<(String, SocketAddr)>,
     pending_connect_rx: Arc<tokio::sync::Mutex<mpsc::Receiver<(String, SocketAddr)>>>,
+    /// Per-connection senders for messages addressed to one peer (DHT routing)
+    peer_tx: Arc<RwLock<HashMap<String, mpsc::Sender<FloodMessage>>>>,
+    /// Our DHT shard, persisted alongside the rest of storage
+    dht: tokio::sync::Mutex<DhtState<RocksDhtStore>>,
+    /// DHT requests in flight through this node
+    dht_requests: tokio::sync::Mutex<DhtRequests>,
+    /// Number of nodes holding each DHT key
+    dht_replication_factor: usize,
+    /// VDF race iterations per link
+    vdf_iterations: u32,
+    /// CVDF iterations per round
+    cvdf_iterations: u32,
+    /// Interval between CVDF coordination steps
+    cvdf_tick: std::time::Duration,
+    /// Flood rate limits for TGP sessions
+    flood_rate: FloodRateConfig,
+    /// Content-addressed blobs, served to and fetched from peers
+    blobs: BlobStore,
+    /// Blob downloads in progress
+    blob_downloads: tokio::sync::Mutex<HashMap<Cid, SwarmDownload>>,
 }
 
 impl MeshService {
@@ -386,7 +498,8 @@ impl MeshService {
             let hash = blake3::hash(verifying_key.as_bytes());
             U256::from_be_bytes(hash.as_bytes())
         };
-        let spore_sync = SporeSyncManager::new(peer_id_u256);
+        // SPORE messages are signed so peers can't forge our WantList
+        let spore_sync = SporeSyncManager::new(peer_id_u256).with_signing_key(signing_key.clone());
 
         // Pre-compute TGP keypair once for zerocopy/CoW responder sessions
         // This is derived from signing_key and shared via Arc across all sessions
@@ -398,6 +511,10 @@ impl MeshService {
         // Channel for pending connections to spawn from listener
         let (pending_connect_tx, pending_connect_rx) = mpsc::channel(256);
 
+        let dht = DhtState::with_store(RocksDhtStore::new(Arc::clone(&storage)))
+            .with_shared_policy(dht::shared_policy(Arc::clone(&storage)));
+        let blobs = BlobStore::new(Arc::clone(&storage));
+
         Self {
             listen_addr,
             entry_peers,
@@ -426,9 +543,52 @@ impl MeshService {
             // Channel for pending peer connections
             pending_connect_tx,
             pending_connect_rx: Arc::new(tokio::sync::Mutex::new(pending_connect_rx)),
+            peer_tx: Arc::new(RwLock::new(HashMap::new())),
+            dht: tokio::sync::Mutex::new(dht),
+            dht_requests: tokio::sync::Mutex::new(DhtRequests::default()),
+            dht_replication_factor: DEFAULT_REPLICATION_FACTOR,
+            vdf_iterations: crate::vdf_race::VDF_ITERATIONS,
+            cvdf_iterations: crate::cvdf::CVDF_ITERATIONS,
+            cvdf_tick: std::time::Duration::from_millis(100),
+            flood_rate: FloodRateConfig::fast(),
+            blobs,
+            blob_downloads: tokio::sync::Mutex::new(HashMap::new()),
         }
     }
 
+    /// Set how many nodes hold each DHT key (at least 1)
+    pub fn with_dht_replication_factor(mut self, replication_factor: usize) -> Self {
+        self.dht_replication_factor = replication_factor.max(1);
+        self
+    }
+
+    /// Set how long DHT tombstones are kept. Must be longer than any peer is
+    /// expected to stay partitioned, or it can bring deleted keys back.
+    pub fn with_dht_tombstone_horizon(mut self, horizon: std::time::Duration) -> Self {
+        self.dht = tokio::sync::Mutex::new(self.dht.into_inner().with_tombstone_horizon(horizon));
+        self
+    }
+
+    /// Set VDF race and CVDF difficulty. Must match the rest of the mesh,
+    /// or chains from other nodes fail verification.
+    pub fn with_vdf_iterations(mut self, vdf_iterations: u32, cvdf_iterations: u32) -> Self {
+        self.vdf_iterations = vdf_iterations;
+        self.cvdf_iterations = cvdf_iterations;
+        self
+    }
+
+    /// Set the interval between CVDF coordination steps
+    pub fn with_cvdf_tick(mut self, tick: std::time::Duration) -> Self {
+        self.cvdf_tick = tick;
+        self
+    }
+
+    /// Set flood rate limits for TGP sessions
+    pub fn with_flood_rate(mut self, flood_rate: FloodRateConfig) -> Self {
+        self.flood_rate = flood_rate;
+        self
+    }
+
     /// Attempt to occupy a SPIRAL slot.
     ///
     /// # CURRENT LIMITATION
@@ -529,7 +689,7 @@ impl MeshService {
         let mut state = self.state.write().await;
         let signing_key = state.signing_key.clone();
 
-        let vdf_race = VdfRace::new_genesis(Self::VDF_GENESIS_SEED, signing_key);
+        let vdf_race = VdfRace::new_genesis(Self::VDF_GENESIS_SEED, signing_key, self.vdf_iterations);
         info!("VDF Race initialized as genesis (height 0)");
 
         state.vdf_race = Some(vdf_race);
@@ -541,7 +701,7 @@ impl MeshService {
         let mut state = self.state.write().await;
         let signing_key = state.signing_key.clone();
 
-        match VdfRace::join(Self::VDF_GENESIS_SEED, signing_key, chain_links) {
+        match VdfRace::join(Self::VDF_GENESIS_SEED, signing_key, chain_links, self.vdf_iterations) {
             Some(vdf_race) => {
                 let height = vdf_race.height();
                 info!("VDF Race initialized by joining (height {})", height);
@@ -732,7 +892,7 @@ impl MeshService {
         let mut state = self.state.write().await;
         let signing_key = state.signing_key.clone();
 
-        let cvdf = CvdfCoordinator::new_genesis(Self::CVDF_GENESIS_SEED, signing_key);
+        let cvdf = CvdfCoordinator::new_genesis(Self::CVDF_GENESIS_SEED, signing_key, self.cvdf_iterations);
         info!("CVDF initialized as genesis (height 0, weight 1)");
 
         state.cvdf = Some(cvdf);
@@ -748,7 +908,7 @@ impl MeshService {
         let mut state = self.state.write().await;
         let signing_key = state.signing_key.clone();
 
-        match CvdfCoordinator::join(Self::CVDF_GENESIS_SEED, rounds, signing_key) {
+        match CvdfCoordinator::join(Self::CVDF_GENESIS_SEED, rounds, signing_key, self.cvdf_iterations) {
             Some(mut cvdf) => {
                 // Register known slots
                 for (slot, pubkey) in slots {
@@ -879,13 +1039,13 @@ impl MeshService {
     /// Run CVDF coordination loop
     /// This handles periodic attestation and round production
     pub async fn run_cvdf_loop(&self) {
-        use tokio::time::{interval, Duration};
+        use tokio::time::interval;
 
         // Wait for CVDF to be initialized (event-driven, no polling)
         self.cvdf_init_notify.notified().await;
 
         // Run coordination loop
-        let mut tick = interval(Duration::from_millis(100)); // 10Hz coordination
+        let mut tick = interval(self.cvdf_tick);
 
         loop {
             tick.tick().await;
@@ -1037,7 +1197,7 @@ impl MeshService {
                 CoordinatorConfig::default()
                     .with_commitment(commitment_msg.clone().into_bytes())
                     .with_timeout(std::time::Duration::from_secs(10))
-                    .with_flood_rate(FloodRateConfig::fast()),
+                    .with_flood_rate(self.flood_rate),
             );
             coordinator.set_active(true);
 
@@ -1242,7 +1402,7 @@ impl MeshService {
         Arc::clone(&self.state)
     }
 
-    /// Get the flood sender (for admin socket to propagate changes)
+    /// Get the flood sender
     pub fn flood_tx(&self) -> broadcast::Sender<FloodMessage> {
         self.flood_tx.clone()
     }
@@ -1328,7 +1488,7 @@ impl MeshService {
                     counterparty_key.clone(),
                     CoordinatorConfig::default()
                         .with_timeout(std::time::Duration::from_secs(30))
-                        .with_flood_rate(FloodRateConfig::fast()),
+                        .with_flood_rate(self.flood_rate),
                 );
                 coordinator.set_active(true);
                 sessions.insert(
@@ -1459,6 +1619,63 @@ impl MeshService {
             state.udp_socket = Some(Arc::clone(&udp_socket));
         }
 
+        // SPORE content replication: subscribe before seeding so no write is missed,
+        // then announce every local write to the mesh as it happens
+        let mut changes = self.storage.subscribe_changes();
+        self.publish_storage_blocks().await;
+        let self_clone = Arc::clone(&self);
+        tokio::spawn(async move {
+            loop {
+                match changes.recv().await {
+                    Ok(change) => {
+                        match replication::local_block(&self_clone.storage, change.into(), replication::now_ms()) {
+                            Ok(block) => self_clone.publish_blocks(vec![block]).await,
+                            Err(e) => warn!("Failed to replicate local write: {}", e),
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        warn!("Replication missed {} local writes, rescanning storage", missed);
+                        self_clone.publish_storage_blocks().await;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+
+        // DHT handoff: when slots change hands, push our keys to their new replicas
+        let self_clone = Arc::clone(&self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(DHT_HANDOFF_INTERVAL);
+            let mut view = MeshView::default();
+            loop {
+                interval.tick().await;
+                view = self_clone.dht_handoff(view).await;
+            }
+        });
+
+        // DHT tombstone GC: forget deletions older than the tombstone horizon
+        let self_clone = Arc::clone(&self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(DHT_GC_INTERVAL);
+            loop {
+                interval.tick().await;
+                self_clone.dht_gc().await;
+            }
+        });
+
+        // Blob swarm: resume unfinished downloads, then keep requesting chunks
+        let self_clone = Arc::clone(&self);
+        tokio::spawn(async move {
+            if let Err(e) = self_clone.blob_resume().await {
+                warn!("Failed to resume blob downloads: {}", e);
+            }
+            let mut interval = tokio::time::interval(SWARM_TICK);
+            loop {
+                interval.tick().await;
+                self_clone.blob_tick().await;
+            }
+        });
+
         // Spawn UDP listener for incoming TGP messages (event-driven, no polling)
         let self_clone = Arc::clone(&self);
         let udp_clone = Arc::clone(&udp_socket);
@@ -1646,174 +1863,199 @@ impl MeshService {
 
         info!("Peer {} registered", peer_id);
 
-        // Simple protocol: exchange node info and sync state
+        // Hello is always a JSON line so nodes without the binary codec can read it.
+        // It advertises the wire versions we speak; the peer's hello tells us theirs.
         let (reader, mut writer) = stream.into_split();
         let mut reader = BufReader::new(reader);
 
-        // Send our node info with public key for TGP
+        // Send our node info with public key for TGP, and a challenge the peer
+        // signs to prove it holds the key from its own hello
+        let challenge: [u8; 32] = rand::random();
         let state = self.state.read().await;
-        let self_id = state.self_id.clone();
-        let self_pubkey = state.signing_key.verifying_key();
-        let pubkey_hex = hex::encode(self_pubkey.as_bytes());
+        let signing_key = state.signing_key.clone();
+        let hello = Hello {
+            node_id: state.self_id.clone(),
+            addr: self.listen_addr.to_string(),
+            public_key: Some(state.signing_key.verifying_key().to_bytes().to_vec()),
+            wire_versions: SUPPORTED_WIRE_VERSIONS.to_vec(),
+            challenge: Some(challenge.to_vec()),
+        };
         drop(state);
-        let hello = serde_json::json!({
-            "type": "hello",
-            "node_id": self_id,
-            "addr": self.listen_addr.to_string(),
-            "public_key": pubkey_hex,
-        });
-        writer.write_all(hello.to_string().as_bytes()).await?;
-        writer.write_all(b"\n").await?;
-
-        // Flood our complete state to this peer (event-driven, no request/response)
-        // Admin list
-        if let Ok(admins) = self.storage.list_admins() {
-            if !admins.is_empty() {
-                let flood_admins = serde_json::json!({
-                    "type": "flood_admins",
-                    "admins": admins,
-                });
-                writer.write_all(flood_admins.to_string().as_bytes()).await?;
-                writer.write_all(b"\n").await?;
-                debug!("Flooded {} admins to peer {}", admins.len(), peer_id);
+        writer.write_all(&hello.to_line()).await?;
+
+        // Track current peer key (may change from peer-{port} to real PeerID)
+        let mut current_peer_key = peer_id.clone();
+
+        // Wait for the peer's hello before flooding anything else - it decides whether
+        // the rest of the connection uses binary frames. Legacy nodes also open with hello.
+        let (codec, peer_hello) = match wire::read_message(&mut reader, WireCodec::Json).await {
+            Ok(Some(msg)) => {
+                let peer_hello = match &msg {
+                    WireMessage::Hello(hello) => Some(hello.clone()),
+                    _ => None,
+                };
+                let codec = peer_hello
+                    .as_ref()
+                    .map_or(WireCodec::Json, |hello| WireCodec::negotiate(&hello.wire_versions));
+                self.dispatch_message(&mut current_peer_key, msg).await;
+                (codec, peer_hello)
+            }
+            Ok(None) | Err(_) => {
+                info!("Peer {} disconnected before hello", current_peer_key);
+                self.state.write().await.peers.remove(&current_peer_key);
+                return Ok(());
+            }
+        };
+        debug!("Peer {} uses {:?} wire codec", current_peer_key, codec);
+
+        // Binary links exchange signed SPORE messages, so before switching codecs
+        // both sides prove they hold the key from their hello
+        if let Some(peer_hello) = peer_hello.filter(|_| codec != WireCodec::Json) {
+            if let Some(peer_challenge) = peer_hello.challenge.as_deref() {
+                writer.write_all(&HelloProof::sign(peer_challenge, &signing_key).to_line()).await?;
+            }
+            let proven = match wire::read_message(&mut reader, WireCodec::Json).await {
+                Ok(Some(WireMessage::HelloProof(proof))) => peer_hello
+                    .public_key
+                    .as_deref()
+                    .is_some_and(|public_key| proof.verify(&challenge, public_key)),
+                _ => false,
+            };
+            if !proven {
+                warn!("Peer {} did not prove it holds the key from its hello", current_peer_key);
+                self.state.write().await.peers.remove(&current_peer_key);
+                return Ok(());
             }
         }
 
-        // Peer list - flood our complete view of the mesh with slot info and public keys
-        // SPORE: only flood real peer IDs (b3b3/...), never temp IDs
-        {
-            let state = self.state.read().await;
-            let self_slot = state.self_slot.as_ref().map(|s| s.index);
-            let self_pubkey = hex::encode(state.signing_key.verifying_key().as_bytes());
-            let mut all_peers = vec![serde_json::json!({
-                "id": state.self_id,
-                "addr": self.listen_addr.to_string(),
-                "slot": self_slot,
-                "public_key": self_pubkey,
-            })];
-            for peer in state.peers.values() {
-                // Only flood peers with real IDs (b3b3/...), skip temp IDs
-                if !peer.id.starts_with("b3b3/") {
-                    continue;
+        let mut initial_state = Vec::new();
+        if codec == WireCodec::Json {
+            // Nodes without the binary codec don't replicate content through SPORE -
+            // flood our complete state to them (event-driven, no request/response)
+
+            // Peer list - flood our complete view of the mesh with slot info and public keys
+            // SPORE: only flood real peer IDs (b3b3/...), never temp IDs
+            {
+                let state = self.state.read().await;
+                let self_slot = state.self_slot.as_ref().map(|s| s.index);
+                let self_pubkey = state.signing_key.verifying_key().to_bytes().to_vec();
+                let mut all_peers = vec![(
+                    state.self_id.clone(),
+                    self.listen_addr.to_string(),
+                    self_slot,
+                    Some(self_pubkey),
+                )];
+                for peer in state.peers.values() {
+                    // Only flood peers with real IDs (b3b3/...), skip temp IDs
+                    if !peer.id.starts_with("b3b3/") {
+                        continue;
+                    }
+                    all_peers.push((
+                        peer.id.clone(),
+                        peer.addr.to_string(),
+                        peer.slot.as_ref().map(|s| s.index),
+                        peer.public_key.clone(),
+                    ));
+                }
+                initial_state.push(FloodMessage::Peers(all_peers));
+
+                // Also flood all claimed slots (with public keys for TGP)
+                for claim in state.claimed_slots.values() {
+                    initial_state.push(FloodMessage::SlotClaim {
+                        index: claim.index,
+                        peer_id: claim.peer_id.clone(),
+                        coord: (claim.coord.q, claim.coord.r, claim.coord.z),
+                        public_key: claim.public_key.clone(),
+                    });
                 }
-                all_peers.push(serde_json::json!({
-                    "id": peer.id,
-                    "addr": peer.addr.to_string(),
-                    "slot": peer.slot.as_ref().map(|s| s.index),
-                    "public_key": peer.public_key.as_ref().map(hex::encode),
-                }));
-            }
 
-            let flood_peers = serde_json::json!({
-                "type": "flood_peers",
-                "peers": all_peers,
-            });
-            writer.write_all(flood_peers.to_string().as_bytes()).await?;
-            writer.write_all(b"\n").await?;
-
-            // Also flood all claimed slots (with public keys for TGP)
-            for claim in state.claimed_slots.values() {
-                let slot_msg = serde_json::json!({
-                    "type": "slot_claim",
-                    "index": claim.index,
-                    "peer_id": claim.peer_id,
-                    "coord": [claim.coord.q, claim.coord.r, claim.coord.z],
-                    "public_key": claim.public_key.as_ref().map(hex::encode),
+                // SPORE: Send our HaveList so peer can identify missing slots
+                initial_state.push(FloodMessage::SporeHaveList {
+                    peer_id: state.self_id.clone(),
+                    slots: state.claimed_slots.keys().copied().collect(),
                 });
-                writer.write_all(slot_msg.to_string().as_bytes()).await?;
-                writer.write_all(b"\n").await?;
             }
-
-            // SPORE: Send our HaveList so peer can identify missing slots
-            let have_slots: Vec<u64> = state.claimed_slots.keys().copied().collect();
-            let have_list = serde_json::json!({
-                "type": "spore_have_list",
-                "peer_id": state.self_id,
-                "slots": have_slots,
-            });
-            writer.write_all(have_list.to_string().as_bytes()).await?;
-            writer.write_all(b"\n").await?;
+        } else {
+            // SPORE: send our HaveList/WantList. The peer replies with only the blocks
+            // we lack (the XOR difference), so reconnects cost nothing when in sync.
+            self.publish_blocks(self.mesh_blocks().await).await;
+            let spore_id = self.spore_peer_id(&current_peer_key).await;
+            let mut state = self.state.write().await;
+            // Their SPORE messages must be signed by the key proven after their hello
+            let peer_key = state.peers.get(&current_peer_key)
+                .and_then(|p| p.public_key.as_deref())
+                .and_then(|pk| <[u8; 32]>::try_from(pk).ok())
+                .and_then(|pk| VerifyingKey::from_bytes(&pk).ok());
+            if let Some(ref mut sync) = state.spore_sync {
+                if let Some(key) = peer_key {
+                    sync.register_peer_key(spore_id, key);
+                }
+                sync.get_or_create_peer(spore_id);
+                if let Some(msg) = sync.create_spore_message(&spore_id) {
+                    initial_state.push(FloodMessage::SporeSync { msg });
+                }
+            }
         }
 
         // CVDF chain sync: Send our chain state so peer can adopt heavier chain
         // CRITICAL: This enables swarm merge during initial connection
         if let Some((rounds, slots)) = self.cvdf_chain_state().await {
-            let rounds_json: Vec<serde_json::Value> = rounds.iter().map(|r| {
-                serde_json::json!({
-                    "round": r.round,
-                    "prev_output": hex::encode(r.prev_output),
-                    "washed_input": hex::encode(r.washed_input),
-                    "output": hex::encode(r.output),
-                    "producer": hex::encode(r.producer),
-                    "producer_signature": hex::encode(r.producer_signature),
-                    "timestamp_ms": r.timestamp_ms,
-                    "attestations": r.attestations.iter().map(|a| {
-                        serde_json::json!({
-                            "round": a.round,
-                            "prev_output": hex::encode(a.prev_output),
-                            "attester": hex::encode(a.attester),
-                            "slot": a.slot,
-                            "signature": hex::encode(a.signature),
-                        })
-                    }).collect::<Vec<_>>(),
-                })
-            }).collect();
-            let slots_json: Vec<serde_json::Value> = slots.iter().map(|(idx, pk)| {
-                serde_json::json!({
-                    "index": idx,
-                    "pubkey": hex::encode(pk),
-                })
-            }).collect();
-            let cvdf_sync = serde_json::json!({
-                "type": "cvdf_sync_response",
-                "rounds": rounds_json,
-                "slots": slots_json,
-                "height": rounds.last().map(|r| r.round).unwrap_or(0),
-                "total_weight": rounds.iter().map(|r| r.weight() as u64).sum::<u64>(),
-            });
-            writer.write_all(cvdf_sync.to_string().as_bytes()).await?;
-            writer.write_all(b"\n").await?;
-            debug!("Sent CVDF chain state to peer {} (height {}, {} slots)",
-                peer_id,
+            debug!("Sending CVDF chain state to peer {} (height {}, {} slots)",
+                current_peer_key,
                 rounds.last().map(|r| r.round).unwrap_or(0),
                 slots.len()
             );
+            initial_state.push(FloodMessage::CvdfSyncResponse { rounds, slots });
+        }
+
+        for msg in &initial_state {
+            writer.write_all(&codec.encode(msg)?).await?;
         }
 
         // Subscribe to broadcast floods
         let mut flood_rx = self.flood_tx.subscribe();
 
-        // Track current peer key (may change from peer-{port} to real PeerID)
-        let mut current_peer_key = peer_id.clone();
+        // Messages addressed to this peer only (DHT routing)
+        let (direct_tx, mut direct_rx) = mpsc::channel(256);
+        self.peer_tx.write().await.insert(current_peer_key.clone(), direct_tx.clone());
+
+        // Read on a separate task: a partially read frame would be lost if the read
+        // future were dropped by select! when a flood arrives first
+        let (incoming_tx, mut incoming_rx) = mpsc::channel(256);
+        let reader_task = tokio::spawn(async move {
+            loop {
+                let result = wire::read_message(&mut reader, codec).await;
+                let done = !matches!(result, Ok(Some(_)));
+                if incoming_tx.send(result).await.is_err() || done {
+                    break;
+                }
+            }
+        });
 
         // Read peer messages and forward floods concurrently
         // NOTE: TGP is now over UDP (connectionless), not TCP
-        let mut line = String::new();
         loop {
-            line.clear();
             tokio::select! {
                 // Handle incoming messages from peer
-                read_result = reader.read_line(&mut line) => {
-                    match read_result {
-                        Ok(0) => {
-                            info!("Peer {} disconnected", current_peer_key);
-                            break;
-                        }
-                        Ok(_) => {
-                            if let Ok(msg) = serde_json::from_str::<serde_json::Value>(&line) {
-                                // handle_message returns (real_id, peers_to_connect)
-                                if let Ok((real_id, peers_to_connect)) = self.handle_message(&current_peer_key, msg).await {
-                                    if let Some(id) = real_id {
-                                        current_peer_key = id;
+                incoming = incoming_rx.recv() => {
+                    match incoming {
+                        Some(Ok(Some(msg))) => {
+                            for reply in self.dispatch_message(&mut current_peer_key, msg).await {
+                                match codec.encode(&reply) {
+                                    Ok(bytes) => {
+                                        let _ = writer.write_all(&bytes).await;
                                     }
-                                    // Queue discovered peers for connection (spawned by listener)
-                                    for (discovered_id, addr) in peers_to_connect {
-                                        let _ = self.pending_connect_tx.send((discovered_id, addr)).await;
+                                    Err(e) => {
+                                        warn!("Failed to encode reply for {}: {}", current_peer_key, e);
                                     }
                                 }
                             }
                         }
-                        Err(e) => {
+                        Some(Ok(None)) | None => {
+                            info!("Peer {} disconnected", current_peer_key);
+                            break;
+                        }
+                        Some(Err(e)) => {
                             warn!("Read error from {}: {}", current_peer_key, e);
                             break;
                         }
@@ -1822,809 +2064,927 @@ impl MeshService {
                 // Forward broadcast floods to this peer
                 flood_result = flood_rx.recv() => {
                     match flood_result {
-                        Ok(FloodMessage::Peers(peers)) => {
-                            let flood_msg = serde_json::json!({
-                                "type": "flood_peers",
-                                "peers": peers.into_iter().map(|(id, addr, slot, public_key)| {
-                                    serde_json::json!({
-                                        "id": id,
-                                        "addr": addr,
-                                        "slot": slot,
-                                        "public_key": public_key.map(hex::encode),
-                                    })
-                                }).collect::<Vec<_>>(),
-                            });
-                            let _ = writer.write_all(flood_msg.to_string().as_bytes()).await;
-                            let _ = writer.write_all(b"\n").await;
-                        }
-                        Ok(FloodMessage::Admins(admins)) => {
-                            let flood_msg = serde_json::json!({
-                                "type": "flood_admins",
-                                "admins": admins,
-                            });
-                            let _ = writer.write_all(flood_msg.to_string().as_bytes()).await;
-                            let _ = writer.write_all(b"\n").await;
-                        }
-                        Ok(FloodMessage::SlotClaim { index, peer_id, coord, public_key }) => {
-                            let flood_msg = serde_json::json!({
-                                "type": "slot_claim",
-                                "index": index,
-                                "peer_id": peer_id,
-                                "coord": [coord.0, coord.1, coord.2],
-                                "public_key": public_key.map(hex::encode),
-                            });
-                            let _ = writer.write_all(flood_msg.to_string().as_bytes()).await;
-                            let _ = writer.write_all(b"\n").await;
-                        }
-                        Ok(FloodMessage::SlotValidation { index, peer_id, validator_id, accepted }) => {
-                            let flood_msg = serde_json::json!({
-                                "type": "slot_validation",
-                                "index": index,
-                                "peer_id": peer_id,
-                                "validator_id": validator_id,
-                                "accepted": accepted,
-                            });
-                            let _ = writer.write_all(flood_msg.to_string().as_bytes()).await;
-                            let _ = writer.write_all(b"\n").await;
-                        }
-                        Ok(FloodMessage::SporeHaveList { peer_id, slots }) => {
-                            let flood_msg = serde_json::json!({
-                                "type": "spore_have_list",
-                                "peer_id": peer_id,
-                                "slots": slots,
-                            });
-                            let _ = writer.write_all(flood_msg.to_string().as_bytes()).await;
-                            let _ = writer.write_all(b"\n").await;
-                        }
-                        Ok(FloodMessage::VdfChain { links }) => {
-                            let flood_msg = serde_json::json!({
-                                "type": "vdf_chain",
-                                "links": links.iter().map(|l| serde_json::json!({
-                                    "height": l.height,
-                                    "output": hex::encode(l.output),
-                                    "producer": hex::encode(l.producer),
-                                    "previous": hex::encode(l.previous),
-                                    "timestamp_ms": l.timestamp_ms,
-                                })).collect::<Vec<_>>(),
-                            });
-                            let _ = writer.write_all(flood_msg.to_string().as_bytes()).await;
-                            let _ = writer.write_all(b"\n").await;
-                        }
-                        Ok(FloodMessage::VdfSlotClaim { claim }) => {
-                            let flood_msg = serde_json::json!({
-                                "type": "vdf_slot_claim",
-                                "slot": claim.slot,
-                                "claimer": hex::encode(claim.claimer),
-                                "vdf_height": claim.vdf_height,
-                                "vdf_output": hex::encode(claim.vdf_output),
-                                "signature": hex::encode(claim.signature),
-                            });
-                            let _ = writer.write_all(flood_msg.to_string().as_bytes()).await;
-                            let _ = writer.write_all(b"\n").await;
-                        }
-                        Ok(FloodMessage::PoLPing { from, nonce, vdf_height }) => {
-                            let flood_msg = serde_json::json!({
-                                "type": "pol_ping",
-                                "from": hex::encode(from),
-                                "nonce": nonce,
-                                "vdf_height": vdf_height,
-                            });
-                            let _ = writer.write_all(flood_msg.to_string().as_bytes()).await;
-                            let _ = writer.write_all(b"\n").await;
-                        }
-                        Ok(FloodMessage::PoLPong { from, nonce, vdf_height }) => {
-                            let flood_msg = serde_json::json!({
-                                "type": "pol_pong",
-                                "from": hex::encode(from),
-                                "nonce": nonce,
-                                "vdf_height": vdf_height,
-                            });
-                            let _ = writer.write_all(flood_msg.to_string().as_bytes()).await;
-                            let _ = writer.write_all(b"\n").await;
-                        }
-                        Ok(FloodMessage::PoLSwapProposal { proposal }) => {
-                            let flood_msg = serde_json::json!({
-                                "type": "pol_swap_proposal",
-                                "initiator": hex::encode(proposal.initiator),
-                                "target": hex::encode(proposal.target),
-                                "initiator_slot": proposal.initiator_slot,
-                                "target_slot": proposal.target_slot,
-                                "proposal_height": proposal.proposal_height,
-                                "proposal_vdf_output": hex::encode(proposal.proposal_vdf_output),
-                                "signature": hex::encode(proposal.signature),
-                                "initiator_proofs": proposal.initiator_proofs.iter().map(|p| serde_json::json!({
-                                    "from_node": hex::encode(p.from_node),
-                                    "to_node": hex::encode(p.to_node),
-                                    "latency_us": p.latency_us,
-                                    "vdf_height": p.vdf_height,
-                                    "vdf_output": hex::encode(p.vdf_output),
-                                    "timestamp_ms": p.timestamp_ms,
-                                    "signature": hex::encode(p.signature),
-                                })).collect::<Vec<_>>(),
-                                "initiator_at_target_proofs": proposal.initiator_at_target_proofs.iter().map(|p| serde_json::json!({
-                                    "from_node": hex::encode(p.from_node),
-                                    "to_node": hex::encode(p.to_node),
-                                    "latency_us": p.latency_us,
-                                    "vdf_height": p.vdf_height,
-                                    "vdf_output": hex::encode(p.vdf_output),
-                                    "timestamp_ms": p.timestamp_ms,
-                                    "signature": hex::encode(p.signature),
-                                })).collect::<Vec<_>>(),
-                            });
-                            let _ = writer.write_all(flood_msg.to_string().as_bytes()).await;
-                            let _ = writer.write_all(b"\n").await;
-                        }
-                        Ok(FloodMessage::PoLSwapResponse { response }) => {
-                            let flood_msg = serde_json::json!({
-                                "type": "pol_swap_response",
-                                "responder": hex::encode(response.responder),
-                                "proposal_height": response.proposal_height,
-                                "decision": match response.decision {
-                                    crate::proof_of_latency::SwapDecision::Attack => "attack",
-                                    crate::proof_of_latency::SwapDecision::Retreat => "retreat",
-                                },
-                                "response_height": response.response_height,
-                                "signature": hex::encode(response.signature),
-                                "target_proofs": response.target_proofs.iter().map(|p| serde_json::json!({
-                                    "from_node": hex::encode(p.from_node),
-                                    "to_node": hex::encode(p.to_node),
-                                    "latency_us": p.latency_us,
-                                    "vdf_height": p.vdf_height,
-                                    "vdf_output": hex::encode(p.vdf_output),
-                                    "timestamp_ms": p.timestamp_ms,
-                                    "signature": hex::encode(p.signature),
-                                })).collect::<Vec<_>>(),
-                                "target_at_initiator_proofs": response.target_at_initiator_proofs.iter().map(|p| serde_json::json!({
-                                    "from_node": hex::encode(p.from_node),
-                                    "to_node": hex::encode(p.to_node),
-                                    "latency_us": p.latency_us,
-                                    "vdf_height": p.vdf_height,
-                                    "vdf_output": hex::encode(p.vdf_output),
-                                    "timestamp_ms": p.timestamp_ms,
-                                    "signature": hex::encode(p.signature),
-                                })).collect::<Vec<_>>(),
-                            });
-                            let _ = writer.write_all(flood_msg.to_string().as_bytes()).await;
-                            let _ = writer.write_all(b"\n").await;
-                        }
-                        Ok(FloodMessage::CvdfAttestation { att }) => {
-                            let flood_msg = serde_json::json!({
-                                "type": "cvdf_attestation",
-                                "round": att.round,
-                                "slot": att.slot,
-                                "prev_output": hex::encode(att.prev_output),
-                                "attester": hex::encode(att.attester),
-                            });
-                            let _ = writer.write_all(flood_msg.to_string().as_bytes()).await;
-                            let _ = writer.write_all(b"\n").await;
-                        }
-                        Ok(FloodMessage::CvdfNewRound { round }) => {
-                            let flood_msg = serde_json::json!({
-                                "type": "cvdf_new_round",
-                                "round": round.round,
-                                "prev_output": hex::encode(round.prev_output),
-                                "washed_input": hex::encode(round.washed_input),
-                                "output": hex::encode(round.output),
-                                "producer": hex::encode(round.producer),
-                                "attestation_count": round.attestations.len(),
-                                "weight": round.weight(),
-                            });
-                            let _ = writer.write_all(flood_msg.to_string().as_bytes()).await;
-                            let _ = writer.write_all(b"\n").await;
-                        }
-                        Ok(FloodMessage::CvdfSyncRequest { from_node, from_height }) => {
-                            let flood_msg = serde_json::json!({
-                                "type": "cvdf_sync_request",
-                                "from_node": from_node,
-                                "from_height": from_height,
-                            });
-                            let _ = writer.write_all(flood_msg.to_string().as_bytes()).await;
-                            let _ = writer.write_all(b"\n").await;
-                        }
-                        Ok(FloodMessage::CvdfSyncResponse { rounds, slots }) => {
-                            // Serialize full chain data for proper sync
-                            // Rounds contain attestations, slots are (index, pubkey) pairs
-                            let rounds_json: Vec<serde_json::Value> = rounds.iter().map(|r| {
-                                serde_json::json!({
-                                    "round": r.round,
-                                    "prev_output": hex::encode(r.prev_output),
-                                    "washed_input": hex::encode(r.washed_input),
-                                    "output": hex::encode(r.output),
-                                    "producer": hex::encode(r.producer),
-                                    "producer_signature": hex::encode(r.producer_signature),
-                                    "timestamp_ms": r.timestamp_ms,
-                                    "attestations": r.attestations.iter().map(|a| {
-                                        serde_json::json!({
-                                            "round": a.round,
-                                            "prev_output": hex::encode(a.prev_output),
-                                            "attester": hex::encode(a.attester),
-                                            "slot": a.slot,
-                                            "signature": hex::encode(a.signature),
-                                        })
-                                    }).collect::<Vec<_>>(),
-                                })
-                            }).collect();
-                            let slots_json: Vec<serde_json::Value> = slots.iter().map(|(idx, pk)| {
-                                serde_json::json!({
-                                    "index": idx,
-                                    "pubkey": hex::encode(pk),
-                                })
-                            }).collect();
-                            let flood_msg = serde_json::json!({
-                                "type": "cvdf_sync_response",
-                                "rounds": rounds_json,
-                                "slots": slots_json,
-                                "height": rounds.last().map(|r| r.round).unwrap_or(0),
-                                "total_weight": rounds.iter().map(|r| r.weight() as u64).sum::<u64>(),
-                            });
-                            let _ = writer.write_all(flood_msg.to_string().as_bytes()).await;
-                            let _ = writer.write_all(b"\n").await;
-                        }
+                        Ok(msg) => match codec.encode(&msg) {
+                            Ok(bytes) => {
+                                let _ = writer.write_all(&bytes).await;
+                            }
+                            Err(e) => {
+                                warn!("Failed to encode flood for {}: {}", current_peer_key, e);
+                            }
+                        },
                         Err(_) => {
                             // Channel closed or lagged, continue
                         }
                     }
                 }
+                // Forward messages addressed to this peer
+                Some(msg) = direct_rx.recv() => {
+                    match codec.encode(&msg) {
+                        Ok(bytes) => {
+                            let _ = writer.write_all(&bytes).await;
+                        }
+                        Err(e) => {
+                            warn!("Failed to encode message for {}: {}", current_peer_key, e);
+                        }
+                    }
+                }
             }
         }
+        reader_task.abort();
 
         // Remove peer on disconnect using current key
         {
             let mut state = self.state.write().await;
             state.peers.remove(&current_peer_key);
         }
+        {
+            // A newer connection to the same peer may have replaced our sender
+            let mut senders = self.peer_tx.write().await;
+            if senders.get(&current_peer_key).is_some_and(|tx| tx.same_channel(&direct_tx)) {
+                senders.remove(&current_peer_key);
+            }
+        }
 
         Ok(())
     }
 
-    /// Handle incoming message from peer
-    /// Returns (real_peer_id, peers_to_connect) where:
-    /// - real_peer_id: Some(id) if learned from hello (for re-keying)
-    /// - peers_to_connect: Vec of (peer_id, addr) to connect to in background
-    async fn handle_message(
-        self: &Arc<Self>,
-        peer_id: &str,
-        msg: serde_json::Value,
-    ) -> Result<(Option<String>, Vec<(String, SocketAddr)>)> {
-        let msg_type = msg.get("type").and_then(|t| t.as_str()).unwrap_or("");
+    /// Handle a decoded peer message: re-key the connection when the peer identifies
+    /// itself and queue discovered peers for connection (spawned by listener).
+    /// Returns the replies to send back on this connection.
+    async fn dispatch_message(self: &Arc<Self>, current_peer_key: &mut String, msg: WireMessage) -> Vec<FloodMessage> {
+        let Ok(outcome) = self.handle_message(current_peer_key, msg).await else {
+            return Vec::new();
+        };
+        if let Some(id) = outcome.real_id {
+            *current_peer_key = id;
+        }
+        for (discovered_id, addr) in outcome.peers_to_connect {
+            let _ = self.pending_connect_tx.send((discovered_id, addr)).await;
+        }
+        outcome.replies
+    }
 
-        match msg_type {
-            "hello" => {
-                debug!("Received hello from {}: {:?}", peer_id, msg);
-                // Re-key peer entry with real PeerID and store public key for TGP
-                if let Some(node_id) = msg.get("node_id").and_then(|n| n.as_str()) {
-                    // Extract public key from hello (hex-encoded ed25519 public key)
-                    let public_key = msg.get("public_key")
-                        .and_then(|p| p.as_str())
-                        .and_then(|hex_str| hex::decode(hex_str).ok());
-
-                    // Extract listening port from hello (for TGP UDP)
-                    // We keep the IP from the TCP connection (routable) but use their listening port
-                    let listen_port = msg.get("addr")
-                        .and_then(|a| a.as_str())
-                        .and_then(|addr_str| addr_str.parse::<SocketAddr>().ok())
-                        .map(|addr| addr.port())
-                        .unwrap_or(9000);  // Default to 9000 if not provided
+    /// SPORE identity of a connected peer: BLAKE3 of its public key, matching the
+    /// ID each node gives its own sync manager in [`MeshService::new`]
+    async fn spore_peer_id(&self, peer_key: &str) -> U256 {
+        let state = self.state.read().await;
+        let hash = match state.peers.get(peer_key).and_then(|p| p.public_key.as_ref()) {
+            Some(public_key) => blake3::hash(public_key),
+            None => blake3::hash(peer_key.as_bytes()),
+        };
+        U256::from_be_bytes(hash.as_bytes())
+    }
 
-                    let mut state = self.state.write().await;
-                    // Remove temporary peer-{port} entry and re-add with real ID
-                    if let Some(mut peer) = state.peers.remove(peer_id) {
-                        // Only add if we don't already have this peer (avoid duplicates)
-                        if node_id != state.self_id && !state.peers.contains_key(node_id) {
-                            peer.id = node_id.to_string();
-                            peer.public_key = public_key;
-                            // Keep peer's IP but use their listening port (not ephemeral TCP source port)
-                            // TCP and UDP share the same port for both mesh and TGP
-                            peer.addr = SocketAddr::new(peer.addr.ip(), listen_port);
-                            peer.last_seen = std::time::Instant::now();
-                            let peer_addr = peer.addr;
-                            state.peers.insert(node_id.to_string(), peer);
-                            info!("Peer {} identified as {} at {}", peer_id, node_id, peer_addr);
-                            return Ok((Some(node_id.to_string()), vec![]));
-                        }
-                    }
+    /// Add locally produced blocks to the SPORE store and flood the ones that are new
+    async fn publish_blocks(&self, blocks: Vec<ContentBlock>) {
+        let mut new_blocks = Vec::new();
+        {
+            let mut state = self.state.write().await;
+            let Some(ref mut sync) = state.spore_sync else {
+                return;
+            };
+            for block in blocks {
+                if !sync.has_content(&block.hash) {
+                    sync.add_content(block.clone());
+                    new_blocks.push(block);
                 }
             }
-            "flood_admins" | "sync_admins" => {
-                // Merge flooded admin list into our state
-                if let Some(admins) = msg.get("admins").and_then(|a| a.as_array()) {
-                    for admin in admins {
-                        if let Some(key) = admin.as_str() {
-                            let _ = self.storage.set_admin(key, true);
-                            info!("Merged admin from {}: {}", peer_id, key);
-                        }
-                    }
-                }
+        }
+        for msg in spore_content_messages(new_blocks) {
+            self.flood(msg);
+        }
+    }
+
+    /// Publish everything replicated from storage (releases, content, categories, admins)
+    async fn publish_storage_blocks(&self) {
+        match replication::storage_blocks(&self.storage) {
+            Ok(blocks) => {
+                debug!("SPORE: {} blocks from storage", blocks.len());
+                self.publish_blocks(blocks).await;
             }
-            "flood_peers" | "sync_peers" => {
-                // Merge flooded peer list - this propagates mesh topology
-                // SPORE: only accept real peer IDs, skip those we already know
-                // Parse peer data OUTSIDE the lock to minimize lock hold time
-                let parsed_peers: Vec<_> = msg.get("peers")
-                    .and_then(|p| p.as_array())
-                    .map(|peers| {
-                        peers.iter().filter_map(|peer_info| {
-                            let id = peer_info.get("id").and_then(|i| i.as_str())?;
-                            let addr_str = peer_info.get("addr").and_then(|a| a.as_str())?;
-                            // SPORE: only accept real peer IDs (b3b3/...)
-                            if !id.starts_with("b3b3/") {
-                                return None;
-                            }
-                            let slot_index = peer_info.get("slot").and_then(|s| s.as_u64());
-                            let public_key = peer_info.get("public_key")
-                                .and_then(|p| p.as_str())
-                                .and_then(|hex_str| hex::decode(hex_str).ok());
-                            let addr: SocketAddr = addr_str.parse().ok()?;
-                            Some((id.to_string(), addr_str.to_string(), addr, slot_index, public_key))
-                        }).collect()
-                    })
-                    .unwrap_or_default();
-
-                // Now acquire lock briefly to update state
-                let mut new_peers = Vec::new();
-                if !parsed_peers.is_empty() {
-                    let mut state = self.state.write().await;
-                    for (id, addr_str, addr, slot_index, public_key) in parsed_peers {
-                        // Don't add ourselves or peers we already know
-                        if id != state.self_id && !state.peers.contains_key(&id) {
-                            let slot = slot_index.map(|idx| SlotClaim::with_public_key(idx, id.clone(), public_key.clone()));
-
-                            // Record slot claim if present (with public key for TGP)
-                            if let Some(idx) = slot_index {
-                                if !state.claimed_slots.contains_key(&idx) {
-                                    let claim = SlotClaim::with_public_key(idx, id.clone(), public_key.clone());
-                                    state.slot_coords.insert(claim.coord);
-                                    state.claimed_slots.insert(idx, claim);
-                                }
-                            }
+            Err(e) => warn!("Failed to read storage for replication: {}", e),
+        }
+    }
 
-                            state.peers.insert(
-                                id.clone(),
-                                MeshPeer {
-                                    id: id.clone(),
-                                    addr,
-                                    public_key: public_key.clone(),
-                                    last_seen: std::time::Instant::now(),
-                                    coordinated: false,
-                                    slot,
-                                },
-                            );
-                            new_peers.push((id.clone(), addr_str, slot_index, public_key));
-                            debug!("Discovered peer {} (slot {:?}) via flood from {}", id, slot_index, peer_id);
-                        }
-                    }
-                }
-                // Re-flood newly discovered peers to propagate through mesh
-                if !new_peers.is_empty() {
-                    // Collect addresses to connect (we'll connect after releasing locks)
-                    let peers_to_connect: Vec<(String, SocketAddr)> = new_peers.iter()
-                        .filter_map(|(peer_id, addr_str, _, _)| {
-                            addr_str.parse::<SocketAddr>().ok().map(|addr| (peer_id.clone(), addr))
-                        })
-                        .collect();
+    /// Mesh topology as replicated blocks: ourselves, identified peers and slot claims
+    async fn mesh_blocks(&self) -> Vec<ContentBlock> {
+        let state = self.state.read().await;
+        let mut bodies = vec![RecordBody::Peer {
+            id: state.self_id.clone(),
+            addr: self.listen_addr.to_string(),
+            public_key: Some(state.signing_key.verifying_key().to_bytes().to_vec()),
+        }];
+        // SPORE: only replicate real peer IDs (b3b3/...), never temp IDs
+        for peer in state.peers.values().filter(|p| p.id.starts_with("b3b3/")) {
+            bodies.push(RecordBody::Peer {
+                id: peer.id.clone(),
+                addr: peer.addr.to_string(),
+                public_key: peer.public_key.clone(),
+            });
+        }
+        for claim in state.claimed_slots.values() {
+            bodies.push(RecordBody::SlotClaim {
+                index: claim.index,
+                peer_id: claim.peer_id.clone(),
+                public_key: claim.public_key.clone(),
+            });
+        }
+        drop(state);
 
-                    self.flood(FloodMessage::Peers(new_peers));
+        bodies.into_iter()
+            .filter_map(|body| Record::new(0, body).to_block().ok())
+            .collect()
+    }
 
-                    // Return peers to connect - caller will spawn connections
-                    return Ok((None, peers_to_connect));
+    /// Apply replicated blocks from a peer, then store and re-flood the ones
+    /// that were accepted
+    ///
+    /// Blocks that fail to decode or apply (bad signature, no rights, stamped
+    /// in the future) are dropped, so they are neither announced nor passed on.
+    async fn receive_spore_content(&self, peer_id: &str, blocks: Vec<ContentBlock>) -> MessageOutcome {
+        let spore_id = self.spore_peer_id(peer_id).await;
+        let mut candidates = Vec::new();
+        {
+            let state = self.state.read().await;
+            let Some(ref sync) = state.spore_sync else {
+                return MessageOutcome::default();
+            };
+            for block in blocks {
+                if sync.has_content(&block.hash) {
+                    continue;
                 }
-                // Note: No bootstrap sync signal needed - CVDF swarm merge handles everything.
-                // When we receive heavier chains, we adopt them automatically.
-            }
-            "slot_claim" => {
-                // Process a slot claim from another node
-                // SPORE: re-flood new claims to propagate through mesh
-                if let (Some(index), Some(claimer_id)) = (
-                    msg.get("index").and_then(|i| i.as_u64()),
-                    msg.get("peer_id").and_then(|p| p.as_str()),
-                ) {
-                    let coord = msg.get("coord")
-                        .and_then(|c| c.as_array())
-                        .map(|arr| {
-                            let q = arr.first().and_then(|v| v.as_i64()).unwrap_or(0);
-                            let r = arr.get(1).and_then(|v| v.as_i64()).unwrap_or(0);
-                            let z = arr.get(2).and_then(|v| v.as_i64()).unwrap_or(0);
-                            (q, r, z)
-                        })
-                        .unwrap_or((0, 0, 0));
-
-                    // Extract public key if present (hex-encoded)
-                    let public_key = msg.get("public_key")
-                        .and_then(|p| p.as_str())
-                        .and_then(|hex_str| hex::decode(hex_str).ok());
-
-                    // Check if this is a new claim before processing
-                    let is_new = !self.state.read().await.claimed_slots.contains_key(&index);
-
-                    // Process the slot claim (stores public key in claim and peer)
-                    let we_lost = self.process_slot_claim(index, claimer_id.to_string(), coord, public_key.clone()).await;
-
-                    // Re-flood new claims to propagate through mesh (with public key)
-                    if is_new {
-                        self.flood(FloodMessage::SlotClaim {
-                            index,
-                            peer_id: claimer_id.to_string(),
-                            coord,
-                            public_key,
-                        });
-                    }
-
-                    // If we lost our slot, attempt to join at next available via TGP
-                    if we_lost {
-                        let mut target_slot = self.state.read().await.next_available_slot();
-                        info!("Lost slot race, attempting slot {} via TGP", target_slot);
-                        // Try slots until one succeeds
-                        while !self.attempt_slot_via_tgp(target_slot).await {
-                            target_slot += 1;
-                            if target_slot > 1000 {
-                                error!("Failed to rejoin mesh after 1000 slot attempts");
-                                break;
-                            }
-                        }
-                    }
+                match Record::from_block(&block) {
+                    Ok(record) => candidates.push((record, block)),
+                    Err(e) => warn!("Rejected content block from {}: {}", peer_id, e),
                 }
             }
-            "slot_validation" => {
-                // Process a slot validation response
-                if let (Some(index), Some(claimer_id), Some(_validator_id), Some(accepted)) = (
-                    msg.get("index").and_then(|i| i.as_u64()),
-                    msg.get("peer_id").and_then(|p| p.as_str()),
-                    msg.get("validator_id").and_then(|v| v.as_str()),
-                    msg.get("accepted").and_then(|a| a.as_bool()),
-                ) {
-                    if accepted {
-                        let mut state = self.state.write().await;
-                        if let Some(claim) = state.claimed_slots.get_mut(&index) {
-                            if claim.peer_id == claimer_id {
-                                claim.confirmations += 1;
-                                debug!("Slot {} now has {} confirmations",
-                                       index, claim.confirmations);
-                            }
-                        }
-                    }
+        }
+        if candidates.is_empty() {
+            return MessageOutcome::default();
+        }
+        debug!("SPORE: received {} new blocks from {}", candidates.len(), peer_id);
+
+        let mut received = Vec::with_capacity(candidates.len());
+        let mut discovered = Vec::new();
+        let mut we_lost = false;
+        let mut records = Vec::new();
+        for (record, block) in candidates {
+            match &record.body {
+                RecordBody::Peer { id, addr, public_key } => {
+                    discovered.push((id.clone(), addr.clone(), None, public_key.clone()));
+                }
+                RecordBody::SlotClaim { index, peer_id: claimer_id, public_key } => {
+                    let coord = spiral3d_to_coord(Spiral3DIndex::new(*index));
+                    we_lost |= self.process_slot_claim(
+                        *index,
+                        claimer_id.clone(),
+                        (coord.q, coord.r, coord.z),
+                        public_key.clone(),
+                    ).await;
+                }
+                _ => {
+                    records.push((record, block));
+                    continue;
                 }
             }
-            "spore_have_list" => {
-                // SPORE: Compare their HaveList with ours and send missing slots
-                if let Some(their_slots) = msg.get("slots").and_then(|s| s.as_array()) {
-                    let their_slots: std::collections::HashSet<u64> = their_slots
-                        .iter()
-                        .filter_map(|v| v.as_u64())
-                        .collect();
-
-                    let state = self.state.read().await;
-
-                    // Find slots we have that they don't
-                    let mut missing_slots = Vec::new();
-                    for (index, claim) in &state.claimed_slots {
-                        if !their_slots.contains(index) {
-                            missing_slots.push(claim.clone());
-                        }
-                    }
-                    drop(state);
-
-                    // Send missing slots to this peer
-                    if !missing_slots.is_empty() {
-                        info!("SPORE: Sending {} missing slots to {}", missing_slots.len(), peer_id);
-                        for claim in missing_slots {
-                            self.flood(FloodMessage::SlotClaim {
-                                index: claim.index,
-                                peer_id: claim.peer_id,
-                                coord: (claim.coord.q, claim.coord.r, claim.coord.z),
-                                public_key: claim.public_key,
-                            });
+            received.push(block);
+        }
+
+        // Storage records go in one batch, so admin operations update rights once
+        match replication::apply_remote_all(&self.storage, &records) {
+            Ok(results) => {
+                for (result, (_, block)) in results.into_iter().zip(records) {
+                    match result {
+                        Ok(true) => debug!("Applied replicated {:?} from {}", block.content_type, peer_id),
+                        Ok(false) => {}
+                        Err(e) => {
+                            warn!("Dropped replicated {:?} from {}: {}", block.content_type, peer_id, e);
+                            continue;
                         }
                     }
+                    received.push(block);
                 }
             }
-            "vdf_chain" => {
-                // VDF chain sync - try to adopt longer chain
-                if let Some(links_arr) = msg.get("links").and_then(|l| l.as_array()) {
-                    let mut links = Vec::new();
-                    for link_json in links_arr {
-                        if let (Some(height), Some(output_hex), Some(producer_hex), Some(previous_hex), Some(timestamp_ms)) = (
-                            link_json.get("height").and_then(|h| h.as_u64()),
-                            link_json.get("output").and_then(|o| o.as_str()),
-                            link_json.get("producer").and_then(|p| p.as_str()),
-                            link_json.get("previous").and_then(|p| p.as_str()),
-                            link_json.get("timestamp_ms").and_then(|t| t.as_u64()),
-                        ) {
-                            if let (Ok(output), Ok(producer), Ok(previous)) = (
-                                hex::decode(output_hex),
-                                hex::decode(producer_hex),
-                                hex::decode(previous_hex),
-                            ) {
-                                if output.len() == 32 && producer.len() == 32 && previous.len() == 32 {
-                                    let mut output_arr = [0u8; 32];
-                                    let mut producer_arr = [0u8; 32];
-                                    let mut previous_arr = [0u8; 32];
-                                    output_arr.copy_from_slice(&output);
-                                    producer_arr.copy_from_slice(&producer);
-                                    previous_arr.copy_from_slice(&previous);
-
-                                    links.push(VdfLink {
-                                        height,
-                                        output: output_arr,
-                                        producer: producer_arr,
-                                        previous: previous_arr,
-                                        timestamp_ms,
-                                    });
-                                }
-                            }
-                        }
-                    }
+            Err(e) => warn!("Failed to apply replicated records from {}: {}", peer_id, e),
+        }
+        if let Some(ref mut sync) = self.state.write().await.spore_sync {
+            for block in &received {
+                sync.receive_content(spore_id, block.clone());
+            }
+        }
+
+        // Re-flood so the blocks propagate beyond this link
+        for msg in spore_content_messages(received) {
+            self.flood(msg);
+        }
+
+        let new_peers = self.merge_peers(peer_id, discovered).await;
+        if we_lost {
+            self.rejoin_after_lost_slot().await;
+        }
+        MessageOutcome {
+            peers_to_connect: peer_addrs(&new_peers),
+            ..Default::default()
+        }
+    }
 
-                    if !links.is_empty() {
-                        let their_height = links.last().map(|l| l.height).unwrap_or(0);
-                        let our_height = self.vdf_height().await;
-                        debug!("Received VDF chain from {}: height {} (ours: {})", peer_id, their_height, our_height);
+    /// Merge a flooded peer list into our state, returning the peers that were new.
+    /// SPORE: only accept real peer IDs, skip those we already know
+    async fn merge_peers(&self, from: &str, peers: Vec<PeerEntry>) -> Vec<PeerEntry> {
+        // Parse peer data OUTSIDE the lock to minimize lock hold time
+        let parsed_peers: Vec<_> = peers.into_iter().filter_map(|(id, addr_str, slot_index, public_key)| {
+            // SPORE: only accept real peer IDs (b3b3/...)
+            if !id.starts_with("b3b3/") {
+                return None;
+            }
+            let addr: SocketAddr = addr_str.parse().ok()?;
+            Some((id, addr_str, addr, slot_index, public_key))
+        }).collect();
 
-                        // Try to adopt if longer
-                        if self.try_adopt_vdf_chain(links.clone()).await {
-                            info!("Adopted VDF chain from {} (new height: {})", peer_id, their_height);
-                            // Re-flood to propagate
-                            self.flood(FloodMessage::VdfChain { links });
+        // Now acquire lock briefly to update state
+        let mut new_peers = Vec::new();
+        if !parsed_peers.is_empty() {
+            let mut state = self.state.write().await;
+            for (id, addr_str, addr, slot_index, public_key) in parsed_peers {
+                // Don't add ourselves or peers we already know
+                if id != state.self_id && !state.peers.contains_key(&id) {
+                    let slot = slot_index.map(|idx| SlotClaim::with_public_key(idx, id.clone(), public_key.clone()));
+
+                    // Record slot claim if present (with public key for TGP)
+                    if let Some(idx) = slot_index {
+                        if !state.claimed_slots.contains_key(&idx) {
+                            let claim = SlotClaim::with_public_key(idx, id.clone(), public_key.clone());
+                            state.slot_coords.insert(claim.coord);
+                            state.claimed_slots.insert(idx, claim);
                         }
                     }
+
+                    state.peers.insert(
+                        id.clone(),
+                        MeshPeer {
+                            id: id.clone(),
+                            addr,
+                            public_key: public_key.clone(),
+                            last_seen: std::time::Instant::now(),
+                            coordinated: false,
+                            slot,
+                        },
+                    );
+                    new_peers.push((id.clone(), addr_str, slot_index, public_key));
+                    debug!("Discovered peer {} (slot {:?}) via flood from {}", id, slot_index, from);
                 }
             }
-            "vdf_slot_claim" => {
-                // VDF-anchored slot claim with priority ordering
-                if let (Some(slot), Some(claimer_hex), Some(vdf_height), Some(vdf_output_hex), Some(signature_hex)) = (
-                    msg.get("slot").and_then(|s| s.as_u64()),
-                    msg.get("claimer").and_then(|c| c.as_str()),
-                    msg.get("vdf_height").and_then(|h| h.as_u64()),
-                    msg.get("vdf_output").and_then(|o| o.as_str()),
-                    msg.get("signature").and_then(|s| s.as_str()),
-                ) {
-                    if let (Ok(claimer), Ok(vdf_output), Ok(signature)) = (
-                        hex::decode(claimer_hex),
-                        hex::decode(vdf_output_hex),
-                        hex::decode(signature_hex),
-                    ) {
-                        if claimer.len() == 32 && vdf_output.len() == 32 && signature.len() == 64 {
-                            let mut claimer_arr = [0u8; 32];
-                            let mut vdf_output_arr = [0u8; 32];
-                            let mut signature_arr = [0u8; 64];
-                            claimer_arr.copy_from_slice(&claimer);
-                            vdf_output_arr.copy_from_slice(&vdf_output);
-                            signature_arr.copy_from_slice(&signature);
-
-                            let claim = AnchoredSlotClaim {
-                                slot,
-                                claimer: claimer_arr,
-                                vdf_height,
-                                vdf_output: vdf_output_arr,
-                                signature: signature_arr,
-                            };
-
-                            debug!("Received VDF slot claim from {}: slot {} at height {}", peer_id, slot, vdf_height);
-
-                            // Process with priority ordering
-                            if self.process_vdf_claim(claim.clone()).await {
-                                // Re-flood winning claim
-                                self.flood(FloodMessage::VdfSlotClaim { claim });
-                            }
+        }
+        new_peers
+    }
+
+    /// After losing our slot to a higher-priority claim, join at the next available slot via TGP
+    async fn rejoin_after_lost_slot(&self) {
+        let mut target_slot = self.state.read().await.next_available_slot();
+        info!("Lost slot race, attempting slot {} via TGP", target_slot);
+        // Try slots until one succeeds
+        while !self.attempt_slot_via_tgp(target_slot).await {
+            target_slot += 1;
+            if target_slot > 1000 {
+                error!("Failed to rejoin mesh after 1000 slot attempts");
+                break;
+            }
+        }
+    }
+
+    // ==================== DHT METHODS ====================
+    //
+    // Requests are routed greedily toward the key's slot over single-peer
+    // channels; see crate::dht for the protocol.
+
+    /// Look up a key in the DHT, routing to the node responsible for it.
+    /// Returns `None` if the key is absent or deleted.
+    pub async fn dht_get(&self, key: DhtKey) -> Result<Option<DhtEntry>> {
+        match self.dht_request(DhtOp::Get { key }).await? {
+            DhtReply::Value(entry) => Ok(entry),
+            reply => Err(dht_reply_error(reply)),
+        }
+    }
+
+    /// Look up `name` in the shared namespace (see [`DhtKey::shared`]).
+    /// Returns `None` if the key is absent or deleted.
+    pub async fn dht_find(&self, name: DhtKey) -> Result<Option<DhtEntry>> {
+        self.dht_get(DhtKey::shared(&name)).await
+    }
+
+    /// Publish a record under `name` in the shared namespace, signed with
+    /// this node's key (see [`dht::shared_policy`] for who may write there).
+    /// Returns false if the responsible node already had a newer entry.
+    pub async fn dht_publish(&self, name: DhtKey, value: DhtValue) -> Result<bool> {
+        let mut entry = DhtEntry::new(name, value);
+        entry.sign_shared(&self.state.read().await.signing_key);
+        self.dht_put_entry(entry).await
+    }
+
+    /// Delete `name` from the shared namespace on behalf of `deleter` (hex
+    /// key of the record's uploader or an admin) with a signed tombstone.
+    pub async fn dht_unpublish(&self, name: DhtKey, deleter: &str) -> Result<bool> {
+        let mut entry = DhtEntry::tombstone(name, replication::now_ms());
+        entry.value = deleter.into();
+        entry.sign_shared(&self.state.read().await.signing_key);
+        self.dht_put_entry(entry).await
+    }
+
+    /// Store a value under `name` in this node's namespace, so only it can
+    /// write there. Returns false if the responsible node already had a
+    /// newer entry.
+    pub async fn dht_put(&self, name: DhtKey, value: DhtValue) -> Result<bool> {
+        let mut entry = DhtEntry::new(name, value);
+        entry.sign(&self.state.read().await.signing_key);
+        self.dht_put_entry(entry).await
+    }
+
+    /// Delete `name` from this node's namespace by storing a signed tombstone.
+    pub async fn dht_delete(&self, name: DhtKey) -> Result<bool> {
+        let mut entry = DhtEntry::tombstone(name, replication::now_ms());
+        entry.sign(&self.state.read().await.signing_key);
+        self.dht_put_entry(entry).await
+    }
+
+    /// Store a pre-built (possibly signed) entry at the key's responsible node.
+    pub async fn dht_put_entry(&self, entry: DhtEntry) -> Result<bool> {
+        match self.dht_request(DhtOp::Put { entry }).await? {
+            DhtReply::Stored(stored) => Ok(stored),
+            reply => Err(dht_reply_error(reply)),
+        }
+    }
+
+    /// Send an operation toward its key and wait for the answer. Applied locally
+    /// if no connected peer is closer to the key than we are.
+    async fn dht_request(&self, op: DhtOp) -> Result<DhtReply> {
+        let Some(next) = self.dht_next_hop(op.key()).await else {
+            return Ok(self.dht_apply_local(op).await);
+        };
+
+        let id: u64 = rand::random();
+        let key = *op.key();
+        let rx = self.dht_requests.lock().await.wait(id);
+        let request = FloodMessage::DhtRequest { id, hops: 0, op: op.clone() };
+        if !self.send_to_peer(&next, request).await {
+            self.dht_requests.lock().await.cancel(id);
+            return Ok(self.dht_apply_local(op).await);
+        }
+
+        match tokio::time::timeout(DHT_REQUEST_TIMEOUT, rx).await {
+            Ok(Ok(reply)) => Ok(reply),
+            _ => {
+                self.dht_requests.lock().await.cancel(id);
+                Err(Error::Network(format!("DHT request for {} via {} timed out", key, next)))
+            }
+        }
+    }
+
+    /// Connected peer to forward a request for `key` to, if any is closer than us
+    async fn dht_next_hop(&self, key: &DhtKey) -> Option<String> {
+        let mesh_size = dht::mesh_size(self.state.read().await.claimed_slots.keys());
+        self.dht_next_hop_toward(key_to_coord(key, mesh_size)).await
+    }
+
+    /// Connected peer to forward a message for slot `target` to, if any is closer than us
+    async fn dht_next_hop_toward(&self, target: HexCoord) -> Option<String> {
+        let state = self.state.read().await;
+        let senders = self.peer_tx.read().await;
+        let peers: Vec<(String, HexCoord)> = senders.keys()
+            .filter_map(|id| {
+                let coord = state.peers.get(id)
+                    .and_then(|p| p.slot.as_ref())
+                    .or_else(|| state.claimed_slots.values().find(|c| &c.peer_id == id))?
+                    .coord;
+                Some((id.clone(), coord))
+            })
+            .collect();
+        let self_coord = state.self_slot.as_ref().map(|s| s.coord);
+        dht::next_hop_toward(self_coord, target, &peers).map(String::from)
+    }
+
+    /// Answer an operation from our own shard
+    async fn dht_apply_local(&self, op: DhtOp) -> DhtReply {
+        match op {
+            DhtOp::Get { key } => match self.dht.lock().await.get(&key) {
+                Ok(entry) => DhtReply::Value(entry),
+                Err(e) => DhtReply::Failed(e.to_string()),
+            },
+            DhtOp::Put { entry } => {
+                let result = self.dht.lock().await.put_entry(entry.clone());
+                match result {
+                    Ok(stored) => {
+                        if stored {
+                            self.dht_replicate(entry).await;
                         }
+                        DhtReply::Stored(stored)
                     }
+                    Err(DhtError::Rejected(reason)) => DhtReply::Rejected(reason.to_string()),
+                    Err(DhtError::Store(e)) => DhtReply::Failed(e.to_string()),
                 }
             }
-            "pol_ping" => {
-                // Proof of Latency ping - respond with pong for RTT measurement
-                if let (Some(from_hex), Some(nonce), Some(vdf_height)) = (
-                    msg.get("from").and_then(|f| f.as_str()),
-                    msg.get("nonce").and_then(|n| n.as_u64()),
-                    msg.get("vdf_height").and_then(|h| h.as_u64()),
-                ) {
-                    if let Ok(from) = hex::decode(from_hex) {
-                        if from.len() == 32 {
-                            let mut from_arr = [0u8; 32];
-                            from_arr.copy_from_slice(&from);
-                            debug!("Received PoL ping from {}, nonce {}", peer_id, nonce);
-
-                            // Respond with pong using our public key
-                            let state = self.state.read().await;
-                            let our_pubkey = state.signing_key.verifying_key().to_bytes();
-                            drop(state);
-
-                            self.flood(FloodMessage::PoLPong {
-                                from: our_pubkey,
-                                nonce,
-                                vdf_height,
-                            });
+        }
+    }
+
+    /// Push an entry we stored to the rest of its replica set
+    async fn dht_replicate(&self, entry: DhtEntry) {
+        let view = self.dht_view().await;
+        let self_id = self.self_id().await;
+        for (coord, peer) in view.replicas(&entry.key, self.dht_replication_factor) {
+            if *peer == self_id {
+                continue;
+            }
+            if !self.dht_push(coord, peer, vec![entry.clone()]).await {
+                debug!("DHT: no route to replica {}, skipping", peer);
+            }
+        }
+    }
+
+    /// Send entries to the replica `peer` at slot `coord`: directly if we're
+    /// connected to it, otherwise forwarded toward its slot.
+    /// Returns false if the entries couldn't be sent anywhere.
+    async fn dht_push(&self, coord: HexCoord, peer: &str, entries: Vec<DhtEntry>) -> bool {
+        if self.peer_tx.read().await.contains_key(peer) {
+            return self.send_to_peer(peer, FloodMessage::DhtReplicate { entries }).await;
+        }
+        let Some(next) = self.dht_next_hop_toward(coord).await else {
+            return false;
+        };
+        let msg = FloodMessage::DhtForward { coord: (coord.q, coord.r, coord.z), hops: 0, entries };
+        self.send_to_peer(&next, msg).await
+    }
+
+    /// Store entries pushed to us as a replica
+    async fn dht_store_replicas(&self, from: &str, entries: Vec<DhtEntry>) {
+        let result = self.dht.lock().await.merge_entries(entries);
+        match result {
+            Ok(0) => {}
+            Ok(updated) => debug!("DHT: stored {} replicas from {}", updated, from),
+            Err(e) => warn!("DHT: failed to store replicas from {}: {}", from, e),
+        }
+    }
+
+    /// Every claimed slot and its holder, ourselves included
+    async fn dht_view(&self) -> MeshView<String> {
+        let state = self.state.read().await;
+        let mut occupants: HashMap<HexCoord, String> = state.claimed_slots.values()
+            .map(|claim| (claim.coord, claim.peer_id.clone()))
+            .collect();
+        if let Some(ref slot) = state.self_slot {
+            occupants.insert(slot.coord, state.self_id.clone());
+        }
+        MeshView::new(dht::mesh_size(state.claimed_slots.keys()), occupants)
+    }
+
+    /// Drop tombstones older than the tombstone horizon from our shard
+    async fn dht_gc(&self) {
+        match self.dht.lock().await.gc_tombstones() {
+            Ok(0) => {}
+            Ok(removed) => info!("DHT: dropped {} expired tombstones", removed),
+            Err(e) => warn!("DHT: tombstone GC failed: {}", e),
+        }
+    }
+
+    /// Hand off our DHT entries if the mesh changed since `old`.
+    /// Returns the current view, to compare against next time.
+    async fn dht_handoff(&self, old: MeshView<String>) -> MeshView<String> {
+        let view = self.dht_view().await;
+        if view == old {
+            return view;
+        }
+
+        let entries = match self.dht.lock().await.entries() {
+            Ok(entries) => entries,
+            Err(e) => {
+                warn!("DHT: failed to read shard for handoff: {}", e);
+                return view;
+            }
+        };
+        let self_id = self.self_id().await;
+        let coords: HashMap<&String, HexCoord> = view.occupants.iter().map(|(coord, peer)| (peer, *coord)).collect();
+        let mut outgoing: HashMap<&String, Vec<DhtEntry>> = HashMap::new();
+        for entry in entries {
+            for peer in view.handoff(&old, &entry.key, &self_id, self.dht_replication_factor) {
+                outgoing.entry(peer).or_default().push(entry.clone());
+            }
+        }
+
+        for (peer, entries) in outgoing {
+            info!("DHT: handing off {} entries to {}", entries.len(), peer);
+            for batch in entries.chunks(DHT_HANDOFF_BATCH) {
+                if !self.dht_push(coords[peer], peer, batch.to_vec()).await {
+                    debug!("DHT: no route to handoff target {}", peer);
+                    break;
+                }
+            }
+        }
+        view
+    }
+
+    // ==================== BLOB SWARM METHODS ====================
+    //
+    // Blobs are fetched range by range from every peer holding them, over the
+    // same single-peer channels as DHT requests; see crate::swarm.
+
+    /// Start fetching a blob from the mesh. Returns its progress; a blob that
+    /// is already stored is reported complete.
+    pub async fn blob_fetch(&self, cid: Cid) -> Result<SwarmProgress> {
+        if let Some(manifest) = self.blobs.manifest(&cid)? {
+            return Ok(SwarmProgress::stored(&manifest));
+        }
+        let mut downloads = self.blob_downloads.lock().await;
+        if let Some(download) = downloads.get(&cid) {
+            return Ok(download.progress());
+        }
+        let download = match self.blobs.pending(&cid)? {
+            Some(manifest) => {
+                let have = swarm::local_have(&self.blobs, &manifest)?;
+                SwarmDownload::resume(manifest, have)
+            }
+            None => SwarmDownload::new(cid),
+        };
+        let progress = download.progress();
+        downloads.insert(cid, download);
+        info!("Blob: fetching {} from the mesh", cid);
+        Ok(progress)
+    }
+
+    /// Progress of a blob download, or `None` if the blob is neither stored
+    /// nor being fetched.
+    pub async fn blob_progress(&self, cid: &Cid) -> Result<Option<SwarmProgress>> {
+        if let Some(download) = self.blob_downloads.lock().await.get(cid) {
+            return Ok(Some(download.progress()));
+        }
+        Ok(self.blobs.manifest(cid)?.map(|manifest| SwarmProgress::stored(&manifest)))
+    }
+
+    /// Pick up downloads left unfinished by a previous run
+    async fn blob_resume(&self) -> Result<()> {
+        let pending = self.blobs.all_pending()?;
+        let mut downloads = self.blob_downloads.lock().await;
+        for manifest in pending {
+            let have = swarm::local_have(&self.blobs, &manifest)?;
+            info!("Blob: resuming download of {}", manifest.cid());
+            downloads.insert(manifest.cid(), SwarmDownload::resume(manifest, have));
+        }
+        Ok(())
+    }
+
+    /// Ask peers what they have and request missing chunks from them
+    async fn blob_tick(&self) {
+        let connected: Vec<String> = self.peer_tx.read().await.keys().cloned().collect();
+        let now = std::time::Instant::now();
+        let mut outgoing = Vec::new();
+        {
+            let mut downloads = self.blob_downloads.lock().await;
+            for (cid, download) in downloads.iter_mut() {
+                for peer in download.want_targets(&connected, now) {
+                    outgoing.push((peer, FloodMessage::BlobWant { cid: *cid }));
+                }
+                for (peer, range) in download.plan(now) {
+                    outgoing.push((peer, FloodMessage::BlobRequest { cid: *cid, start: range.start, end: range.end }));
+                }
+            }
+        }
+        for (peer, msg) in outgoing {
+            if !self.send_to_peer(&peer, msg).await {
+                debug!("Blob: peer {} disconnected", peer);
+            }
+        }
+    }
+
+    /// Manifest of a blob we hold all or part of, with the chunks we have
+    fn blob_availability(&self, cid: &Cid) -> Result<Option<(BlobManifest, Spore)>> {
+        if let Some(manifest) = self.blobs.manifest(cid)? {
+            let have = swarm::chunk_range_spore(0..manifest.chunks.len() as u64);
+            return Ok(Some((manifest, have)));
+        }
+        match self.blobs.pending(cid)? {
+            Some(manifest) => {
+                let have = swarm::local_have(&self.blobs, &manifest)?;
+                Ok((!have.is_empty()).then_some((manifest, have)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Chunks of a blob for a peer's request (at most one batch)
+    fn blob_serve(&self, cid: &Cid, start: u64, end: u64) -> Result<Vec<FloodMessage>> {
+        let manifest = match self.blobs.manifest(cid)? {
+            Some(manifest) => manifest,
+            None => match self.blobs.pending(cid)? {
+                Some(manifest) => manifest,
+                None => return Ok(Vec::new()),
+            },
+        };
+        let end = end.min(start.saturating_add(SWARM_BATCH)).min(manifest.chunks.len() as u64);
+        let mut chunks = Vec::new();
+        for index in start..end {
+            if self.blobs.has_chunk(&manifest, index as usize)? {
+                let data = self.blobs.read_chunk(&manifest, index as usize)?;
+                chunks.push(FloodMessage::BlobChunk { cid: *cid, index, data });
+            }
+        }
+        Ok(chunks)
+    }
+
+    /// Record a peer's have-list, adopting its manifest if we lack one
+    async fn blob_have(&self, peer_id: &str, cid: Cid, manifest: BlobManifest, have: Spore) -> Result<()> {
+        let mut downloads = self.blob_downloads.lock().await;
+        let Some(download) = downloads.get_mut(&cid) else {
+            return Ok(());
+        };
+        if download.manifest().is_none() && manifest.cid() == cid && manifest.check().is_ok() {
+            // Chunks shared with blobs we already hold don't need fetching
+            let local = swarm::local_have(&self.blobs, &manifest)?;
+            self.blobs.begin(&manifest)?;
+            download.set_manifest(manifest, local);
+        }
+        download.peer_has(peer_id, have);
+        if download.is_complete() {
+            self.blob_finish(&mut downloads, &cid)?;
+        }
+        Ok(())
+    }
+
+    /// Store a chunk from a peer, completing the blob if it was the last one
+    async fn blob_receive(&self, peer_id: &str, cid: Cid, index: u64, data: Vec<u8>) -> Result<()> {
+        let mut downloads = self.blob_downloads.lock().await;
+        let Some(download) = downloads.get_mut(&cid) else {
+            return Ok(());
+        };
+        let Some(manifest) = download.manifest() else {
+            return Ok(());
+        };
+        if let Err(e) = self.blobs.put_chunk(manifest, index as usize, &data) {
+            warn!("Blob: bad chunk {} of {} from {}: {}", index, cid, peer_id, e);
+            download.drop_peer(peer_id);
+            return Ok(());
+        }
+        download.received(peer_id, index);
+        if download.is_complete() {
+            self.blob_finish(&mut downloads, &cid)?;
+        }
+        Ok(())
+    }
+
+    fn blob_finish(&self, downloads: &mut HashMap<Cid, SwarmDownload>, cid: &Cid) -> Result<()> {
+        if let Some(manifest) = downloads.remove(cid).and_then(|d| d.manifest().cloned()) {
+            self.blobs.finish(&manifest)?;
+            info!("Blob: {} complete ({} bytes)", cid, manifest.size);
+        }
+        Ok(())
+    }
+
+    /// Send a message to one connected peer. Returns false if not connected.
+    async fn send_to_peer(&self, peer_id: &str, msg: FloodMessage) -> bool {
+        let tx = self.peer_tx.read().await.get(peer_id).cloned();
+        match tx {
+            Some(tx) => tx.send(msg).await.is_ok(),
+            None => false,
+        }
+    }
+
+    /// Handle incoming message from peer
+    async fn handle_message(
+        self: &Arc<Self>,
+        peer_id: &str,
+        msg: WireMessage,
+    ) -> Result<MessageOutcome> {
+        let msg = match msg {
+            WireMessage::Hello(hello) => {
+                debug!("Received hello from {}: {:?}", peer_id, hello);
+                // Re-key peer entry with real PeerID and store public key for TGP
+                let node_id = hello.node_id;
+
+                // Extract listening port from hello (for TGP UDP)
+                // We keep the IP from the TCP connection (routable) but use their listening port
+                let listen_port = hello.addr.parse::<SocketAddr>()
+                    .map(|addr| addr.port())
+                    .unwrap_or(9000);  // Default to 9000 if not provided
+
+                let mut state = self.state.write().await;
+                // Remove temporary peer-{port} entry and re-add with real ID
+                if let Some(mut peer) = state.peers.remove(peer_id) {
+                    // Only add if we don't already have this peer (avoid duplicates)
+                    if node_id != state.self_id && !state.peers.contains_key(&node_id) {
+                        peer.id = node_id.clone();
+                        peer.public_key = hello.public_key;
+                        // Keep peer's IP but use their listening port (not ephemeral TCP source port)
+                        // TCP and UDP share the same port for both mesh and TGP
+                        peer.addr = SocketAddr::new(peer.addr.ip(), listen_port);
+                        peer.last_seen = std::time::Instant::now();
+                        let peer_addr = peer.addr;
+                        state.peers.insert(node_id.clone(), peer);
+                        info!("Peer {} identified as {} at {}", peer_id, node_id, peer_addr);
+                        return Ok(MessageOutcome {
+                            real_id: Some(node_id),
+                            ..Default::default()
+                        });
+                    }
+                }
+                return Ok(MessageOutcome::default());
+            }
+            WireMessage::Flood(msg) => msg,
+            // Only expected right after the hello, where handle_connection reads it
+            WireMessage::HelloProof(_) => {
+                debug!("Unexpected hello proof from {}", peer_id);
+                return Ok(MessageOutcome::default());
+            }
+            // NOTE: TGP messages are now handled over UDP, not TCP
+            // See run_tgp_udp_listener() and handle_tgp_message()
+            WireMessage::Unrecognized(what) => {
+                debug!("Unknown message from {}: {}", peer_id, what);
+                return Ok(MessageOutcome::default());
+            }
+        };
+
+        match msg {
+            FloodMessage::Admins(admins) => {
+                // Unsigned admin lists carry no authority - admin changes
+                // replicate as signed operations through SPORE (crate::acl)
+                debug!("Ignoring unsigned admin list ({} keys) from {}", admins.len(), peer_id);
+            }
+            FloodMessage::Peers(peers) => {
+                // Merge flooded peer list - this propagates mesh topology
+                let new_peers = self.merge_peers(peer_id, peers).await;
+                // Re-flood newly discovered peers to propagate through mesh
+                if !new_peers.is_empty() {
+                    // Collect addresses to connect (we'll connect after releasing locks)
+                    let peers_to_connect = peer_addrs(&new_peers);
+
+                    self.flood(FloodMessage::Peers(new_peers));
+
+                    // Return peers to connect - caller will spawn connections
+                    return Ok(MessageOutcome {
+                        peers_to_connect,
+                        ..Default::default()
+                    });
+                }
+                // Note: No bootstrap sync signal needed - CVDF swarm merge handles everything.
+                // When we receive heavier chains, we adopt them automatically.
+            }
+            FloodMessage::SlotClaim { index, peer_id: claimer_id, coord, public_key } => {
+                // Process a slot claim from another node
+                // SPORE: re-flood new claims to propagate through mesh
+
+                // Check if this is a new claim before processing
+                let is_new = !self.state.read().await.claimed_slots.contains_key(&index);
+
+                // Process the slot claim (stores public key in claim and peer)
+                let we_lost = self.process_slot_claim(index, claimer_id.clone(), coord, public_key.clone()).await;
+
+                // Re-flood new claims to propagate through mesh (with public key)
+                if is_new {
+                    self.flood(FloodMessage::SlotClaim {
+                        index,
+                        peer_id: claimer_id,
+                        coord,
+                        public_key,
+                    });
+                }
+
+                // If we lost our slot, attempt to join at next available via TGP
+                if we_lost {
+                    self.rejoin_after_lost_slot().await;
+                }
+            }
+            FloodMessage::SlotValidation { index, peer_id: claimer_id, accepted, .. } => {
+                // Process a slot validation response
+                if accepted {
+                    let mut state = self.state.write().await;
+                    if let Some(claim) = state.claimed_slots.get_mut(&index) {
+                        if claim.peer_id == claimer_id {
+                            claim.confirmations += 1;
+                            debug!("Slot {} now has {} confirmations",
+                                   index, claim.confirmations);
                         }
                     }
                 }
             }
-            "pol_pong" => {
+            FloodMessage::SporeHaveList { slots, .. } => {
+                // SPORE: Compare their HaveList with ours and send missing slots
+                let their_slots: std::collections::HashSet<u64> = slots.into_iter().collect();
+
+                let state = self.state.read().await;
+
+                // Find slots we have that they don't
+                let mut missing_slots = Vec::new();
+                for (index, claim) in &state.claimed_slots {
+                    if !their_slots.contains(index) {
+                        missing_slots.push(claim.clone());
+                    }
+                }
+                drop(state);
+
+                // Send missing slots to this peer
+                if !missing_slots.is_empty() {
+                    info!("SPORE: Sending {} missing slots to {}", missing_slots.len(), peer_id);
+                    for claim in missing_slots {
+                        self.flood(FloodMessage::SlotClaim {
+                            index: claim.index,
+                            peer_id: claim.peer_id,
+                            coord: (claim.coord.q, claim.coord.r, claim.coord.z),
+                            public_key: claim.public_key,
+                        });
+                    }
+                }
+            }
+            FloodMessage::VdfChain { links } => {
+                // VDF chain sync - try to adopt longer chain
+                if !links.is_empty() {
+                    let their_height = links.last().map(|l| l.height).unwrap_or(0);
+                    let our_height = self.vdf_height().await;
+                    debug!("Received VDF chain from {}: height {} (ours: {})", peer_id, their_height, our_height);
+
+                    // Try to adopt if longer
+                    if self.try_adopt_vdf_chain(links.clone()).await {
+                        info!("Adopted VDF chain from {} (new height: {})", peer_id, their_height);
+                        // Re-flood to propagate
+                        self.flood(FloodMessage::VdfChain { links });
+                    }
+                }
+            }
+            FloodMessage::VdfSlotClaim { claim } => {
+                // VDF-anchored slot claim with priority ordering
+                debug!("Received VDF slot claim from {}: slot {} at height {}", peer_id, claim.slot, claim.vdf_height);
+
+                // Process with priority ordering
+                if self.process_vdf_claim(claim.clone()).await {
+                    // Re-flood winning claim
+                    self.flood(FloodMessage::VdfSlotClaim { claim });
+                }
+            }
+            FloodMessage::PoLPing { nonce, vdf_height, .. } => {
+                // Proof of Latency ping - respond with pong for RTT measurement
+                debug!("Received PoL ping from {}, nonce {}", peer_id, nonce);
+
+                // Respond with pong using our public key
+                let state = self.state.read().await;
+                let our_pubkey = state.signing_key.verifying_key().to_bytes();
+                drop(state);
+
+                self.flood(FloodMessage::PoLPong {
+                    from: our_pubkey,
+                    nonce,
+                    vdf_height,
+                });
+            }
+            FloodMessage::PoLPong { from, nonce, vdf_height } => {
                 // Proof of Latency pong - complete latency measurement
-                if let (Some(from_hex), Some(nonce), Some(vdf_height)) = (
-                    msg.get("from").and_then(|f| f.as_str()),
-                    msg.get("nonce").and_then(|n| n.as_u64()),
-                    msg.get("vdf_height").and_then(|h| h.as_u64()),
-                ) {
-                    if let Ok(from) = hex::decode(from_hex) {
-                        if from.len() == 32 {
-                            let mut from_arr = [0u8; 32];
-                            from_arr.copy_from_slice(&from);
-
-                            // Check if this pong is for one of our pending pings
-                            let mut state = self.state.write().await;
-                            if let Some(target) = state.pol_pending_pings.remove(&nonce) {
-                                if target == from_arr {
-                                    // Complete the latency measurement in PoL manager
-                                    // Get VDF output from chain tip
-                                    let vdf_output = state.vdf_race.as_ref()
-                                        .and_then(|v| v.chain_links().last())
-                                        .map(|l| l.output)
-                                        .unwrap_or([0u8; 32]);
-
-                                    if let Some(ref mut pol) = state.pol_manager {
-                                        if let Some(proof) = pol.complete_ping(from_arr, vdf_height, vdf_output) {
-                                            debug!("PoL: measured latency to {} = {}µs", peer_id, proof.latency_us);
-                                        }
-                                    }
-                                }
+                // Check if this pong is for one of our pending pings
+                let mut state = self.state.write().await;
+                if let Some(target) = state.pol_pending_pings.remove(&nonce) {
+                    if target == from {
+                        // Complete the latency measurement in PoL manager
+                        // Get VDF output from chain tip
+                        let vdf_output = state.vdf_race.as_ref()
+                            .and_then(|v| v.chain_links().last())
+                            .map(|l| l.output)
+                            .unwrap_or([0u8; 32]);
+
+                        if let Some(ref mut pol) = state.pol_manager {
+                            if let Some(proof) = pol.complete_ping(from, vdf_height, vdf_output) {
+                                debug!("PoL: measured latency to {} = {}µs", peer_id, proof.latency_us);
                             }
                         }
                     }
                 }
             }
-            "pol_swap_proposal" => {
+            FloodMessage::PoLSwapProposal { .. } => {
                 // Proof of Latency swap proposal - check if we should accept
                 debug!("Received PoL swap proposal from {}", peer_id);
-                // Full implementation would parse the proposal and call pol_manager.process_proposal()
+                // Full implementation would call pol_manager.process_proposal()
                 // For now, log and skip - swap handling requires bidirectional communication
             }
-            "pol_swap_response" => {
+            FloodMessage::PoLSwapResponse { .. } => {
                 // Proof of Latency swap response - process decision
                 debug!("Received PoL swap response from {}", peer_id);
-                // Full implementation would parse the response and call pol_manager.process_response()
+                // Full implementation would call pol_manager.process_response()
                 // For now, log and skip - swap handling requires bidirectional communication
             }
             // ==================== CVDF MESSAGE HANDLERS ====================
-            "cvdf_attestation" => {
-                // Parse attestation and process it
-                if let (Some(round), Some(slot), Some(prev_output_hex), Some(attester_hex), Some(sig_hex)) = (
-                    msg.get("round").and_then(|r| r.as_u64()),
-                    msg.get("slot").and_then(|s| s.as_u64()),
-                    msg.get("prev_output").and_then(|p| p.as_str()),
-                    msg.get("attester").and_then(|a| a.as_str()),
-                    msg.get("signature").and_then(|s| s.as_str()),
-                ) {
-                    if let (Ok(prev_output), Ok(attester), Ok(signature)) = (
-                        hex::decode(prev_output_hex),
-                        hex::decode(attester_hex),
-                        hex::decode(sig_hex),
-                    ) {
-                        if prev_output.len() == 32 && attester.len() == 32 && signature.len() == 64 {
-                            let att = RoundAttestation {
-                                round,
-                                prev_output: prev_output.try_into().unwrap(),
-                                attester: attester.try_into().unwrap(),
-                                slot: Some(slot),
-                                signature: signature.try_into().unwrap(),
-                            };
-                            if self.cvdf_process_attestation(att).await {
-                                debug!("Processed CVDF attestation for round {} from {}", round, peer_id);
-                            }
-                        }
-                    }
+            FloodMessage::CvdfAttestation { att } => {
+                let round = att.round;
+                if self.cvdf_process_attestation(att).await {
+                    debug!("Processed CVDF attestation for round {} from {}", round, peer_id);
                 }
             }
-            "cvdf_new_round" => {
-                // Parse and process new round
-                // Note: This requires full round data including attestations
-                debug!("Received cvdf_new_round from {} (round {})", peer_id,
-                    msg.get("round").and_then(|r| r.as_u64()).unwrap_or(0));
-                // Full round processing requires attestations array - handled by flood
+            FloodMessage::CvdfNewRound { round } => {
+                // Rounds are adopted through cvdf_sync_response (heaviest chain wins)
+                debug!("Received cvdf_new_round from {} (round {})", peer_id, round.round);
             }
-            "cvdf_sync_request" => {
+            FloodMessage::CvdfSyncRequest { from_height, .. } => {
                 // Respond with our chain state
-                if let Some(from_height) = msg.get("from_height").and_then(|h| h.as_u64()) {
-                    debug!("Received CVDF sync request from {} (from_height {})", peer_id, from_height);
-                    // Send our chain state via flood
-                    if let Some((rounds, slots)) = self.cvdf_chain_state().await {
-                        self.flood(FloodMessage::CvdfSyncResponse { rounds, slots });
-                    }
+                debug!("Received CVDF sync request from {} (from_height {})", peer_id, from_height);
+                // Send our chain state via flood
+                if let Some((rounds, slots)) = self.cvdf_chain_state().await {
+                    self.flood(FloodMessage::CvdfSyncResponse { rounds, slots });
                 }
             }
-            "cvdf_sync_response" => {
-                // Parse chain data, adopt if heavier, process slots with tiebreaker
+            FloodMessage::CvdfSyncResponse { rounds: parsed_rounds, slots: parsed_slots } => {
+                // Adopt chain if heavier, process slots with tiebreaker
                 debug!("Received CVDF sync response from {}", peer_id);
 
-                // Parse rounds
-                let mut parsed_rounds: Vec<CvdfRound> = Vec::new();
-                if let Some(rounds_arr) = msg.get("rounds").and_then(|r| r.as_array()) {
-                    for round_json in rounds_arr {
-                        if let (
-                            Some(round_num), Some(prev_output_hex), Some(washed_input_hex),
-                            Some(output_hex), Some(producer_hex), Some(producer_sig_hex),
-                            Some(timestamp_ms), Some(attestations_arr)
-                        ) = (
-                            round_json.get("round").and_then(|r| r.as_u64()),
-                            round_json.get("prev_output").and_then(|p| p.as_str()),
-                            round_json.get("washed_input").and_then(|w| w.as_str()),
-                            round_json.get("output").and_then(|o| o.as_str()),
-                            round_json.get("producer").and_then(|p| p.as_str()),
-                            round_json.get("producer_signature").and_then(|s| s.as_str()),
-                            round_json.get("timestamp_ms").and_then(|t| t.as_u64()),
-                            round_json.get("attestations").and_then(|a| a.as_array()),
-                        ) {
-                            // Parse byte arrays
-                            let prev_output = hex::decode(prev_output_hex).ok();
-                            let washed_input = hex::decode(washed_input_hex).ok();
-                            let output = hex::decode(output_hex).ok();
-                            let producer = hex::decode(producer_hex).ok();
-                            let producer_sig = hex::decode(producer_sig_hex).ok();
-
-                            if let (Some(prev), Some(washed), Some(out), Some(prod), Some(sig)) =
-                                (prev_output, washed_input, output, producer, producer_sig)
-                            {
-                                if prev.len() == 32 && washed.len() == 32 && out.len() == 32 &&
-                                   prod.len() == 32 && sig.len() == 64
-                                {
-                                    // Parse attestations
-                                    let mut attestations = Vec::new();
-                                    for att_json in attestations_arr {
-                                        if let (Some(att_round), Some(att_prev_hex), Some(att_attester_hex), Some(att_sig_hex)) = (
-                                            att_json.get("round").and_then(|r| r.as_u64()),
-                                            att_json.get("prev_output").and_then(|p| p.as_str()),
-                                            att_json.get("attester").and_then(|a| a.as_str()),
-                                            att_json.get("signature").and_then(|s| s.as_str()),
-                                        ) {
-                                            let att_prev = hex::decode(att_prev_hex).ok();
-                                            let att_attester = hex::decode(att_attester_hex).ok();
-                                            let att_sig = hex::decode(att_sig_hex).ok();
-                                            let att_slot = att_json.get("slot").and_then(|s| s.as_u64());
-
-                                            if let (Some(ap), Some(aa), Some(asig)) = (att_prev, att_attester, att_sig) {
-                                                if ap.len() == 32 && aa.len() == 32 && asig.len() == 64 {
-                                                    attestations.push(RoundAttestation {
-                                                        round: att_round,
-                                                        prev_output: ap.try_into().unwrap(),
-                                                        attester: aa.try_into().unwrap(),
-                                                        slot: att_slot,
-                                                        signature: asig.try_into().unwrap(),
-                                                    });
-                                                }
-                                            }
-                                        }
-                                    }
-
-                                    parsed_rounds.push(CvdfRound {
-                                        round: round_num,
-                                        prev_output: prev.try_into().unwrap(),
-                                        washed_input: washed.try_into().unwrap(),
-                                        output: out.try_into().unwrap(),
-                                        producer: prod.try_into().unwrap(),
-                                        producer_signature: sig.try_into().unwrap(),
-                                        timestamp_ms,
-                                        attestations,
-                                    });
-                                }
-                            }
-                        }
-                    }
-                }
-
-                // Parse slots
-                let mut parsed_slots: Vec<(u64, [u8; 32])> = Vec::new();
-                if let Some(slots_arr) = msg.get("slots").and_then(|s| s.as_array()) {
-                    for slot_json in slots_arr {
-                        if let (Some(index), Some(pubkey_hex)) = (
-                            slot_json.get("index").and_then(|i| i.as_u64()),
-                            slot_json.get("pubkey").and_then(|p| p.as_str()),
-                        ) {
-                            if let Ok(pubkey) = hex::decode(pubkey_hex) {
-                                if pubkey.len() == 32 {
-                                    parsed_slots.push((index, pubkey.try_into().unwrap()));
-                                }
-                            }
-                        }
-                    }
-                }
-
                 // Check if we should adopt this chain
                 if !parsed_rounds.is_empty() && self.cvdf_should_adopt(&parsed_rounds).await {
                     let their_height = parsed_rounds.last().map(|r| r.round).unwrap_or(0);
@@ -2684,14 +3044,108 @@ impl MeshService {
                 }
             }
             // ==================== END CVDF MESSAGE HANDLERS ====================
-            // NOTE: TGP messages are now handled over UDP, not TCP
-            // See run_tgp_udp_listener() and handle_tgp_message()
-            _ => {
-                debug!("Unknown message type from {}: {}", peer_id, msg_type);
+            FloodMessage::SporeSync { msg } => {
+                // Reply with what we have that they want - the XOR difference
+                let spore_id = self.spore_peer_id(peer_id).await;
+                let blocks = {
+                    let mut state = self.state.write().await;
+                    match state.spore_sync {
+                        Some(ref mut sync) => {
+                            if !sync.receive_spore_message(spore_id, msg) {
+                                warn!("SPORE: Rejected message with bad signature from {}", peer_id);
+                                return Ok(MessageOutcome::default());
+                            }
+                            sync.blocks_to_send(&spore_id)
+                        }
+                        None => Vec::new(),
+                    }
+                };
+                if !blocks.is_empty() {
+                    info!("SPORE: Sending {} missing blocks to {}", blocks.len(), peer_id);
+                    return Ok(MessageOutcome {
+                        replies: spore_content_messages(blocks),
+                        ..Default::default()
+                    });
+                }
+            }
+            FloodMessage::SporeContent { blocks } => {
+                return Ok(self.receive_spore_content(peer_id, blocks).await);
+            }
+            FloodMessage::DhtRequest { id, hops, op } => {
+                // Forward toward the key, or answer if no peer is closer
+                if hops < MAX_DHT_HOPS {
+                    if let Some(next) = self.dht_next_hop(op.key()).await {
+                        self.dht_requests.lock().await.forwarded(id, peer_id.to_string());
+                        let request = FloodMessage::DhtRequest { id, hops: hops + 1, op: op.clone() };
+                        if self.send_to_peer(&next, request).await {
+                            debug!("DHT: forwarded request {} for {} to {}", id, op.key(), next);
+                            return Ok(MessageOutcome::default());
+                        }
+                    }
+                }
+                let reply = self.dht_apply_local(op).await;
+                return Ok(MessageOutcome {
+                    replies: vec![FloodMessage::DhtResponse { id, reply }],
+                    ..Default::default()
+                });
+            }
+            FloodMessage::DhtResponse { id, reply } => {
+                let next = self.dht_requests.lock().await.respond(id, reply);
+                if let Some((prev, reply)) = next {
+                    if !self.send_to_peer(&prev, FloodMessage::DhtResponse { id, reply }).await {
+                        debug!("DHT: response {} lost, {} disconnected", id, prev);
+                    }
+                }
+            }
+            FloodMessage::DhtReplicate { entries } => {
+                self.dht_store_replicas(peer_id, entries).await;
+            }
+            FloodMessage::DhtForward { coord, hops, entries } => {
+                // Store if we hold the slot, otherwise pass it on toward it
+                let target = HexCoord::new(coord.0, coord.1, coord.2);
+                let ours = self.state.read().await.self_slot.as_ref().map(|s| s.coord) == Some(target);
+                if ours {
+                    self.dht_store_replicas(peer_id, entries).await;
+                } else if hops >= MAX_DHT_HOPS {
+                    debug!("DHT: dropping replicas for slot {:?}, hop limit reached", coord);
+                } else if let Some(next) = self.dht_next_hop_toward(target).await {
+                    let msg = FloodMessage::DhtForward { coord, hops: hops + 1, entries };
+                    if !self.send_to_peer(&next, msg).await {
+                        debug!("DHT: replicas for slot {:?} lost, {} disconnected", coord, next);
+                    }
+                } else {
+                    debug!("DHT: no route to slot {:?}, dropping replicas", coord);
+                }
+            }
+            FloodMessage::BlobWant { cid } => match self.blob_availability(&cid) {
+                Ok(Some((manifest, have))) => {
+                    return Ok(MessageOutcome {
+                        replies: vec![FloodMessage::BlobHave { cid, manifest, have }],
+                        ..Default::default()
+                    });
+                }
+                Ok(None) => {}
+                Err(e) => warn!("Blob: failed to check {} for {}: {}", cid, peer_id, e),
+            },
+            FloodMessage::BlobHave { cid, manifest, have } => {
+                if let Err(e) = self.blob_have(peer_id, cid, manifest, have).await {
+                    warn!("Blob: failed to record have-list for {}: {}", cid, e);
+                }
+            }
+            FloodMessage::BlobRequest { cid, start, end } => match self.blob_serve(&cid, start, end) {
+                Ok(replies) => {
+                    return Ok(MessageOutcome { replies, ..Default::default() });
+                }
+                Err(e) => warn!("Blob: failed to serve {} to {}: {}", cid, peer_id, e),
+            },
+            FloodMessage::BlobChunk { cid, index, data } => {
+                if let Err(e) = self.blob_receive(peer_id, cid, index, data).await {
+                    warn!("Blob: failed to store chunk of {}: {}", cid, e);
+                }
             }
         }
 
-        Ok((None, vec![]))
+        Ok(MessageOutcome::default())
     }
 }
 
@@ -2709,6 +3163,212 @@ mod tests {
         citadel_protocols::KeyPair::from_seed(&secret_bytes).expect("valid 32-byte seed")
     }
 
+    /// Start a node that accepts mesh connections at `slot` without joining via TGP
+    async fn start_dht_node(slot: u64) -> (Arc<MeshService>, tempfile::TempDir) {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(Storage::open(dir.path()).unwrap());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let node = Arc::new(MeshService::new(listener.local_addr().unwrap(), Vec::new(), storage));
+        assert!(node.claim_slot(slot).await);
+
+        let acceptor = Arc::clone(&node);
+        tokio::spawn(async move {
+            while let Ok((stream, addr)) = listener.accept().await {
+                let node = Arc::clone(&acceptor);
+                tokio::spawn(async move {
+                    let _ = node.handle_connection(stream, addr).await;
+                });
+            }
+        });
+        (node, dir)
+    }
+
+    async fn connect_dht_nodes(from: &Arc<MeshService>, to: &Arc<MeshService>) {
+        let stream = TcpStream::connect(to.listen_addr).await.unwrap();
+        let addr = stream.peer_addr().unwrap();
+        let node = Arc::clone(from);
+        tokio::spawn(async move {
+            let _ = node.handle_connection(stream, addr).await;
+        });
+    }
+
+    async fn wait_until_connected(node: &MeshService, peers: &[String]) {
+        for _ in 0..250 {
+            let senders = node.peer_tx.read().await;
+            if peers.iter().all(|id| senders.contains_key(id)) {
+                return;
+            }
+            drop(senders);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("peers {:?} never connected", peers);
+    }
+
+    /// Three nodes in a line, A - B - C: A's requests for a key owned by C's slot
+    /// must travel through B, and C replicates what it stores to its neighbor B.
+    #[tokio::test]
+    async fn test_dht_multi_hop_get_put() {
+        use citadel_dht::{hash_key, key_to_slot};
+
+        let coord = |index: u64| spiral3d_to_coord(Spiral3DIndex::new(index));
+        let c_slot = (1..200)
+            .find(|&c| HexCoord::ORIGIN.distance(&coord(c)) == 2)
+            .unwrap();
+        let b_slot = (1..c_slot)
+            .find(|&b| HexCoord::ORIGIN.distance(&coord(b)) == 1 && coord(b).distance(&coord(c_slot)) == 1)
+            .unwrap();
+
+        let (a, _a_dir) = start_dht_node(0).await;
+        let (b, _b_dir) = start_dht_node(b_slot).await;
+        let (c, _c_dir) = start_dht_node(c_slot).await;
+        let nodes = [&a, &b, &c];
+
+        // Everyone agrees on the slot map
+        let mut claims = Vec::new();
+        for node in nodes {
+            claims.push(node.state.read().await.self_slot.clone().unwrap());
+        }
+        for node in nodes {
+            let mut state = node.state.write().await;
+            for claim in &claims {
+                state.slot_coords.insert(claim.coord);
+                state.claimed_slots.insert(claim.index, claim.clone());
+            }
+        }
+
+        connect_dht_nodes(&a, &b).await;
+        connect_dht_nodes(&c, &b).await;
+        let (a_id, b_id, c_id) = (a.self_id().await, b.self_id().await, c.self_id().await);
+        wait_until_connected(&a, std::slice::from_ref(&b_id)).await;
+        wait_until_connected(&b, &[a_id, c_id.clone()]).await;
+        wait_until_connected(&c, std::slice::from_ref(&b_id)).await;
+        assert!(!a.peer_tx.read().await.contains_key(&c_id));
+
+        let mesh_size = c_slot + 1;
+        let a_key = a.state.read().await.signing_key.verifying_key().to_bytes();
+        let (name, key) = (0u32..)
+            .map(|i| hash_key(format!("release:{}", i).as_bytes()))
+            .map(|name| (name, DhtKey::owned(&a_key, &name)))
+            .find(|(_, key)| key_to_slot(key, mesh_size) == c_slot)
+            .unwrap();
+
+        assert!(a.dht_put(name, "hello".into()).await.unwrap());
+
+        // Stored at the responsible node
+        assert_eq!(c.dht.lock().await.get(&key).unwrap().unwrap().value.as_str(), Some("hello"));
+
+        let entry = a.dht_get(key).await.unwrap().unwrap();
+        assert_eq!(entry.value.as_str(), Some("hello"));
+        assert!(a.dht_get(hash_key(b"missing")).await.unwrap().is_none());
+
+        // The key is in A's namespace - C can't overwrite it
+        let mut hijack = DhtEntry::signed(name, "hijack".into(), replication::now_ms(), &c.state.read().await.signing_key);
+        hijack.key = key;
+        assert!(c.dht_put_entry(hijack).await.is_err());
+
+        // B holds a replica, and so does A, which C reaches only through B
+        for _ in 0..250 {
+            if b.dht.lock().await.get(&key).unwrap().is_some() && a.dht.lock().await.get(&key).unwrap().is_some() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("replicas never received the entry");
+    }
+
+    /// B publishes a release and A, an admin, deletes it: every node finds
+    /// the record with one lookup and the deletion sticks, while C, which
+    /// holds no rights, can't write the key at all.
+    #[tokio::test]
+    async fn test_dht_shared_delete_sticks() {
+        use citadel_dht::hash_prefixed_key;
+
+        let (a, _a_dir) = start_dht_node(0).await;
+        let (b, _b_dir) = start_dht_node(1).await;
+        let (c, _c_dir) = start_dht_node(2).await;
+        let nodes = [&a, &b, &c];
+        let mut claims = Vec::new();
+        let mut keys = Vec::new();
+        for node in nodes {
+            let state = node.state.read().await;
+            claims.push(state.self_slot.clone().unwrap());
+            keys.push(hex::encode(state.signing_key.verifying_key().as_bytes()));
+        }
+        for node in nodes {
+            let mut state = node.state.write().await;
+            for claim in &claims {
+                state.slot_coords.insert(claim.coord);
+                state.claimed_slots.insert(claim.index, claim.clone());
+            }
+            drop(state);
+            node.storage.add_root_admin(&keys[0]).unwrap();
+            crate::acl::rebuild(&node.storage).unwrap();
+            node.storage.grant_permission(&keys[1], "upload").unwrap();
+        }
+
+        connect_dht_nodes(&b, &a).await;
+        connect_dht_nodes(&c, &a).await;
+        connect_dht_nodes(&c, &b).await;
+        let ids = [a.self_id().await, b.self_id().await, c.self_id().await];
+        wait_until_connected(&c, &ids[..2]).await;
+        wait_until_connected(&b, &ids[..1]).await;
+
+        let uploader = "cd".repeat(32);
+        let name = hash_prefixed_key(crate::models::Release::DHT_PREFIX, "r1");
+        let release = serde_json::json!({ "id": "r1", "uploader": uploader }).to_string();
+        assert!(b.dht_publish(name, release.into()).await.unwrap());
+        assert!(c.dht_find(name).await.unwrap().is_some());
+        assert!(c.dht_publish(name, "forged".into()).await.is_err());
+
+        // Only the uploader or an admin may delete it
+        assert!(b.dht_unpublish(name, &keys[2]).await.is_err());
+        assert!(a.dht_unpublish(name, &keys[0]).await.unwrap());
+        for node in nodes {
+            assert!(node.dht_find(name).await.unwrap().is_none());
+        }
+    }
+
+    /// A and B each hold part of a blob; C must fetch from both to get all of it.
+    #[tokio::test]
+    async fn test_blob_swarm_download() {
+        use crate::blob::CHUNK_SIZE;
+
+        let (a, _a_dir) = start_dht_node(0).await;
+        let (b, _b_dir) = start_dht_node(1).await;
+        let (c, _c_dir) = start_dht_node(2).await;
+
+        let data: Vec<u8> = (0..40 * CHUNK_SIZE).map(|i| (i % 251) as u8 ^ (i / CHUNK_SIZE) as u8).collect();
+        let manifest = BlobManifest::for_data(&data);
+        let cid = manifest.cid();
+        for (node, chunks) in [(&a, 0..20), (&b, 16..40)] {
+            node.blobs.begin(&manifest).unwrap();
+            for index in chunks {
+                let chunk = &data[index * CHUNK_SIZE..(index + 1) * CHUNK_SIZE];
+                node.blobs.put_chunk(&manifest, index, chunk).unwrap();
+            }
+        }
+
+        connect_dht_nodes(&c, &a).await;
+        connect_dht_nodes(&c, &b).await;
+        wait_until_connected(&c, &[a.self_id().await, b.self_id().await]).await;
+
+        let progress = c.blob_fetch(cid).await.unwrap();
+        assert!(!progress.complete);
+        assert_eq!(progress.chunks, None);
+
+        for _ in 0..500 {
+            c.blob_tick().await;
+            if c.blob_progress(&cid).await.unwrap().unwrap().complete {
+                let read = c.blobs.read_range(&cid, 0..data.len() as u64).unwrap().unwrap();
+                assert!(read == data, "downloaded blob differs");
+                assert!(c.blobs.pending(&cid).unwrap().is_none());
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("download never completed: {:?}", c.blob_progress(&cid).await.unwrap());
+    }
+
     /// Test SYMMETRIC TGP handshake - both peers use same constructor, roles assigned by key comparison
     #[test]
     fn test_tgp_symmetric_handshake() {
@@ -0,0 +1,399 @@
+//! Schema migrations for persisted data.
+//!
+//! Two kinds of change are versioned here:
+//!
+//! - **Model schemas.** Releases and content items carry a `schema_version`.
+//!   When a model changes, its `SCHEMA_VERSION` is bumped and a
+//!   [`RecordMigration`] rewrites the JSON of the previous version into the new
+//!   shape. Migrations chain, so a record several versions behind is upgraded
+//!   step by step. Every read goes through [`decode`], so records from
+//!   peers, the DHT and storage are upgraded as they are read, and nodes on
+//!   older builds can keep syncing.
+//! - **Storage layout.** Numbered [`StorageMigration`] steps (such as new
+//!   indexes) run once each, in order, when [`Storage::open`] is called. The
+//!   last applied step is kept under `meta:schema_version`.
+//!
+//! When the model schema versions differ from those storage was last opened
+//! with, [`run`] also rewrites stored records with [`upgrade_records`], so a
+//! model change needs only its record migration and stored records are
+//! rewritten once rather than upgraded on every read.
+
+use crate::error::{Error, Result};
+use crate::models::{ContentItem, Release};
+use crate::storage::Storage;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Records rewritten per write batch by [`upgrade_records`].
+pub const MIGRATION_BATCH: usize = 500;
+
+/// Schema version of records written before versions were bumped.
+const INITIAL_SCHEMA_VERSION: &str = "1.0.0";
+
+/// A persisted model with a schema version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelKind {
+    Release,
+    ContentItem,
+}
+
+impl ModelKind {
+    /// Schema version this build writes.
+    pub fn schema_version(self) -> &'static str {
+        match self {
+            ModelKind::Release => Release::SCHEMA_VERSION,
+            ModelKind::ContentItem => ContentItem::SCHEMA_VERSION,
+        }
+    }
+
+    /// Storage key prefix.
+    fn prefix(self) -> &'static str {
+        match self {
+            ModelKind::Release => "release:",
+            ModelKind::ContentItem => "content:",
+        }
+    }
+}
+
+/// A model that can be upgraded from older schema versions.
+pub trait Versioned: DeserializeOwned {
+    const KIND: ModelKind;
+}
+
+impl Versioned for Release {
+    const KIND: ModelKind = ModelKind::Release;
+}
+
+impl Versioned for ContentItem {
+    const KIND: ModelKind = ModelKind::ContentItem;
+}
+
+/// Rewrites a model's JSON from one schema version to the next.
+#[derive(Debug, Clone, Copy)]
+pub struct RecordMigration {
+    pub kind: ModelKind,
+    pub from: &'static str,
+    pub to: &'static str,
+    /// Reshape the JSON object; `schema_version` is set to `to` afterwards
+    pub upgrade: fn(&mut serde_json::Map<String, Value>),
+}
+
+/// A one-off change to stored data.
+#[derive(Debug, Clone, Copy)]
+pub struct StorageMigration {
+    /// Storage schema version after this step; steps run in ascending order
+    pub version: u32,
+    pub description: &'static str,
+    /// Apply the step (or with `dry_run`, only count what it would touch),
+    /// returning the number of records affected
+    pub run: fn(&Storage, &Registry, bool) -> Result<usize>,
+}
+
+/// The migrations known to a build.
+#[derive(Debug, Clone, Copy)]
+pub struct Registry {
+    pub records: &'static [RecordMigration],
+    pub steps: &'static [StorageMigration],
+}
+
+/// Migrations for this build.
+pub const BUILTIN: Registry = Registry {
+    records: &[],
+    steps: &[
+        StorageMigration {
+            version: 1,
+            description: "build release listing indexes",
+            run: |storage, _, dry_run| rebuild(storage, dry_run, Storage::rebuild_release_indexes),
+        },
+        StorageMigration {
+            version: 2,
+            description: "build full-text search index",
+            run: |storage, _, dry_run| rebuild(storage, dry_run, Storage::rebuild_search_index),
+        },
+        StorageMigration {
+            version: 3,
+            description: "build OAI-PMH datestamp index",
+            run: |storage, _, dry_run| rebuild(storage, dry_run, Storage::rebuild_oai_index),
+        },
+    ],
+};
+
+fn rebuild(storage: &Storage, dry_run: bool, rebuild: fn(&Storage) -> Result<()>) -> Result<usize> {
+    let records = storage.list_releases()?.len() + storage.list_content_items()?.len();
+    if !dry_run {
+        rebuild(storage)?;
+    }
+    Ok(records)
+}
+
+impl Registry {
+    /// Storage schema version once every step has run.
+    pub fn latest(&self) -> u32 {
+        self.steps.iter().map(|step| step.version).max().unwrap_or(0)
+    }
+
+    /// Upgrade a model's JSON to the schema version this build writes.
+    ///
+    /// Returns whether anything changed. Versions with no migration (such as
+    /// one written by a newer build) are left for serde to read as best it can.
+    pub fn upgrade(&self, kind: ModelKind, value: &mut Value) -> bool {
+        let Value::Object(object) = value else {
+            return false;
+        };
+        let mut changed = false;
+        // Bounded, in case a bad registry has a cycle
+        for _ in 0..=self.records.len() {
+            let version = object
+                .get("schema_version")
+                .and_then(Value::as_str)
+                .unwrap_or(INITIAL_SCHEMA_VERSION)
+                .to_string();
+            if version == kind.schema_version() {
+                break;
+            }
+            let Some(migration) = self.records.iter().find(|m| m.kind == kind && m.from == version) else {
+                break;
+            };
+            (migration.upgrade)(object);
+            object.insert("schema_version".into(), migration.to.into());
+            changed = true;
+        }
+        changed
+    }
+}
+
+/// Decode a model, upgrading it from an older schema version first.
+pub fn decode<T: Versioned>(data: &[u8]) -> Result<T> {
+    let mut value: Value = serde_json::from_slice(data)?;
+    BUILTIN.upgrade(T::KIND, &mut value);
+    Ok(serde_json::from_value(value)?)
+}
+
+/// Model schema versions this build writes, as kept under
+/// `meta:model_versions`.
+fn model_versions() -> String {
+    [ModelKind::Release, ModelKind::ContentItem].map(ModelKind::schema_version).join(",")
+}
+
+/// Rewrite stored records of every model kind to the current schema version,
+/// [`MIGRATION_BATCH`] at a time, then rebuild the indexes derived from them.
+///
+/// Records are rewritten in place without announcing a change: every node
+/// upgrades its own copy, and replicated records are upgraded on receipt.
+pub fn upgrade_records(storage: &Storage, registry: &Registry, dry_run: bool) -> Result<usize> {
+    let mut upgraded = 0;
+    for kind in [ModelKind::Release, ModelKind::ContentItem] {
+        let prefix = kind.prefix().as_bytes();
+        let mut after: Option<Vec<u8>> = None;
+        loop {
+            let page = storage.scan_raw(prefix, after.as_deref(), MIGRATION_BATCH)?;
+            let Some((last, _)) = page.last() else {
+                break;
+            };
+            after = Some(last.clone());
+            let mut batch = Vec::new();
+            for (key, data) in &page {
+                let mut value: Value = serde_json::from_slice(data)?;
+                if registry.upgrade(kind, &mut value) {
+                    batch.push((key.clone(), serde_json::to_vec(&value)?));
+                }
+            }
+            upgraded += batch.len();
+            if !dry_run && !batch.is_empty() {
+                storage.put_raw(batch)?;
+            }
+            if page.len() < MIGRATION_BATCH {
+                break;
+            }
+        }
+    }
+    if upgraded > 0 && !dry_run {
+        storage.rebuild_release_indexes()?;
+        storage.rebuild_search_index()?;
+        storage.rebuild_oai_index()?;
+    }
+    Ok(upgraded)
+}
+
+/// A storage step that ran (or would run).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AppliedStep {
+    pub version: u32,
+    pub description: &'static str,
+    /// Records the step touched
+    pub records: usize,
+}
+
+/// Outcome of [`run`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MigrationReport {
+    /// Storage schema version before
+    pub from: u32,
+    /// Storage schema version after (or that would be reached)
+    pub to: u32,
+    pub dry_run: bool,
+    pub steps: Vec<AppliedStep>,
+    /// Stored records rewritten (or that would be) to current model schemas
+    pub records_upgraded: usize,
+}
+
+impl MigrationReport {
+    /// Whether anything was (or would be) migrated.
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty() && self.records_upgraded == 0
+    }
+}
+
+impl std::fmt::Display for MigrationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "Storage schema is up to date (version {})", self.from);
+        }
+        if !self.steps.is_empty() {
+            let verb = if self.dry_run { "would migrate" } else { "migrated" };
+            writeln!(f, "Storage schema {} from version {} to {}", verb, self.from, self.to)?;
+            for step in &self.steps {
+                writeln!(f, "  {}: {} ({} records)", step.version, step.description, step.records)?;
+            }
+        }
+        if self.records_upgraded > 0 {
+            let verb = if self.dry_run { "Would upgrade" } else { "Upgraded" };
+            writeln!(f, "{} {} stored records to the current model schemas", verb, self.records_upgraded)?;
+        }
+        Ok(())
+    }
+}
+
+/// Run the steps newer than the stored schema version, recording each one as
+/// it completes, then upgrade stored records if the model schema versions
+/// changed since storage was last migrated. With `dry_run` nothing is written.
+pub fn run(storage: &Storage, registry: &Registry, dry_run: bool) -> Result<MigrationReport> {
+    let from = storage.schema_version()?;
+    if from > registry.latest() {
+        return Err(Error::Storage(format!(
+            "storage schema version {} is newer than this build supports ({})",
+            from,
+            registry.latest()
+        )));
+    }
+
+    let mut pending: Vec<&StorageMigration> = registry.steps.iter().filter(|step| step.version > from).collect();
+    pending.sort_by_key(|step| step.version);
+    let mut report = MigrationReport { from, to: from, dry_run, steps: Vec::new(), records_upgraded: 0 };
+    for step in pending {
+        if !dry_run {
+            tracing::info!("Migrating storage to version {}: {}", step.version, step.description);
+        }
+        let records = (step.run)(storage, registry, dry_run)?;
+        if !dry_run {
+            storage.set_schema_version(step.version)?;
+        }
+        report.to = step.version;
+        report.steps.push(AppliedStep { version: step.version, description: step.description, records });
+    }
+
+    let versions = model_versions();
+    if !registry.records.is_empty() && storage.model_versions()?.as_deref() != Some(versions.as_str()) {
+        if !dry_run {
+            tracing::info!("Upgrading stored records to model schemas {}", versions);
+        }
+        report.records_upgraded = upgrade_records(storage, registry, dry_run)?;
+        if !dry_run {
+            storage.set_model_versions(&versions)?;
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    /// Release 0.9 called the title `name`.
+    fn rename_name(object: &mut serde_json::Map<String, Value>) {
+        if let Some(name) = object.remove("name") {
+            object.insert("title".into(), name);
+        }
+    }
+
+    const RENAMES: &[RecordMigration] = &[
+        RecordMigration { kind: ModelKind::Release, from: "0.8.0", to: "0.9.0", upgrade: |_| {} },
+        RecordMigration { kind: ModelKind::Release, from: "0.9.0", to: "1.0.0", upgrade: rename_name },
+    ];
+
+    const TEST_REGISTRY: Registry = Registry { records: RENAMES, steps: BUILTIN.steps };
+
+    fn old_release(id: &str, version: &str) -> Vec<u8> {
+        serde_json::to_vec(&serde_json::json!({
+            "id": id,
+            "name": "Old Title",
+            "category_id": "movies",
+            "tags": [],
+            "schema_version": version,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_upgrade_chains() {
+        let mut value: Value = serde_json::from_slice(&old_release("a", "0.8.0")).unwrap();
+        assert!(TEST_REGISTRY.upgrade(ModelKind::Release, &mut value));
+        let release: Release = serde_json::from_value(value).unwrap();
+        assert_eq!(release.title, "Old Title");
+        assert_eq!(release.schema_version, Release::SCHEMA_VERSION);
+
+        // Current and unknown versions are left alone
+        let mut current = serde_json::to_value(Release::new("b".into(), "T".into(), "c".into())).unwrap();
+        assert!(!TEST_REGISTRY.upgrade(ModelKind::Release, &mut current));
+        let mut future: Value = serde_json::from_slice(&old_release("c", "9.0.0")).unwrap();
+        assert!(!TEST_REGISTRY.upgrade(ModelKind::Release, &mut future));
+    }
+
+    #[test]
+    fn test_run_upgrades_stored_records() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::open(dir.path()).unwrap();
+        assert_eq!(storage.schema_version().unwrap(), BUILTIN.latest());
+        let old: Vec<(Vec<u8>, Vec<u8>)> = (0..MIGRATION_BATCH + 3)
+            .map(|i| (format!("release:r{:04}", i).into_bytes(), old_release(&format!("r{:04}", i), "0.9.0")))
+            .collect();
+        storage.put_raw(old).unwrap();
+        assert!(storage.get_release("r0000").is_err());
+
+        // Nothing to do without record migrations
+        assert!(run(&storage, &BUILTIN, false).unwrap().is_empty());
+
+        let dry = run(&storage, &TEST_REGISTRY, true).unwrap();
+        assert!(dry.steps.is_empty());
+        assert_eq!(dry.records_upgraded, MIGRATION_BATCH + 3);
+        assert!(storage.get_release("r0000").is_err());
+
+        let report = run(&storage, &TEST_REGISTRY, false).unwrap();
+        assert_eq!(report.records_upgraded, MIGRATION_BATCH + 3);
+        let last = format!("r{:04}", MIGRATION_BATCH + 2);
+        assert_eq!(storage.get_release(&last).unwrap().unwrap().title, "Old Title");
+        // Indexes were rebuilt over the upgraded records
+        assert_eq!(crate::search::search(&storage, &crate::search::SearchQuery {
+            q: "old title".into(),
+            limit: 1000,
+            ..Default::default()
+        })
+        .unwrap()
+        .total, MIGRATION_BATCH + 3);
+
+        // Records are rewritten once per model schema change
+        assert!(run(&storage, &TEST_REGISTRY, false).unwrap().is_empty());
+        // An older build refuses storage it doesn't understand
+        storage.set_schema_version(BUILTIN.latest() + 1).unwrap();
+        assert!(run(&storage, &BUILTIN, false).is_err());
+    }
+
+    #[test]
+    fn test_decode_current() {
+        let release = Release::new("a".into(), "Title".into(), "movies".into());
+        let decoded: Release = decode(&serde_json::to_vec(&release).unwrap()).unwrap();
+        assert_eq!(decoded, release);
+    }
+}
@@ -73,10 +73,18 @@ pub struct ContentItem {
     /// Schema version for this content item.
     #[serde(default = "default_schema_version")]
     pub schema_version: String,
+
+    /// Release this item belongs to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub release_id: Option<String>,
+
+    /// Hex public key of the uploader (set by the API on create)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uploader: Option<String>,
 }
 
 fn default_schema_version() -> String {
-    "1.0.0".to_string()
+    ContentItem::SCHEMA_VERSION.to_string()
 }
 
 impl ContentItem {
@@ -99,6 +107,8 @@ impl ContentItem {
             type_specific: serde_json::Value::Null,
             custom: HashMap::new(),
             schema_version: default_schema_version(),
+            release_id: None,
+            uploader: None,
         }
     }
 
@@ -162,6 +172,12 @@ impl ContentItem {
         self
     }
 
+    /// Builder: Link to a parent release.
+    pub fn with_release(mut self, release_id: String) -> Self {
+        self.release_id = Some(release_id);
+        self
+    }
+
     /// Builder: Set publication date.
     pub fn with_published_at(mut self, date: String) -> Self {
         self.published_at = Some(date);
@@ -174,6 +190,9 @@ impl ContentItem {
         hex::encode(hash.as_bytes())
     }
 
+    /// Schema version written by this build (see [`crate::migrations`]).
+    pub const SCHEMA_VERSION: &'static str = "1.0.0";
+
     /// DHT key prefix for content items.
     pub const DHT_PREFIX: &'static str = "content";
 
@@ -98,6 +98,19 @@ impl Default for ContentType {
     }
 }
 
+impl ContentType {
+    /// The type's snake_case name, or the custom name for [`ContentType::Custom`].
+    pub fn name(&self) -> String {
+        match self {
+            Self::Custom(name) => name.clone(),
+            other => serde_json::to_value(other)
+                .ok()
+                .and_then(|value| value.as_str().map(str::to_string))
+                .unwrap_or_default(),
+        }
+    }
+}
+
 /// Creator/contributor role.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -299,6 +312,12 @@ mod tests {
         assert_eq!(json, "\"scientific_paper\"");
     }
 
+    #[test]
+    fn content_type_name() {
+        assert_eq!(ContentType::TvEpisode.name(), "tv_episode");
+        assert_eq!(ContentType::Custom("nft".to_string()).name(), "nft");
+    }
+
     #[test]
     fn creator_with_role() {
         let creator = Creator::with_identifier(
@@ -26,6 +26,7 @@ mod content_item;
 mod content_types;
 mod metadata;
 mod release;
+mod validation;
 
 pub use category::Category;
 pub use content_item::ContentItem;
@@ -35,3 +36,4 @@ pub use metadata::{
     StandardMetadata,
 };
 pub use release::Release;
+pub use validation::{type_specific_fields, FieldKind, TypeField};
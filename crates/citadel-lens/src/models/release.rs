@@ -33,13 +33,21 @@ pub struct Release {
     #[serde(default)]
     pub tags: Vec<String>,
 
+    /// Hex public key of the account that uploaded it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uploader: Option<String>,
+
+    /// Creation timestamp (ISO 8601, set by the node that accepted the upload)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+
     /// Schema version for forward compatibility
     #[serde(default = "default_schema_version")]
     pub schema_version: String,
 }
 
 fn default_schema_version() -> String {
-    "1.0.0".to_string()
+    Release::SCHEMA_VERSION.to_string()
 }
 
 impl Release {
@@ -54,6 +62,8 @@ impl Release {
             thumbnail_cid: None,
             description: None,
             tags: Vec::new(),
+            uploader: None,
+            created_at: None,
             schema_version: default_schema_version(),
         }
     }
@@ -64,6 +74,9 @@ impl Release {
         hex::encode(hash.as_bytes())
     }
 
+    /// Schema version written by this build (see [`crate::migrations`]).
+    pub const SCHEMA_VERSION: &'static str = "1.0.0";
+
     /// DHT key prefix for releases.
     pub const DHT_PREFIX: &'static str = "release";
 
@@ -108,6 +121,8 @@ mod tests {
             thumbnail_cid: None,
             description: Some("A test release".to_string()),
             tags: vec!["rock".to_string(), "indie".to_string()],
+            uploader: Some("ab".repeat(32)),
+            created_at: Some("2024-01-01T00:00:00Z".to_string()),
             schema_version: "1.0.0".to_string(),
         };
 
@@ -0,0 +1,270 @@
+//! Validation for content items.
+//!
+//! `type_specific` is free-form JSON, but each [`ContentType`] has a few
+//! well-known fields (a TV episode's season and episode numbers, a book's
+//! ISBN, a paper's DOI). When present they must have the expected JSON type,
+//! and some are required. Fields not listed here are left alone, so clients
+//! can still add their own.
+
+use super::content_item::ContentItem;
+use super::content_types::ContentType;
+use serde_json::Value;
+
+/// Expected JSON type of a type-specific field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    /// Non-negative integer
+    Count,
+    /// Any string
+    Text,
+    /// List of strings
+    TextList,
+    /// ISBN-10 or ISBN-13, hyphens allowed, checksum verified
+    Isbn,
+    /// DOI (`10.<registrant>/<suffix>`)
+    Doi,
+}
+
+/// A well-known field in `type_specific`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TypeField {
+    pub name: &'static str,
+    pub kind: FieldKind,
+    pub required: bool,
+}
+
+const fn field(name: &'static str, kind: FieldKind) -> TypeField {
+    TypeField { name, kind, required: false }
+}
+
+const fn required(name: &'static str, kind: FieldKind) -> TypeField {
+    TypeField { name, kind, required: true }
+}
+
+use FieldKind::*;
+
+const TIMED_MEDIA: &[TypeField] = &[field("duration_seconds", Count)];
+const SERIES: &[TypeField] = &[field("seasons", Count), field("episodes", Count)];
+const EPISODE: &[TypeField] = &[
+    required("season", Count),
+    required("episode", Count),
+    field("series_id", Text),
+    field("duration_seconds", Count),
+];
+const ALBUM: &[TypeField] = &[field("track_count", Count), field("label", Text)];
+const TRACK: &[TypeField] = &[
+    field("track_number", Count),
+    field("duration_seconds", Count),
+    field("album_id", Text),
+];
+const PODCAST_EPISODE: &[TypeField] = &[field("episode_number", Count), field("duration_seconds", Count)];
+const AUDIOBOOK: &[TypeField] = &[field("duration_seconds", Count), field("isbn", Isbn), field("narrator", Text)];
+const BOOK: &[TypeField] = &[
+    field("isbn", Isbn),
+    field("pages", Count),
+    field("edition", Text),
+    field("publisher", Text),
+];
+const PAPER: &[TypeField] = &[
+    field("doi", Doi),
+    field("journal", Text),
+    field("volume", Text),
+    field("pages", Text),
+];
+const DATASET: &[TypeField] = &[
+    field("doi", Doi),
+    field("format", Text),
+    field("record_count", Count),
+    field("size_bytes", Count),
+];
+const SOFTWARE: &[TypeField] = &[
+    field("version", Text),
+    field("repository", Text),
+    field("platforms", TextList),
+];
+const MODEL: &[TypeField] = &[
+    field("parameters", Count),
+    field("framework", Text),
+    field("architecture", Text),
+];
+const IMAGE: &[TypeField] = &[field("width", Count), field("height", Count)];
+
+/// The well-known `type_specific` fields for a content type.
+pub fn type_specific_fields(content_type: &ContentType) -> &'static [TypeField] {
+    match content_type {
+        ContentType::Movie | ContentType::Video | ContentType::VideoClip | ContentType::Lecture => TIMED_MEDIA,
+        ContentType::TvSeries => SERIES,
+        ContentType::TvEpisode => EPISODE,
+        ContentType::MusicAlbum => ALBUM,
+        ContentType::MusicTrack => TRACK,
+        ContentType::PodcastEpisode => PODCAST_EPISODE,
+        ContentType::Audiobook => AUDIOBOOK,
+        ContentType::Book | ContentType::Ebook => BOOK,
+        ContentType::ScientificPaper | ContentType::Thesis | ContentType::Report | ContentType::Article => PAPER,
+        ContentType::Dataset => DATASET,
+        ContentType::Software | ContentType::Library | ContentType::Framework | ContentType::Application => SOFTWARE,
+        ContentType::AiModel | ContentType::MachineLearningModel => MODEL,
+        ContentType::Photo | ContentType::Artwork | ContentType::Drawing => IMAGE,
+        _ => &[],
+    }
+}
+
+fn isbn_valid(isbn: &str) -> bool {
+    let chars: Vec<char> = isbn.chars().filter(|c| *c != '-' && *c != ' ').collect();
+    match chars.len() {
+        10 => {
+            let mut sum = 0;
+            for (i, c) in chars.iter().enumerate() {
+                let digit = match c {
+                    'X' | 'x' if i == 9 => 10,
+                    c => match c.to_digit(10) {
+                        Some(d) => d,
+                        None => return false,
+                    },
+                };
+                sum += digit * (10 - i as u32);
+            }
+            sum % 11 == 0
+        }
+        13 => {
+            let mut sum = 0;
+            for (i, c) in chars.iter().enumerate() {
+                let Some(digit) = c.to_digit(10) else {
+                    return false;
+                };
+                sum += if i % 2 == 0 { digit } else { digit * 3 };
+            }
+            sum % 10 == 0
+        }
+        _ => false,
+    }
+}
+
+fn doi_valid(doi: &str) -> bool {
+    doi.strip_prefix("10.")
+        .and_then(|rest| rest.split_once('/'))
+        .is_some_and(|(registrant, suffix)| !registrant.is_empty() && !suffix.is_empty())
+}
+
+/// ISO 639 code with an optional region or script, e.g. `en`, `fra`, `pt-BR`.
+fn language_valid(language: &str) -> bool {
+    let mut parts = language.split('-');
+    let primary = parts.next().unwrap_or_default();
+    (2..=3).contains(&primary.len())
+        && primary.chars().all(|c| c.is_ascii_alphabetic())
+        && parts.all(|part| (2..=8).contains(&part.len()) && part.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+fn check_field(field: &TypeField, value: &Value) -> Option<String> {
+    let ok = match field.kind {
+        Count => value.as_u64().is_some(),
+        Text => value.is_string(),
+        TextList => value.as_array().is_some_and(|items| items.iter().all(Value::is_string)),
+        Isbn => value.as_str().is_some_and(isbn_valid),
+        Doi => value.as_str().is_some_and(doi_valid),
+    };
+    if ok {
+        return None;
+    }
+    let expected = match field.kind {
+        Count => "a non-negative integer",
+        Text => "a string",
+        TextList => "a list of strings",
+        Isbn => "a valid ISBN-10 or ISBN-13",
+        Doi => "a DOI like 10.1234/abc",
+    };
+    Some(format!("type_specific.{}: expected {}", field.name, expected))
+}
+
+impl ContentItem {
+    /// Check the item's common fields and its type-specific fields.
+    ///
+    /// Returns every problem found, not just the first.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+
+        if self.title.trim().is_empty() {
+            errors.push("title: must not be empty".to_string());
+        }
+        if let ContentType::Custom(name) = &self.content_type {
+            if name.trim().is_empty() {
+                errors.push("content_type: custom type name must not be empty".to_string());
+            }
+        }
+        if let Some(ref language) = self.language {
+            if !language_valid(language) {
+                errors.push(format!("language: '{}' is not an ISO 639 language code", language));
+            }
+        }
+        if self.tags.iter().any(|tag| tag.trim().is_empty()) {
+            errors.push("tags: must not contain empty tags".to_string());
+        }
+        if self.creators.iter().any(|creator| creator.name.trim().is_empty()) {
+            errors.push("creators: every creator needs a name".to_string());
+        }
+        if self.resources.iter().any(|resource| resource.id.trim().is_empty()) {
+            errors.push("resources: every resource needs an id".to_string());
+        }
+
+        let fields = type_specific_fields(&self.content_type);
+        match &self.type_specific {
+            Value::Null => {
+                for field in fields.iter().filter(|f| f.required) {
+                    errors.push(format!("type_specific.{}: required", field.name));
+                }
+            }
+            Value::Object(map) => {
+                for field in fields {
+                    match map.get(field.name) {
+                        Some(value) => errors.extend(check_field(field, value)),
+                        None if field.required => errors.push(format!("type_specific.{}: required", field.name)),
+                        None => {}
+                    }
+                }
+            }
+            _ => errors.push("type_specific: must be an object".to_string()),
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn item(content_type: ContentType, type_specific: Value) -> ContentItem {
+        ContentItem::new("id".into(), content_type, "Title".into()).with_type_specific(type_specific)
+    }
+
+    #[test]
+    fn type_specific_fields_checked() {
+        assert!(item(ContentType::TvEpisode, json!({"season": 1, "episode": 4})).validate().is_ok());
+        assert!(item(ContentType::Book, json!({"isbn": "978-0-306-40615-7", "custom": true})).validate().is_ok());
+        assert!(item(ContentType::Book, json!({"isbn": "0-306-40615-2"})).validate().is_ok());
+        assert!(item(ContentType::Dataset, json!({"doi": "10.5281/zenodo.123"})).validate().is_ok());
+        assert!(item(ContentType::Custom("nft".into()), json!({"anything": [1, 2]})).validate().is_ok());
+
+        let errors = item(ContentType::TvEpisode, json!({"season": "one"})).validate().unwrap_err();
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+        assert!(item(ContentType::TvEpisode, Value::Null).validate().is_err());
+        assert!(item(ContentType::Book, json!({"isbn": "978-0-306-40615-8"})).validate().is_err());
+        assert!(item(ContentType::ScientificPaper, json!({"doi": "doi.org/x"})).validate().is_err());
+        assert!(item(ContentType::Software, json!({"platforms": ["linux", 3]})).validate().is_err());
+        assert!(item(ContentType::Movie, json!([1])).validate().is_err());
+    }
+
+    #[test]
+    fn common_fields_checked() {
+        assert!(item(ContentType::Movie, Value::Null).with_language("pt-BR".into()).validate().is_ok());
+
+        let mut bad = item(ContentType::Movie, Value::Null).with_language("english!".into());
+        bad.title = " ".into();
+        assert_eq!(bad.validate().unwrap_err().len(), 2);
+    }
+}
@@ -5,102 +5,23 @@
 //! - HTTP API for clients (releases, categories, accounts)
 //! - Unix admin socket for local admin ops (lens-admin CLI)
 
+use crate::acl;
 use crate::admin_socket::AdminSocket;
 use crate::api;
+use crate::config::LensConfig;
 use crate::error::Result;
 use crate::mesh::{MeshService, MeshState};
 use crate::storage::Storage;
-use std::net::SocketAddr;
-use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
-/// Configuration for a Lens node.
-#[derive(Debug, Clone)]
-pub struct LensConfig {
-    /// Data directory for storage
-    pub data_dir: PathBuf,
-
-    /// HTTP API listen address
-    pub api_addr: SocketAddr,
-
-    /// P2P listen address (for mesh)
-    pub p2p_addr: SocketAddr,
-
-    /// Bootstrap peers
-    pub bootstrap_peers: Vec<String>,
-
-    /// Admin socket path (for lens-admin CLI)
-    pub admin_socket: PathBuf,
-
-    /// Initial admin public key (hex-encoded ed25519 public key)
-    pub admin_public_key: Option<String>,
-}
-
-impl Default for LensConfig {
-    fn default() -> Self {
-        Self::from_env()
-    }
-}
-
-impl LensConfig {
-    /// Create config from environment variables with sensible defaults.
-    pub fn from_env() -> Self {
-        let data_dir = PathBuf::from(
-            std::env::var("LENS_DATA_DIR").unwrap_or_else(|_| "./lens-data".to_string())
-        );
-
-        let api_addr = std::env::var("LENS_API_BIND")
-            .unwrap_or_else(|_| "0.0.0.0:8080".to_string())
-            .parse()
-            .expect("Invalid LENS_API_BIND");
-
-        let p2p_addr = std::env::var("LENS_P2P_BIND")
-            .unwrap_or_else(|_| "0.0.0.0:9000".to_string())
-            .parse()
-            .expect("Invalid LENS_P2P_BIND");
-
-        // Parse CITADEL_PEERS - accepts DNS names and portless entries (default 9000)
-        let bootstrap_peers = std::env::var("CITADEL_PEERS")
-            .map(|s| {
-                s.split(',')
-                    .map(|p| p.trim().to_string())
-                    .filter(|p| !p.is_empty())
-                    .map(|p| {
-                        // If no port specified, append :9000
-                        if p.contains(':') {
-                            p
-                        } else {
-                            format!("{}:9000", p)
-                        }
-                    })
-                    .collect()
-            })
-            .unwrap_or_default();
-
-        let admin_socket = std::env::var("LENS_ADMIN_SOCKET")
-            .map(PathBuf::from)
-            .unwrap_or_else(|_| data_dir.join("admin.sock"));
-
-        let admin_public_key = std::env::var("ADMIN_PUBLIC_KEY").ok()
-            .filter(|s| !s.is_empty());
-
-        Self {
-            data_dir,
-            api_addr,
-            p2p_addr,
-            bootstrap_peers,
-            admin_socket,
-            admin_public_key,
-        }
-    }
-}
-
 /// Shared state for the Lens node - single storage instance shared by all components.
 pub struct LensState {
     pub storage: Arc<Storage>,
     pub config: LensConfig,
     pub mesh_state: Option<Arc<RwLock<MeshState>>>,
+    /// Mesh service, for DHT lookups (set once the mesh starts)
+    pub mesh: Option<Arc<MeshService>>,
 }
 
 /// A Lens node instance.
@@ -121,16 +42,18 @@ impl LensNode {
         // Initialize default categories
         storage.init_default_categories()?;
 
-        // Set initial admin public key if provided via env var
+        // Root admin from config; everyone else comes from the signed op log
         if let Some(ref admin_key) = config.admin_public_key {
-            storage.set_admin(admin_key, true)?;
+            storage.add_root_admin(admin_key)?;
             tracing::info!("Admin public key set: {}", admin_key);
         }
+        acl::rebuild(&storage)?;
 
         let state = Arc::new(RwLock::new(LensState {
             storage,
             config: config.clone(),
             mesh_state: None,
+            mesh: None,
         }));
 
         Ok(Self { state, config })
@@ -168,16 +91,18 @@ impl LensNode {
             self.config.p2p_addr,
             self.config.bootstrap_peers.clone(),
             mesh_storage,
-        ));
-
-        // Get flood sender for admin socket
-        let flood_tx = mesh_service.flood_tx();
-
-        // Start admin socket server in background (with flood capability)
+        )
+        .with_dht_replication_factor(self.config.dht_replication_factor)
+        .with_dht_tombstone_horizon(self.config.dht_tombstone_horizon)
+        .with_vdf_iterations(self.config.vdf_iterations, self.config.cvdf_iterations)
+        .with_cvdf_tick(self.config.cvdf_tick)
+        .with_flood_rate(self.config.flood_rate));
+
+        // Start admin socket server in background (admin ops replicate via SPORE)
         let admin_socket = AdminSocket::new(
             Arc::clone(&storage),
             self.config.admin_socket.to_str().unwrap_or("./lens-data/admin.sock"),
-        ).with_flood_tx(flood_tx);
+        )?;
         tokio::spawn(async move {
             if let Err(e) = admin_socket.run().await {
                 tracing::error!("Admin socket error: {}", e);
@@ -188,6 +113,7 @@ impl LensNode {
         {
             let mut state = self.state.write().await;
             state.mesh_state = Some(mesh_service.mesh_state());
+            state.mesh = Some(Arc::clone(&mesh_service));
         }
 
         let mesh_clone = Arc::clone(&mesh_service);
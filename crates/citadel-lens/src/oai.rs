@@ -0,0 +1,680 @@
+//! OAI-PMH 2.0 repository interface.
+//!
+//! Library and archive harvesters collect metadata over the Open Archives
+//! Initiative Protocol for Metadata Harvesting. Every release and content
+//! item is a record, identified as `oai:lens:release:<id>` or
+//! `oai:lens:content:<id>`, and disseminated as `oai_dc` or DataCite using the
+//! renderings in [`crate::export`]. Categories are exposed as sets; a content
+//! item belongs to the set of the release it is attached to.
+//!
+//! Records are harvested oldest first, ordered by datestamp and identifier,
+//! from a datestamp index kept by [`Storage`]. A resumption token is the
+//! original request plus the datestamp and identifier of the last record
+//! returned, and the next page resumes strictly after it, so records added or
+//! removed between pages don't shift the rest of the list.
+//! Deletions are not tracked (`deletedRecord` is `no`).
+
+use crate::error::Result;
+use crate::export::{self, Xml, XML_DECLARATION};
+use crate::models::{ContentItem, Release};
+use crate::storage::Storage;
+use crate::timestamp;
+use std::collections::HashMap;
+
+/// Records or headers returned per ListRecords/ListIdentifiers response.
+pub const PAGE_SIZE: usize = 100;
+
+const OAI_NAMESPACE: &str = "http://www.openarchives.org/OAI/2.0/";
+const OAI_SCHEMA: &str = "http://www.openarchives.org/OAI/2.0/OAI-PMH.xsd";
+const EPOCH: &str = "1970-01-01T00:00:00Z";
+const RELEASE_PREFIX: &str = "oai:lens:release:";
+const CONTENT_PREFIX: &str = "oai:lens:content:";
+
+/// Metadata formats offered for every record: (prefix, schema, namespace).
+const FORMATS: &[(&str, &str, &str)] = &[
+    ("oai_dc", "http://www.openarchives.org/OAI/2.0/oai_dc.xsd", "http://www.openarchives.org/OAI/2.0/oai_dc/"),
+    ("datacite", "http://schema.datacite.org/meta/kernel-4.5/metadata.xsd", "http://datacite.org/schema/kernel-4"),
+];
+
+/// Details reported by the Identify verb.
+#[derive(Debug, Clone)]
+pub struct Repository<'a> {
+    pub name: &'a str,
+    pub admin_email: &'a str,
+    /// URL the endpoint is served at; echoed in every response
+    pub base_url: &'a str,
+}
+
+/// Why a request failed: an OAI-PMH error code and message, reported in the
+/// response, or a storage error.
+#[derive(Debug)]
+enum Failure {
+    Oai(&'static str, String),
+    Storage(crate::Error),
+}
+
+impl From<crate::Error> for Failure {
+    fn from(e: crate::Error) -> Self {
+        Failure::Storage(e)
+    }
+}
+
+fn fail<T>(code: &'static str, message: impl Into<String>) -> std::result::Result<T, Failure> {
+    Err(Failure::Oai(code, message.into()))
+}
+
+/// A harvestable record.
+struct Record {
+    identifier: String,
+    datestamp: String,
+    set: Option<String>,
+    item: ContentItem,
+}
+
+/// Handle one OAI-PMH request given its query or form arguments.
+///
+/// Protocol errors become `<error>` elements in the returned document;
+/// only storage failures are returned as `Err`.
+pub fn handle(storage: &Storage, repository: &Repository<'_>, args: &[(String, String)]) -> Result<String> {
+    let mut xml = Xml::default();
+    xml.0.push_str(XML_DECLARATION);
+    xml.open(
+        "OAI-PMH",
+        &[
+            ("xmlns", OAI_NAMESPACE),
+            ("xmlns:xsi", "http://www.w3.org/2001/XMLSchema-instance"),
+            ("xsi:schemaLocation", &format!("{} {}", OAI_NAMESPACE, OAI_SCHEMA)),
+        ],
+    );
+    xml.element("responseDate", &[], &timestamp::now_utc());
+
+    let attrs: Vec<(&str, &str)> = args.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+    let mut body = Xml::default();
+    match dispatch(storage, repository, args, &mut body) {
+        Ok(()) => {
+            xml.element("request", &attrs, repository.base_url);
+            xml.0.push_str(&body.0);
+        }
+        Err(Failure::Oai(code, message)) => {
+            // The request is only echoed if its arguments were understood
+            let echoed = if matches!(code, "badVerb" | "badArgument") { &[][..] } else { &attrs[..] };
+            xml.element("request", echoed, repository.base_url);
+            xml.element("error", &[("code", code)], &message);
+        }
+        Err(Failure::Storage(e)) => return Err(e),
+    }
+    xml.close("OAI-PMH");
+    Ok(xml.0)
+}
+
+/// Check the arguments against a verb's required, optional and exclusive ones.
+fn check_args<'a>(
+    args: &'a [(String, String)],
+    required: &[&str],
+    optional: &[&str],
+    exclusive: Option<&str>,
+) -> std::result::Result<HashMap<&'a str, &'a str>, Failure> {
+    let mut map = HashMap::new();
+    for (key, value) in args.iter().filter(|(key, _)| key != "verb") {
+        let known = required.contains(&key.as_str()) || optional.contains(&key.as_str()) || exclusive == Some(key);
+        if !known {
+            return fail("badArgument", format!("illegal argument '{}'", key));
+        }
+        if map.insert(key.as_str(), value.as_str()).is_some() {
+            return fail("badArgument", format!("argument '{}' repeated", key));
+        }
+    }
+    if let Some(exclusive) = exclusive.filter(|e| map.contains_key(e)) {
+        if map.len() > 1 {
+            return fail("badArgument", format!("'{}' must be the only argument", exclusive));
+        }
+        return Ok(map);
+    }
+    if let Some(missing) = required.iter().find(|name| !map.contains_key(*name)) {
+        return fail("badArgument", format!("missing required argument '{}'", missing));
+    }
+    Ok(map)
+}
+
+fn dispatch(
+    storage: &Storage,
+    repository: &Repository<'_>,
+    args: &[(String, String)],
+    xml: &mut Xml,
+) -> std::result::Result<(), Failure> {
+    let verbs: Vec<&str> = args.iter().filter(|(k, _)| k == "verb").map(|(_, v)| v.as_str()).collect();
+    let verb = match verbs.as_slice() {
+        [verb] => *verb,
+        [] => return fail("badVerb", "missing verb"),
+        _ => return fail("badVerb", "verb repeated"),
+    };
+    match verb {
+        "Identify" => {
+            check_args(args, &[], &[], None)?;
+            identify(storage, repository, xml)
+        }
+        "ListMetadataFormats" => {
+            let args = check_args(args, &[], &["identifier"], None)?;
+            list_metadata_formats(storage, args.get("identifier").copied(), xml)
+        }
+        "ListSets" => {
+            let args = check_args(args, &[], &[], Some("resumptionToken"))?;
+            if args.contains_key("resumptionToken") {
+                return fail("badResumptionToken", "sets are returned in a single response");
+            }
+            list_sets(storage, xml)
+        }
+        "ListIdentifiers" | "ListRecords" => {
+            let args = check_args(args, &["metadataPrefix"], &["from", "until", "set"], Some("resumptionToken"))?;
+            let request = match args.get("resumptionToken") {
+                Some(token) => ListRequest::from_token(token)?,
+                None => ListRequest::from_args(&args)?,
+            };
+            list(storage, &request, verb == "ListRecords", xml)
+        }
+        "GetRecord" => {
+            let args = check_args(args, &["identifier", "metadataPrefix"], &[], None)?;
+            get_record(storage, args["identifier"], args["metadataPrefix"], xml)
+        }
+        other => fail("badVerb", format!("'{}' is not an OAI-PMH verb", other)),
+    }
+}
+
+fn identify(storage: &Storage, repository: &Repository<'_>, xml: &mut Xml) -> std::result::Result<(), Failure> {
+    let earliest = storage.scan_oai_index("", 1)?.into_iter().next().and_then(|entry| {
+        entry.split_once('\0').map(|(datestamp, _)| datestamp.to_string())
+    });
+    let earliest = earliest.unwrap_or_else(|| EPOCH.to_string());
+    xml.open("Identify", &[]);
+    xml.element("repositoryName", &[], repository.name);
+    xml.element("baseURL", &[], repository.base_url);
+    xml.element("protocolVersion", &[], "2.0");
+    xml.element("adminEmail", &[], repository.admin_email);
+    xml.element("earliestDatestamp", &[], &earliest);
+    xml.element("deletedRecord", &[], "no");
+    xml.element("granularity", &[], "YYYY-MM-DDThh:mm:ssZ");
+    xml.close("Identify");
+    Ok(())
+}
+
+fn list_metadata_formats(
+    storage: &Storage,
+    identifier: Option<&str>,
+    xml: &mut Xml,
+) -> std::result::Result<(), Failure> {
+    if let Some(identifier) = identifier {
+        find_record(storage, identifier)?;
+    }
+    xml.open("ListMetadataFormats", &[]);
+    for (prefix, schema, namespace) in FORMATS {
+        xml.open("metadataFormat", &[]);
+        xml.element("metadataPrefix", &[], prefix);
+        xml.element("schema", &[], schema);
+        xml.element("metadataNamespace", &[], namespace);
+        xml.close("metadataFormat");
+    }
+    xml.close("ListMetadataFormats");
+    Ok(())
+}
+
+fn list_sets(storage: &Storage, xml: &mut Xml) -> std::result::Result<(), Failure> {
+    let mut categories = storage.list_categories()?;
+    if categories.is_empty() {
+        return fail("noSetHierarchy", "this repository has no categories");
+    }
+    categories.sort_by(|a, b| a.id.cmp(&b.id));
+    xml.open("ListSets", &[]);
+    for category in categories {
+        xml.open("set", &[]);
+        xml.element("setSpec", &[], &category.id);
+        xml.element("setName", &[], &category.name);
+        if let Some(ref description) = category.description {
+            xml.open("setDescription", &[]);
+            xml.0.push_str(&export::oai_dc_element(&description_item(&category.name, description)));
+            xml.close("setDescription");
+        }
+        xml.close("set");
+    }
+    xml.close("ListSets");
+    Ok(())
+}
+
+/// Set descriptions are Dublin Core, so borrow the item renderer.
+fn description_item(name: &str, description: &str) -> ContentItem {
+    let mut item = ContentItem::new(String::new(), Default::default(), name.to_string());
+    item.description = Some(description.to_string());
+    item
+}
+
+/// A selective harvest, carried across pages in the resumption token.
+#[derive(Debug, Clone, PartialEq)]
+struct ListRequest {
+    prefix: String,
+    from: Option<String>,
+    until: Option<String>,
+    set: Option<String>,
+    /// Records returned by earlier pages
+    cursor: usize,
+    /// Datestamp and identifier of the last record returned
+    after: Option<(String, String)>,
+}
+
+impl ListRequest {
+    fn from_args(args: &HashMap<&str, &str>) -> std::result::Result<Self, Failure> {
+        let prefix = args["metadataPrefix"];
+        if !FORMATS.iter().any(|(p, _, _)| *p == prefix) {
+            return fail("cannotDisseminateFormat", format!("unknown metadata format '{}'", prefix));
+        }
+        let from = args.get("from").map(|date| parse_date(date, "T00:00:00Z")).transpose()?;
+        let until = args.get("until").map(|date| parse_date(date, "T23:59:59Z")).transpose()?;
+        if let (Some(from), Some(until)) = (args.get("from"), args.get("until")) {
+            if from.len() != until.len() {
+                return fail("badArgument", "'from' and 'until' must have the same granularity");
+            }
+        }
+        Ok(ListRequest {
+            prefix: prefix.to_string(),
+            from,
+            until,
+            set: args.get("set").map(|s| s.to_string()),
+            cursor: 0,
+            after: None,
+        })
+    }
+
+    fn token(&self) -> String {
+        let (datestamp, identifier) = self.after.as_ref().map_or(("", ""), |(d, i)| (d.as_str(), i.as_str()));
+        let fields = [
+            self.prefix.as_str(),
+            self.from.as_deref().unwrap_or_default(),
+            self.until.as_deref().unwrap_or_default(),
+            self.set.as_deref().unwrap_or_default(),
+            &self.cursor.to_string(),
+            datestamp,
+            identifier,
+        ];
+        hex::encode(fields.join("\n"))
+    }
+
+    fn from_token(token: &str) -> std::result::Result<Self, Failure> {
+        let bad = || Failure::Oai("badResumptionToken", format!("invalid resumption token '{}'", token));
+        let decoded = hex::decode(token).ok().and_then(|bytes| String::from_utf8(bytes).ok()).ok_or_else(bad)?;
+        let fields: Vec<&str> = decoded.split('\n').collect();
+        let [prefix, from, until, set, cursor, datestamp, identifier] = fields.as_slice() else {
+            return Err(bad());
+        };
+        if datestamp.is_empty() || identifier.is_empty() || !FORMATS.iter().any(|(p, _, _)| p == prefix) {
+            return Err(bad());
+        }
+        let some = |s: &str| (!s.is_empty()).then(|| s.to_string());
+        Ok(ListRequest {
+            prefix: prefix.to_string(),
+            from: some(from),
+            until: some(until),
+            set: some(set),
+            cursor: cursor.parse().map_err(|_| bad())?,
+            after: Some((datestamp.to_string(), identifier.to_string())),
+        })
+    }
+
+    /// Where the index scan starts: after the last record returned, or at `from`.
+    fn start(&self) -> String {
+        match (&self.after, &self.from) {
+            (Some((datestamp, identifier)), _) => index_entry(datestamp, identifier),
+            (None, Some(from)) => from.clone(),
+            (None, None) => String::new(),
+        }
+    }
+
+    fn matches(&self, record: &Record) -> bool {
+        self.from.as_ref().is_none_or(|from| record.datestamp >= *from)
+            && self.until.as_ref().is_none_or(|until| record.datestamp <= *until)
+            && self.set.as_ref().is_none_or(|set| record.set.as_ref() == Some(set))
+    }
+}
+
+/// Parse a `from`/`until` argument, padding day granularity with `time`.
+fn parse_date(date: &str, time: &str) -> std::result::Result<String, Failure> {
+    let digits = |range: std::ops::Range<usize>| date[range].bytes().all(|b| b.is_ascii_digit());
+    let shape = |seps: &[(usize, u8)]| seps.iter().all(|&(i, c)| date.as_bytes()[i] == c);
+    let day = date.len() >= 10 && date.is_ascii() && digits(0..4) && digits(5..7) && digits(8..10)
+        && shape(&[(4, b'-'), (7, b'-')]);
+    match date.len() {
+        10 if day => Ok(format!("{}{}", date, time)),
+        20 if day && digits(11..13) && digits(14..16) && digits(17..19)
+            && shape(&[(10, b'T'), (13, b':'), (16, b':'), (19, b'Z')]) =>
+        {
+            Ok(date.to_string())
+        }
+        _ => fail("badArgument", format!("'{}' is not a UTC date", date)),
+    }
+}
+
+/// Normalize a model timestamp to `YYYY-MM-DDThh:mm:ssZ`.
+fn datestamp(item: &ContentItem) -> String {
+    let Some(stamp) = item.updated_at.as_deref().or(item.created_at.as_deref()) else {
+        return EPOCH.to_string();
+    };
+    let parsed = match stamp.get(..19) {
+        Some(seconds) => parse_date(&format!("{}Z", seconds), ""),
+        None => parse_date(stamp.get(..10).unwrap_or(stamp), "T00:00:00Z"),
+    };
+    parsed.unwrap_or_else(|_| EPOCH.to_string())
+}
+
+/// Datestamp index entry, `<datestamp>\0<identifier>`, so entries sort in
+/// harvest order.
+fn index_entry(datestamp: &str, identifier: &str) -> String {
+    format!("{}\0{}", datestamp, identifier)
+}
+
+/// A release's entry in the datestamp index kept by [`Storage`].
+pub(crate) fn release_index_entry(release: &Release) -> String {
+    let identifier = format!("{}{}", RELEASE_PREFIX, release.id);
+    index_entry(&datestamp(&export::release_item(release)), &identifier)
+}
+
+/// A content item's entry in the datestamp index kept by [`Storage`].
+pub(crate) fn content_index_entry(item: &ContentItem) -> String {
+    index_entry(&datestamp(item), &format!("{}{}", CONTENT_PREFIX, item.id))
+}
+
+fn release_record(release: &Release) -> Record {
+    let item = export::release_item(release);
+    Record {
+        identifier: format!("{}{}", RELEASE_PREFIX, release.id),
+        datestamp: datestamp(&item),
+        set: Some(release.category_id.clone()),
+        item,
+    }
+}
+
+fn content_record(item: ContentItem, set: Option<String>) -> Record {
+    Record {
+        identifier: format!("{}{}", CONTENT_PREFIX, item.id),
+        datestamp: datestamp(&item),
+        set,
+        item,
+    }
+}
+
+fn find_record(storage: &Storage, identifier: &str) -> std::result::Result<Record, Failure> {
+    let record = if let Some(id) = identifier.strip_prefix(RELEASE_PREFIX) {
+        storage.get_release(id)?.map(|release| release_record(&release))
+    } else if let Some(id) = identifier.strip_prefix(CONTENT_PREFIX) {
+        match storage.get_content_item(id)? {
+            Some(item) => {
+                let release = match item.release_id {
+                    Some(ref release_id) => storage.get_release(release_id)?,
+                    None => None,
+                };
+                Some(content_record(item, release.map(|r| r.category_id)))
+            }
+            None => None,
+        }
+    } else {
+        None
+    };
+    match record {
+        Some(record) => Ok(record),
+        None => fail("idDoesNotExist", format!("no record '{}'", identifier)),
+    }
+}
+
+fn write_header(record: &Record, xml: &mut Xml) {
+    xml.open("header", &[]);
+    xml.element("identifier", &[], &record.identifier);
+    xml.element("datestamp", &[], &record.datestamp);
+    if let Some(ref set) = record.set {
+        xml.element("setSpec", &[], set);
+    }
+    xml.close("header");
+}
+
+fn write_record(record: &Record, prefix: &str, xml: &mut Xml) {
+    xml.open("record", &[]);
+    write_header(record, xml);
+    xml.open("metadata", &[]);
+    match prefix {
+        "datacite" => xml.0.push_str(&export::datacite_element(&record.item)),
+        _ => xml.0.push_str(&export::oai_dc_element(&record.item)),
+    }
+    xml.close("metadata");
+    xml.close("record");
+}
+
+fn get_record(storage: &Storage, identifier: &str, prefix: &str, xml: &mut Xml) -> std::result::Result<(), Failure> {
+    let record = find_record(storage, identifier)?;
+    if !FORMATS.iter().any(|(p, _, _)| *p == prefix) {
+        return fail("cannotDisseminateFormat", format!("unknown metadata format '{}'", prefix));
+    }
+    xml.open("GetRecord", &[]);
+    write_record(&record, prefix, xml);
+    xml.close("GetRecord");
+    Ok(())
+}
+
+/// The next page of records matching a request, plus whether more follow.
+///
+/// Reads the datestamp index from where the request leaves off, loading only
+/// the records it passes over.
+fn next_page(storage: &Storage, request: &ListRequest) -> Result<(Vec<Record>, bool)> {
+    let mut page = Vec::new();
+    let mut after = request.start();
+    loop {
+        let entries = storage.scan_oai_index(&after, PAGE_SIZE)?;
+        for entry in &entries {
+            let Some((datestamp, identifier)) = entry.split_once('\0') else {
+                continue;
+            };
+            if request.until.as_deref().is_some_and(|until| datestamp > until) {
+                return Ok((page, false));
+            }
+            // Skip entries whose record changed or went away since the scan
+            let record = match find_record(storage, identifier) {
+                Ok(record) if record.datestamp == datestamp => record,
+                Ok(_) | Err(Failure::Oai(..)) => continue,
+                Err(Failure::Storage(e)) => return Err(e),
+            };
+            if !request.matches(&record) {
+                continue;
+            }
+            if page.len() == PAGE_SIZE {
+                return Ok((page, true));
+            }
+            page.push(record);
+        }
+        match entries.last() {
+            Some(last) if entries.len() == PAGE_SIZE => after = last.clone(),
+            _ => return Ok((page, false)),
+        }
+    }
+}
+
+fn list(storage: &Storage, request: &ListRequest, full: bool, xml: &mut Xml) -> std::result::Result<(), Failure> {
+    let (page, more) = next_page(storage, request)?;
+    let Some(last) = page.last() else {
+        return fail("noRecordsMatch", "no records match the request");
+    };
+
+    let element = if full { "ListRecords" } else { "ListIdentifiers" };
+    xml.open(element, &[]);
+    for record in &page {
+        if full {
+            write_record(record, &request.prefix, xml);
+        } else {
+            write_header(record, xml);
+        }
+    }
+    // Incomplete lists carry a token; their last page carries an empty one
+    if more || request.after.is_some() {
+        let cursor = request.cursor.to_string();
+        let token = if more {
+            ListRequest {
+                cursor: request.cursor + page.len(),
+                after: Some((last.datestamp.clone(), last.identifier.clone())),
+                ..request.clone()
+            }
+            .token()
+        } else {
+            String::new()
+        };
+        xml.element("resumptionToken", &[("cursor", cursor.as_str())], &token);
+    }
+    xml.close(element);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Release;
+    use tempfile::tempdir;
+
+    const REPOSITORY: Repository<'static> = Repository {
+        name: "Test Lens",
+        admin_email: "admin@example.org",
+        base_url: "http://lens.test/oai",
+    };
+
+    fn request(storage: &Storage, args: &[(&str, &str)]) -> String {
+        let args: Vec<(String, String)> = args.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        handle(storage, &REPOSITORY, &args).unwrap()
+    }
+
+    fn release(id: &str, category: &str, created_at: &str) -> Release {
+        let mut release = Release::new(id.into(), format!("Release {}", id), category.into());
+        release.created_at = Some(created_at.into());
+        release
+    }
+
+    /// The text of every `<tag>` element in a response.
+    fn texts(xml: &str, tag: &str) -> Vec<String> {
+        let open = format!("<{}>", tag);
+        let close = format!("</{}>", tag);
+        xml.split(&open).skip(1).map(|rest| rest.split(&close).next().unwrap().to_string()).collect()
+    }
+
+    fn error_code(xml: &str) -> Option<&str> {
+        let start = xml.find("<error code=\"")? + 13;
+        xml[start..].split('"').next()
+    }
+
+    #[test]
+    fn test_identify_and_errors() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::open(dir.path()).unwrap();
+        storage.put_release(&release("a", "movies", "2024-03-01T10:00:00Z")).unwrap();
+
+        let identify = request(&storage, &[("verb", "Identify")]);
+        assert!(identify.contains("<repositoryName>Test Lens</repositoryName>"));
+        assert_eq!(texts(&identify, "earliestDatestamp"), ["2024-03-01T10:00:00Z"]);
+        assert!(identify.contains("<request verb=\"Identify\">http://lens.test/oai</request>"));
+
+        assert_eq!(error_code(&request(&storage, &[])), Some("badVerb"));
+        assert_eq!(error_code(&request(&storage, &[("verb", "Harvest")])), Some("badVerb"));
+        assert_eq!(error_code(&request(&storage, &[("verb", "Identify"), ("set", "x")])), Some("badArgument"));
+        assert_eq!(error_code(&request(&storage, &[("verb", "ListRecords")])), Some("badArgument"));
+        assert_eq!(
+            error_code(&request(&storage, &[("verb", "ListRecords"), ("metadataPrefix", "marc21")])),
+            Some("cannotDisseminateFormat")
+        );
+        assert_eq!(
+            error_code(&request(&storage, &[("verb", "ListRecords"), ("metadataPrefix", "oai_dc"), ("from", "2024-3-1")])),
+            Some("badArgument")
+        );
+        assert_eq!(
+            error_code(&request(&storage, &[("verb", "ListIdentifiers"), ("resumptionToken", "zz")])),
+            Some("badResumptionToken")
+        );
+        assert_eq!(
+            error_code(&request(&storage, &[("verb", "GetRecord"), ("identifier", "oai:lens:release:b"), ("metadataPrefix", "oai_dc")])),
+            Some("idDoesNotExist")
+        );
+    }
+
+    #[test]
+    fn test_selective_harvest() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::open(dir.path()).unwrap();
+        storage.put_release(&release("old", "movies", "2023-06-01T00:00:00Z")).unwrap();
+        storage.put_release(&release("new", "movies", "2024-06-01T00:00:00Z")).unwrap();
+        storage.put_release(&release("book", "books", "2024-06-02T00:00:00Z")).unwrap();
+        let episode = ContentItem::new("ep".into(), Default::default(), "Episode".into())
+            .with_release("new".into())
+            .with_created_at("2024-07-01T12:30:00Z".into());
+        storage.put_content_item(&episode).unwrap();
+
+        let all = request(&storage, &[("verb", "ListIdentifiers"), ("metadataPrefix", "oai_dc")]);
+        assert_eq!(
+            texts(&all, "identifier"),
+            ["oai:lens:release:old", "oai:lens:release:new", "oai:lens:release:book", "oai:lens:content:ep"]
+        );
+
+        let movies = request(
+            &storage,
+            &[("verb", "ListIdentifiers"), ("metadataPrefix", "oai_dc"), ("set", "movies"), ("from", "2024-01-01")],
+        );
+        assert_eq!(texts(&movies, "identifier"), ["oai:lens:release:new", "oai:lens:content:ep"]);
+
+        let until = request(
+            &storage,
+            &[("verb", "ListIdentifiers"), ("metadataPrefix", "oai_dc"), ("until", "2024-06-01")],
+        );
+        assert_eq!(texts(&until, "identifier"), ["oai:lens:release:old", "oai:lens:release:new"]);
+
+        let none = request(&storage, &[("verb", "ListRecords"), ("metadataPrefix", "oai_dc"), ("set", "music")]);
+        assert_eq!(error_code(&none), Some("noRecordsMatch"));
+
+        let record = request(
+            &storage,
+            &[("verb", "GetRecord"), ("identifier", "oai:lens:content:ep"), ("metadataPrefix", "datacite")],
+        );
+        assert!(record.contains("<setSpec>movies</setSpec>"));
+        assert!(record.contains("<resource xmlns=\"http://datacite.org/schema/kernel-4\""));
+    }
+
+    #[test]
+    fn test_resumption_token() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::open(dir.path()).unwrap();
+        for i in 0..PAGE_SIZE + 5 {
+            let created = timestamp::format_utc(1_700_000_000_000 + i as u64 * 1000);
+            storage.put_release(&release(&format!("r{:03}", i), "movies", &created)).unwrap();
+        }
+
+        let first = request(&storage, &[("verb", "ListRecords"), ("metadataPrefix", "oai_dc"), ("set", "movies")]);
+        assert_eq!(texts(&first, "identifier").len(), PAGE_SIZE);
+        let token = first.split("cursor=\"0\">").nth(1).unwrap().split('<').next().unwrap();
+
+        // Records added or removed before the token's position don't shift the rest
+        storage.put_release(&release("early", "movies", "2020-01-01T00:00:00Z")).unwrap();
+        storage.delete_release("r010").unwrap();
+
+        let second = request(&storage, &[("verb", "ListRecords"), ("resumptionToken", token)]);
+        assert_eq!(texts(&second, "identifier").len(), 5);
+        assert_eq!(texts(&second, "identifier")[0], "oai:lens:release:r100");
+        assert!(second.contains("cursor=\"100\"></resumptionToken>"));
+
+        // A record updated after the harvest started moves to the end
+        storage.put_release(&release("r050", "movies", "2025-01-01T00:00:00Z")).unwrap();
+        let again = request(&storage, &[("verb", "ListIdentifiers"), ("resumptionToken", token)]);
+        assert_eq!(texts(&again, "identifier").last().unwrap(), "oai:lens:release:r050");
+
+        let mixed = request(&storage, &[("verb", "ListRecords"), ("resumptionToken", token), ("set", "movies")]);
+        assert_eq!(error_code(&mixed), Some("badArgument"));
+    }
+
+    #[test]
+    fn test_list_sets_and_formats() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::open(dir.path()).unwrap();
+        storage.init_default_categories().unwrap();
+
+        let sets = request(&storage, &[("verb", "ListSets")]);
+        assert!(texts(&sets, "setSpec").contains(&"movies".to_string()));
+
+        let formats = request(&storage, &[("verb", "ListMetadataFormats")]);
+        assert_eq!(texts(&formats, "metadataPrefix"), ["oai_dc", "datacite"]);
+    }
+}
@@ -60,7 +60,7 @@
 //!
 //! Nodes in same swarm have same VDF chain tip (or converging to it).
 
-use crate::vdf_race::{VdfChain, VdfLink, AnchoredSlotClaim, claim_has_priority, REORG_THRESHOLD};
+use crate::vdf_race::{VdfChain, VdfLink, AnchoredSlotClaim, claim_has_priority, REORG_THRESHOLD, VDF_ITERATIONS};
 use ed25519_dalek::SigningKey;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
@@ -181,7 +181,7 @@ impl SwarmState {
     /// Create new swarm as genesis (we are the founding node)
     pub fn new_genesis(genesis_seed: [u8; 32], signing_key: SigningKey) -> Self {
         let our_pubkey = signing_key.verifying_key().to_bytes();
-        let vdf_chain = VdfChain::new_genesis(genesis_seed, our_pubkey);
+        let vdf_chain = VdfChain::new_genesis(genesis_seed, our_pubkey, VDF_ITERATIONS);
 
         Self {
             signing_key,
@@ -203,7 +203,7 @@ impl SwarmState {
         existing_chain: Vec<VdfLink>,
     ) -> Option<Self> {
         let our_pubkey = signing_key.verifying_key().to_bytes();
-        let vdf_chain = VdfChain::from_links(genesis_seed, existing_chain, our_pubkey)?;
+        let vdf_chain = VdfChain::from_links(genesis_seed, existing_chain, our_pubkey, VDF_ITERATIONS)?;
 
         Some(Self {
             signing_key,
@@ -291,7 +291,7 @@ impl SwarmState {
 
         // Verify link is valid
         let expected_previous = self.vdf_chain.tip().map(|l| l.output).unwrap_or([0u8; 32]);
-        if !link.verify(&expected_previous) {
+        if !link.verify(&expected_previous, self.vdf_chain.iterations()) {
             return false;
         }
 
@@ -300,7 +300,7 @@ impl SwarmState {
         let mut links = self.vdf_chain.all_links().to_vec();
         links.push(link);
 
-        if let Some(new_chain) = VdfChain::from_links(self.genesis_seed, links, self.our_pubkey) {
+        if let Some(new_chain) = VdfChain::from_links(self.genesis_seed, links, self.our_pubkey, self.vdf_chain.iterations()) {
             self.vdf_chain = new_chain;
             true
         } else {
@@ -437,6 +437,7 @@ impl SwarmState {
             self.genesis_seed,
             candidate.chain_links,
             self.our_pubkey,
+            self.vdf_chain.iterations(),
         ) {
             self.vdf_chain = new_chain;
 
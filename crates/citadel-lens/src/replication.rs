@@ -0,0 +1,644 @@
+//! Lens Content Replication
+//!
+//! Maps lens data onto SPORE content blocks so the mesh can replicate it with
+//! [`SporeSyncManager`](citadel_protocols::SporeSyncManager). Peers exchange
+//! HaveLists over block hashes and only transfer blocks in the XOR difference.
+//!
+//! Every replicated fact is a [`Record`]: a JSON body plus the millisecond
+//! timestamp of the write. The block hash is BLAKE3 of the encoded record, so
+//! any edit produces a new block.
+//!
+//! # Conflict resolution
+//!
+//! Releases, content items and categories are last-writer-wins per record key,
+//! ordered by `(updated_ms, block hash)`. Timestamps more than
+//! [`MAX_RECORD_SKEW_MS`] ahead of the local clock are rejected, so no record
+//! can claim the key forever. The encoded record that won is kept
+//! under the `sync:` prefix in storage, which lets a deleted release or content
+//! item live on as a tombstone (`release: None`, `ContentItemDeleted`) and lets
+//! restarts re-announce the same blocks.
+//!
+//! Admin operations, peers and slot claims are grow-only sets. Their records
+//! carry `updated_ms = 0` so nodes that learn the same fact produce the same
+//! block. Admin operations are signed and only take effect through the
+//! replayed log in [`crate::acl`].
+//! Records that predate replication are also stamped 0, which keeps identical
+//! defaults (e.g. the built-in categories) from being transferred at all.
+//!
+//! # Authorization
+//!
+//! Last-writer-wins records are signed by the node that made the write, with
+//! its node key. Nodes check who may call their API (see [`crate::api`]); the
+//! mesh checks which nodes may publish, using the rights from [`crate::acl`]:
+//!
+//! - releases and content items, including tombstones, need a signature from
+//!   an admin or a key granted upload, so a node's key must be granted upload
+//!   before its writes replicate
+//! - categories need an admin signature, except the unsigned built-in
+//!   defaults stamped 0, which are accepted only where the key is unset
+//!
+//! Releases and content items from nodes on older builds may carry an older
+//! schema version; they are upgraded as records are decoded (see
+//! [`crate::migrations`]). The encoded block is kept as received, so its hash
+//! still matches on every node.
+
+use crate::acl::{self, AdminOp};
+use crate::error::{Error, Result};
+use crate::migrations::{self, ModelKind};
+use crate::models::{Category, ContentItem, Release};
+use crate::storage::{Storage, StorageChange};
+use citadel_protocols::{ContentBlock, ContentType};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Domain separation tag for record signatures.
+pub const RECORD_SIGNING_TAG: &[u8] = b"CITADEL-LENS-RECORD-V1";
+
+/// How far in the future a replicated record's timestamp may be.
+pub const MAX_RECORD_SKEW_MS: u64 = 5 * 60 * 1000;
+
+/// A replicated fact with the time it was written.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    /// Milliseconds since the Unix epoch (0 for sets and pre-existing data)
+    pub updated_ms: u64,
+    /// The replicated value
+    pub body: RecordBody,
+    /// Hex public key of the node that signed the record
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signer: Option<String>,
+    /// Hex ed25519 signature over [`Record::signing_bytes`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+/// The value carried by a [`Record`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RecordBody {
+    /// Release metadata, `None` once deleted
+    Release { id: String, release: Option<Release> },
+    /// Content item
+    ContentItem { item: Box<ContentItem> },
+    /// Category
+    Category { category: Category },
+    /// Signed change to admin or upload rights
+    AdminOp { op: AdminOp },
+    /// Mesh peer address and identity
+    Peer { id: String, addr: String, public_key: Option<Vec<u8>> },
+    /// SPIRAL slot claim
+    SlotClaim { index: u64, peer_id: String, public_key: Option<Vec<u8>> },
+    /// Content item tombstone
+    ContentItemDeleted { id: String },
+}
+
+impl RecordBody {
+    /// SPORE content type for blocks carrying this body.
+    pub fn content_type(&self) -> ContentType {
+        match self {
+            RecordBody::Release { .. } => ContentType::Release,
+            RecordBody::ContentItem { .. } | RecordBody::ContentItemDeleted { .. } => ContentType::ContentItem,
+            RecordBody::Category { .. } => ContentType::Category,
+            RecordBody::AdminOp { .. } => ContentType::Admin,
+            RecordBody::Peer { .. } => ContentType::PeerInfo,
+            RecordBody::SlotClaim { .. } => ContentType::SlotClaim,
+        }
+    }
+
+    /// Storage key for last-writer-wins records, `None` for grow-only sets.
+    pub fn record_key(&self) -> Option<String> {
+        match self {
+            RecordBody::Release { id, .. } => Some(format!("release:{}", id)),
+            RecordBody::ContentItem { item } => Some(format!("content:{}", item.id)),
+            RecordBody::ContentItemDeleted { id } => Some(format!("content:{}", id)),
+            RecordBody::Category { category } => Some(format!("category:{}", category.id)),
+            RecordBody::AdminOp { .. } | RecordBody::Peer { .. } | RecordBody::SlotClaim { .. } => None,
+        }
+    }
+}
+
+impl From<StorageChange> for RecordBody {
+    fn from(change: StorageChange) -> Self {
+        match change {
+            StorageChange::Release(release) => RecordBody::Release {
+                id: release.id.clone(),
+                release: Some(release),
+            },
+            StorageChange::ReleaseDeleted(id) => RecordBody::Release { id, release: None },
+            StorageChange::ContentItem(item) => RecordBody::ContentItem { item },
+            StorageChange::ContentItemDeleted(id) => RecordBody::ContentItemDeleted { id },
+            StorageChange::Category(category) => RecordBody::Category { category },
+            StorageChange::AdminOp(op) => RecordBody::AdminOp { op },
+        }
+    }
+}
+
+impl Record {
+    /// Create an unsigned record.
+    pub fn new(updated_ms: u64, body: RecordBody) -> Self {
+        Self { updated_ms, body, signer: None, signature: None }
+    }
+
+    /// Create a record signed by `signing_key`.
+    pub fn signed(updated_ms: u64, body: RecordBody, signing_key: &SigningKey) -> Result<Self> {
+        let signer = signing_key.verifying_key();
+        let bytes = Self::signing_bytes(&signer, updated_ms, &serde_json::to_value(&body)?)?;
+        Ok(Self {
+            updated_ms,
+            body,
+            signer: Some(hex::encode(signer.as_bytes())),
+            signature: Some(hex::encode(signing_key.sign(&bytes).to_bytes())),
+        })
+    }
+
+    /// Bytes covered by the signature: tag ‖ signer ‖ updated_ms (BE) ‖ body
+    /// as JSON with sorted keys.
+    pub fn signing_bytes(signer: &VerifyingKey, updated_ms: u64, body: &serde_json::Value) -> Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(RECORD_SIGNING_TAG.len() + 40);
+        bytes.extend_from_slice(RECORD_SIGNING_TAG);
+        bytes.extend_from_slice(signer.as_bytes());
+        bytes.extend_from_slice(&updated_ms.to_be_bytes());
+        serde_json::to_writer(&mut bytes, body)?;
+        Ok(bytes)
+    }
+
+    /// Check the signature of an encoded record, returning the signer if it
+    /// is signed at all.
+    ///
+    /// The body is checked as encoded, before any schema upgrade, so records
+    /// from older builds still verify.
+    pub fn verified_signer(data: &[u8]) -> Result<Option<String>> {
+        let value: serde_json::Value = serde_json::from_slice(data)?;
+        let field = |name: &str| value.get(name).and_then(|v| v.as_str());
+        let (Some(signer), Some(signature)) = (field("signer"), field("signature")) else {
+            return Ok(None);
+        };
+
+        let invalid = || Error::Unauthorized("Invalid record signature".into());
+        let key: [u8; 32] = hex::decode(signer).ok().and_then(|b| b.try_into().ok()).ok_or_else(invalid)?;
+        let signature: [u8; 64] = hex::decode(signature).ok().and_then(|b| b.try_into().ok()).ok_or_else(invalid)?;
+        // One spelling per key, so rights lookups can't be dodged with case variants
+        if hex::encode(key) != signer {
+            return Err(invalid());
+        }
+        let key = VerifyingKey::from_bytes(&key).map_err(|_| invalid())?;
+        let updated_ms = value.get("updated_ms").and_then(|v| v.as_u64()).ok_or_else(invalid)?;
+        let body = value.get("body").ok_or_else(invalid)?;
+        key.verify_strict(&Self::signing_bytes(&key, updated_ms, body)?, &Signature::from_bytes(&signature))
+            .map_err(|_| invalid())?;
+        Ok(Some(signer.to_string()))
+    }
+
+    /// Encode as a SPORE content block.
+    pub fn to_block(&self) -> Result<ContentBlock> {
+        Ok(ContentBlock::new(self.body.content_type(), serde_json::to_vec(self)?))
+    }
+
+    /// Decode an encoded record, upgrading the release or content item it
+    /// carries to the current schema version.
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let mut value: serde_json::Value = serde_json::from_slice(data)?;
+        if let Some(body) = value.get_mut("body") {
+            let (field, kind) = match body.get("kind").and_then(|kind| kind.as_str()) {
+                Some("release") => ("release", ModelKind::Release),
+                Some("content_item") => ("item", ModelKind::ContentItem),
+                _ => return Ok(serde_json::from_value(value)?),
+            };
+            if let Some(model) = body.get_mut(field) {
+                migrations::BUILTIN.upgrade(kind, model);
+            }
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Decode a content block, checking its hash and content type.
+    pub fn from_block(block: &ContentBlock) -> Result<Self> {
+        if blake3::hash(&block.data).as_bytes() != &block.hash {
+            return Err(Error::Protocol("content block hash mismatch".into()));
+        }
+        let record = Record::decode(&block.data)?;
+        if record.body.content_type() != block.content_type {
+            return Err(Error::Protocol(format!(
+                "content block type {:?} does not match record",
+                block.content_type
+            )));
+        }
+        Ok(record)
+    }
+}
+
+/// Milliseconds since the Unix epoch.
+pub fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn stored_block(storage: &Storage, record_key: &str) -> Result<Option<(Record, ContentBlock)>> {
+    let Some(data) = storage.get_sync_record(record_key)? else {
+        return Ok(None);
+    };
+    let record = Record::decode(&data)?;
+    let block = ContentBlock::new(record.body.content_type(), data);
+    Ok(Some((record, block)))
+}
+
+/// Block for a local write.
+///
+/// If the stored record already has this body (e.g. the write was a replica
+/// applied by [`apply_remote`]) its block is returned unchanged. Otherwise the
+/// body is stamped with `now_ms`, or just after the previous stamp if the clock
+/// is behind it, signed with the node key and stored as the winning record.
+/// Categories stamped 0 stay unsigned so every node's defaults match.
+pub fn local_block(storage: &Storage, body: RecordBody, now_ms: u64) -> Result<ContentBlock> {
+    let Some(key) = body.record_key() else {
+        return Record::new(0, body).to_block();
+    };
+
+    let updated_ms = match stored_block(storage, &key)? {
+        Some((prev, block)) if prev.body == body => return Ok(block),
+        Some((prev, _)) => now_ms.max(prev.updated_ms + 1),
+        None => now_ms,
+    };
+
+    let record = match body {
+        RecordBody::Category { .. } if updated_ms == 0 => Record::new(updated_ms, body),
+        _ => Record::signed(updated_ms, body, &storage.get_or_create_node_key()?)?,
+    };
+    let block = record.to_block()?;
+    storage.put_sync_record(&key, &block.data)?;
+    Ok(block)
+}
+
+/// Apply a record received from the mesh to storage.
+///
+/// Returns `true` if storage changed. Last-writer-wins records that are not
+/// newer than the stored one are ignored; records that are unauthorized (see
+/// the module docs) or stamped too far in the future are errors. Admin
+/// operations must carry a valid signature. Peer and slot claim records are
+/// mesh state and never touch storage.
+pub fn apply_remote(storage: &Storage, record: &Record, block: &ContentBlock) -> Result<bool> {
+    apply_remote_at(storage, record, block, now_ms())
+}
+
+/// Apply records received together, returning [`apply_remote`]'s result for
+/// each.
+///
+/// Admin operations are added first and rights updated once for the batch
+/// (see [`acl::apply_all`]), so records they authorize are accepted. The
+/// outer error is a failure to update the rights.
+pub fn apply_remote_all(storage: &Storage, records: &[(Record, ContentBlock)]) -> Result<Vec<Result<bool>>> {
+    let now_ms = now_ms();
+    let mut results: Vec<Option<Result<bool>>> = records.iter().map(|_| None).collect();
+    let (positions, ops): (Vec<usize>, Vec<&AdminOp>) = records
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (record, _))| match &record.body {
+            RecordBody::AdminOp { op } => Some((i, op)),
+            _ => None,
+        })
+        .unzip();
+    for (i, result) in positions.into_iter().zip(acl::apply_all(storage, ops, now_ms)?) {
+        results[i] = Some(result);
+    }
+    Ok(results
+        .into_iter()
+        .zip(records)
+        .map(|(result, (record, block))| result.unwrap_or_else(|| apply_remote_at(storage, record, block, now_ms)))
+        .collect())
+}
+
+fn apply_remote_at(storage: &Storage, record: &Record, block: &ContentBlock, now_ms: u64) -> Result<bool> {
+    if let RecordBody::AdminOp { op } = &record.body {
+        return acl::apply(storage, op, now_ms);
+    }
+    let Some(key) = record.body.record_key() else {
+        return Ok(false);
+    };
+    if record.updated_ms > now_ms.saturating_add(MAX_RECORD_SKEW_MS) {
+        return Err(Error::InvalidInput("Replicated record timestamp is in the future".into()));
+    }
+
+    let prev = stored_block(storage, &key)?;
+    authorize(storage, record, block, prev.is_some())?;
+    if let Some((prev, prev_block)) = prev {
+        if (prev.updated_ms, prev_block.hash) >= (record.updated_ms, block.hash) {
+            return Ok(false);
+        }
+    }
+
+    // Record the winner first so the change this write publishes is
+    // recognised as a replica rather than a new local edit
+    storage.put_sync_record(&key, &block.data)?;
+    match &record.body {
+        RecordBody::Release { release: Some(release), .. } => storage.put_release(release)?,
+        RecordBody::Release { id, release: None } => storage.delete_release(id)?,
+        RecordBody::ContentItem { item } => storage.put_content_item(item)?,
+        RecordBody::ContentItemDeleted { id } => storage.delete_content_item(id)?,
+        RecordBody::Category { category } => storage.put_category(category)?,
+        RecordBody::AdminOp { .. } | RecordBody::Peer { .. } | RecordBody::SlotClaim { .. } => {}
+    }
+    Ok(true)
+}
+
+/// Check that a last-writer-wins record was signed by a key allowed to write
+/// it. `replaces` is whether a record is already stored under its key.
+fn authorize(storage: &Storage, record: &Record, block: &ContentBlock, replaces: bool) -> Result<()> {
+    let signer = Record::verified_signer(&block.data)?;
+    let allowed = match (&record.body, signer) {
+        (RecordBody::Category { .. }, None) => record.updated_ms == 0 && !replaces,
+        (RecordBody::Category { .. }, Some(signer)) => storage.is_admin(&signer)?,
+        (_, Some(signer)) => storage.is_admin(&signer)? || storage.has_permission(&signer, "upload")?,
+        (_, None) => false,
+    };
+    if !allowed {
+        return Err(Error::Unauthorized(format!(
+            "{:?} record not signed by an authorized key",
+            block.content_type
+        )));
+    }
+    Ok(())
+}
+
+/// Blocks for everything replicated from storage: releases and content
+/// items (including tombstones), categories and admin operations.
+///
+/// Records written while the mesh was not listening are stamped here, and
+/// releases and content items deleted in that time get a tombstone.
+pub fn storage_blocks(storage: &Storage) -> Result<Vec<ContentBlock>> {
+    let mut bodies: Vec<RecordBody> = Vec::new();
+    bodies.extend(storage.list_releases()?.into_iter().map(|r| StorageChange::Release(r).into()));
+    bodies.extend(storage.list_content_items()?.into_iter().map(|i| RecordBody::ContentItem { item: Box::new(i) }));
+    bodies.extend(storage.list_categories()?.into_iter().map(|c| RecordBody::Category { category: c }));
+    bodies.extend(storage.list_admin_ops()?.into_iter().map(|op| RecordBody::AdminOp { op }));
+
+    let live: HashSet<String> = bodies.iter().filter_map(RecordBody::record_key).collect();
+    let now = now_ms();
+    let mut blocks = Vec::with_capacity(bodies.len());
+    for data in storage.list_sync_records()? {
+        let record = Record::decode(&data)?;
+        match record.body {
+            RecordBody::Release { id, .. } if !live.contains(&format!("release:{}", id)) => {
+                blocks.push(local_block(storage, RecordBody::Release { id, release: None }, now)?);
+            }
+            RecordBody::ContentItem { item } if !live.contains(&format!("content:{}", item.id)) => {
+                blocks.push(local_block(storage, RecordBody::ContentItemDeleted { id: item.id }, now)?);
+            }
+            RecordBody::ContentItemDeleted { id } if !live.contains(&format!("content:{}", id)) => {
+                blocks.push(local_block(storage, RecordBody::ContentItemDeleted { id }, now)?);
+            }
+            _ => {}
+        }
+    }
+
+    for body in bodies {
+        // Pre-existing data is stamped 0; edits made offline get a fresh stamp
+        blocks.push(local_block(storage, body, 0)?);
+    }
+    Ok(blocks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use citadel_protocols::SporeSyncManager;
+    use citadel_spore::U256;
+    use tempfile::tempdir;
+
+    fn release(id: &str, title: &str) -> Release {
+        Release::new(id.into(), title.into(), "music".into())
+    }
+
+    fn root_key() -> ed25519_dalek::SigningKey {
+        ed25519_dalek::SigningKey::from_bytes(&[1; 32])
+    }
+
+    fn add_admin_op(ts: u64) -> AdminOp {
+        AdminOp::signed(acl::AdminAction::AddAdmin, &"ab".repeat(32), ts, &root_key()).unwrap()
+    }
+
+    fn grant_upload(storage: &Storage, key: &ed25519_dalek::SigningKey) {
+        let subject = hex::encode(key.verifying_key().as_bytes());
+        let op = AdminOp::signed(acl::AdminAction::GrantUpload, &subject, 1, &root_key()).unwrap();
+        acl::apply(storage, &op, now_ms()).unwrap();
+    }
+
+    fn storage_with_root() -> (tempfile::TempDir, Storage) {
+        let dir = tempdir().unwrap();
+        let storage = Storage::open(dir.path()).unwrap();
+        storage.add_root_admin(&hex::encode(root_key().verifying_key().as_bytes())).unwrap();
+        acl::rebuild(&storage).unwrap();
+        (dir, storage)
+    }
+
+    fn signed(updated_ms: u64, body: RecordBody) -> Record {
+        Record::signed(updated_ms, body, &root_key()).unwrap()
+    }
+
+    fn apply(storage: &Storage, record: &Record) -> Result<bool> {
+        apply_remote(storage, record, &record.to_block().unwrap())
+    }
+
+    #[test]
+    fn test_block_roundtrip() {
+        let record = Record::new(42, RecordBody::Release { id: "a".into(), release: Some(release("a", "A")) });
+        let block = record.to_block().unwrap();
+        assert_eq!(block.content_type, ContentType::Release);
+        assert_eq!(Record::from_block(&block).unwrap(), record);
+
+        let mut tampered = block.clone();
+        tampered.data.push(b' ');
+        assert!(Record::from_block(&tampered).is_err());
+
+        let mut mislabeled = block;
+        mislabeled.content_type = ContentType::Admin;
+        assert!(Record::from_block(&mislabeled).is_err());
+    }
+
+    #[test]
+    fn test_local_block_stamping() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::open(dir.path()).unwrap();
+        let body = |title: &str| RecordBody::Release { id: "a".into(), release: Some(release("a", title)) };
+
+        let first = local_block(&storage, body("A"), 1000).unwrap();
+        // Same body again (e.g. an applied replica) reuses the stored block
+        assert_eq!(local_block(&storage, body("A"), 5000).unwrap().hash, first.hash);
+
+        // An edit with a lagging clock still sorts after the previous write
+        let edited = local_block(&storage, body("B"), 10).unwrap();
+        assert_eq!(Record::from_block(&edited).unwrap().updated_ms, 1001);
+
+        // Set records are stamped 0 so every node agrees on the block
+        let op = add_admin_op(5);
+        let admin = |now| local_block(&storage, RecordBody::AdminOp { op: op.clone() }, now).unwrap();
+        assert_eq!(admin(1).hash, admin(2).hash);
+    }
+
+    #[test]
+    fn test_apply_remote_last_writer_wins() {
+        let (_dir, storage) = storage_with_root();
+
+        let older = signed(100, RecordBody::Release { id: "a".into(), release: Some(release("a", "old")) });
+        let newer = signed(200, RecordBody::Release { id: "a".into(), release: Some(release("a", "new")) });
+        let tombstone = signed(300, RecordBody::Release { id: "a".into(), release: None });
+
+        assert!(apply(&storage, &newer).unwrap());
+        assert!(!apply(&storage, &older).unwrap());
+        assert_eq!(storage.get_release("a").unwrap().unwrap().title, "new");
+
+        assert!(apply(&storage, &tombstone).unwrap());
+        assert!(storage.get_release("a").unwrap().is_none());
+        // The tombstone outlives the release and keeps stale copies out
+        assert!(!apply(&storage, &newer).unwrap());
+        assert!(storage.get_release("a").unwrap().is_none());
+
+        let admin = Record::new(0, RecordBody::AdminOp { op: add_admin_op(5) });
+        assert!(apply_remote(&storage, &admin, &admin.to_block().unwrap()).unwrap());
+        assert!(!apply_remote(&storage, &admin, &admin.to_block().unwrap()).unwrap());
+        assert!(storage.is_admin(&"ab".repeat(32)).unwrap());
+
+        // Unsigned or tampered admin changes are refused
+        let mut forged = add_admin_op(6);
+        forged.subject = "cd".repeat(32);
+        let forged = Record::new(0, RecordBody::AdminOp { op: forged });
+        assert!(apply_remote(&storage, &forged, &forged.to_block().unwrap()).is_err());
+        assert!(!storage.is_admin(&"cd".repeat(32)).unwrap());
+    }
+
+    #[test]
+    fn test_apply_remote_requires_authorized_signer() {
+        let (_dir, storage) = storage_with_root();
+        let body = |title: &str| RecordBody::Release { id: "a".into(), release: Some(release("a", title)) };
+        let node = ed25519_dalek::SigningKey::from_bytes(&[9; 32]);
+
+        // Unsigned, or signed by a key without rights
+        assert!(apply(&storage, &Record::new(100, body("A"))).is_err());
+        let by_node = Record::signed(100, body("A"), &node).unwrap();
+        assert!(apply(&storage, &by_node).is_err());
+        assert!(storage.get_release("a").unwrap().is_none());
+
+        // Once the node key may upload, its writes replicate
+        grant_upload(&storage, &node);
+        assert!(apply(&storage, &by_node).unwrap());
+
+        // A body altered after signing no longer verifies
+        let mut tampered = Record::signed(200, body("A"), &node).unwrap();
+        tampered.body = body("B");
+        assert!(apply(&storage, &tampered).is_err());
+
+        // Nothing can claim a key forever with a far-future stamp
+        let wipe = signed(u64::MAX, RecordBody::Release { id: "a".into(), release: None });
+        assert!(apply(&storage, &wipe).is_err());
+        let ahead = signed(1000 + MAX_RECORD_SKEW_MS + 1, body("C"));
+        assert!(apply_remote_at(&storage, &ahead, &ahead.to_block().unwrap(), 1000).is_err());
+        assert_eq!(storage.get_release("a").unwrap().unwrap().title, "A");
+
+        // Unsigned categories are only the defaults filling an unset key;
+        // edits need an admin
+        let category = |name: &str| RecordBody::Category { category: Category::new("c".into(), name.into()) };
+        assert!(apply(&storage, &Record::new(0, category("C"))).unwrap());
+        assert!(apply(&storage, &Record::new(0, category("D"))).is_err());
+        assert!(apply(&storage, &Record::signed(10, category("D"), &node).unwrap()).is_err());
+        assert!(apply(&storage, &signed(10, category("D"))).unwrap());
+    }
+
+    #[test]
+    fn test_storage_blocks_include_tombstones() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::open(dir.path()).unwrap();
+        storage.put_release(&release("a", "A")).unwrap();
+        storage.put_release(&release("b", "B")).unwrap();
+        storage.put_content_item(&ContentItem::new("c".into(), Default::default(), "C".into())).unwrap();
+        let before = storage_blocks(&storage).unwrap();
+
+        // Deleted while the mesh wasn't running
+        storage.delete_release("b").unwrap();
+        storage.delete_content_item("c").unwrap();
+        let after = storage_blocks(&storage).unwrap();
+        assert_eq!(after.len(), before.len());
+
+        let records: Vec<Record> = after.iter().map(|b| Record::from_block(b).unwrap()).collect();
+        assert!(records.iter().any(|r| r.body == RecordBody::Release { id: "b".into(), release: None }));
+        assert!(records.iter().any(|r| r.body == RecordBody::ContentItemDeleted { id: "c".into() }));
+
+        // Unchanged storage re-announces the same blocks
+        let again: HashSet<_> = storage_blocks(&storage).unwrap().iter().map(|b| b.hash).collect();
+        assert_eq!(again, after.iter().map(|b| b.hash).collect());
+    }
+
+    /// Exchange SPORE messages both ways and deliver the blocks, as two mesh
+    /// nodes do on connect. Returns the number of blocks transferred.
+    fn exchange(
+        a: (&Storage, &mut SporeSyncManager, U256),
+        b: (&Storage, &mut SporeSyncManager, U256),
+    ) -> usize {
+        let (a_storage, a_sync, a_id) = a;
+        let (b_storage, b_sync, b_id) = b;
+        let a_msg = a_sync.get_or_create_peer(b_id).create_spore_message();
+        let b_msg = b_sync.get_or_create_peer(a_id).create_spore_message();
+        assert!(a_sync.receive_spore_message(b_id, b_msg));
+        assert!(b_sync.receive_spore_message(a_id, a_msg));
+
+        let to_b = a_sync.blocks_to_send(&b_id);
+        let to_a = b_sync.blocks_to_send(&a_id);
+        let transferred = to_a.len() + to_b.len();
+        for block in to_b {
+            let record = Record::from_block(&block).unwrap();
+            b_sync.receive_content(a_id, block.clone());
+            apply_remote(b_storage, &record, &block).unwrap();
+        }
+        for block in to_a {
+            let record = Record::from_block(&block).unwrap();
+            a_sync.receive_content(b_id, block.clone());
+            apply_remote(a_storage, &record, &block).unwrap();
+        }
+        transferred
+    }
+
+    #[test]
+    fn test_two_nodes_converge_on_xor_difference() {
+        let (_dir_a, a) = storage_with_root();
+        let (_dir_b, b) = storage_with_root();
+        for storage in [&a, &b] {
+            grant_upload(storage, &a.get_or_create_node_key().unwrap());
+            grant_upload(storage, &b.get_or_create_node_key().unwrap());
+        }
+        a.init_default_categories().unwrap();
+        b.init_default_categories().unwrap();
+        a.put_release(&release("shared", "Shared")).unwrap();
+        b.put_release(&release("shared", "Shared")).unwrap();
+        a.put_release(&release("only-a", "A")).unwrap();
+        acl::apply(&b, &add_admin_op(5), now_ms()).unwrap();
+
+        let (a_id, b_id) = (U256::from_u64(1), U256::from_u64(2));
+        let mut a_sync = SporeSyncManager::new(a_id);
+        let mut b_sync = SporeSyncManager::new(b_id);
+        for block in storage_blocks(&a).unwrap() {
+            a_sync.add_content(block);
+        }
+        for block in storage_blocks(&b).unwrap() {
+            b_sync.add_content(block);
+        }
+
+        // Defaults and shared admin operations cancel out. Releases are signed
+        // by the node that stored them, so both copies of the shared one move
+        // and the same copy wins on each side
+        let moved = exchange((&a, &mut a_sync, a_id), (&b, &mut b_sync, b_id));
+        assert_eq!(moved, 4);
+        assert_eq!(b.get_release("only-a").unwrap().unwrap().title, "A");
+        assert_eq!(a.get_sync_record("release:shared").unwrap(), b.get_sync_record("release:shared").unwrap());
+        assert!(a.is_admin(&"ab".repeat(32)).unwrap());
+
+        // Reconnecting transfers nothing
+        assert_eq!(exchange((&a, &mut a_sync, a_id), (&b, &mut b_sync, b_id)), 0);
+
+        // An edit on one side is the only block that moves next time
+        let block = local_block(&b, StorageChange::Release(release("shared", "Edited")).into(), now_ms()).unwrap();
+        b_sync.add_content(block);
+        assert_eq!(exchange((&a, &mut a_sync, a_id), (&b, &mut b_sync, b_id)), 1);
+        assert_eq!(a.get_release("shared").unwrap().unwrap().title, "Edited");
+    }
+}
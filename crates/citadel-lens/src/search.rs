@@ -0,0 +1,390 @@
+//! Full-text search over releases and content items.
+//!
+//! [`Storage`] keeps an inverted index next to the records and updates it in
+//! the same write batch, so local edits and content replicated from the mesh
+//! are searchable as soon as they are stored:
+//!
+//! ```text
+//! search:post:<term>\0<doc>  -> weighted term frequency (u32 LE)
+//! search:vocab:<term>        -> number of documents with the term (u64 LE)
+//! search:doc:<doc>           -> SearchDoc (JSON)
+//! ```
+//!
+//! `<doc>` is `release:<id>` or `content:<id>`. Terms are lowercased
+//! alphanumeric runs; a term in a title counts more than one in a
+//! description. Queries match every word, exactly, as a prefix or within a
+//! small edit distance, and rank by a tf-idf score weighted by match type.
+
+use crate::error::Result;
+use crate::models::{ContentItem, Release};
+use crate::storage::Storage;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Terms longer than this are not indexed.
+const MAX_TERM_LEN: usize = 64;
+
+/// Field weights for term frequencies.
+const TITLE_WEIGHT: u32 = 4;
+const NAME_WEIGHT: u32 = 2;
+const TEXT_WEIGHT: u32 = 1;
+
+/// Score multipliers by how a query word matched a term.
+const EXACT_MATCH: f64 = 1.0;
+const PREFIX_MATCH: f64 = 0.6;
+const FUZZY_MATCH: f64 = 0.4;
+
+/// Kind of record a search document describes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DocKind {
+    Release,
+    ContentItem,
+}
+
+/// The indexed form of a release or content item.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchDoc {
+    pub kind: DocKind,
+    pub id: String,
+    pub title: String,
+    /// Release category, if a release
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    /// Content type name, if a content item
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    /// Term to weighted frequency
+    pub terms: BTreeMap<String, u32>,
+}
+
+/// Split text into lowercase alphanumeric terms.
+pub fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty() && word.len() <= MAX_TERM_LEN)
+        .map(str::to_lowercase)
+}
+
+fn add_terms(terms: &mut BTreeMap<String, u32>, text: &str, weight: u32) {
+    for term in tokenize(text) {
+        *terms.entry(term).or_default() += weight;
+    }
+}
+
+/// Every string in a JSON value.
+fn json_strings<'a>(value: &'a serde_json::Value, out: &mut Vec<&'a str>) {
+    match value {
+        serde_json::Value::String(s) => out.push(s),
+        serde_json::Value::Array(items) => items.iter().for_each(|item| json_strings(item, out)),
+        serde_json::Value::Object(map) => map.values().for_each(|item| json_strings(item, out)),
+        _ => {}
+    }
+}
+
+impl SearchDoc {
+    /// Index a release's title, description, creator and tags.
+    pub fn from_release(release: &Release) -> Self {
+        let mut terms = BTreeMap::new();
+        add_terms(&mut terms, &release.title, TITLE_WEIGHT);
+        for tag in &release.tags {
+            add_terms(&mut terms, tag, NAME_WEIGHT);
+        }
+        if let Some(ref creator) = release.creator {
+            add_terms(&mut terms, creator, NAME_WEIGHT);
+        }
+        if let Some(ref description) = release.description {
+            add_terms(&mut terms, description, TEXT_WEIGHT);
+        }
+        Self {
+            kind: DocKind::Release,
+            id: release.id.clone(),
+            title: release.title.clone(),
+            category: Some(release.category_id.clone()),
+            content_type: None,
+            terms,
+        }
+    }
+
+    /// Index a content item's title, description, creators, tags and
+    /// standard metadata.
+    pub fn from_content_item(item: &ContentItem) -> Self {
+        let mut terms = BTreeMap::new();
+        add_terms(&mut terms, &item.title, TITLE_WEIGHT);
+        for tag in &item.tags {
+            add_terms(&mut terms, tag, NAME_WEIGHT);
+        }
+        for creator in &item.creators {
+            add_terms(&mut terms, &creator.name, NAME_WEIGHT);
+        }
+        if let Some(ref description) = item.description {
+            add_terms(&mut terms, description, TEXT_WEIGHT);
+        }
+        if let Ok(metadata) = serde_json::to_value(&item.metadata) {
+            let mut strings = Vec::new();
+            json_strings(&metadata, &mut strings);
+            for text in strings {
+                add_terms(&mut terms, text, TEXT_WEIGHT);
+            }
+        }
+        Self {
+            kind: DocKind::ContentItem,
+            id: item.id.clone(),
+            title: item.title.clone(),
+            category: None,
+            content_type: Some(item.content_type.name()),
+            terms,
+        }
+    }
+
+    /// Key of the document in the index.
+    pub fn key(&self) -> String {
+        match self.kind {
+            DocKind::Release => format!("release:{}", self.id),
+            DocKind::ContentItem => format!("content:{}", self.id),
+        }
+    }
+}
+
+/// A search request.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchQuery {
+    /// Words to match; every word must match
+    pub q: String,
+    /// Only this kind of record
+    pub kind: Option<DocKind>,
+    /// Only releases in this category
+    pub category: Option<String>,
+    /// Only content items of this type
+    pub content_type: Option<String>,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+/// One search result.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchHit {
+    pub kind: DocKind,
+    pub id: String,
+    pub title: String,
+    pub score: f64,
+}
+
+/// Result counts per category and content type.
+///
+/// Counted over everything matching the words (and kind), before the
+/// category and type filters, so clients can show what each filter would
+/// leave.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Facets {
+    pub category: BTreeMap<String, usize>,
+    pub content_type: BTreeMap<String, usize>,
+}
+
+/// A page of ranked results.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SearchResults {
+    /// Matches after filtering, across all pages
+    pub total: usize,
+    pub hits: Vec<SearchHit>,
+    pub facets: Facets,
+}
+
+/// Edit distance allowed for a fuzzy match of a word this long.
+fn max_edits(word: &str) -> usize {
+    match word.chars().count() {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+/// Levenshtein distance, giving up once it exceeds `limit`.
+fn edit_distance(a: &str, b: &str, limit: usize) -> Option<usize> {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > limit {
+        return None;
+    }
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut row = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != cb);
+            row[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(row[j] + 1);
+        }
+        if row.iter().min().is_some_and(|&min| min > limit) {
+            return None;
+        }
+        prev = row;
+    }
+    prev.last().copied().filter(|&d| d <= limit)
+}
+
+/// Index terms a query word matches, with the multiplier for each.
+///
+/// Fuzzy candidates must share the word's first character, which keeps the
+/// vocabulary scan to one slice of the index.
+fn matching_terms(storage: &Storage, word: &str) -> Result<Vec<(String, u64, f64)>> {
+    let edits = max_edits(word);
+    let scan_prefix = if edits > 0 {
+        word.chars().next().map(String::from).unwrap_or_default()
+    } else {
+        word.to_string()
+    };
+
+    let mut terms = Vec::new();
+    for (term, df) in storage.search_terms(&scan_prefix)? {
+        let weight = if term == word {
+            EXACT_MATCH
+        } else if term.starts_with(word) {
+            PREFIX_MATCH
+        } else if edit_distance(word, &term, edits).is_some() {
+            FUZZY_MATCH
+        } else {
+            continue;
+        };
+        terms.push((term, df, weight));
+    }
+    Ok(terms)
+}
+
+/// Run a search against the index.
+pub fn search(storage: &Storage, query: &SearchQuery) -> Result<SearchResults> {
+    let mut words: Vec<String> = tokenize(&query.q).collect();
+    words.sort();
+    words.dedup();
+    if words.is_empty() {
+        return Ok(SearchResults::default());
+    }
+
+    let doc_count = storage.search_doc_count()?.max(1) as f64;
+    let mut scores: Option<HashMap<String, f64>> = None;
+    for word in &words {
+        // Best score per document for this word
+        let mut word_scores: HashMap<String, f64> = HashMap::new();
+        for (term, df, weight) in matching_terms(storage, word)? {
+            let idf = (1.0 + (doc_count - df as f64 + 0.5) / (df as f64 + 0.5)).ln();
+            for (doc, tf) in storage.search_postings(&term)? {
+                if scores.as_ref().is_some_and(|s| !s.contains_key(&doc)) {
+                    continue;
+                }
+                let score = weight * idf * (1.0 + f64::from(tf).ln());
+                let best = word_scores.entry(doc).or_default();
+                *best = best.max(score);
+            }
+        }
+        scores = Some(match scores {
+            None => word_scores,
+            Some(prev) => word_scores
+                .into_iter()
+                .map(|(doc, score)| {
+                    let total = prev[&doc] + score;
+                    (doc, total)
+                })
+                .collect(),
+        });
+    }
+
+    let mut results = SearchResults::default();
+    let mut hits = Vec::new();
+    for (key, score) in scores.unwrap_or_default() {
+        let Some(doc) = storage.get_search_doc(&key)? else {
+            continue;
+        };
+        if query.kind.is_some_and(|kind| kind != doc.kind) {
+            continue;
+        }
+        if let Some(ref category) = doc.category {
+            *results.facets.category.entry(category.clone()).or_default() += 1;
+        }
+        if let Some(ref content_type) = doc.content_type {
+            *results.facets.content_type.entry(content_type.clone()).or_default() += 1;
+        }
+        let filtered_out = |filter: &Option<String>, value: &Option<String>| {
+            filter.as_ref().is_some_and(|f| !value.as_ref().is_some_and(|v| v.eq_ignore_ascii_case(f)))
+        };
+        if filtered_out(&query.category, &doc.category) || filtered_out(&query.content_type, &doc.content_type) {
+            continue;
+        }
+        hits.push(SearchHit { kind: doc.kind, id: doc.id, title: doc.title, score });
+    }
+
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.id.cmp(&b.id)));
+    results.total = hits.len();
+    results.hits = hits.into_iter().skip(query.offset).take(query.limit).collect();
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ContentType;
+    use tempfile::tempdir;
+
+    fn release(id: &str, title: &str, category: &str, description: &str) -> Release {
+        let mut release = Release::new(id.into(), title.into(), category.into());
+        release.description = Some(description.into());
+        release
+    }
+
+    fn query(q: &str) -> SearchQuery {
+        SearchQuery { q: q.into(), limit: 10, ..Default::default() }
+    }
+
+    fn ids(results: &SearchResults) -> Vec<&str> {
+        results.hits.iter().map(|hit| hit.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("kitten", "sitting", 3), Some(3));
+        assert_eq!(edit_distance("kitten", "sitting", 2), None);
+        assert_eq!(edit_distance("rust", "rust", 0), Some(0));
+    }
+
+    #[test]
+    fn test_search_ranking_and_matching() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::open(dir.path()).unwrap();
+        storage.put_release(&release("a", "Symphony No. 5", "music", "Beethoven's fifth")).unwrap();
+        storage.put_release(&release("b", "Night Songs", "music", "A symphony of city sounds")).unwrap();
+        storage.put_release(&release("c", "Space Film", "movies", "Orbital footage")).unwrap();
+        let item = ContentItem::new("d".into(), ContentType::Dataset, "Orbital debris catalogue".into());
+        storage.put_content_item(&item).unwrap();
+
+        // Title matches outrank description matches
+        assert_eq!(ids(&search(&storage, &query("symphony")).unwrap()), ["a", "b"]);
+        // Prefix and fuzzy
+        assert_eq!(ids(&search(&storage, &query("sympho")).unwrap()), ["a", "b"]);
+        assert_eq!(ids(&search(&storage, &query("beethovn")).unwrap()), ["a"]);
+        // Every word must match
+        assert_eq!(ids(&search(&storage, &query("symphony city")).unwrap()), ["b"]);
+
+        let results = search(&storage, &query("orbital")).unwrap();
+        assert_eq!(results.total, 2);
+        assert_eq!(results.facets.category.get("movies"), Some(&1));
+        assert_eq!(results.facets.content_type.get("dataset"), Some(&1));
+
+        let datasets = SearchQuery { content_type: Some("dataset".into()), ..query("orbital") };
+        assert_eq!(ids(&search(&storage, &datasets).unwrap()), ["d"]);
+        let releases = SearchQuery { kind: Some(DocKind::Release), ..query("orbital") };
+        assert_eq!(ids(&search(&storage, &releases).unwrap()), ["c"]);
+    }
+
+    #[test]
+    fn test_index_follows_writes() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::open(dir.path()).unwrap();
+        storage.put_release(&release("a", "Old Title", "music", "")).unwrap();
+        storage.put_release(&release("a", "New Title", "music", "")).unwrap();
+
+        assert!(search(&storage, &query("old")).unwrap().hits.is_empty());
+        assert_eq!(ids(&search(&storage, &query("new")).unwrap()), ["a"]);
+        assert_eq!(storage.search_terms("title").unwrap(), vec![("title".to_string(), 1)]);
+
+        storage.delete_release("a").unwrap();
+        assert!(search(&storage, &query("title")).unwrap().hits.is_empty());
+        assert!(storage.search_terms("").unwrap().is_empty());
+        assert_eq!(storage.search_doc_count().unwrap(), 0);
+    }
+}
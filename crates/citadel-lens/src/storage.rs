@@ -1,32 +1,386 @@
 //! Persistent storage using RocksDB.
 
-use crate::error::Result;
+use crate::acl::AdminOp;
+use crate::error::{Error, Result};
+use crate::migrations;
 use crate::models::{Category, ContentItem, Release};
+use crate::oai;
+use crate::search::SearchDoc;
+use citadel_dht::{DhtEntry, DhtKey, DhtStore};
 use ed25519_dalek::SigningKey;
-use rocksdb::{Options, DB};
+use rocksdb::{Direction, IteratorMode, Options, WriteBatch, DB};
 use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::sync::broadcast;
+
+/// A write to replicated content, published so the mesh can announce it.
+#[derive(Debug, Clone)]
+pub enum StorageChange {
+    /// Release created or updated
+    Release(Release),
+    /// Release deleted (by ID)
+    ReleaseDeleted(String),
+    /// Content item created or updated
+    ContentItem(Box<ContentItem>),
+    /// Content item deleted (by ID)
+    ContentItemDeleted(String),
+    /// Category created or updated
+    Category(Category),
+    /// Admin operation added to the log
+    AdminOp(AdminOp),
+}
+
+const ACL_ROOT_PREFIX: &[u8] = b"acl:root:";
+const ACL_OP_PREFIX: &[u8] = b"acl:op:";
+const DHT_PREFIX: &[u8] = b"dht:";
+const DHT_ENTRY_PREFIX: &[u8] = b"dht:entry:";
+const DHT_TIME_PREFIX: &[u8] = b"dht:time:";
+const BLOB_CHUNK_PREFIX: &[u8] = b"blob:chunk:";
+const BLOB_MANIFEST_PREFIX: &[u8] = b"blob:manifest:";
+const BLOB_PENDING_PREFIX: &[u8] = b"blob:pending:";
+const RELEASE_INDEX_PREFIX: &str = "idx:release:";
+const OAI_INDEX_PREFIX: &str = "idx:oai:";
+const SEARCH_PREFIX: &[u8] = b"search:";
+const SEARCH_POST_PREFIX: &str = "search:post:";
+const SEARCH_VOCAB_PREFIX: &str = "search:vocab:";
+const SEARCH_DOC_PREFIX: &str = "search:doc:";
+const SEARCH_DOC_COUNT: &[u8] = b"search:count";
+/// Last storage migration applied (u32 BE); see [`crate::migrations`].
+const SCHEMA_VERSION_KEY: &[u8] = b"meta:schema_version";
+/// Model schema versions stored records were last upgraded to; see
+/// [`crate::migrations::run`].
+const MODEL_VERSIONS_KEY: &[u8] = b"meta:model_versions";
+/// Index markers written before migrations were versioned.
+const LEGACY_MARKERS: [&[u8]; 2] = [b"meta:release_index:v1", b"meta:search_index:v1"];
+
+/// Filters for listing content items. Unset fields match everything;
+/// string comparisons ignore case.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContentQuery {
+    /// Content type name (see [`ContentType::name`](crate::models::ContentType::name))
+    pub content_type: Option<String>,
+    /// Tag the item must carry
+    pub tag: Option<String>,
+    /// Language code
+    pub language: Option<String>,
+    /// Creator name
+    pub creator: Option<String>,
+    /// Parent release ID
+    pub release_id: Option<String>,
+    /// Return items after this ID (the previous page's `next_cursor`)
+    pub after: Option<String>,
+    /// Maximum number of items to return
+    pub limit: usize,
+}
+
+impl ContentQuery {
+    /// Whether an item passes the filters (ignores `after` and `limit`).
+    pub fn matches(&self, item: &ContentItem) -> bool {
+        fn eq(filter: &Option<String>, value: &str) -> bool {
+            filter.as_ref().is_none_or(|f| f.eq_ignore_ascii_case(value))
+        }
+        eq(&self.content_type, &item.content_type.name())
+            && (self.tag.is_none() || item.tags.iter().any(|t| eq(&self.tag, t)))
+            && (self.language.is_none() || item.language.as_deref().is_some_and(|l| eq(&self.language, l)))
+            && (self.creator.is_none() || item.creators.iter().any(|c| eq(&self.creator, &c.name)))
+            && (self.release_id.is_none() || item.release_id.as_deref().is_some_and(|r| eq(&self.release_id, r)))
+    }
+}
+
+/// One page of a listing.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Pass as `after` to fetch the next page; `None` on the last page
+    pub next_cursor: Option<String>,
+}
+
+/// Field a release listing is ordered by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReleaseSortField {
+    /// Creation time; releases without one sort first
+    #[default]
+    Created,
+    /// Release year; releases without one sort first
+    Year,
+    /// Title, ignoring case
+    Title,
+}
+
+impl ReleaseSortField {
+    const ALL: [Self; 3] = [Self::Created, Self::Year, Self::Title];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Created => "created",
+            Self::Year => "year",
+            Self::Title => "title",
+        }
+    }
+
+    fn value(self, release: &Release) -> String {
+        match self {
+            Self::Created => release.created_at.as_deref().map(index_value).unwrap_or_default(),
+            Self::Year => release.year.map(year_value).unwrap_or_default(),
+            Self::Title => index_value(&release.title),
+        }
+    }
+}
+
+/// Ordering for a release listing, parsed from `created`, `-year`, etc.
+/// (a leading `-` means descending). Defaults to newest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReleaseSort {
+    pub field: ReleaseSortField,
+    pub descending: bool,
+}
+
+impl Default for ReleaseSort {
+    fn default() -> Self {
+        Self { field: ReleaseSortField::Created, descending: true }
+    }
+}
+
+impl std::str::FromStr for ReleaseSort {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (descending, name) = match s.strip_prefix('-') {
+            Some(name) => (true, name),
+            None => (false, s),
+        };
+        let field = ReleaseSortField::ALL
+            .into_iter()
+            .find(|field| field.name() == name)
+            .ok_or_else(|| Error::InvalidInput(format!("Unknown sort '{}'", s)))?;
+        Ok(Self { field, descending })
+    }
+}
+
+/// Filters and ordering for listing releases. Unset filters match
+/// everything; string comparisons ignore case.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReleaseQuery {
+    /// Category ID
+    pub category: Option<String>,
+    /// Tag the release must carry
+    pub tag: Option<String>,
+    /// Creator
+    pub creator: Option<String>,
+    /// Release year
+    pub year: Option<u32>,
+    pub sort: ReleaseSort,
+    /// Return releases after this cursor (the previous page's `next_cursor`)
+    pub after: Option<String>,
+    /// Maximum number of releases to return
+    pub limit: usize,
+}
+
+impl ReleaseQuery {
+    /// Whether a release passes the filters (ignores ordering and paging).
+    pub fn matches(&self, release: &Release) -> bool {
+        fn eq(filter: &Option<String>, value: &str) -> bool {
+            filter.as_ref().is_none_or(|f| index_value(f) == index_value(value))
+        }
+        eq(&self.category, &release.category_id)
+            && (self.tag.is_none() || release.tags.iter().any(|t| eq(&self.tag, t)))
+            && (self.creator.is_none() || release.creator.as_deref().is_some_and(|c| eq(&self.creator, c)))
+            && self.year.is_none_or(|year| release.year == Some(year))
+    }
+
+    /// The index to scan: the sort field, narrowed by the most selective filter.
+    fn index_prefix(&self) -> String {
+        let filter = if let Some(ref tag) = self.tag {
+            format!("tag={}", index_value(tag))
+        } else if let Some(ref creator) = self.creator {
+            format!("creator={}", index_value(creator))
+        } else if let Some(year) = self.year {
+            format!("year={}", year_value(year))
+        } else if let Some(ref category) = self.category {
+            format!("category={}", index_value(category))
+        } else {
+            String::new()
+        };
+        format!("{}{}:{}\0", RELEASE_INDEX_PREFIX, self.sort.field.name(), filter)
+    }
+}
+
+/// Normalised form of a string in an index key.
+fn index_value(value: &str) -> String {
+    value.to_lowercase().replace('\0', "")
+}
+
+/// Years are zero-padded so they sort numerically.
+fn year_value(year: u32) -> String {
+    format!("{:010}", year)
+}
+
+/// Index keys for a release: `idx:release:<sort>:<filter>\0<sort value>\0<id>`
+/// for each sort field, both unfiltered (empty filter) and under each value
+/// it can be filtered by. A filtered, ordered page is then one range scan.
+fn release_index_keys(release: &Release) -> Vec<String> {
+    let mut filters = vec![String::new(), format!("category={}", index_value(&release.category_id))];
+    filters.extend(release.tags.iter().map(|tag| format!("tag={}", index_value(tag))));
+    filters.extend(release.creator.as_deref().map(|c| format!("creator={}", index_value(c))));
+    filters.extend(release.year.map(|year| format!("year={}", year_value(year))));
+    filters.sort();
+    filters.dedup();
+
+    let mut keys = Vec::with_capacity(filters.len() * ReleaseSortField::ALL.len());
+    for field in ReleaseSortField::ALL {
+        let value = field.value(release);
+        for filter in &filters {
+            keys.push(format!("{}{}:{}\0{}\0{}", RELEASE_INDEX_PREFIX, field.name(), filter, value, release.id));
+        }
+    }
+    keys
+}
+
+/// Key of an OAI-PMH datestamp index entry (see [`oai::release_index_entry`]).
+fn oai_index_key(entry: &str) -> String {
+    format!("{}{}", OAI_INDEX_PREFIX, entry)
+}
 
 /// Storage backend for Lens data.
 pub struct Storage {
     db: DB,
+    /// Change feed for replicated content
+    changes: broadcast::Sender<StorageChange>,
+    /// Held by writes that read index rows or search counters and write
+    /// them back, so concurrent writes don't lose each other's updates
+    index_lock: Mutex<()>,
 }
 
 impl Storage {
-    /// Open or create storage at the given path.
+    /// Open or create storage at the given path, applying any pending
+    /// migrations (see [`crate::migrations`]).
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let storage = Self::open_unmigrated(path)?;
+        let report = migrations::run(&storage, &migrations::BUILTIN, false)?;
+        if !report.steps.is_empty() {
+            tracing::info!("Storage migrated from schema version {} to {}", report.from, report.to);
+        }
+        if report.records_upgraded > 0 {
+            tracing::info!("Upgraded {} stored records", report.records_upgraded);
+        }
+        Ok(storage)
+    }
+
+    /// Open storage without running migrations, e.g. to dry-run them.
+    pub fn open_unmigrated<P: AsRef<Path>>(path: P) -> Result<Self> {
         let mut opts = Options::default();
         opts.create_if_missing(true);
         let db = DB::open(&opts, path)?;
-        Ok(Self { db })
+        let (changes, _) = broadcast::channel(1024);
+        Ok(Self { db, changes, index_lock: Mutex::new(()) })
+    }
+
+    /// Serialize an index read-modify-write (see `index_lock`).
+    fn lock_indexes(&self) -> MutexGuard<'_, ()> {
+        self.index_lock.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // --- Schema ---
+
+    /// Last storage migration applied, 0 for storage that predates them.
+    pub fn schema_version(&self) -> Result<u32> {
+        match self.db.get(SCHEMA_VERSION_KEY)? {
+            Some(data) => {
+                let bytes: [u8; 4] = data.as_slice().try_into()
+                    .map_err(|_| Error::Storage("Invalid stored schema version".into()))?;
+                Ok(u32::from_be_bytes(bytes))
+            }
+            None => Ok(0),
+        }
+    }
+
+    /// Record a completed migration.
+    pub(crate) fn set_schema_version(&self, version: u32) -> Result<()> {
+        let mut batch = WriteBatch::default();
+        batch.put(SCHEMA_VERSION_KEY, version.to_be_bytes());
+        for marker in LEGACY_MARKERS {
+            batch.delete(marker);
+        }
+        self.db.write(batch)?;
+        Ok(())
+    }
+
+    /// Model schema versions stored records were last upgraded to.
+    pub(crate) fn model_versions(&self) -> Result<Option<String>> {
+        Ok(self.db.get(MODEL_VERSIONS_KEY)?.map(|data| String::from_utf8_lossy(&data).into_owned()))
+    }
+
+    /// Record that stored records were upgraded to `versions`.
+    pub(crate) fn set_model_versions(&self, versions: &str) -> Result<()> {
+        self.db.put(MODEL_VERSIONS_KEY, versions.as_bytes())?;
+        Ok(())
+    }
+
+    /// Up to `limit` raw entries under `prefix`, starting after key `after`.
+    pub(crate) fn scan_raw(&self, prefix: &[u8], after: Option<&[u8]>, limit: usize) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let start = after.unwrap_or(prefix);
+        let mut entries = Vec::new();
+        for entry in self.db.iterator(IteratorMode::From(start, Direction::Forward)) {
+            let (key, value) = entry?;
+            if !key.starts_with(prefix) || entries.len() == limit {
+                break;
+            }
+            if after.is_some_and(|after| *key == *after) {
+                continue;
+            }
+            entries.push((key.to_vec(), value.to_vec()));
+        }
+        Ok(entries)
+    }
+
+    /// Write raw entries in one batch, bypassing indexes and the change feed.
+    pub(crate) fn put_raw(&self, entries: Vec<(Vec<u8>, Vec<u8>)>) -> Result<()> {
+        let mut batch = WriteBatch::default();
+        for (key, value) in entries {
+            batch.put(key, value);
+        }
+        self.db.write(batch)?;
+        Ok(())
+    }
+
+    /// Subscribe to writes of replicated content (releases, content items,
+    /// categories, admins). Writes made before subscribing are not replayed.
+    pub fn subscribe_changes(&self) -> broadcast::Receiver<StorageChange> {
+        self.changes.subscribe()
+    }
+
+    fn publish(&self, change: StorageChange) {
+        // No subscribers is fine - the mesh may not be running
+        let _ = self.changes.send(change);
     }
 
     // --- Releases ---
 
-    /// Store a release.
+    /// Store a release, updating its index entries in the same write.
+    ///
+    /// The old entries to remove come from the stored release, so the read
+    /// and the write happen under the index lock; otherwise two concurrent
+    /// updates could each remove only the original entries and leave the
+    /// other's behind.
     pub fn put_release(&self, release: &Release) -> Result<()> {
         let key = format!("release:{}", release.id);
         let value = serde_json::to_vec(release)?;
-        self.db.put(key.as_bytes(), value)?;
+        let _indexes = self.lock_indexes();
+        let mut batch = WriteBatch::default();
+        if let Some(old) = self.get_release(&release.id)? {
+            for index_key in release_index_keys(&old) {
+                batch.delete(index_key);
+            }
+            batch.delete(oai_index_key(&oai::release_index_entry(&old)));
+        }
+        for index_key in release_index_keys(release) {
+            batch.put(index_key, b"");
+        }
+        batch.put(oai_index_key(&oai::release_index_entry(release)), b"");
+        self.index_search_doc(&mut batch, &key, Some(&SearchDoc::from_release(release)))?;
+        batch.put(key.as_bytes(), value);
+        self.db.write(batch)?;
+        self.publish(StorageChange::Release(release.clone()));
         Ok(())
     }
 
@@ -34,15 +388,27 @@ impl Storage {
     pub fn get_release(&self, id: &str) -> Result<Option<Release>> {
         let key = format!("release:{}", id);
         match self.db.get(key.as_bytes())? {
-            Some(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            Some(data) => Ok(Some(migrations::decode(&data)?)),
             None => Ok(None),
         }
     }
 
-    /// Delete a release.
+    /// Delete a release and its index entries (under the index lock, as in
+    /// [`put_release`](Self::put_release)).
     pub fn delete_release(&self, id: &str) -> Result<()> {
         let key = format!("release:{}", id);
-        self.db.delete(key.as_bytes())?;
+        let _indexes = self.lock_indexes();
+        let mut batch = WriteBatch::default();
+        if let Some(old) = self.get_release(id)? {
+            for index_key in release_index_keys(&old) {
+                batch.delete(index_key);
+            }
+            batch.delete(oai_index_key(&oai::release_index_entry(&old)));
+        }
+        self.index_search_doc(&mut batch, &key, None)?;
+        batch.delete(key.as_bytes());
+        self.db.write(batch)?;
+        self.publish(StorageChange::ReleaseDeleted(id.to_string()));
         Ok(())
     }
 
@@ -55,7 +421,7 @@ impl Storage {
         for item in iter {
             let (key, value) = item?;
             if key.starts_with(prefix) {
-                let release: Release = serde_json::from_slice(&value)?;
+                let release: Release = migrations::decode(&value)?;
                 releases.push(release);
             } else {
                 break;
@@ -65,13 +431,129 @@ impl Storage {
         Ok(releases)
     }
 
+    /// List releases matching a query using the release indexes.
+    ///
+    /// Only the index range for the query is read, plus one lookup per
+    /// candidate release, so a page costs about `limit` reads.
+    pub fn query_releases(&self, query: &ReleaseQuery) -> Result<Page<Release>> {
+        let prefix = query.index_prefix();
+        let after = match query.after {
+            Some(ref cursor) => {
+                let suffix = hex::decode(cursor)
+                    .ok()
+                    .and_then(|bytes| String::from_utf8(bytes).ok())
+                    .ok_or_else(|| Error::InvalidInput("Malformed cursor".into()))?;
+                Some(format!("{}{}", prefix, suffix))
+            }
+            None => None,
+        };
+        let seek = match (&after, query.sort.descending) {
+            (Some(after), _) => after.clone().into_bytes(),
+            (None, false) => prefix.clone().into_bytes(),
+            // Just past the last key with this prefix (the prefix ends in \0)
+            (None, true) => {
+                let mut end = prefix.clone().into_bytes();
+                *end.last_mut().expect("prefix is not empty") = 1;
+                end
+            }
+        };
+        let direction = if query.sort.descending { Direction::Reverse } else { Direction::Forward };
+
+        let mut items: Vec<Release> = Vec::new();
+        let mut last_key = None;
+        let mut next_cursor = None;
+        for entry in self.db.iterator(IteratorMode::From(&seek, direction)) {
+            let (key, _) = entry?;
+            if !key.starts_with(prefix.as_bytes()) {
+                break;
+            }
+            if after.as_deref().is_some_and(|after| *key == *after.as_bytes()) {
+                continue;
+            }
+            let Some(id) = key.rsplit(|b| *b == 0).next() else {
+                continue;
+            };
+            let Some(release) = self.get_release(&String::from_utf8_lossy(id))? else {
+                continue;
+            };
+            if !query.matches(&release) {
+                continue;
+            }
+            if items.len() == query.limit {
+                next_cursor = last_key.map(|key: Box<[u8]>| hex::encode(&key[prefix.len()..]));
+                break;
+            }
+            items.push(release);
+            last_key = Some(key);
+        }
+
+        Ok(Page { items, next_cursor })
+    }
+
+    /// Rebuild the release indexes from the stored releases.
+    pub fn rebuild_release_indexes(&self) -> Result<()> {
+        let mut batch = WriteBatch::default();
+        for entry in self.db.prefix_iterator(RELEASE_INDEX_PREFIX.as_bytes()) {
+            let (key, _) = entry?;
+            if !key.starts_with(RELEASE_INDEX_PREFIX.as_bytes()) {
+                break;
+            }
+            batch.delete(key);
+        }
+        for release in self.list_releases()? {
+            for index_key in release_index_keys(&release) {
+                batch.put(index_key, b"");
+            }
+        }
+        self.db.write(batch)?;
+        Ok(())
+    }
+
+    /// Rebuild the OAI-PMH datestamp index from the stored releases and
+    /// content items.
+    pub fn rebuild_oai_index(&self) -> Result<()> {
+        let mut batch = WriteBatch::default();
+        for (key, _) in self.scan_raw(OAI_INDEX_PREFIX.as_bytes(), None, usize::MAX)? {
+            batch.delete(key);
+        }
+        for release in self.list_releases()? {
+            batch.put(oai_index_key(&oai::release_index_entry(&release)), b"");
+        }
+        for item in self.list_content_items()? {
+            batch.put(oai_index_key(&oai::content_index_entry(&item)), b"");
+        }
+        self.db.write(batch)?;
+        Ok(())
+    }
+
+    /// Up to `limit` OAI-PMH datestamp index entries, oldest first, starting
+    /// after `after` (an entry or any prefix of one).
+    pub fn scan_oai_index(&self, after: &str, limit: usize) -> Result<Vec<String>> {
+        let start = oai_index_key(after);
+        let entries = self.scan_raw(OAI_INDEX_PREFIX.as_bytes(), Some(start.as_bytes()), limit)?;
+        Ok(entries
+            .into_iter()
+            .map(|(key, _)| String::from_utf8_lossy(&key[OAI_INDEX_PREFIX.len()..]).into_owned())
+            .collect())
+    }
+
     // --- Content Items ---
 
-    /// Store a content item.
+    /// Store a content item, updating its OAI-PMH index entry in the same
+    /// write (under the index lock, as in [`put_release`](Self::put_release)).
     pub fn put_content_item(&self, item: &ContentItem) -> Result<()> {
         let key = format!("content:{}", item.id);
         let value = serde_json::to_vec(item)?;
-        self.db.put(key.as_bytes(), value)?;
+        let _indexes = self.lock_indexes();
+        let mut batch = WriteBatch::default();
+        if let Some(old) = self.get_content_item(&item.id)? {
+            batch.delete(oai_index_key(&oai::content_index_entry(&old)));
+        }
+        batch.put(oai_index_key(&oai::content_index_entry(item)), b"");
+        self.index_search_doc(&mut batch, &key, Some(&SearchDoc::from_content_item(item)))?;
+        batch.put(key.as_bytes(), value);
+        self.db.write(batch)?;
+        self.publish(StorageChange::ContentItem(Box::new(item.clone())));
         Ok(())
     }
 
@@ -79,11 +561,218 @@ impl Storage {
     pub fn get_content_item(&self, id: &str) -> Result<Option<ContentItem>> {
         let key = format!("content:{}", id);
         match self.db.get(key.as_bytes())? {
+            Some(data) => Ok(Some(migrations::decode(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Delete a content item and its OAI-PMH index entry.
+    pub fn delete_content_item(&self, id: &str) -> Result<()> {
+        let key = format!("content:{}", id);
+        let _indexes = self.lock_indexes();
+        let mut batch = WriteBatch::default();
+        if let Some(old) = self.get_content_item(id)? {
+            batch.delete(oai_index_key(&oai::content_index_entry(&old)));
+        }
+        self.index_search_doc(&mut batch, &key, None)?;
+        batch.delete(key.as_bytes());
+        self.db.write(batch)?;
+        self.publish(StorageChange::ContentItemDeleted(id.to_string()));
+        Ok(())
+    }
+
+    /// List content items matching a query, in ID order.
+    pub fn query_content_items(&self, query: &ContentQuery) -> Result<Page<ContentItem>> {
+        let prefix = b"content:";
+        let seek = match query.after {
+            Some(ref after) => format!("content:{}", after),
+            None => "content:".to_string(),
+        };
+        let mut items: Vec<ContentItem> = Vec::new();
+        let mut next_cursor = None;
+
+        let iter = self.db.iterator(IteratorMode::From(seek.as_bytes(), Direction::Forward));
+        for entry in iter {
+            let (key, value) = entry?;
+            if !key.starts_with(prefix) {
+                break;
+            }
+            if *key == *seek.as_bytes() && query.after.is_some() {
+                continue;
+            }
+            let item: ContentItem = migrations::decode(&value)?;
+            if !query.matches(&item) {
+                continue;
+            }
+            if items.len() == query.limit {
+                next_cursor = items.last().map(|last| last.id.clone());
+                break;
+            }
+            items.push(item);
+        }
+
+        Ok(Page { items, next_cursor })
+    }
+
+    /// List all content items.
+    pub fn list_content_items(&self) -> Result<Vec<ContentItem>> {
+        let prefix = b"content:";
+        let mut items = Vec::new();
+
+        let iter = self.db.prefix_iterator(prefix);
+        for item in iter {
+            let (key, value) = item?;
+            if key.starts_with(prefix) {
+                items.push(migrations::decode(&value)?);
+            } else {
+                break;
+            }
+        }
+
+        Ok(items)
+    }
+
+    // --- Search index (see crate::search) ---
+
+    fn search_count(&self, key: &[u8]) -> Result<u64> {
+        Ok(self
+            .db
+            .get(key)?
+            .and_then(|data| data.try_into().ok())
+            .map(u64::from_le_bytes)
+            .unwrap_or(0))
+    }
+
+    /// Add the changes that replace the search document under `key` with
+    /// `doc` (or remove it) to a write batch.
+    ///
+    /// Document frequencies and the document count are read here and written
+    /// by the batch, so the caller must hold the index lock until the batch
+    /// is written.
+    fn index_search_doc(&self, batch: &mut WriteBatch, key: &str, doc: Option<&SearchDoc>) -> Result<()> {
+        let old = self.get_search_doc(key)?;
+        let old_terms = old.as_ref().map(|d| &d.terms);
+        let new_terms = doc.map(|d| &d.terms);
+
+        let mut df_changes: Vec<(&String, bool)> = Vec::new();
+        for term in old_terms.into_iter().flat_map(|t| t.keys()) {
+            if !new_terms.is_some_and(|t| t.contains_key(term)) {
+                batch.delete(format!("{}{}\0{}", SEARCH_POST_PREFIX, term, key));
+                df_changes.push((term, false));
+            }
+        }
+        for (term, tf) in new_terms.into_iter().flatten() {
+            batch.put(format!("{}{}\0{}", SEARCH_POST_PREFIX, term, key), tf.to_le_bytes());
+            if !old_terms.is_some_and(|t| t.contains_key(term)) {
+                df_changes.push((term, true));
+            }
+        }
+        for (term, added) in df_changes {
+            let vocab_key = format!("{}{}", SEARCH_VOCAB_PREFIX, term);
+            let df = self.search_count(vocab_key.as_bytes())?;
+            match (added, df) {
+                (true, df) => batch.put(vocab_key, (df + 1).to_le_bytes()),
+                (false, 0 | 1) => batch.delete(vocab_key),
+                (false, df) => batch.put(vocab_key, (df - 1).to_le_bytes()),
+            }
+        }
+
+        let doc_key = format!("{}{}", SEARCH_DOC_PREFIX, key);
+        let count = self.search_doc_count()?;
+        match (old.is_some(), doc) {
+            (_, Some(doc)) => batch.put(doc_key, serde_json::to_vec(doc)?),
+            (true, None) => batch.delete(doc_key),
+            (false, None) => {}
+        }
+        match (old.is_some(), doc.is_some()) {
+            (false, true) => batch.put(SEARCH_DOC_COUNT, (count + 1).to_le_bytes()),
+            (true, false) => batch.put(SEARCH_DOC_COUNT, count.saturating_sub(1).to_le_bytes()),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Get the search document for `release:<id>` or `content:<id>`.
+    pub fn get_search_doc(&self, key: &str) -> Result<Option<SearchDoc>> {
+        match self.db.get(format!("{}{}", SEARCH_DOC_PREFIX, key).as_bytes())? {
             Some(data) => Ok(Some(serde_json::from_slice(&data)?)),
             None => Ok(None),
         }
     }
 
+    /// Number of searchable documents.
+    pub fn search_doc_count(&self) -> Result<u64> {
+        self.search_count(SEARCH_DOC_COUNT)
+    }
+
+    /// Indexed terms starting with `prefix`, with the number of documents
+    /// containing each.
+    pub fn search_terms(&self, prefix: &str) -> Result<Vec<(String, u64)>> {
+        let seek = format!("{}{}", SEARCH_VOCAB_PREFIX, prefix);
+        let mut terms = Vec::new();
+        for entry in self.db.prefix_iterator(seek.as_bytes()) {
+            let (key, value) = entry?;
+            if !key.starts_with(seek.as_bytes()) {
+                break;
+            }
+            let term = String::from_utf8_lossy(&key[SEARCH_VOCAB_PREFIX.len()..]).into_owned();
+            let df = value[..].try_into().map(u64::from_le_bytes).unwrap_or(0);
+            terms.push((term, df));
+        }
+        Ok(terms)
+    }
+
+    /// Documents containing `term`, with its weighted frequency in each.
+    pub fn search_postings(&self, term: &str) -> Result<Vec<(String, u32)>> {
+        let seek = format!("{}{}\0", SEARCH_POST_PREFIX, term);
+        let mut postings = Vec::new();
+        for entry in self.db.prefix_iterator(seek.as_bytes()) {
+            let (key, value) = entry?;
+            if !key.starts_with(seek.as_bytes()) {
+                break;
+            }
+            let doc = String::from_utf8_lossy(&key[seek.len()..]).into_owned();
+            let tf = value[..].try_into().map(u32::from_le_bytes).unwrap_or(0);
+            postings.push((doc, tf));
+        }
+        Ok(postings)
+    }
+
+    /// Rebuild the search index from the stored releases and content items.
+    pub fn rebuild_search_index(&self) -> Result<()> {
+        let _indexes = self.lock_indexes();
+        let mut batch = WriteBatch::default();
+        for entry in self.db.prefix_iterator(SEARCH_PREFIX) {
+            let (key, _) = entry?;
+            if !key.starts_with(SEARCH_PREFIX) {
+                break;
+            }
+            batch.delete(key);
+        }
+
+        let docs: Vec<SearchDoc> = self
+            .list_releases()?
+            .iter()
+            .map(SearchDoc::from_release)
+            .chain(self.list_content_items()?.iter().map(SearchDoc::from_content_item))
+            .collect();
+        let mut vocab: std::collections::BTreeMap<&str, u64> = Default::default();
+        for doc in &docs {
+            let key = doc.key();
+            for (term, tf) in &doc.terms {
+                batch.put(format!("{}{}\0{}", SEARCH_POST_PREFIX, term, key), tf.to_le_bytes());
+                *vocab.entry(term).or_default() += 1;
+            }
+            batch.put(format!("{}{}", SEARCH_DOC_PREFIX, key), serde_json::to_vec(doc)?);
+        }
+        for (term, df) in vocab {
+            batch.put(format!("{}{}", SEARCH_VOCAB_PREFIX, term), df.to_le_bytes());
+        }
+        batch.put(SEARCH_DOC_COUNT, (docs.len() as u64).to_le_bytes());
+        self.db.write(batch)?;
+        Ok(())
+    }
+
     // --- Categories ---
 
     /// Store a category.
@@ -91,6 +780,7 @@ impl Storage {
         let key = format!("category:{}", category.id);
         let value = serde_json::to_vec(category)?;
         self.db.put(key.as_bytes(), value)?;
+        self.publish(StorageChange::Category(category.clone()));
         Ok(())
     }
 
@@ -141,6 +831,9 @@ impl Storage {
     }
 
     /// Set a public key as admin.
+    ///
+    /// Admin rows are derived from the operation log (see [`crate::acl`]) and
+    /// are overwritten whenever it changes.
     pub fn set_admin(&self, public_key: &str, is_admin: bool) -> Result<()> {
         let key = format!("admin:{}", public_key);
         if is_admin {
@@ -157,6 +850,12 @@ impl Storage {
         Ok(self.db.get(key.as_bytes())?.is_some())
     }
 
+    /// Check if a public key holds any permission.
+    pub fn has_any_permission(&self, public_key: &str) -> Result<bool> {
+        let prefix = format!("perm:{}:", public_key);
+        Ok(!self.scan_raw(prefix.as_bytes(), None, 1)?.is_empty())
+    }
+
     /// Grant a permission to a public key.
     pub fn grant_permission(&self, public_key: &str, permission: &str) -> Result<()> {
         let key = format!("perm:{}:{}", public_key, permission);
@@ -193,6 +892,292 @@ impl Storage {
         Ok(admins)
     }
 
+    /// Register a root admin (trusted without a signed operation).
+    pub fn add_root_admin(&self, public_key: &str) -> Result<()> {
+        self.db.put([ACL_ROOT_PREFIX, public_key.to_lowercase().as_bytes()].concat(), b"1")?;
+        Ok(())
+    }
+
+    /// List root admin public keys.
+    pub fn list_root_admins(&self) -> Result<Vec<String>> {
+        let mut roots = Vec::new();
+        for item in self.db.prefix_iterator(ACL_ROOT_PREFIX) {
+            let (key, _) = item?;
+            if !key.starts_with(ACL_ROOT_PREFIX) {
+                break;
+            }
+            roots.push(String::from_utf8_lossy(&key[ACL_ROOT_PREFIX.len()..]).into_owned());
+        }
+        Ok(roots)
+    }
+
+    /// Append an admin operation to the log. Returns false if already present.
+    pub fn append_admin_op(&self, op: &AdminOp) -> Result<bool> {
+        // Keyed by timestamp then hash, so iteration yields replay order
+        let key = [ACL_OP_PREFIX, &op.timestamp_ms.to_be_bytes(), &op.hash()].concat();
+        if self.db.get(&key)?.is_some() {
+            return Ok(false);
+        }
+        self.db.put(key, serde_json::to_vec(op)?)?;
+        self.publish(StorageChange::AdminOp(op.clone()));
+        Ok(true)
+    }
+
+    /// All admin operations, in replay order.
+    pub fn list_admin_ops(&self) -> Result<Vec<AdminOp>> {
+        let mut ops = Vec::new();
+        for item in self.db.prefix_iterator(ACL_OP_PREFIX) {
+            let (key, value) = item?;
+            if !key.starts_with(ACL_OP_PREFIX) {
+                break;
+            }
+            ops.push(serde_json::from_slice(&value)?);
+        }
+        Ok(ops)
+    }
+
+    /// Replace all admin rows and upload grants with the given sets.
+    pub fn set_access<'a>(
+        &self,
+        admins: impl IntoIterator<Item = &'a String>,
+        uploaders: impl IntoIterator<Item = &'a String>,
+    ) -> Result<()> {
+        let mut batch = WriteBatch::default();
+        for prefix in [&b"admin:"[..], &b"perm:"[..]] {
+            for item in self.db.prefix_iterator(prefix) {
+                let (key, _) = item?;
+                if !key.starts_with(prefix) {
+                    break;
+                }
+                if prefix == b"admin:" || key.ends_with(b":upload") {
+                    batch.delete(key);
+                }
+            }
+        }
+        for key in admins {
+            batch.put(format!("admin:{}", key).as_bytes(), b"1");
+        }
+        for key in uploaders {
+            batch.put(format!("perm:{}:upload", key).as_bytes(), b"1");
+        }
+        self.db.write(batch)?;
+        Ok(())
+    }
+
+    // --- Replication ---
+
+    /// Get the replicated record stored for a record key (see [`crate::replication`]).
+    pub fn get_sync_record(&self, record_key: &str) -> Result<Option<Vec<u8>>> {
+        let key = format!("sync:{}", record_key);
+        Ok(self.db.get(key.as_bytes())?)
+    }
+
+    /// Store the replicated record for a record key.
+    pub fn put_sync_record(&self, record_key: &str, data: &[u8]) -> Result<()> {
+        let key = format!("sync:{}", record_key);
+        self.db.put(key.as_bytes(), data)?;
+        Ok(())
+    }
+
+    /// List all replicated records, including tombstones of deleted content.
+    pub fn list_sync_records(&self) -> Result<Vec<Vec<u8>>> {
+        let prefix = b"sync:";
+        let mut records = Vec::new();
+
+        let iter = self.db.prefix_iterator(prefix);
+        for item in iter {
+            let (key, value) = item?;
+            if key.starts_with(prefix) {
+                records.push(value.to_vec());
+            } else {
+                break;
+            }
+        }
+
+        Ok(records)
+    }
+
+    // --- DHT Shard ---
+    //
+    // Entries live under `dht:entry:<key>`; `dht:time:<timestamp BE><key>`
+    // is an empty index row so sync can range-scan by timestamp.
+
+    fn dht_entry_key(key: &DhtKey) -> Vec<u8> {
+        [DHT_ENTRY_PREFIX, key.as_bytes()].concat()
+    }
+
+    fn dht_time_key(timestamp: u64, key: &DhtKey) -> Vec<u8> {
+        [DHT_TIME_PREFIX, &timestamp.to_be_bytes(), key.as_bytes()].concat()
+    }
+
+    fn decode_dht_entry(data: &[u8]) -> Result<DhtEntry> {
+        bincode::deserialize(data)
+            .map_err(|e| Error::Storage(format!("Invalid DHT entry: {}", e)))
+    }
+
+    /// Get a DHT entry by key.
+    pub fn get_dht_entry(&self, key: &DhtKey) -> Result<Option<DhtEntry>> {
+        match self.db.get(Self::dht_entry_key(key))? {
+            Some(data) => Ok(Some(Self::decode_dht_entry(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Store a DHT entry, replacing any existing entry and its index row.
+    pub fn put_dht_entry(&self, entry: &DhtEntry) -> Result<()> {
+        let value = bincode::serialize(entry)
+            .map_err(|e| Error::Storage(format!("Invalid DHT entry: {}", e)))?;
+        let mut batch = WriteBatch::default();
+        if let Some(old) = self.get_dht_entry(&entry.key)? {
+            batch.delete(Self::dht_time_key(old.timestamp, &old.key));
+        }
+        batch.put(Self::dht_entry_key(&entry.key), value);
+        batch.put(Self::dht_time_key(entry.timestamp, &entry.key), b"");
+        self.db.write(batch)?;
+        Ok(())
+    }
+
+    /// Delete a DHT entry, returning it if present.
+    pub fn delete_dht_entry(&self, key: &DhtKey) -> Result<Option<DhtEntry>> {
+        let old = self.get_dht_entry(key)?;
+        if let Some(entry) = &old {
+            let mut batch = WriteBatch::default();
+            batch.delete(Self::dht_entry_key(key));
+            batch.delete(Self::dht_time_key(entry.timestamp, key));
+            self.db.write(batch)?;
+        }
+        Ok(old)
+    }
+
+    /// List DHT entries whose key starts with `prefix`, ordered by key.
+    pub fn list_dht_entries(&self, prefix: &[u8]) -> Result<Vec<DhtEntry>> {
+        let prefix = [DHT_ENTRY_PREFIX, prefix].concat();
+        let mut entries = Vec::new();
+
+        let iter = self.db.prefix_iterator(&prefix);
+        for item in iter {
+            let (key, value) = item?;
+            if key.starts_with(&prefix) {
+                entries.push(Self::decode_dht_entry(&value)?);
+            } else {
+                break;
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// List DHT entries with a timestamp strictly greater than `timestamp`,
+    /// oldest first. Walks the timestamp index rather than every entry.
+    pub fn list_dht_entries_since(&self, timestamp: u64) -> Result<Vec<DhtEntry>> {
+        let Some(start) = timestamp.checked_add(1) else {
+            return Ok(Vec::new());
+        };
+        let seek = [DHT_TIME_PREFIX, &start.to_be_bytes()].concat();
+        let mut entries = Vec::new();
+
+        let iter = self.db.iterator(IteratorMode::From(&seek, Direction::Forward));
+        for item in iter {
+            let (key, _) = item?;
+            let Some(index) = key.strip_prefix(DHT_TIME_PREFIX) else {
+                break;
+            };
+            let dht_key: [u8; 32] = index[8..].try_into()
+                .map_err(|_| Error::Storage("Invalid DHT index row".into()))?;
+            if let Some(entry) = self.get_dht_entry(&DhtKey(dht_key))? {
+                entries.push(entry);
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Count stored DHT entries.
+    pub fn count_dht_entries(&self) -> Result<usize> {
+        let mut count = 0;
+        for item in self.db.prefix_iterator(DHT_TIME_PREFIX) {
+            let (key, _) = item?;
+            if !key.starts_with(DHT_TIME_PREFIX) {
+                break;
+            }
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Delete every DHT entry and index row.
+    pub fn clear_dht(&self) -> Result<()> {
+        let mut batch = WriteBatch::default();
+        for item in self.db.prefix_iterator(DHT_PREFIX) {
+            let (key, _) = item?;
+            if !key.starts_with(DHT_PREFIX) {
+                break;
+            }
+            batch.delete(key);
+        }
+        self.db.write(batch)?;
+        Ok(())
+    }
+
+    // --- Blobs ---
+
+    /// Store a blob chunk under its hash.
+    pub fn put_blob_chunk(&self, hash: &[u8; 32], data: &[u8]) -> Result<()> {
+        self.db.put([BLOB_CHUNK_PREFIX, hash].concat(), data)?;
+        Ok(())
+    }
+
+    /// Get a blob chunk by hash (unverified - see [`crate::blob::BlobStore`]).
+    pub fn get_blob_chunk(&self, hash: &[u8; 32]) -> Result<Option<Vec<u8>>> {
+        Ok(self.db.get([BLOB_CHUNK_PREFIX, hash].concat())?)
+    }
+
+    /// Check if a blob chunk is stored.
+    pub fn has_blob_chunk(&self, hash: &[u8; 32]) -> Result<bool> {
+        Ok(self.get_blob_chunk(hash)?.is_some())
+    }
+
+    /// Store an encoded blob manifest under its CID.
+    pub fn put_blob_manifest(&self, cid: &[u8; 32], manifest: &[u8]) -> Result<()> {
+        self.db.put([BLOB_MANIFEST_PREFIX, cid].concat(), manifest)?;
+        Ok(())
+    }
+
+    /// Get an encoded blob manifest by CID.
+    pub fn get_blob_manifest(&self, cid: &[u8; 32]) -> Result<Option<Vec<u8>>> {
+        Ok(self.db.get([BLOB_MANIFEST_PREFIX, cid].concat())?)
+    }
+
+    /// Remember the manifest of a blob that is still being downloaded.
+    pub fn put_blob_pending(&self, cid: &[u8; 32], manifest: &[u8]) -> Result<()> {
+        self.db.put([BLOB_PENDING_PREFIX, cid].concat(), manifest)?;
+        Ok(())
+    }
+
+    /// Get the manifest of an unfinished download.
+    pub fn get_blob_pending(&self, cid: &[u8; 32]) -> Result<Option<Vec<u8>>> {
+        Ok(self.db.get([BLOB_PENDING_PREFIX, cid].concat())?)
+    }
+
+    /// Forget an unfinished download.
+    pub fn delete_blob_pending(&self, cid: &[u8; 32]) -> Result<()> {
+        self.db.delete([BLOB_PENDING_PREFIX, cid].concat())?;
+        Ok(())
+    }
+
+    /// Manifests of all unfinished downloads.
+    pub fn list_blob_pending(&self) -> Result<Vec<Vec<u8>>> {
+        let mut manifests = Vec::new();
+        for item in self.db.prefix_iterator(BLOB_PENDING_PREFIX) {
+            let (key, value) = item?;
+            if !key.starts_with(BLOB_PENDING_PREFIX) {
+                break;
+            }
+            manifests.push(value.to_vec());
+        }
+        Ok(manifests)
+    }
+
     // --- Node Identity ---
 
     /// Get or create the node's signing key (persistent identity).
@@ -214,6 +1199,55 @@ impl Storage {
     }
 }
 
+/// [`DhtStore`] over the node's RocksDB, so the DHT shard survives restarts.
+#[derive(Clone)]
+pub struct RocksDhtStore {
+    storage: Arc<Storage>,
+}
+
+impl RocksDhtStore {
+    /// Keep DHT entries in the given storage.
+    pub fn new(storage: Arc<Storage>) -> Self {
+        Self { storage }
+    }
+}
+
+impl DhtStore for RocksDhtStore {
+    type Error = Error;
+
+    fn get(&self, key: &DhtKey) -> Result<Option<DhtEntry>> {
+        self.storage.get_dht_entry(key)
+    }
+
+    fn insert(&mut self, entry: DhtEntry) -> Result<()> {
+        self.storage.put_dht_entry(&entry)
+    }
+
+    fn remove(&mut self, key: &DhtKey) -> Result<Option<DhtEntry>> {
+        self.storage.delete_dht_entry(key)
+    }
+
+    fn len(&self) -> Result<usize> {
+        self.storage.count_dht_entries()
+    }
+
+    fn entries(&self) -> Result<Vec<DhtEntry>> {
+        self.storage.list_dht_entries(&[])
+    }
+
+    fn entries_since(&self, timestamp: u64) -> Result<Vec<DhtEntry>> {
+        self.storage.list_dht_entries_since(timestamp)
+    }
+
+    fn entries_with_prefix(&self, prefix: &[u8]) -> Result<Vec<DhtEntry>> {
+        self.storage.list_dht_entries(prefix)
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        self.storage.clear_dht()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -251,6 +1285,240 @@ mod tests {
         assert_eq!(releases.len(), 2);
     }
 
+    #[test]
+    fn change_feed() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::open(dir.path()).unwrap();
+        let mut changes = storage.subscribe_changes();
+
+        storage
+            .put_release(&Release::new("a".into(), "A".into(), "music".into()))
+            .unwrap();
+        storage.delete_release("a").unwrap();
+        let key = ed25519_dalek::SigningKey::from_bytes(&[1; 32]);
+        let op = crate::acl::AdminOp::signed(crate::acl::AdminAction::AddAdmin, &"ab".repeat(32), 1, &key).unwrap();
+        storage.append_admin_op(&op).unwrap();
+        assert!(!storage.append_admin_op(&op).unwrap());
+        // Derived rights are not part of the feed - the operations are
+        storage.set_admin("abcd", true).unwrap();
+        storage.grant_permission("abcd", "upload").unwrap();
+
+        assert!(matches!(changes.try_recv(), Ok(StorageChange::Release(r)) if r.id == "a"));
+        assert!(matches!(changes.try_recv(), Ok(StorageChange::ReleaseDeleted(id)) if id == "a"));
+        assert!(matches!(changes.try_recv(), Ok(StorageChange::AdminOp(received)) if received == op));
+        assert!(changes.try_recv().is_err());
+    }
+
+    #[test]
+    fn dht_shard_survives_reopen() {
+        use citadel_dht::{hash_key, DhtState};
+
+        let dir = tempdir().unwrap();
+        let old = hash_key(b"old");
+        let new = hash_key(b"new");
+        {
+            let storage = Arc::new(Storage::open(dir.path()).unwrap());
+            let mut state = DhtState::with_store(RocksDhtStore::new(storage));
+            state.put_with_timestamp(old, "old".into(), 100).unwrap();
+            state.put_with_timestamp(new, "first".into(), 150).unwrap();
+            state.put_with_timestamp(new, "second".into(), 300).unwrap();
+            assert!(!state.put_with_timestamp(new, "stale".into(), 200).unwrap());
+        }
+
+        let storage = Arc::new(Storage::open(dir.path()).unwrap());
+        let mut state = DhtState::with_store(RocksDhtStore::new(storage));
+        assert_eq!(state.len().unwrap(), 2);
+        assert_eq!(state.get(&new).unwrap().unwrap().value.as_str(), Some("second"));
+
+        // The overwritten timestamp no longer shows up in the index
+        let since: Vec<_> = state.entries_since(120).unwrap().into_iter().map(|e| e.key).collect();
+        assert_eq!(since, vec![new]);
+        assert_eq!(state.iter_prefix(&old.0[..4]).unwrap().len(), 1);
+
+        // Deletes persist as tombstones so they replicate
+        state.delete(&new).unwrap();
+        assert!(state.get(&new).unwrap().is_none());
+        assert!(state.entries_since(120).unwrap()[0].is_tombstone());
+        state.clear().unwrap();
+        assert!(state.is_empty().unwrap());
+    }
+
+    fn release_with(id: &str, title: &str, category: &str, year: u32, created: &str, tags: &[&str]) -> Release {
+        let mut release = Release::new(id.into(), title.into(), category.into());
+        release.year = Some(year);
+        release.created_at = Some(created.into());
+        release.tags = tags.iter().map(|t| t.to_string()).collect();
+        release
+    }
+
+    fn page_ids(page: &Page<Release>) -> Vec<&str> {
+        page.items.iter().map(|r| r.id.as_str()).collect()
+    }
+
+    #[test]
+    fn query_releases_by_index() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::open(dir.path()).unwrap();
+        storage.put_release(&release_with("a", "Zebra", "music", 1999, "2024-01-01T00:00:00Z", &["Rock"])).unwrap();
+        storage.put_release(&release_with("b", "apple", "music", 2010, "2024-01-03T00:00:00Z", &["jazz"])).unwrap();
+        storage.put_release(&release_with("c", "Mango", "movies", 2005, "2024-01-02T00:00:00Z", &["rock"])).unwrap();
+        storage.put_release(&release_with("d", "Kiwi", "music", 2020, "2024-01-04T00:00:00Z", &["rock"])).unwrap();
+
+        // Newest first by default, paged with a cursor
+        let query = ReleaseQuery { limit: 2, ..Default::default() };
+        let page = storage.query_releases(&query).unwrap();
+        assert_eq!(page_ids(&page), ["d", "b"]);
+        let page = storage.query_releases(&ReleaseQuery { after: page.next_cursor, ..query.clone() }).unwrap();
+        assert_eq!(page_ids(&page), ["c", "a"]);
+        assert!(page.next_cursor.is_none());
+
+        let by_title = ReleaseQuery { sort: "title".parse().unwrap(), limit: 10, ..Default::default() };
+        assert_eq!(page_ids(&storage.query_releases(&by_title).unwrap()), ["b", "d", "c", "a"]);
+
+        let rock_music = ReleaseQuery {
+            category: Some("music".into()),
+            tag: Some("ROCK".into()),
+            sort: "-year".parse().unwrap(),
+            limit: 10,
+            ..Default::default()
+        };
+        assert_eq!(page_ids(&storage.query_releases(&rock_music).unwrap()), ["d", "a"]);
+
+        let by_year = ReleaseQuery { year: Some(2005), limit: 10, ..Default::default() };
+        assert_eq!(page_ids(&storage.query_releases(&by_year).unwrap()), ["c"]);
+
+        // Updates move index entries, deletes remove them
+        storage.put_release(&release_with("d", "Kiwi", "music", 2020, "2024-01-04T00:00:00Z", &["pop"])).unwrap();
+        storage.delete_release("a").unwrap();
+        assert!(storage.query_releases(&rock_music).unwrap().items.is_empty());
+
+        assert!("-popularity".parse::<ReleaseSort>().is_err());
+        let bad_cursor = ReleaseQuery { after: Some("zz".into()), ..query };
+        assert!(matches!(storage.query_releases(&bad_cursor), Err(Error::InvalidInput(_))));
+    }
+
+    #[test]
+    fn concurrent_release_updates_leave_no_stale_index_entries() {
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(Storage::open(dir.path()).unwrap());
+        storage.put_release(&release_with("r", "Title", "music", 2000, "2024-01-01T00:00:00Z", &[])).unwrap();
+
+        let writers: Vec<_> = (0..8)
+            .map(|t| {
+                let storage = Arc::clone(&storage);
+                std::thread::spawn(move || {
+                    for i in 0..20 {
+                        let title = format!("Title {} {}", t, i);
+                        let created = format!("2024-01-{:02}T00:00:00Z", i + 1);
+                        storage.put_release(&release_with("r", &title, "music", 2000 + t, &created, &[])).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        // Only the final version's index entries remain, so the release is listed once
+        let index_rows = storage.scan_raw(RELEASE_INDEX_PREFIX.as_bytes(), None, usize::MAX).unwrap();
+        let current = storage.get_release("r").unwrap().unwrap();
+        assert_eq!(index_rows.len(), release_index_keys(&current).len());
+        for sort in ["created", "-created", "title", "year"] {
+            let query = ReleaseQuery { sort: sort.parse().unwrap(), limit: 10, ..Default::default() };
+            assert_eq!(page_ids(&storage.query_releases(&query).unwrap()), ["r"]);
+        }
+
+        storage.delete_release("r").unwrap();
+        assert!(storage.scan_raw(RELEASE_INDEX_PREFIX.as_bytes(), None, usize::MAX).unwrap().is_empty());
+    }
+
+    #[test]
+    fn release_indexes_built_for_existing_data() {
+        let dir = tempdir().unwrap();
+        {
+            // Releases stored before indexes (and migrations) existed
+            let storage = Storage::open(dir.path()).unwrap();
+            let release = release_with("old", "Old", "music", 1980, "2020-01-01T00:00:00Z", &[]);
+            storage.db.put(b"release:old", serde_json::to_vec(&release).unwrap()).unwrap();
+            storage.db.delete(SCHEMA_VERSION_KEY).unwrap();
+        }
+
+        let storage = Storage::open(dir.path()).unwrap();
+        let query = ReleaseQuery { category: Some("music".into()), limit: 10, ..Default::default() };
+        assert_eq!(page_ids(&storage.query_releases(&query).unwrap()), ["old"]);
+    }
+
+    #[test]
+    fn query_content_items() {
+        use crate::models::{ContentType, Creator, CreatorRole};
+
+        let dir = tempdir().unwrap();
+        let storage = Storage::open(dir.path()).unwrap();
+
+        for i in 0..5 {
+            let content_type = if i % 2 == 0 { ContentType::Movie } else { ContentType::Book };
+            let item = ContentItem::new(format!("item{}", i), content_type, format!("Item {}", i))
+                .with_tag(if i < 3 { "Classic".into() } else { "new".into() })
+                .with_language("en".into())
+                .with_creator(Creator::new("Ada".into(), CreatorRole::Author))
+                .with_release("r1".into());
+            storage.put_content_item(&item).unwrap();
+        }
+
+        let query = ContentQuery { content_type: Some("movie".into()), limit: 2, ..Default::default() };
+        let page = storage.query_content_items(&query).unwrap();
+        let ids: Vec<_> = page.items.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(ids, ["item0", "item2"]);
+        assert_eq!(page.next_cursor.as_deref(), Some("item2"));
+
+        let next = ContentQuery { after: page.next_cursor, ..query };
+        let page = storage.query_content_items(&next).unwrap();
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].id, "item4");
+        assert!(page.next_cursor.is_none());
+
+        let query = ContentQuery {
+            tag: Some("classic".into()),
+            creator: Some("ada".into()),
+            release_id: Some("r1".into()),
+            language: Some("EN".into()),
+            limit: 10,
+            ..Default::default()
+        };
+        assert_eq!(storage.query_content_items(&query).unwrap().items.len(), 3);
+
+        storage.delete_content_item("item0").unwrap();
+        assert!(storage.get_content_item("item0").unwrap().is_none());
+        assert_eq!(storage.query_content_items(&query).unwrap().items.len(), 2);
+    }
+
+    #[test]
+    fn concurrent_writes_keep_search_counts() {
+        use crate::models::ContentType;
+
+        let dir = tempdir().unwrap();
+        let storage = Arc::new(Storage::open(dir.path()).unwrap());
+
+        let writers: Vec<_> = (0..8)
+            .map(|t| {
+                let storage = Arc::clone(&storage);
+                std::thread::spawn(move || {
+                    for i in 0..20 {
+                        let item = ContentItem::new(format!("item{}-{}", t, i), ContentType::Book, "Shared Title".into());
+                        storage.put_content_item(&item).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        assert_eq!(storage.search_doc_count().unwrap(), 160);
+        let terms = storage.search_terms("").unwrap();
+        assert_eq!(terms.iter().map(|(_, df)| *df).max(), Some(160));
+    }
+
     #[test]
     fn default_categories() {
         let dir = tempdir().unwrap();
@@ -0,0 +1,396 @@
+//! Swarm downloads: fetching a blob from several peers at once.
+//!
+//! Chunk availability is a SPORE over chunk indices - chunk `i` is the value
+//! `i` - so a peer holding chunks 0..40 and 64..100 advertises two ranges
+//! however large the blob is. Peers still downloading a blob advertise (and
+//! serve) the chunks they already have.
+//!
+//! ```text
+//! fetcher ──BlobWant────────────────────▶ every connected peer
+//! fetcher ◀─BlobHave(manifest, have)───── peers holding any of it
+//! fetcher ──BlobRequest(start..end)─────▶ several peers, disjoint ranges
+//! fetcher ◀─BlobChunk(index, data)─────── one message per chunk
+//! ```
+//!
+//! The manifest is checked against the CID before use, and every chunk
+//! against the manifest before it is stored. Chunks are written as they
+//! arrive and the manifest of an unfinished download is kept, so after a
+//! restart a download resumes with what is already on disk.
+
+use crate::blob::{BlobManifest, BlobStore, Cid};
+use crate::error::Result;
+use citadel_spore::{Range256, Spore, U256};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+/// Chunks asked for in one `BlobRequest`.
+pub const SWARM_BATCH: u64 = 16;
+
+/// Requests outstanding to one peer at a time.
+pub const SWARM_MAX_IN_FLIGHT: usize = 4;
+
+/// A request not answered within this time is given to another peer.
+pub const SWARM_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often peers are asked again what they have while a download runs.
+pub const SWARM_WANT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often downloads send new requests.
+pub const SWARM_TICK: Duration = Duration::from_millis(500);
+
+/// SPORE covering the given chunk index ranges.
+pub fn chunk_spore(ranges: impl IntoIterator<Item = Range<u64>>) -> Spore {
+    Spore::from_ranges(
+        ranges
+            .into_iter()
+            .map(|r| Range256::new(U256::from_u64(r.start), U256::from_u64(r.end.max(r.start))))
+            .collect(),
+    )
+}
+
+/// SPORE covering one range of chunk indices.
+pub fn chunk_range_spore(range: Range<u64>) -> Spore {
+    chunk_spore(std::iter::once(range))
+}
+
+/// Chunk index ranges covered by a SPORE. Values past `u64::MAX` are clamped.
+pub fn chunk_ranges(spore: &Spore) -> Vec<Range<u64>> {
+    let index = |v: &U256| if v.limbs[1..].iter().any(|&l| l != 0) { u64::MAX } else { v.limbs[0] };
+    spore
+        .ranges()
+        .iter()
+        .map(|r| index(&r.start)..index(&r.stop))
+        .filter(|r| !r.is_empty())
+        .collect()
+}
+
+fn chunk_count(spore: &Spore) -> u64 {
+    chunk_ranges(spore).iter().map(|r| r.end - r.start).sum()
+}
+
+/// Chunks of a blob present in local storage.
+pub fn local_have(blobs: &BlobStore, manifest: &BlobManifest) -> Result<Spore> {
+    let mut ranges = Vec::new();
+    let mut run: Option<u64> = None;
+    for index in 0..manifest.chunks.len() {
+        let present = blobs.has_chunk(manifest, index)?;
+        match (present, run) {
+            (true, None) => run = Some(index as u64),
+            (false, Some(start)) => {
+                ranges.push(start..index as u64);
+                run = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(start) = run {
+        ranges.push(start..manifest.chunks.len() as u64);
+    }
+    Ok(chunk_spore(ranges))
+}
+
+/// Progress of a blob download, as reported by the API.
+#[derive(Debug, Clone, Serialize)]
+pub struct SwarmProgress {
+    pub cid: String,
+    /// Blob size, once a peer has sent the manifest
+    pub size: Option<u64>,
+    /// Total chunks, once known
+    pub chunks: Option<usize>,
+    /// Chunks stored locally
+    pub have: u64,
+    /// Peers asked
+    pub peers: usize,
+    /// Peers holding at least one chunk we need
+    pub sources: usize,
+    /// Requests awaiting chunks
+    pub in_flight: usize,
+    pub complete: bool,
+}
+
+impl SwarmProgress {
+    /// Progress of a blob that is already stored.
+    pub fn stored(manifest: &BlobManifest) -> Self {
+        Self {
+            cid: manifest.cid().to_hex(),
+            size: Some(manifest.size),
+            chunks: Some(manifest.chunks.len()),
+            have: manifest.chunks.len() as u64,
+            peers: 0,
+            sources: 0,
+            in_flight: 0,
+            complete: true,
+        }
+    }
+}
+
+/// What one peer has told us about a blob.
+struct PeerState {
+    have: Spore,
+    asked: Instant,
+    in_flight: Vec<(Range<u64>, Instant)>,
+}
+
+/// Scheduling state of one blob download.
+///
+/// Decides which peer to ask for which chunks; the caller sends the
+/// messages and stores the chunks.
+pub struct SwarmDownload {
+    cid: Cid,
+    manifest: Option<BlobManifest>,
+    /// Chunks stored locally
+    have: Spore,
+    peers: HashMap<String, PeerState>,
+}
+
+impl SwarmDownload {
+    /// Start a download knowing only the CID.
+    pub fn new(cid: Cid) -> Self {
+        Self { cid, manifest: None, have: Spore::empty(), peers: HashMap::new() }
+    }
+
+    /// Continue a download with a known manifest and the chunks already stored.
+    pub fn resume(manifest: BlobManifest, have: Spore) -> Self {
+        Self { cid: manifest.cid(), manifest: Some(manifest), have, peers: HashMap::new() }
+    }
+
+    /// The blob being downloaded.
+    pub fn cid(&self) -> Cid {
+        self.cid
+    }
+
+    /// The blob's manifest, once known.
+    pub fn manifest(&self) -> Option<&BlobManifest> {
+        self.manifest.as_ref()
+    }
+
+    /// Adopt a manifest received from a peer. Returns false if we already
+    /// have one, it doesn't match the CID or its chunk count doesn't match its size.
+    pub fn set_manifest(&mut self, manifest: BlobManifest, have: Spore) -> bool {
+        if self.manifest.is_some() || manifest.cid() != self.cid || manifest.check().is_err() {
+            return false;
+        }
+        self.manifest = Some(manifest);
+        self.have = have;
+        true
+    }
+
+    /// Peers to send a `BlobWant` to: connected peers not asked recently.
+    /// Peers no longer connected are dropped, and their requests with them.
+    pub fn want_targets(&mut self, connected: &[String], now: Instant) -> Vec<String> {
+        self.peers.retain(|peer, _| connected.contains(peer));
+        let mut targets = Vec::new();
+        for peer in connected {
+            let mut new = false;
+            let state = self.peers.entry(peer.clone()).or_insert_with(|| {
+                new = true;
+                PeerState { have: Spore::empty(), asked: now, in_flight: Vec::new() }
+            });
+            if new || now.duration_since(state.asked) >= SWARM_WANT_INTERVAL {
+                state.asked = now;
+                targets.push(peer.clone());
+            }
+        }
+        targets
+    }
+
+    /// Record a peer's have-list.
+    pub fn peer_has(&mut self, peer: &str, have: Spore) {
+        if let Some(state) = self.peers.get_mut(peer) {
+            state.have = have;
+        }
+    }
+
+    /// Forget a peer's have-list and requests (e.g. it sent a bad chunk).
+    /// It is asked again like a new peer on the next round.
+    pub fn drop_peer(&mut self, peer: &str) {
+        self.peers.remove(peer);
+    }
+
+    /// Chunks we still need.
+    pub fn missing(&self) -> Spore {
+        match &self.manifest {
+            Some(manifest) => chunk_range_spore(0..manifest.chunks.len() as u64).subtract(&self.have),
+            None => Spore::empty(),
+        }
+    }
+
+    /// Check if every chunk is stored.
+    pub fn is_complete(&self) -> bool {
+        self.manifest.is_some() && self.missing().is_empty()
+    }
+
+    /// Assign missing chunks to peers that have them: disjoint ranges of up
+    /// to [`SWARM_BATCH`] chunks, round-robin, at most [`SWARM_MAX_IN_FLIGHT`]
+    /// per peer. Requests that timed out are reassigned.
+    pub fn plan(&mut self, now: Instant) -> Vec<(String, Range<u64>)> {
+        let mut wanted = self.missing();
+        for state in self.peers.values_mut() {
+            state.in_flight.retain(|(_, at)| now.duration_since(*at) < SWARM_REQUEST_TIMEOUT);
+            wanted = wanted.subtract(&chunk_spore(state.in_flight.iter().map(|(r, _)| r.clone())));
+        }
+
+        let mut peers: Vec<String> = self.peers.keys().cloned().collect();
+        peers.sort();
+
+        let mut assigned = Vec::new();
+        loop {
+            let mut progress = false;
+            for peer in &peers {
+                let state = self.peers.get_mut(peer).expect("peer listed above");
+                if state.in_flight.len() >= SWARM_MAX_IN_FLIGHT {
+                    continue;
+                }
+                let Some(first) = chunk_ranges(&wanted.intersect(&state.have)).into_iter().next() else {
+                    continue;
+                };
+                let range = first.start..first.end.min(first.start + SWARM_BATCH);
+                wanted = wanted.subtract(&chunk_range_spore(range.clone()));
+                state.in_flight.push((range.clone(), now));
+                assigned.push((peer.clone(), range));
+                progress = true;
+            }
+            if !progress {
+                return assigned;
+            }
+        }
+    }
+
+    /// Record that chunk `index` (received from `peer`) has been stored.
+    pub fn received(&mut self, peer: &str, index: u64) {
+        self.have = self.have.union(&chunk_range_spore(index..index + 1));
+        if let Some(state) = self.peers.get_mut(peer) {
+            let have = &self.have;
+            state.in_flight.retain(|(range, _)| !chunk_range_spore(range.clone()).subtract(have).is_empty());
+        }
+    }
+
+    /// Current progress.
+    pub fn progress(&self) -> SwarmProgress {
+        let missing = self.missing();
+        SwarmProgress {
+            cid: self.cid.to_hex(),
+            size: self.manifest.as_ref().map(|m| m.size),
+            chunks: self.manifest.as_ref().map(|m| m.chunks.len()),
+            have: chunk_count(&self.have),
+            peers: self.peers.len(),
+            sources: self.peers.values().filter(|p| !p.have.intersect(&missing).is_empty()).count(),
+            in_flight: self.peers.values().map(|p| p.in_flight.len()).sum(),
+            complete: self.is_complete(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blob::CHUNK_SIZE;
+    use crate::storage::Storage;
+    use std::sync::Arc;
+
+    fn manifest(chunks: usize) -> (Vec<u8>, BlobManifest) {
+        let data: Vec<u8> = (0..chunks * CHUNK_SIZE).map(|i| (i / CHUNK_SIZE) as u8 ^ (i as u8)).collect();
+        let manifest = BlobManifest::for_data(&data);
+        (data, manifest)
+    }
+
+    #[test]
+    fn test_chunk_spore_roundtrip() {
+        let spore = chunk_spore([0..40, 64..100, 40..41]);
+        assert_eq!(chunk_ranges(&spore), vec![0..41, 64..100]);
+        assert_eq!(chunk_count(&spore), 77);
+        assert_eq!(chunk_ranges(&Spore::full()), vec![0..u64::MAX]);
+        assert!(chunk_ranges(&chunk_range_spore(5..5)).is_empty());
+    }
+
+    #[test]
+    fn test_plan_splits_work_across_peers() {
+        let (_, manifest) = manifest(100);
+        let mut download = SwarmDownload::new(manifest.cid());
+        let peers = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let now = Instant::now();
+
+        assert_eq!(download.want_targets(&peers, now).len(), 3);
+        assert!(download.want_targets(&peers, now + Duration::from_secs(1)).is_empty());
+        assert!(download.plan(now).is_empty(), "nothing to plan without a manifest");
+
+        assert!(download.set_manifest(manifest.clone(), chunk_range_spore(0..10)));
+        download.peer_has("a", chunk_range_spore(0..100));
+        download.peer_has("b", chunk_range_spore(50..100));
+        // c has nothing
+
+        let plan = download.plan(now);
+        let mut covered = Spore::empty();
+        for (peer, range) in &plan {
+            assert!(range.end - range.start <= SWARM_BATCH);
+            assert_ne!(peer, "c");
+            let range = chunk_range_spore(range.clone());
+            assert!(covered.is_disjoint(&range), "ranges overlap");
+            assert!(chunk_range_spore(0..10).is_disjoint(&range), "already have these");
+            covered = covered.union(&range);
+        }
+        assert!(plan.iter().any(|(p, _)| p == "a") && plan.iter().any(|(p, _)| p == "b"));
+        assert_eq!(plan.iter().filter(|(p, _)| p == "a").count(), SWARM_MAX_IN_FLIGHT);
+
+        // Nothing new until requests complete or time out
+        assert!(download.plan(now).is_empty());
+        let retry = download.plan(now + SWARM_REQUEST_TIMEOUT);
+        assert_eq!(chunk_spore(retry.into_iter().map(|(_, r)| r)), covered);
+    }
+
+    #[test]
+    fn test_download_completes() {
+        let (_, manifest) = manifest(3);
+        let mut download = SwarmDownload::new(manifest.cid());
+        let now = Instant::now();
+        download.want_targets(&["a".to_string()], now);
+
+        let mut wrong = manifest.clone();
+        wrong.chunks.reverse();
+        assert!(!download.set_manifest(wrong, Spore::empty()));
+        assert!(download.set_manifest(manifest, Spore::empty()));
+        download.peer_has("a", chunk_range_spore(0..3));
+
+        let plan = download.plan(now);
+        assert_eq!(plan, vec![("a".to_string(), 0..3)]);
+        for index in 0..3 {
+            assert!(!download.is_complete());
+            download.received("a", index);
+        }
+        let progress = download.progress();
+        assert!(progress.complete);
+        assert_eq!((progress.have, progress.in_flight, progress.sources), (3, 0, 0));
+    }
+
+    #[test]
+    fn test_resume_after_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let (data, manifest) = manifest(4);
+        let cid = manifest.cid();
+        {
+            let blobs = BlobStore::new(Arc::new(Storage::open(dir.path()).unwrap()));
+            blobs.begin(&manifest).unwrap();
+            blobs.put_chunk(&manifest, 1, &data[CHUNK_SIZE..2 * CHUNK_SIZE]).unwrap();
+            blobs.put_chunk(&manifest, 2, &data[2 * CHUNK_SIZE..3 * CHUNK_SIZE]).unwrap();
+            assert!(blobs.put_chunk(&manifest, 3, b"garbage").is_err());
+        }
+
+        let blobs = BlobStore::new(Arc::new(Storage::open(dir.path()).unwrap()));
+        let pending = blobs.all_pending().unwrap();
+        assert_eq!(pending, vec![manifest.clone()]);
+        let have = local_have(&blobs, &manifest).unwrap();
+        assert_eq!(chunk_ranges(&have), vec![1..3]);
+
+        let download = SwarmDownload::resume(manifest.clone(), have);
+        assert_eq!(chunk_ranges(&download.missing()), vec![0..1, 3..4]);
+        assert!(blobs.finish(&manifest).is_err(), "chunks still missing");
+
+        blobs.put_chunk(&manifest, 0, &data[..CHUNK_SIZE]).unwrap();
+        blobs.put_chunk(&manifest, 3, &data[3 * CHUNK_SIZE..]).unwrap();
+        blobs.finish(&manifest).unwrap();
+        assert!(blobs.pending(&cid).unwrap().is_none());
+        assert_eq!(blobs.read_range(&cid, 0..data.len() as u64).unwrap().unwrap(), data);
+    }
+}
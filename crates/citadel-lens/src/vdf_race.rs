@@ -31,9 +31,10 @@
 
 use serde::{Deserialize, Serialize};
 
-/// VDF difficulty - number of sequential hash iterations per step
+/// Default VDF difficulty - number of sequential hash iterations per step
 /// Higher = more delay, more security against grinding
 /// 100_000 iterations ≈ 10-50ms on modern hardware
+/// Every node in a mesh must use the same value.
 pub const VDF_ITERATIONS: u32 = 100_000;
 
 /// Minimum VDF height difference to trigger re-org
@@ -57,8 +58,8 @@ pub struct VdfLink {
 
 impl VdfLink {
     /// Create genesis link (height 0)
-    pub fn genesis(seed: &[u8], producer: [u8; 32]) -> Self {
-        let output = compute_vdf(seed, VDF_ITERATIONS);
+    pub fn genesis(seed: &[u8], producer: [u8; 32], iterations: u32) -> Self {
+        let output = compute_vdf(seed, iterations);
         Self {
             height: 0,
             output,
@@ -72,13 +73,13 @@ impl VdfLink {
     }
 
     /// Extend chain with new link
-    pub fn extend(&self, producer: [u8; 32]) -> Self {
+    pub fn extend(&self, producer: [u8; 32], iterations: u32) -> Self {
         // Input to VDF: previous output || producer pubkey
         let mut input = Vec::with_capacity(64);
         input.extend_from_slice(&self.output);
         input.extend_from_slice(&producer);
 
-        let output = compute_vdf(&input, VDF_ITERATIONS);
+        let output = compute_vdf(&input, iterations);
 
         Self {
             height: self.height + 1,
@@ -93,7 +94,7 @@ impl VdfLink {
     }
 
     /// Verify this link is valid extension of previous
-    pub fn verify(&self, previous_output: &[u8; 32]) -> bool {
+    pub fn verify(&self, previous_output: &[u8; 32], iterations: u32) -> bool {
         if self.height == 0 {
             // Genesis verification needs the seed
             return true; // Caller must verify genesis separately
@@ -109,7 +110,7 @@ impl VdfLink {
         input.extend_from_slice(previous_output);
         input.extend_from_slice(&self.producer);
 
-        let expected = compute_vdf(&input, VDF_ITERATIONS);
+        let expected = compute_vdf(&input, iterations);
         self.output == expected
     }
 }
@@ -135,21 +136,29 @@ pub struct VdfChain {
     links: Vec<VdfLink>,
     /// Our public key for producing links
     our_pubkey: [u8; 32],
+    /// VDF iterations per link
+    iterations: u32,
 }
 
 impl VdfChain {
     /// Create new chain (we are genesis producer)
-    pub fn new_genesis(genesis_seed: [u8; 32], our_pubkey: [u8; 32]) -> Self {
-        let genesis = VdfLink::genesis(&genesis_seed, our_pubkey);
+    pub fn new_genesis(genesis_seed: [u8; 32], our_pubkey: [u8; 32], iterations: u32) -> Self {
+        let genesis = VdfLink::genesis(&genesis_seed, our_pubkey, iterations);
         Self {
             genesis_seed,
             links: vec![genesis],
             our_pubkey,
+            iterations,
         }
     }
 
     /// Create chain from received links (joining existing swarm)
-    pub fn from_links(genesis_seed: [u8; 32], links: Vec<VdfLink>, our_pubkey: [u8; 32]) -> Option<Self> {
+    pub fn from_links(
+        genesis_seed: [u8; 32],
+        links: Vec<VdfLink>,
+        our_pubkey: [u8; 32],
+        iterations: u32,
+    ) -> Option<Self> {
         if links.is_empty() {
             return None;
         }
@@ -159,6 +168,7 @@ impl VdfChain {
             genesis_seed,
             links,
             our_pubkey,
+            iterations,
         };
 
         if chain.verify_full() {
@@ -173,6 +183,11 @@ impl VdfChain {
         self.links.last().map(|l| l.height).unwrap_or(0)
     }
 
+    /// VDF iterations per link
+    pub fn iterations(&self) -> u32 {
+        self.iterations
+    }
+
     /// Get tip (latest link)
     pub fn tip(&self) -> Option<&VdfLink> {
         self.links.last()
@@ -181,7 +196,7 @@ impl VdfChain {
     /// Extend chain (we produce next link)
     pub fn extend(&mut self) -> &VdfLink {
         let tip = self.links.last().expect("Chain must have genesis");
-        let new_link = tip.extend(self.our_pubkey);
+        let new_link = tip.extend(self.our_pubkey, self.iterations);
         self.links.push(new_link);
         self.links.last().unwrap()
     }
@@ -206,6 +221,7 @@ impl VdfChain {
             genesis_seed: self.genesis_seed,
             links: other_links.clone(),
             our_pubkey: self.our_pubkey,
+            iterations: self.iterations,
         };
 
         if !temp_chain.verify_full() {
@@ -229,7 +245,7 @@ impl VdfChain {
             return false;
         }
 
-        let expected_genesis_output = compute_vdf(&self.genesis_seed, VDF_ITERATIONS);
+        let expected_genesis_output = compute_vdf(&self.genesis_seed, self.iterations);
         if genesis.output != expected_genesis_output {
             return false;
         }
@@ -243,7 +259,7 @@ impl VdfChain {
                 return false;
             }
 
-            if !curr.verify(&prev.output) {
+            if !curr.verify(&prev.output, self.iterations) {
                 return false;
             }
         }
@@ -290,16 +306,24 @@ pub mod signature_serde {
     where
         S: Serializer,
     {
-        // Serialize as hex string
-        hex::encode(bytes).serialize(serializer)
+        // Hex for JSON, raw bytes for binary formats (mesh wire codec)
+        if serializer.is_human_readable() {
+            hex::encode(bytes).serialize(serializer)
+        } else {
+            bytes.as_slice().serialize(serializer)
+        }
     }
 
     pub fn deserialize<'de, D>(deserializer: D) -> Result<[u8; 64], D::Error>
     where
         D: Deserializer<'de>,
     {
-        let s = String::deserialize(deserializer)?;
-        let bytes = hex::decode(&s).map_err(serde::de::Error::custom)?;
+        let bytes = if deserializer.is_human_readable() {
+            let s = String::deserialize(deserializer)?;
+            hex::decode(&s).map_err(serde::de::Error::custom)?
+        } else {
+            Vec::<u8>::deserialize(deserializer)?
+        };
         if bytes.len() != 64 {
             return Err(serde::de::Error::custom("expected 64 bytes"));
         }
@@ -412,9 +436,9 @@ pub struct VdfRace {
 
 impl VdfRace {
     /// Create new VDF race (genesis node)
-    pub fn new_genesis(genesis_seed: [u8; 32], signing_key: ed25519_dalek::SigningKey) -> Self {
+    pub fn new_genesis(genesis_seed: [u8; 32], signing_key: ed25519_dalek::SigningKey, iterations: u32) -> Self {
         let pubkey = signing_key.verifying_key().to_bytes();
-        let chain = VdfChain::new_genesis(genesis_seed, pubkey);
+        let chain = VdfChain::new_genesis(genesis_seed, pubkey, iterations);
 
         Self {
             chain,
@@ -430,9 +454,10 @@ impl VdfRace {
         genesis_seed: [u8; 32],
         signing_key: ed25519_dalek::SigningKey,
         existing_chain: Vec<VdfLink>,
+        iterations: u32,
     ) -> Option<Self> {
         let pubkey = signing_key.verifying_key().to_bytes();
-        let chain = VdfChain::from_links(genesis_seed, existing_chain, pubkey)?;
+        let chain = VdfChain::from_links(genesis_seed, existing_chain, pubkey, iterations)?;
 
         Some(Self {
             chain,
@@ -566,7 +591,7 @@ mod tests {
         let signing_key = SigningKey::generate(&mut OsRng);
         let pubkey = signing_key.verifying_key().to_bytes();
 
-        let chain = VdfChain::new_genesis(genesis_seed, pubkey);
+        let chain = VdfChain::new_genesis(genesis_seed, pubkey, VDF_ITERATIONS);
 
         assert_eq!(chain.height(), 0);
         assert!(chain.verify_full());
@@ -580,7 +605,7 @@ mod tests {
         let signing_key = SigningKey::generate(&mut OsRng);
         let pubkey = signing_key.verifying_key().to_bytes();
 
-        let mut chain = VdfChain::new_genesis(genesis_seed, pubkey);
+        let mut chain = VdfChain::new_genesis(genesis_seed, pubkey, VDF_ITERATIONS);
 
         // Extend 5 times
         for i in 1..=5 {
@@ -600,7 +625,7 @@ mod tests {
 
         // Node A starts chain
         let key_a = SigningKey::generate(&mut OsRng);
-        let mut chain_a = VdfChain::new_genesis(genesis_seed, key_a.verifying_key().to_bytes());
+        let mut chain_a = VdfChain::new_genesis(genesis_seed, key_a.verifying_key().to_bytes(), VDF_ITERATIONS);
 
         // Extend A's chain
         for _ in 0..20 {
@@ -609,7 +634,7 @@ mod tests {
 
         // Node B starts its own chain (simulating partition)
         let key_b = SigningKey::generate(&mut OsRng);
-        let mut chain_b = VdfChain::new_genesis(genesis_seed, key_b.verifying_key().to_bytes());
+        let mut chain_b = VdfChain::new_genesis(genesis_seed, key_b.verifying_key().to_bytes(), VDF_ITERATIONS);
 
         // B's chain is shorter
         for _ in 0..5 {
@@ -635,7 +660,7 @@ mod tests {
         let key_b = SigningKey::generate(&mut OsRng);
 
         // Shared chain
-        let mut chain = VdfChain::new_genesis(genesis_seed, key_a.verifying_key().to_bytes());
+        let mut chain = VdfChain::new_genesis(genesis_seed, key_a.verifying_key().to_bytes(), VDF_ITERATIONS);
         chain.extend();
         chain.extend();
 
@@ -685,7 +710,7 @@ mod tests {
 
         // Genesis node starts race
         let mut races: Vec<VdfRace> = vec![
-            VdfRace::new_genesis(genesis_seed, keys[0].clone())
+            VdfRace::new_genesis(genesis_seed, keys[0].clone(), VDF_ITERATIONS)
         ];
 
         // Genesis claims slot 0
@@ -701,7 +726,7 @@ mod tests {
         for (i, key) in keys.iter().enumerate().skip(1) {
             // Join with current chain
             let chain_links = races[0].chain_links().to_vec();
-            let mut race = VdfRace::join(genesis_seed, key.clone(), chain_links)
+            let mut race = VdfRace::join(genesis_seed, key.clone(), chain_links, VDF_ITERATIONS)
                 .expect("Should join");
 
             // Sync existing claims to new node (simulates joining mesh and receiving state)
@@ -774,7 +799,7 @@ mod tests {
             .map(|_| SigningKey::generate(&mut OsRng))
             .collect();
 
-        let mut chain_a = VdfChain::new_genesis(genesis_seed, keys_a[0].verifying_key().to_bytes());
+        let mut chain_a = VdfChain::new_genesis(genesis_seed, keys_a[0].verifying_key().to_bytes(), VDF_ITERATIONS);
 
         // A extends chain 100 times (simulating 30 nodes taking turns)
         for i in 0..100 {
@@ -784,6 +809,7 @@ mod tests {
                 genesis_seed,
                 links: chain_a.links.clone(),
                 our_pubkey: keys_a[producer_idx].verifying_key().to_bytes(),
+                iterations: VDF_ITERATIONS,
             };
             chain_a.extend();
         }
@@ -793,7 +819,7 @@ mod tests {
             .map(|_| SigningKey::generate(&mut OsRng))
             .collect();
 
-        let mut chain_b = VdfChain::new_genesis(genesis_seed, keys_b[0].verifying_key().to_bytes());
+        let mut chain_b = VdfChain::new_genesis(genesis_seed, keys_b[0].verifying_key().to_bytes(), VDF_ITERATIONS);
 
         // B extends chain 60 times
         for i in 0..60 {
@@ -802,6 +828,7 @@ mod tests {
                 genesis_seed,
                 links: chain_b.links.clone(),
                 our_pubkey: keys_b[producer_idx].verifying_key().to_bytes(),
+                iterations: VDF_ITERATIONS,
             };
             chain_b.extend();
         }
@@ -0,0 +1,971 @@
+//! Mesh Wire Codec
+//!
+//! Framing and encoding for the TCP links between lens nodes.
+//!
+//! Every connection opens with a single newline-terminated JSON `hello`. The hello
+//! advertises which binary wire versions the sender understands (`wire_versions`)
+//! and a random `challenge`. If both sides share a version, each then sends a
+//! JSON `hello_proof` signing the other's challenge with the key from its hello,
+//! and everything after that travels as length-prefixed binary frames:
+//!
+//! ```text
+//! ┌────────────────┬─────────────┬────────────────────────────────┐
+//! │ length: u32 BE │ version: u8 │ payload: bincode(FloodMessage) │
+//! └────────────────┴─────────────┴────────────────────────────────┘
+//!   length = 1 + payload.len()
+//! ```
+//!
+//! Nodes that predate the binary codec send a hello without `wire_versions`.
+//! Links to them stay on newline-delimited JSON with the original `"type"` strings,
+//! so old and new nodes can share a mesh.
+//!
+//! # Compatibility
+//!
+//! Version 1 payloads are bincode with varint integers. Keys, VDF outputs and
+//! signatures are raw bytes instead of hex strings. Variants of
+//! [`FloodMessage`] are identified by their index, so new variants must be
+//! appended at the end of the enum. Any other change to the payload layout needs
+//! a new [`WIRE_VERSION`].
+
+use crate::blob::{BlobManifest, Cid};
+use crate::cvdf::{CvdfRound, RoundAttestation};
+use crate::error::{Error, Result};
+use crate::mesh::FloodMessage;
+use crate::proof_of_latency::{LatencyProof, SwapDecision, SwapProposal, SwapResponse};
+use crate::vdf_race::{AnchoredSlotClaim, VdfLink};
+use bincode::Options;
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt};
+
+/// Current binary wire version
+pub const WIRE_VERSION: u8 = 1;
+
+/// Binary wire versions this node can speak, oldest first
+pub const SUPPORTED_WIRE_VERSIONS: &[u8] = &[WIRE_VERSION];
+
+/// Largest frame we accept (version byte + payload)
+pub const MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
+/// Encoding used on a connection after the hello exchange
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WireCodec {
+    /// Newline-delimited JSON (legacy peers)
+    Json,
+    /// Length-prefixed binary frames at the given version
+    Binary(u8),
+}
+
+impl WireCodec {
+    /// Pick the newest binary version both sides support, falling back to JSON
+    pub fn negotiate(peer_versions: &[u8]) -> Self {
+        SUPPORTED_WIRE_VERSIONS
+            .iter()
+            .rev()
+            .find(|v| peer_versions.contains(v))
+            .map(|v| WireCodec::Binary(*v))
+            .unwrap_or(WireCodec::Json)
+    }
+
+    /// Encode a flood message into the bytes to write on the socket
+    pub fn encode(&self, msg: &FloodMessage) -> Result<Vec<u8>> {
+        match self {
+            WireCodec::Json => {
+                let mut line = serde_json::to_vec(&flood_to_json(msg))?;
+                line.push(b'\n');
+                Ok(line)
+            }
+            WireCodec::Binary(version) => encode_frame(*version, msg),
+        }
+    }
+}
+
+/// Connection hello (always sent as a JSON line)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hello {
+    /// Sender's PeerID (b3b3/...)
+    pub node_id: String,
+    /// Sender's listen address (TCP and UDP share the port)
+    pub addr: String,
+    /// Sender's ed25519 public key
+    pub public_key: Option<Vec<u8>>,
+    /// Binary wire versions the sender understands (empty for legacy nodes)
+    pub wire_versions: Vec<u8>,
+    /// Random bytes the peer must sign in its [`HelloProof`] (absent for legacy nodes)
+    pub challenge: Option<Vec<u8>>,
+}
+
+impl Hello {
+    /// Encode as a JSON value (legacy field names plus `wire_versions`)
+    pub fn to_json(&self) -> Value {
+        json!({
+            "type": "hello",
+            "node_id": self.node_id,
+            "addr": self.addr,
+            "public_key": self.public_key.as_ref().map(hex::encode),
+            "wire_versions": self.wire_versions,
+            "challenge": self.challenge.as_ref().map(hex::encode),
+        })
+    }
+
+    /// Encode as a newline-terminated JSON line
+    pub fn to_line(&self) -> Vec<u8> {
+        let mut line = self.to_json().to_string().into_bytes();
+        line.push(b'\n');
+        line
+    }
+
+    fn from_json(msg: &Value) -> Option<Self> {
+        Some(Hello {
+            node_id: msg.get("node_id")?.as_str()?.to_string(),
+            addr: str_field(msg, "addr").unwrap_or_default(),
+            public_key: msg
+                .get("public_key")
+                .and_then(|p| p.as_str())
+                .and_then(|hex_str| hex::decode(hex_str).ok()),
+            wire_versions: msg
+                .get("wire_versions")
+                .and_then(|v| v.as_array())
+                .map(|arr| {
+                    arr.iter()
+                        .filter_map(|v| v.as_u64())
+                        .filter_map(|v| u8::try_from(v).ok())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            challenge: msg
+                .get("challenge")
+                .and_then(|c| c.as_str())
+                .and_then(|hex_str| hex::decode(hex_str).ok()),
+        })
+    }
+}
+
+/// Domain separation tag for hello proofs.
+pub const HELLO_PROOF_TAG: &[u8] = b"CITADEL-LENS-HELLO-PROOF-V1";
+
+/// Proof that a peer holds the key from its hello: its signature over the
+/// challenge from our hello (always sent as a JSON line)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HelloProof {
+    /// Ed25519 signature over [`HelloProof::signing_bytes`]
+    pub signature: Vec<u8>,
+}
+
+impl HelloProof {
+    /// Bytes covered by the signature: tag ‖ challenge ‖ signer's public key.
+    pub fn signing_bytes(challenge: &[u8], public_key: &[u8]) -> Vec<u8> {
+        [HELLO_PROOF_TAG, challenge, public_key].concat()
+    }
+
+    /// Answer a peer's challenge.
+    pub fn sign(challenge: &[u8], signing_key: &SigningKey) -> Self {
+        let bytes = Self::signing_bytes(challenge, signing_key.verifying_key().as_bytes());
+        HelloProof {
+            signature: signing_key.sign(&bytes).to_bytes().to_vec(),
+        }
+    }
+
+    /// Check the proof against our challenge and the key from the peer's hello.
+    pub fn verify(&self, challenge: &[u8], public_key: &[u8]) -> bool {
+        let Some(key) = <[u8; 32]>::try_from(public_key)
+            .ok()
+            .and_then(|key| VerifyingKey::from_bytes(&key).ok())
+        else {
+            return false;
+        };
+        let Ok(signature) = Signature::from_slice(&self.signature) else {
+            return false;
+        };
+        key.verify_strict(&Self::signing_bytes(challenge, public_key), &signature).is_ok()
+    }
+
+    /// Encode as a newline-terminated JSON line
+    pub fn to_line(&self) -> Vec<u8> {
+        let mut line = json!({
+            "type": "hello_proof",
+            "signature": hex::encode(&self.signature),
+        })
+        .to_string()
+        .into_bytes();
+        line.push(b'\n');
+        line
+    }
+
+    fn from_json(msg: &Value) -> Option<Self> {
+        Some(HelloProof {
+            signature: hex::decode(msg.get("signature")?.as_str()?).ok()?,
+        })
+    }
+}
+
+/// A decoded message received from a peer
+#[derive(Clone, Debug)]
+pub enum WireMessage {
+    /// Connection hello
+    Hello(Hello),
+    /// Answer to our hello's challenge
+    HelloProof(HelloProof),
+    /// Flooded mesh state
+    Flood(FloodMessage),
+    /// Message we could not decode (unknown type, missing fields, bad payload)
+    Unrecognized(String),
+}
+
+impl WireMessage {
+    /// Decode a legacy JSON message, dispatching on its `"type"` string
+    pub fn from_json(msg: &Value) -> Self {
+        let msg_type = msg.get("type").and_then(|t| t.as_str()).unwrap_or("");
+        let decoded = if msg_type == "hello" {
+            Hello::from_json(msg).map(WireMessage::Hello)
+        } else if msg_type == "hello_proof" {
+            HelloProof::from_json(msg).map(WireMessage::HelloProof)
+        } else {
+            flood_from_json(msg_type, msg).map(WireMessage::Flood)
+        };
+        decoded.unwrap_or_else(|| WireMessage::Unrecognized(msg_type.to_string()))
+    }
+}
+
+// ==================== BINARY FRAMES ====================
+
+fn bincode_options() -> impl Options {
+    bincode::DefaultOptions::new().with_limit(MAX_FRAME_LEN as u64)
+}
+
+/// Encode a message as a complete binary frame (length prefix included)
+pub fn encode_frame(version: u8, msg: &FloodMessage) -> Result<Vec<u8>> {
+    if !SUPPORTED_WIRE_VERSIONS.contains(&version) {
+        return Err(Error::Protocol(format!("unsupported wire version {}", version)));
+    }
+    let payload = bincode_options()
+        .serialize(msg)
+        .map_err(|e| Error::Protocol(format!("wire encode failed: {}", e)))?;
+    let len = payload.len() + 1;
+    if len > MAX_FRAME_LEN {
+        return Err(Error::Protocol(format!("frame too large: {} bytes", len)));
+    }
+
+    let mut frame = Vec::with_capacity(4 + len);
+    frame.extend_from_slice(&(len as u32).to_be_bytes());
+    frame.push(version);
+    frame.extend_from_slice(&payload);
+    Ok(frame)
+}
+
+/// Decode a frame body (version byte + payload, without the length prefix)
+pub fn decode_frame(body: &[u8]) -> Result<FloodMessage> {
+    let (&version, payload) = body
+        .split_first()
+        .ok_or_else(|| Error::Protocol("empty frame".into()))?;
+    if !SUPPORTED_WIRE_VERSIONS.contains(&version) {
+        return Err(Error::Protocol(format!("unsupported wire version {}", version)));
+    }
+    bincode_options()
+        .deserialize(payload)
+        .map_err(|e| Error::Protocol(format!("wire decode failed: {}", e)))
+}
+
+/// Read the next message from a peer.
+///
+/// Returns `Ok(None)` when the peer closes the connection. Framing errors
+/// (oversized or truncated frames) are returned as IO errors since the stream
+/// cannot be resynchronized; a frame or line that merely fails to decode is
+/// reported as [`WireMessage::Unrecognized`].
+pub async fn read_message<R>(reader: &mut R, codec: WireCodec) -> std::io::Result<Option<WireMessage>>
+where
+    R: AsyncBufRead + Unpin,
+{
+    match codec {
+        WireCodec::Json => {
+            let mut line = String::new();
+            if reader.read_line(&mut line).await? == 0 {
+                return Ok(None);
+            }
+            Ok(Some(match serde_json::from_str::<Value>(&line) {
+                Ok(msg) => WireMessage::from_json(&msg),
+                Err(e) => WireMessage::Unrecognized(format!("invalid JSON: {}", e)),
+            }))
+        }
+        WireCodec::Binary(_) => {
+            let mut len_buf = [0u8; 4];
+            match reader.read_exact(&mut len_buf).await {
+                Ok(_) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+                Err(e) => return Err(e),
+            }
+            let len = u32::from_be_bytes(len_buf) as usize;
+            if len == 0 || len > MAX_FRAME_LEN {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("invalid frame length {}", len),
+                ));
+            }
+            let mut body = vec![0u8; len];
+            reader.read_exact(&mut body).await?;
+            Ok(Some(match decode_frame(&body) {
+                Ok(msg) => WireMessage::Flood(msg),
+                Err(e) => WireMessage::Unrecognized(e.to_string()),
+            }))
+        }
+    }
+}
+
+// ==================== LEGACY JSON ====================
+
+/// Encode a flood message in the legacy JSON format
+pub fn flood_to_json(msg: &FloodMessage) -> Value {
+    match msg {
+        FloodMessage::Peers(peers) => json!({
+            "type": "flood_peers",
+            "peers": peers.iter().map(|(id, addr, slot, public_key)| json!({
+                "id": id,
+                "addr": addr,
+                "slot": slot,
+                "public_key": public_key.as_ref().map(hex::encode),
+            })).collect::<Vec<_>>(),
+        }),
+        FloodMessage::Admins(admins) => json!({
+            "type": "flood_admins",
+            "admins": admins,
+        }),
+        FloodMessage::SlotClaim { index, peer_id, coord, public_key } => json!({
+            "type": "slot_claim",
+            "index": index,
+            "peer_id": peer_id,
+            "coord": [coord.0, coord.1, coord.2],
+            "public_key": public_key.as_ref().map(hex::encode),
+        }),
+        FloodMessage::SlotValidation { index, peer_id, validator_id, accepted } => json!({
+            "type": "slot_validation",
+            "index": index,
+            "peer_id": peer_id,
+            "validator_id": validator_id,
+            "accepted": accepted,
+        }),
+        FloodMessage::SporeHaveList { peer_id, slots } => json!({
+            "type": "spore_have_list",
+            "peer_id": peer_id,
+            "slots": slots,
+        }),
+        FloodMessage::VdfChain { links } => json!({
+            "type": "vdf_chain",
+            "links": links.iter().map(link_to_json).collect::<Vec<_>>(),
+        }),
+        FloodMessage::VdfSlotClaim { claim } => json!({
+            "type": "vdf_slot_claim",
+            "slot": claim.slot,
+            "claimer": hex::encode(claim.claimer),
+            "vdf_height": claim.vdf_height,
+            "vdf_output": hex::encode(claim.vdf_output),
+            "signature": hex::encode(claim.signature),
+        }),
+        FloodMessage::PoLPing { from, nonce, vdf_height } => json!({
+            "type": "pol_ping",
+            "from": hex::encode(from),
+            "nonce": nonce,
+            "vdf_height": vdf_height,
+        }),
+        FloodMessage::PoLPong { from, nonce, vdf_height } => json!({
+            "type": "pol_pong",
+            "from": hex::encode(from),
+            "nonce": nonce,
+            "vdf_height": vdf_height,
+        }),
+        FloodMessage::PoLSwapProposal { proposal } => json!({
+            "type": "pol_swap_proposal",
+            "initiator": hex::encode(proposal.initiator),
+            "target": hex::encode(proposal.target),
+            "initiator_slot": proposal.initiator_slot,
+            "target_slot": proposal.target_slot,
+            "proposal_height": proposal.proposal_height,
+            "proposal_vdf_output": hex::encode(proposal.proposal_vdf_output),
+            "signature": hex::encode(proposal.signature),
+            "initiator_proofs": proposal.initiator_proofs.iter().map(proof_to_json).collect::<Vec<_>>(),
+            "initiator_at_target_proofs": proposal.initiator_at_target_proofs.iter().map(proof_to_json).collect::<Vec<_>>(),
+        }),
+        FloodMessage::PoLSwapResponse { response } => json!({
+            "type": "pol_swap_response",
+            "responder": hex::encode(response.responder),
+            "proposal_height": response.proposal_height,
+            "decision": match response.decision {
+                SwapDecision::Attack => "attack",
+                SwapDecision::Retreat => "retreat",
+            },
+            "response_height": response.response_height,
+            "signature": hex::encode(response.signature),
+            "target_proofs": response.target_proofs.iter().map(proof_to_json).collect::<Vec<_>>(),
+            "target_at_initiator_proofs": response.target_at_initiator_proofs.iter().map(proof_to_json).collect::<Vec<_>>(),
+        }),
+        FloodMessage::CvdfAttestation { att } => {
+            let mut value = attestation_to_json(att);
+            value["type"] = json!("cvdf_attestation");
+            value
+        }
+        FloodMessage::CvdfNewRound { round } => {
+            // Full round data, plus the summary fields older nodes log
+            let mut value = round_to_json(round);
+            value["type"] = json!("cvdf_new_round");
+            value["attestation_count"] = json!(round.attestations.len());
+            value["weight"] = json!(round.weight());
+            value
+        }
+        FloodMessage::CvdfSyncRequest { from_node, from_height } => json!({
+            "type": "cvdf_sync_request",
+            "from_node": from_node,
+            "from_height": from_height,
+        }),
+        FloodMessage::CvdfSyncResponse { rounds, slots } => json!({
+            "type": "cvdf_sync_response",
+            "rounds": rounds.iter().map(round_to_json).collect::<Vec<_>>(),
+            "slots": slots.iter().map(|(idx, pk)| json!({
+                "index": idx,
+                "pubkey": hex::encode(pk),
+            })).collect::<Vec<_>>(),
+            "height": rounds.last().map(|r| r.round).unwrap_or(0),
+            "total_weight": rounds.iter().map(|r| r.weight()).sum::<u64>(),
+        }),
+        FloodMessage::SporeSync { msg } => json!({
+            "type": "spore_sync",
+            "msg": msg,
+        }),
+        FloodMessage::SporeContent { blocks } => json!({
+            "type": "spore_content",
+            "blocks": blocks,
+        }),
+        FloodMessage::DhtRequest { id, hops, op } => json!({
+            "type": "dht_request",
+            "id": id,
+            "hops": hops,
+            "op": op,
+        }),
+        FloodMessage::DhtResponse { id, reply } => json!({
+            "type": "dht_response",
+            "id": id,
+            "reply": reply,
+        }),
+        FloodMessage::DhtReplicate { entries } => json!({
+            "type": "dht_replicate",
+            "entries": entries,
+        }),
+        FloodMessage::DhtForward { coord, hops, entries } => json!({
+            "type": "dht_forward",
+            "coord": [coord.0, coord.1, coord.2],
+            "hops": hops,
+            "entries": entries,
+        }),
+        FloodMessage::BlobWant { cid } => json!({
+            "type": "blob_want",
+            "cid": cid.to_hex(),
+        }),
+        FloodMessage::BlobHave { cid, manifest, have } => json!({
+            "type": "blob_have",
+            "cid": cid.to_hex(),
+            "size": manifest.size,
+            "chunks": manifest.chunks.iter().map(hex::encode).collect::<Vec<_>>(),
+            "have": have,
+        }),
+        FloodMessage::BlobRequest { cid, start, end } => json!({
+            "type": "blob_request",
+            "cid": cid.to_hex(),
+            "start": start,
+            "end": end,
+        }),
+        FloodMessage::BlobChunk { cid, index, data } => json!({
+            "type": "blob_chunk",
+            "cid": cid.to_hex(),
+            "index": index,
+            "data": hex::encode(data),
+        }),
+    }
+}
+
+/// Decode a legacy JSON flood message. Returns `None` for unknown types or
+/// messages missing required fields.
+fn flood_from_json(msg_type: &str, msg: &Value) -> Option<FloodMessage> {
+    match msg_type {
+        "flood_peers" | "sync_peers" => {
+            let peers = msg.get("peers")?.as_array()?.iter().filter_map(|peer| {
+                Some((
+                    str_field(peer, "id")?,
+                    str_field(peer, "addr")?,
+                    u64_field(peer, "slot"),
+                    peer.get("public_key")
+                        .and_then(|p| p.as_str())
+                        .and_then(|hex_str| hex::decode(hex_str).ok()),
+                ))
+            }).collect();
+            Some(FloodMessage::Peers(peers))
+        }
+        "flood_admins" | "sync_admins" => {
+            let admins = msg.get("admins")?.as_array()?.iter()
+                .filter_map(|a| a.as_str().map(String::from))
+                .collect();
+            Some(FloodMessage::Admins(admins))
+        }
+        "slot_claim" => {
+            let coord = msg.get("coord")
+                .and_then(|c| c.as_array())
+                .map(|arr| {
+                    let q = arr.first().and_then(|v| v.as_i64()).unwrap_or(0);
+                    let r = arr.get(1).and_then(|v| v.as_i64()).unwrap_or(0);
+                    let z = arr.get(2).and_then(|v| v.as_i64()).unwrap_or(0);
+                    (q, r, z)
+                })
+                .unwrap_or((0, 0, 0));
+            Some(FloodMessage::SlotClaim {
+                index: u64_field(msg, "index")?,
+                peer_id: str_field(msg, "peer_id")?,
+                coord,
+                public_key: msg.get("public_key")
+                    .and_then(|p| p.as_str())
+                    .and_then(|hex_str| hex::decode(hex_str).ok()),
+            })
+        }
+        "slot_validation" => Some(FloodMessage::SlotValidation {
+            index: u64_field(msg, "index")?,
+            peer_id: str_field(msg, "peer_id")?,
+            validator_id: str_field(msg, "validator_id")?,
+            accepted: msg.get("accepted")?.as_bool()?,
+        }),
+        "spore_have_list" => Some(FloodMessage::SporeHaveList {
+            peer_id: str_field(msg, "peer_id").unwrap_or_default(),
+            slots: msg.get("slots")?.as_array()?.iter().filter_map(|v| v.as_u64()).collect(),
+        }),
+        "vdf_chain" => Some(FloodMessage::VdfChain {
+            links: msg.get("links")?.as_array()?.iter().filter_map(link_from_json).collect(),
+        }),
+        "vdf_slot_claim" => Some(FloodMessage::VdfSlotClaim {
+            claim: AnchoredSlotClaim {
+                slot: u64_field(msg, "slot")?,
+                claimer: hex_field(msg, "claimer")?,
+                vdf_height: u64_field(msg, "vdf_height")?,
+                vdf_output: hex_field(msg, "vdf_output")?,
+                signature: hex_field(msg, "signature")?,
+            },
+        }),
+        "pol_ping" => Some(FloodMessage::PoLPing {
+            from: hex_field(msg, "from")?,
+            nonce: u64_field(msg, "nonce")?,
+            vdf_height: u64_field(msg, "vdf_height")?,
+        }),
+        "pol_pong" => Some(FloodMessage::PoLPong {
+            from: hex_field(msg, "from")?,
+            nonce: u64_field(msg, "nonce")?,
+            vdf_height: u64_field(msg, "vdf_height")?,
+        }),
+        "pol_swap_proposal" => Some(FloodMessage::PoLSwapProposal {
+            proposal: SwapProposal {
+                initiator: hex_field(msg, "initiator")?,
+                target: hex_field(msg, "target")?,
+                initiator_slot: u64_field(msg, "initiator_slot")?,
+                target_slot: u64_field(msg, "target_slot")?,
+                initiator_proofs: proofs_field(msg, "initiator_proofs")?,
+                initiator_at_target_proofs: proofs_field(msg, "initiator_at_target_proofs")?,
+                proposal_height: u64_field(msg, "proposal_height")?,
+                proposal_vdf_output: hex_field(msg, "proposal_vdf_output")?,
+                signature: hex_field(msg, "signature")?,
+            },
+        }),
+        "pol_swap_response" => Some(FloodMessage::PoLSwapResponse {
+            response: SwapResponse {
+                responder: hex_field(msg, "responder")?,
+                proposal_height: u64_field(msg, "proposal_height")?,
+                decision: match msg.get("decision")?.as_str()? {
+                    "attack" => SwapDecision::Attack,
+                    "retreat" => SwapDecision::Retreat,
+                    _ => return None,
+                },
+                target_proofs: proofs_field(msg, "target_proofs")?,
+                target_at_initiator_proofs: proofs_field(msg, "target_at_initiator_proofs")?,
+                response_height: u64_field(msg, "response_height")?,
+                signature: hex_field(msg, "signature")?,
+            },
+        }),
+        "cvdf_attestation" => Some(FloodMessage::CvdfAttestation {
+            att: attestation_from_json(msg)?,
+        }),
+        // Older nodes only send a round summary, which can't be reconstructed
+        "cvdf_new_round" => Some(FloodMessage::CvdfNewRound {
+            round: round_from_json(msg)?,
+        }),
+        "cvdf_sync_request" => Some(FloodMessage::CvdfSyncRequest {
+            from_node: str_field(msg, "from_node").unwrap_or_default(),
+            from_height: u64_field(msg, "from_height")?,
+        }),
+        "cvdf_sync_response" => Some(FloodMessage::CvdfSyncResponse {
+            rounds: msg.get("rounds")
+                .and_then(|r| r.as_array())
+                .map(|arr| arr.iter().filter_map(round_from_json).collect())
+                .unwrap_or_default(),
+            slots: msg.get("slots")
+                .and_then(|s| s.as_array())
+                .map(|arr| {
+                    arr.iter()
+                        .filter_map(|slot| Some((u64_field(slot, "index")?, hex_field(slot, "pubkey")?)))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }),
+        "spore_sync" => Some(FloodMessage::SporeSync {
+            msg: serde_json::from_value(msg.get("msg")?.clone()).ok()?,
+        }),
+        "spore_content" => Some(FloodMessage::SporeContent {
+            blocks: serde_json::from_value(msg.get("blocks")?.clone()).ok()?,
+        }),
+        "dht_request" => Some(FloodMessage::DhtRequest {
+            id: u64_field(msg, "id")?,
+            hops: u8::try_from(u64_field(msg, "hops")?).ok()?,
+            op: serde_json::from_value(msg.get("op")?.clone()).ok()?,
+        }),
+        "dht_response" => Some(FloodMessage::DhtResponse {
+            id: u64_field(msg, "id")?,
+            reply: serde_json::from_value(msg.get("reply")?.clone()).ok()?,
+        }),
+        "dht_replicate" => Some(FloodMessage::DhtReplicate {
+            entries: serde_json::from_value(msg.get("entries")?.clone()).ok()?,
+        }),
+        "dht_forward" => Some(FloodMessage::DhtForward {
+            coord: serde_json::from_value(msg.get("coord")?.clone()).ok()?,
+            hops: u8::try_from(u64_field(msg, "hops")?).ok()?,
+            entries: serde_json::from_value(msg.get("entries")?.clone()).ok()?,
+        }),
+        "blob_want" => Some(FloodMessage::BlobWant {
+            cid: Cid(hex_field(msg, "cid")?),
+        }),
+        "blob_have" => Some(FloodMessage::BlobHave {
+            cid: Cid(hex_field(msg, "cid")?),
+            manifest: BlobManifest {
+                size: u64_field(msg, "size")?,
+                chunks: msg.get("chunks")?.as_array()?.iter()
+                    .map(|c| hex::decode(c.as_str()?).ok()?.try_into().ok())
+                    .collect::<Option<Vec<[u8; 32]>>>()?,
+            },
+            have: serde_json::from_value(msg.get("have")?.clone()).ok()?,
+        }),
+        "blob_request" => Some(FloodMessage::BlobRequest {
+            cid: Cid(hex_field(msg, "cid")?),
+            start: u64_field(msg, "start")?,
+            end: u64_field(msg, "end")?,
+        }),
+        "blob_chunk" => Some(FloodMessage::BlobChunk {
+            cid: Cid(hex_field(msg, "cid")?),
+            index: u64_field(msg, "index")?,
+            data: hex::decode(msg.get("data")?.as_str()?).ok()?,
+        }),
+        _ => None,
+    }
+}
+
+fn str_field(value: &Value, key: &str) -> Option<String> {
+    value.get(key).and_then(|v| v.as_str()).map(String::from)
+}
+
+fn u64_field(value: &Value, key: &str) -> Option<u64> {
+    value.get(key).and_then(|v| v.as_u64())
+}
+
+/// Decode a hex string field into a fixed-size byte array
+fn hex_field<const N: usize>(value: &Value, key: &str) -> Option<[u8; N]> {
+    let bytes = hex::decode(value.get(key)?.as_str()?).ok()?;
+    bytes.try_into().ok()
+}
+
+fn link_to_json(link: &VdfLink) -> Value {
+    json!({
+        "height": link.height,
+        "output": hex::encode(link.output),
+        "producer": hex::encode(link.producer),
+        "previous": hex::encode(link.previous),
+        "timestamp_ms": link.timestamp_ms,
+    })
+}
+
+fn link_from_json(value: &Value) -> Option<VdfLink> {
+    Some(VdfLink {
+        height: u64_field(value, "height")?,
+        output: hex_field(value, "output")?,
+        producer: hex_field(value, "producer")?,
+        previous: hex_field(value, "previous")?,
+        timestamp_ms: u64_field(value, "timestamp_ms")?,
+    })
+}
+
+fn proof_to_json(proof: &LatencyProof) -> Value {
+    json!({
+        "from_node": hex::encode(proof.from_node),
+        "to_node": hex::encode(proof.to_node),
+        "latency_us": proof.latency_us,
+        "vdf_height": proof.vdf_height,
+        "vdf_output": hex::encode(proof.vdf_output),
+        "timestamp_ms": proof.timestamp_ms,
+        "signature": hex::encode(proof.signature),
+    })
+}
+
+fn proofs_field(value: &Value, key: &str) -> Option<Vec<LatencyProof>> {
+    value.get(key)?.as_array()?.iter().map(|proof| {
+        Some(LatencyProof {
+            from_node: hex_field(proof, "from_node")?,
+            to_node: hex_field(proof, "to_node")?,
+            latency_us: u64_field(proof, "latency_us")?,
+            vdf_height: u64_field(proof, "vdf_height")?,
+            vdf_output: hex_field(proof, "vdf_output")?,
+            timestamp_ms: u64_field(proof, "timestamp_ms")?,
+            signature: hex_field(proof, "signature")?,
+        })
+    }).collect()
+}
+
+fn attestation_to_json(att: &RoundAttestation) -> Value {
+    json!({
+        "round": att.round,
+        "prev_output": hex::encode(att.prev_output),
+        "attester": hex::encode(att.attester),
+        "slot": att.slot,
+        "signature": hex::encode(att.signature),
+    })
+}
+
+fn attestation_from_json(value: &Value) -> Option<RoundAttestation> {
+    Some(RoundAttestation {
+        round: u64_field(value, "round")?,
+        prev_output: hex_field(value, "prev_output")?,
+        attester: hex_field(value, "attester")?,
+        slot: u64_field(value, "slot"),
+        signature: hex_field(value, "signature")?,
+    })
+}
+
+fn round_to_json(round: &CvdfRound) -> Value {
+    json!({
+        "round": round.round,
+        "prev_output": hex::encode(round.prev_output),
+        "washed_input": hex::encode(round.washed_input),
+        "output": hex::encode(round.output),
+        "producer": hex::encode(round.producer),
+        "producer_signature": hex::encode(round.producer_signature),
+        "timestamp_ms": round.timestamp_ms,
+        "attestations": round.attestations.iter().map(attestation_to_json).collect::<Vec<_>>(),
+    })
+}
+
+fn round_from_json(value: &Value) -> Option<CvdfRound> {
+    Some(CvdfRound {
+        round: u64_field(value, "round")?,
+        prev_output: hex_field(value, "prev_output")?,
+        washed_input: hex_field(value, "washed_input")?,
+        output: hex_field(value, "output")?,
+        attestations: value.get("attestations")?
+            .as_array()?
+            .iter()
+            .filter_map(attestation_from_json)
+            .collect(),
+        producer: hex_field(value, "producer")?,
+        producer_signature: hex_field(value, "producer_signature")?,
+        timestamp_ms: u64_field(value, "timestamp_ms")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dht::{DhtOp, DhtReply};
+    use citadel_dht::{DhtEntry, DhtKey};
+    use citadel_protocols::{ContentBlock, ContentType};
+    use citadel_spore::{Range256, Spore, SporeMessage, U256};
+
+    fn test_key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    fn sample_messages() -> Vec<FloodMessage> {
+        let key = test_key(1);
+        let pubkey = key.verifying_key().to_bytes();
+        let genesis = VdfLink::genesis(b"wire-test", pubkey, crate::vdf_race::VDF_ITERATIONS);
+        let claim = AnchoredSlotClaim::new(3, pubkey, &genesis, &key);
+        let att = RoundAttestation::new(1, [7u8; 32], Some(2), &key);
+        let proof = LatencyProof::new(pubkey, [9u8; 32], 1500, 4, [5u8; 32], &key);
+        let proposal = SwapProposal::new(1, 2, [9u8; 32], vec![proof.clone()], vec![], 4, [5u8; 32], &key);
+        let response = SwapResponse::new(4, SwapDecision::Attack, vec![proof], vec![], 5, &key);
+        let mut round = CvdfRound::genesis(b"wire-test", &key, crate::cvdf::CVDF_ITERATIONS);
+        round.attestations.push(att.clone());
+        let entry = DhtEntry::signed(DhtKey([3u8; 32]), "value".into(), 1_700_000_000_000, &key);
+
+        vec![
+            FloodMessage::Peers(vec![
+                ("b3b3/aa".into(), "10.0.0.1:9000".into(), Some(4), Some(pubkey.to_vec())),
+                ("b3b3/bb".into(), "10.0.0.2:9000".into(), None, None),
+            ]),
+            FloodMessage::Admins(vec!["abcd".into()]),
+            FloodMessage::SlotClaim { index: 7, peer_id: "b3b3/aa".into(), coord: (1, -1, 0), public_key: Some(pubkey.to_vec()) },
+            FloodMessage::SlotValidation { index: 7, peer_id: "b3b3/aa".into(), validator_id: "b3b3/bb".into(), accepted: true },
+            FloodMessage::SporeHaveList { peer_id: "b3b3/aa".into(), slots: vec![0, 1, 5] },
+            FloodMessage::VdfChain { links: vec![genesis] },
+            FloodMessage::VdfSlotClaim { claim },
+            FloodMessage::PoLPing { from: pubkey, nonce: 42, vdf_height: 9 },
+            FloodMessage::PoLPong { from: pubkey, nonce: 42, vdf_height: 9 },
+            FloodMessage::PoLSwapProposal { proposal },
+            FloodMessage::PoLSwapResponse { response },
+            FloodMessage::CvdfAttestation { att },
+            FloodMessage::CvdfNewRound { round: round.clone() },
+            FloodMessage::CvdfSyncRequest { from_node: "b3b3/aa".into(), from_height: 3 },
+            FloodMessage::CvdfSyncResponse { rounds: vec![round], slots: vec![(0, pubkey)] },
+            FloodMessage::SporeSync {
+                msg: SporeMessage::unsigned(
+                    U256::from_u64(1),
+                    Spore::from_range(Range256::new(U256::from_u64(10), U256::from_u64(20))),
+                    Spore::full(),
+                ),
+            },
+            FloodMessage::SporeContent {
+                blocks: vec![ContentBlock::new(ContentType::Admin, br#"{"kind":"admin"}"#.to_vec())],
+            },
+            FloodMessage::DhtRequest { id: 9, hops: 2, op: DhtOp::Get { key: DhtKey([3u8; 32]) } },
+            FloodMessage::DhtRequest { id: 10, hops: 0, op: DhtOp::Put { entry: entry.clone() } },
+            FloodMessage::DhtResponse { id: 9, reply: DhtReply::Value(Some(entry.clone())) },
+            FloodMessage::DhtResponse { id: 10, reply: DhtReply::Rejected("key is owned by another author".into()) },
+            FloodMessage::DhtReplicate { entries: vec![entry.clone()] },
+            FloodMessage::DhtForward { coord: (1, -1, 0), hops: 3, entries: vec![entry] },
+            FloodMessage::BlobWant { cid: Cid([4u8; 32]) },
+            FloodMessage::BlobHave {
+                cid: Cid([4u8; 32]),
+                manifest: BlobManifest { size: 300_000, chunks: vec![[5u8; 32], [6u8; 32]] },
+                have: Spore::from_range(Range256::new(U256::from_u64(0), U256::from_u64(1))),
+            },
+            FloodMessage::BlobRequest { cid: Cid([4u8; 32]), start: 0, end: 16 },
+            FloodMessage::BlobChunk { cid: Cid([4u8; 32]), index: 1, data: vec![1, 2, 3] },
+        ]
+    }
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(WireCodec::negotiate(&[]), WireCodec::Json);
+        assert_eq!(WireCodec::negotiate(&[WIRE_VERSION]), WireCodec::Binary(WIRE_VERSION));
+        assert_eq!(WireCodec::negotiate(&[WIRE_VERSION, 200]), WireCodec::Binary(WIRE_VERSION));
+        assert_eq!(WireCodec::negotiate(&[200]), WireCodec::Json);
+    }
+
+    #[test]
+    fn test_binary_roundtrip_all_variants() {
+        for msg in sample_messages() {
+            let frame = encode_frame(WIRE_VERSION, &msg).unwrap();
+            let len = u32::from_be_bytes(frame[..4].try_into().unwrap()) as usize;
+            assert_eq!(len, frame.len() - 4);
+            assert_eq!(frame[4], WIRE_VERSION);
+
+            let decoded = decode_frame(&frame[4..]).unwrap();
+            assert_eq!(format!("{:?}", decoded), format!("{:?}", msg));
+        }
+    }
+
+    #[test]
+    fn test_json_roundtrip_all_variants() {
+        for msg in sample_messages() {
+            let json = flood_to_json(&msg);
+            match WireMessage::from_json(&json) {
+                WireMessage::Flood(decoded) => assert_eq!(format!("{:?}", decoded), format!("{:?}", msg)),
+                other => panic!("failed to decode {}: {:?}", json, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_binary_smaller_than_json() {
+        for msg in sample_messages() {
+            let binary = WireCodec::Binary(WIRE_VERSION).encode(&msg).unwrap();
+            let json = WireCodec::Json.encode(&msg).unwrap();
+            assert!(binary.len() < json.len(), "{:?}: {} >= {}", msg, binary.len(), json.len());
+        }
+    }
+
+    #[test]
+    fn test_decode_rejects_bad_frames() {
+        let frame = encode_frame(WIRE_VERSION, &FloodMessage::Admins(vec!["a".into()])).unwrap();
+        assert!(decode_frame(&[]).is_err());
+
+        let mut wrong_version = frame[4..].to_vec();
+        wrong_version[0] = 0xFF;
+        assert!(decode_frame(&wrong_version).is_err());
+
+        // Truncated payload
+        assert!(decode_frame(&frame[4..frame.len() - 1]).is_err());
+        // Trailing garbage
+        let mut trailing = frame[4..].to_vec();
+        trailing.push(0);
+        assert!(decode_frame(&trailing).is_err());
+    }
+
+    #[test]
+    fn test_hello_legacy_compat() {
+        let hello = Hello {
+            node_id: "b3b3/aa".into(),
+            addr: "10.0.0.1:9000".into(),
+            public_key: Some(vec![1u8; 32]),
+            wire_versions: SUPPORTED_WIRE_VERSIONS.to_vec(),
+            challenge: Some(vec![2u8; 32]),
+        };
+        match WireMessage::from_json(&hello.to_json()) {
+            WireMessage::Hello(decoded) => assert_eq!(decoded, hello),
+            other => panic!("expected hello, got {:?}", other),
+        }
+
+        // Hello from a node that predates the binary codec
+        let legacy = json!({"type": "hello", "node_id": "b3b3/bb", "addr": "10.0.0.2:9000", "public_key": null});
+        match WireMessage::from_json(&legacy) {
+            WireMessage::Hello(decoded) => assert_eq!(WireCodec::negotiate(&decoded.wire_versions), WireCodec::Json),
+            other => panic!("expected hello, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_hello_proof() {
+        let key = SigningKey::from_bytes(&[3u8; 32]);
+        let public_key = key.verifying_key().to_bytes();
+        let proof = HelloProof::sign(&[9u8; 32], &key);
+
+        let line = proof.to_line();
+        let decoded = match WireMessage::from_json(&serde_json::from_slice(&line).unwrap()) {
+            WireMessage::HelloProof(decoded) => decoded,
+            other => panic!("expected hello proof, got {:?}", other),
+        };
+        assert!(decoded.verify(&[9u8; 32], &public_key));
+
+        // Another challenge, or a key the signer doesn't hold
+        assert!(!decoded.verify(&[8u8; 32], &public_key));
+        let other = SigningKey::from_bytes(&[4u8; 32]).verifying_key().to_bytes();
+        assert!(!decoded.verify(&[9u8; 32], &other));
+    }
+
+    #[test]
+    fn test_legacy_round_summary_unrecognized() {
+        let summary = json!({"type": "cvdf_new_round", "round": 3, "attestation_count": 2});
+        assert!(matches!(WireMessage::from_json(&summary), WireMessage::Unrecognized(_)));
+        assert!(matches!(WireMessage::from_json(&json!({"type": "bogus"})), WireMessage::Unrecognized(_)));
+    }
+
+    #[tokio::test]
+    async fn test_read_message_binary_stream() {
+        let msgs = sample_messages();
+        let mut stream = Vec::new();
+        for msg in &msgs {
+            stream.extend(WireCodec::Binary(WIRE_VERSION).encode(msg).unwrap());
+        }
+
+        let mut reader = tokio::io::BufReader::new(stream.as_slice());
+        for msg in &msgs {
+            match read_message(&mut reader, WireCodec::Binary(WIRE_VERSION)).await.unwrap() {
+                Some(WireMessage::Flood(decoded)) => assert_eq!(format!("{:?}", decoded), format!("{:?}", msg)),
+                other => panic!("unexpected {:?}", other),
+            }
+        }
+        assert!(read_message(&mut reader, WireCodec::Binary(WIRE_VERSION)).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_read_message_rejects_oversized_frame() {
+        let stream = ((MAX_FRAME_LEN + 1) as u32).to_be_bytes();
+        let mut reader = tokio::io::BufReader::new(&stream[..]);
+        assert!(read_message(&mut reader, WireCodec::Binary(WIRE_VERSION)).await.is_err());
+    }
+}
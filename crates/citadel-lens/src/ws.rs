@@ -344,5 +344,19 @@ pub fn flood_to_event(msg: FloodMessage) -> Option<MeshEvent> {
                 weight: total_weight,
             })
         }
+        FloodMessage::SporeSync { msg } => Some(MeshEvent::SporeSync {
+            peer_id: hex::encode(msg.node_id.to_be_bytes()),
+            have_count: msg.have_list.range_count(),
+            want_count: msg.want_list.range_count(),
+        }),
+        FloodMessage::SporeContent { .. } => None, // Internal replication
+        FloodMessage::DhtRequest { .. }
+        | FloodMessage::DhtResponse { .. }
+        | FloodMessage::DhtReplicate { .. }
+        | FloodMessage::DhtForward { .. } => None, // Point-to-point DHT traffic
+        FloodMessage::BlobWant { .. }
+        | FloodMessage::BlobHave { .. }
+        | FloodMessage::BlobRequest { .. }
+        | FloodMessage::BlobChunk { .. } => None, // Point-to-point blob transfers
     }
 }
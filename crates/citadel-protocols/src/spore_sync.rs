@@ -47,6 +47,7 @@
 //! ```
 
 use citadel_spore::{Range256, Spore, SporeMessage, SyncState, U256};
+use ed25519_dalek::{SigningKey, VerifyingKey};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use tracing::{debug, trace};
@@ -95,6 +96,8 @@ pub enum ContentType {
     ContentItem,
     /// Admin authorization
     Admin,
+    /// Content category
+    Category,
 }
 
 /// SPORE-based synchronization state for a peer connection.
@@ -156,6 +159,24 @@ impl SporeSync {
                hex::encode(&hash[..8]), self.sync_state.my_have.range_count(), self.sync_state.my_want.range_count());
     }
 
+    /// Add many content blocks at once.
+    ///
+    /// Equivalent to calling [`add_content`](Self::add_content) for each block,
+    /// but builds the HaveList update in a single pass.
+    pub fn add_content_batch(&mut self, blocks: impl IntoIterator<Item = ContentBlock>) {
+        let mut ranges = Vec::new();
+        for block in blocks {
+            let start = block.hash_u256();
+            let end = start.checked_add(&U256::from_u64(1)).unwrap_or(U256::MAX);
+            ranges.push(Range256::new(start, end));
+            self.content.insert(block.hash, block);
+        }
+        let added = Spore::from_ranges(ranges);
+
+        self.sync_state.my_have = self.sync_state.my_have.union(&added);
+        self.sync_state.my_want = self.sync_state.my_want.subtract(&added);
+    }
+
     /// Check if we have content with the given hash.
     pub fn has_content(&self, hash: &[u8; 32]) -> bool {
         self.content.contains_key(hash)
@@ -324,6 +345,16 @@ pub struct SporeSyncManager {
     peers: HashMap<U256, SporeSync>,
     /// Global content store (shared across all peer syncs)
     content: HashMap<[u8; 32], ContentBlock>,
+    /// Key used to sign our SPORE messages
+    signing_key: Option<SigningKey>,
+    /// Known public keys of peers (messages from these peers must be signed)
+    peer_keys: HashMap<U256, VerifyingKey>,
+    /// Counter of the last SPORE message we signed
+    last_sent: u64,
+    /// Counter of the last SPORE message accepted from each peer
+    last_received: HashMap<U256, u64>,
+    /// SPORE messages rejected for a bad signature
+    rejected_messages: u64,
 }
 
 impl SporeSyncManager {
@@ -333,9 +364,33 @@ impl SporeSyncManager {
             peer_id,
             peers: HashMap::new(),
             content: HashMap::new(),
+            signing_key: None,
+            peer_keys: HashMap::new(),
+            last_sent: 0,
+            last_received: HashMap::new(),
+            rejected_messages: 0,
         }
     }
 
+    /// Builder: sign outgoing SPORE messages with this key.
+    ///
+    /// Once signing is enabled, messages are only accepted from peers with a
+    /// registered key.
+    pub fn with_signing_key(mut self, signing_key: SigningKey) -> Self {
+        self.signing_key = Some(signing_key);
+        self
+    }
+
+    /// Register a peer's public key.
+    ///
+    /// From then on, SPORE messages from this peer are only accepted if they
+    /// carry its node ID and a valid signature by this key. The caller must
+    /// have checked that the peer holds the key, e.g. by having it sign a
+    /// challenge.
+    pub fn register_peer_key(&mut self, peer_id: U256, key: VerifyingKey) {
+        self.peer_keys.insert(peer_id, key);
+    }
+
     /// Add content to the global store.
     pub fn add_content(&mut self, block: ContentBlock) {
         let hash = block.hash;
@@ -347,27 +402,71 @@ impl SporeSyncManager {
         }
     }
 
+    /// Check if the global store has content with the given hash.
+    pub fn has_content(&self, hash: &[u8; 32]) -> bool {
+        self.content.contains_key(hash)
+    }
+
+    /// Get content from the global store by hash.
+    pub fn get_content(&self, hash: &[u8; 32]) -> Option<&ContentBlock> {
+        self.content.get(hash)
+    }
+
     /// Get or create sync state for a peer.
     pub fn get_or_create_peer(&mut self, peer_id: U256) -> &mut SporeSync {
         self.peers.entry(peer_id).or_insert_with(|| {
             let mut sync = SporeSync::new(self.peer_id);
             // Copy existing content to new peer sync
-            for (_, block) in &self.content {
-                sync.add_content(block.clone());
-            }
+            sync.add_content_batch(self.content.values().cloned());
             sync
         })
     }
 
     /// Process SPORE message from a peer.
-    pub fn receive_spore_message(&mut self, peer_id: U256, msg: SporeMessage) {
+    ///
+    /// Returns `false` (and counts the rejection) if the message is not signed
+    /// by the peer's registered key, or if it has no registered key while we
+    /// sign our own messages. Signed messages must also carry a higher
+    /// counter than the last one accepted from the peer. A forged or replayed
+    /// WantList would otherwise make us resend content the peer already has.
+    pub fn receive_spore_message(&mut self, peer_id: U256, msg: SporeMessage) -> bool {
+        let accepted = match self.peer_keys.get(&peer_id) {
+            Some(key) => {
+                let last = self.last_received.get(&peer_id).copied().unwrap_or(0);
+                msg.node_id == peer_id && msg.sequence > last && msg.verify(key)
+            }
+            None => self.signing_key.is_none(),
+        };
+        if !accepted {
+            self.rejected_messages += 1;
+            debug!("Rejected unsigned, forged or replayed SPORE message from {:?}", peer_id);
+            return false;
+        }
+        if self.peer_keys.contains_key(&peer_id) {
+            self.last_received.insert(peer_id, msg.sequence);
+        }
         let sync = self.get_or_create_peer(peer_id);
         sync.receive_spore_message(msg);
+        true
     }
 
-    /// Get SPORE message to send to a peer.
-    pub fn create_spore_message(&self, peer_id: &U256) -> Option<SporeMessage> {
-        self.peers.get(peer_id).map(|sync| sync.create_spore_message())
+    /// Get SPORE message to send to a peer, signed if we have a signing key.
+    ///
+    /// Signed messages get a new counter: the current time in milliseconds,
+    /// or one more than the last counter if the clock hasn't moved on, so
+    /// counters keep increasing across restarts.
+    pub fn create_spore_message(&mut self, peer_id: &U256) -> Option<SporeMessage> {
+        let mut msg = self.peers.get(peer_id).map(|sync| sync.create_spore_message())?;
+        if let Some(ref key) = self.signing_key {
+            let now_ms = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0);
+            self.last_sent = now_ms.max(self.last_sent + 1);
+            msg.sequence = self.last_sent;
+            msg.sign(key);
+        }
+        Some(msg)
     }
 
     /// Receive content block from a peer.
@@ -420,6 +519,7 @@ impl SporeSyncManager {
             peer_count: self.peers.len(),
             total_xor_ranges: total_xor,
             all_synced,
+            rejected_messages: self.rejected_messages,
             peer_stats,
         }
     }
@@ -436,6 +536,8 @@ pub struct ManagerStats {
     pub total_xor_ranges: usize,
     /// Whether all peers are fully synced
     pub all_synced: bool,
+    /// SPORE messages rejected as unsigned, forged or replayed
+    pub rejected_messages: u64,
     /// Per-peer statistics
     pub peer_stats: Vec<(U256, SporeSyncStats)>,
 }
@@ -543,6 +645,93 @@ mod tests {
         assert_eq!(stats.peer_count, 1);
     }
 
+    #[test]
+    fn test_add_content_batch_matches_individual_adds() {
+        let blocks: Vec<_> = (0..20)
+            .map(|i| ContentBlock::new(ContentType::Category, format!("category {}", i).into_bytes()))
+            .collect();
+
+        let mut one_by_one = SporeSync::new(test_peer_id(1));
+        for block in &blocks {
+            one_by_one.add_content(block.clone());
+        }
+        let mut batched = SporeSync::new(test_peer_id(1));
+        batched.add_content_batch(blocks.clone());
+
+        assert_eq!(one_by_one.sync_state.my_have, batched.sync_state.my_have);
+        assert_eq!(one_by_one.sync_state.my_want, batched.sync_state.my_want);
+        assert!(blocks.iter().all(|b| batched.has_content(&b.hash)));
+    }
+
+    #[test]
+    fn test_manager_new_peer_receives_existing_content() {
+        let mut alice = SporeSyncManager::new(test_peer_id(1));
+        let mut bob = SporeSyncManager::new(test_peer_id(2));
+        for i in 0..5 {
+            alice.add_content(ContentBlock::new(ContentType::Release, format!("release {}", i).into_bytes()));
+        }
+
+        let bob_msg = bob.get_or_create_peer(test_peer_id(1)).create_spore_message();
+        alice.receive_spore_message(test_peer_id(2), bob_msg);
+
+        let blocks = alice.blocks_to_send(&test_peer_id(2));
+        assert_eq!(blocks.len(), 5);
+        for block in blocks {
+            assert!(!bob.has_content(&block.hash));
+            bob.receive_content(test_peer_id(1), block);
+        }
+        assert_eq!(bob.stats().content_count, 5);
+    }
+
+    #[test]
+    fn test_manager_rejects_bad_signatures() {
+        let alice_key = SigningKey::from_bytes(&[1u8; 32]);
+        let mallory_key = SigningKey::from_bytes(&[2u8; 32]);
+        let mut alice = SporeSyncManager::new(test_peer_id(1)).with_signing_key(alice_key.clone());
+        let mut bob = SporeSyncManager::new(test_peer_id(2));
+        bob.register_peer_key(test_peer_id(1), alice_key.verifying_key());
+        let block = ContentBlock::new(ContentType::Release, b"release".to_vec());
+        alice.add_content(block.clone());
+        bob.add_content(block);
+
+        // Signed by the registered key - in sync, nothing to send
+        alice.get_or_create_peer(test_peer_id(2));
+        let msg = alice.create_spore_message(&test_peer_id(2)).unwrap();
+        assert!(bob.receive_spore_message(test_peer_id(1), msg.clone()));
+        assert!(bob.blocks_to_send(&test_peer_id(1)).is_empty());
+
+        // Forged WantList, wrong signer, wrong node ID, unsigned
+        let mut forged = msg.clone();
+        forged.want_list = Spore::full();
+        let wrong_signer = SporeMessage::signed(test_peer_id(1), Spore::empty(), Spore::full(), &mallory_key);
+        let wrong_node = SporeMessage::signed(test_peer_id(3), Spore::empty(), Spore::full(), &alice_key);
+        let unsigned = SporeMessage::unsigned(test_peer_id(1), Spore::empty(), Spore::full());
+        for bad in [forged, wrong_signer, wrong_node, unsigned] {
+            assert!(!bob.receive_spore_message(test_peer_id(1), bad));
+        }
+        assert_eq!(bob.stats().rejected_messages, 4);
+
+        // Replayed, even with a valid signature
+        assert!(!bob.receive_spore_message(test_peer_id(1), msg.clone()));
+        assert_eq!(bob.stats().rejected_messages, 5);
+
+        // Rejected messages can't make us resend content
+        assert!(bob.blocks_to_send(&test_peer_id(1)).is_empty());
+
+        // A fresh message is accepted
+        let next = alice.create_spore_message(&test_peer_id(2)).unwrap();
+        assert!(next.sequence > msg.sequence);
+        assert!(bob.receive_spore_message(test_peer_id(1), next));
+
+        // Peers without a registered key are only accepted while we don't sign
+        let unknown = SporeMessage::unsigned(test_peer_id(4), Spore::empty(), Spore::full());
+        assert!(bob.receive_spore_message(test_peer_id(4), unknown.clone()));
+        assert_eq!(bob.blocks_to_send(&test_peer_id(4)).len(), 1);
+
+        let mut signing_bob = SporeSyncManager::new(test_peer_id(2)).with_signing_key(SigningKey::from_bytes(&[3u8; 32]));
+        assert!(!signing_bob.receive_spore_message(test_peer_id(4), unknown));
+    }
+
     #[test]
     fn test_convergence_to_zero_overhead() {
         // Simulate multiple rounds of sync until convergence
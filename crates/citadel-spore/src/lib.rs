@@ -34,9 +34,28 @@
 //! WantList: Ranges of values I desire
 //! GAPS:     Everything else - IMPLICITLY EXCLUDED (never syncs, zero cost)
 //! ```
-
-use serde::{Deserialize, Serialize};
+//!
+//! ## Binary Encoding
+//!
+//! [`Spore::to_bytes`] is the canonical form used on the wire and for signing.
+//! A normalized SPORE's boundaries strictly increase, so each is stored as the
+//! delta from the previous one with leading zero bytes stripped:
+//!
+//! ```text
+//! range_count: LEB128
+//! 2 × range_count boundaries, each: len (u8, 0..=32) || delta (len bytes, BE)
+//! ```
+//!
+//! Every SPORE has exactly one encoding. Decoding rejects anything else:
+//! non-minimal lengths, zero deltas (empty, adjacent or unsorted ranges),
+//! overflow past 2^256 - 1 and trailing bytes.
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::de::Error as _;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::cmp::{max, min, Ordering};
+use std::fmt;
 
 /// A 256-bit unsigned integer.
 ///
@@ -251,6 +270,123 @@ impl Range256 {
             stop: max(self.stop, other.stop),
         })
     }
+
+    /// Canonical binary encoding: start, then the range length, as stripped
+    /// big-endian values (see [`Spore::to_bytes`])
+    ///
+    /// Fails with [`DecodeError::InvalidRange`] if `start > stop`, which the
+    /// public fields and serde allow.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, DecodeError> {
+        let len = self.stop.checked_sub(&self.start).ok_or(DecodeError::InvalidRange)?;
+        let mut out = Vec::with_capacity(66);
+        put_u256(&mut out, &self.start);
+        put_u256(&mut out, &len);
+        Ok(out)
+    }
+
+    /// Decode the canonical binary encoding
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut input = bytes;
+        let start = take_u256(&mut input)?;
+        let len = take_u256(&mut input)?;
+        if !input.is_empty() {
+            return Err(DecodeError::TrailingBytes);
+        }
+        let stop = start.checked_add(&len).ok_or(DecodeError::Overflow)?;
+        Ok(Range256 { start, stop })
+    }
+}
+
+/// Error decoding the canonical binary form of a [`Spore`] or [`Range256`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// Input ended in the middle of a value
+    Truncated,
+    /// Input continues after the value
+    TrailingBytes,
+    /// A count or length is not in its shortest form
+    NonCanonical,
+    /// A value does not fit in 256 bits
+    Overflow,
+    /// Ranges are empty, overlapping, adjacent or unsorted
+    NotNormalized,
+    /// A range starts after it stops
+    InvalidRange,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Truncated => write!(f, "truncated SPORE encoding"),
+            DecodeError::TrailingBytes => write!(f, "trailing bytes after SPORE encoding"),
+            DecodeError::NonCanonical => write!(f, "non-canonical SPORE encoding"),
+            DecodeError::Overflow => write!(f, "SPORE boundary exceeds 256 bits"),
+            DecodeError::NotNormalized => write!(f, "SPORE ranges are not normalized"),
+            DecodeError::InvalidRange => write!(f, "range starts after it stops"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Append a U256 as a length byte followed by its big-endian bytes without leading zeros
+fn put_u256(out: &mut Vec<u8>, value: &U256) {
+    let bytes = value.to_be_bytes();
+    let skip = bytes.iter().take_while(|&&b| b == 0).count();
+    out.push((32 - skip) as u8);
+    out.extend_from_slice(&bytes[skip..]);
+}
+
+fn take_u256(input: &mut &[u8]) -> Result<U256, DecodeError> {
+    let (&len, rest) = input.split_first().ok_or(DecodeError::Truncated)?;
+    let len = len as usize;
+    if len > 32 {
+        return Err(DecodeError::Overflow);
+    }
+    if rest.len() < len {
+        return Err(DecodeError::Truncated);
+    }
+    let (digits, rest) = rest.split_at(len);
+    if digits.first() == Some(&0) {
+        return Err(DecodeError::NonCanonical);
+    }
+    let mut bytes = [0u8; 32];
+    bytes[32 - len..].copy_from_slice(digits);
+    *input = rest;
+    Ok(U256::from_be_bytes(&bytes))
+}
+
+/// Append an unsigned LEB128 varint
+fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn take_varint(input: &mut &[u8]) -> Result<u64, DecodeError> {
+    let mut value = 0u64;
+    for i in 0..10 {
+        let (&byte, rest) = input.split_first().ok_or(DecodeError::Truncated)?;
+        *input = rest;
+        // The tenth byte holds only the top bit of a u64
+        if i == 9 && byte > 1 {
+            return Err(DecodeError::Overflow);
+        }
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            if byte == 0 && i > 0 {
+                return Err(DecodeError::NonCanonical);
+            }
+            return Ok(value);
+        }
+    }
+    Err(DecodeError::Overflow)
 }
 
 /// SPORE: A sorted list of non-overlapping ranges.
@@ -262,7 +398,10 @@ impl Range256 {
 ///
 /// 64 bytes per range. A single range covering 2^255 values costs the same as
 /// a single range covering 1 value. The cost is O(boundaries), not O(values).
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+///
+/// Binary serializers get the compact canonical form ([`Spore::to_bytes`]);
+/// human-readable ones get the list of ranges. Both reject non-normalized input.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct Spore {
     /// Sorted, non-overlapping ranges
     ranges: Vec<Range256>,
@@ -316,6 +455,68 @@ impl Spore {
         Spore { ranges: merged }
     }
 
+    /// Create from ranges that are already sorted, non-empty and neither
+    /// overlapping nor adjacent (the form every `Spore` keeps internally)
+    pub fn from_normalized(ranges: Vec<Range256>) -> Result<Self, DecodeError> {
+        let mut prev: Option<U256> = None;
+        for range in &ranges {
+            if range.start >= range.stop || prev.is_some_and(|p| p >= range.start) {
+                return Err(DecodeError::NotNormalized);
+            }
+            prev = Some(range.stop);
+        }
+        Ok(Spore { ranges })
+    }
+
+    /// Canonical binary encoding (see the crate docs for the layout)
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(1 + 4 * self.ranges.len());
+        put_varint(&mut out, self.ranges.len() as u64);
+        let mut prev = U256::ZERO;
+        for range in &self.ranges {
+            for bound in [range.start, range.stop] {
+                let delta = bound.checked_sub(&prev).expect("SPORE boundaries increase");
+                put_u256(&mut out, &delta);
+                prev = bound;
+            }
+        }
+        out
+    }
+
+    /// Decode the canonical binary encoding
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut input = bytes;
+        let count = take_varint(&mut input)?;
+        // Every boundary takes at least one byte, which bounds the allocation
+        if count.saturating_mul(2) > input.len() as u64 {
+            return Err(DecodeError::Truncated);
+        }
+
+        let mut ranges = Vec::with_capacity(count as usize);
+        let mut prev = U256::ZERO;
+        for i in 0..count {
+            let start_delta = take_u256(&mut input)?;
+            // Only the first range may start at the origin; later ones must
+            // leave a gap after the previous range
+            if start_delta.is_zero() && i > 0 {
+                return Err(DecodeError::NotNormalized);
+            }
+            let start = prev.checked_add(&start_delta).ok_or(DecodeError::Overflow)?;
+            let len = take_u256(&mut input)?;
+            if len.is_zero() {
+                return Err(DecodeError::NotNormalized);
+            }
+            let stop = start.checked_add(&len).ok_or(DecodeError::Overflow)?;
+            ranges.push(Range256 { start, stop });
+            prev = stop;
+        }
+
+        if !input.is_empty() {
+            return Err(DecodeError::TrailingBytes);
+        }
+        Ok(Spore { ranges })
+    }
+
     /// Get the ranges
     pub fn ranges(&self) -> &[Range256] {
         &self.ranges
@@ -481,6 +682,34 @@ impl Spore {
     }
 }
 
+impl Serialize for Spore {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            let mut state = serializer.serialize_struct("Spore", 1)?;
+            state.serialize_field("ranges", &self.ranges)?;
+            state.end()
+        } else {
+            serializer.serialize_bytes(&self.to_bytes())
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Spore {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            #[derive(Deserialize)]
+            struct Ranges {
+                ranges: Vec<Range256>,
+            }
+            let Ranges { ranges } = Ranges::deserialize(deserializer)?;
+            Spore::from_normalized(ranges).map_err(D::Error::custom)
+        } else {
+            let bytes = serde_bytes::ByteBuf::deserialize(deserializer)?;
+            Spore::from_bytes(&bytes).map_err(D::Error::custom)
+        }
+    }
+}
+
 /// Sync state between two nodes using SPORE.
 ///
 /// Each node maintains HaveList (what they possess) and WantList (what they desire).
@@ -538,6 +767,10 @@ pub struct SporeMessage {
     pub have_list: Spore,
     /// WantList: what this node wants
     pub want_list: Spore,
+    /// Sender's message counter; receivers accept each sender's counters
+    /// only in increasing order, so a captured message can't be replayed
+    #[serde(default)]
+    pub sequence: u64,
     /// Ed25519 signature (64 bytes)
     #[serde(with = "serde_bytes")]
     pub signature: Vec<u8>,
@@ -550,14 +783,52 @@ impl SporeMessage {
             node_id,
             have_list,
             want_list,
+            sequence: 0,
             signature: vec![],
         }
     }
 
+    /// Create a message signed by the node's key
+    pub fn signed(node_id: U256, have_list: Spore, want_list: Spore, signing_key: &SigningKey) -> Self {
+        let mut msg = SporeMessage::unsigned(node_id, have_list, want_list);
+        msg.sign(signing_key);
+        msg
+    }
+
+    /// Canonical bytes covered by the signature.
+    ///
+    /// ```text
+    /// "SPORE-MSG-V2" || node_id (32, BE) || sequence (8, BE) || have_list.to_bytes() || want_list.to_bytes()
+    /// ```
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        let have = self.have_list.to_bytes();
+        let want = self.want_list.to_bytes();
+        let mut bytes = Vec::with_capacity(12 + 32 + 8 + have.len() + want.len());
+        bytes.extend_from_slice(b"SPORE-MSG-V2");
+        bytes.extend_from_slice(&self.node_id.to_be_bytes());
+        bytes.extend_from_slice(&self.sequence.to_be_bytes());
+        bytes.extend_from_slice(&have);
+        bytes.extend_from_slice(&want);
+        bytes
+    }
+
+    /// Sign the message, replacing any existing signature
+    pub fn sign(&mut self, signing_key: &SigningKey) {
+        self.signature = signing_key.sign(&self.signing_bytes()).to_bytes().to_vec();
+    }
+
+    /// Verify the signature against the sender's public key
+    pub fn verify(&self, verifying_key: &VerifyingKey) -> bool {
+        let Ok(signature) = Signature::from_slice(&self.signature) else {
+            return false;
+        };
+        verifying_key.verify(&self.signing_bytes(), &signature).is_ok()
+    }
+
     /// Total encoding size in bytes
     pub fn encoding_size(&self) -> usize {
-        // node_id (32) + have_count (2) + want_count (2) + ranges + signature (64)
-        32 + 4 + self.have_list.encoding_size() + self.want_list.encoding_size() + 64
+        // node_id (32) + sequence (8) + have_count (2) + want_count (2) + ranges + signature (64)
+        32 + 8 + 4 + self.have_list.encoding_size() + self.want_list.encoding_size() + 64
     }
 }
 
@@ -722,4 +993,218 @@ mod tests {
         // First two should merge, third is separate
         assert_eq!(spore.range_count(), 2);
     }
+
+    #[test]
+    fn test_spore_message_sign_verify() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let have = Spore::from_range(Range256::new(U256::from_u64(10), U256::from_u64(20)));
+        let msg = SporeMessage::signed(U256::from_u64(1), have.clone(), have.complement(), &key);
+
+        assert_eq!(msg.signature.len(), 64);
+        assert!(msg.verify(&key.verifying_key()));
+
+        // Wrong key
+        let other = SigningKey::from_bytes(&[8u8; 32]);
+        assert!(!msg.verify(&other.verifying_key()));
+
+        // Tampered WantList
+        let mut tampered = msg.clone();
+        tampered.want_list = Spore::full();
+        assert!(!tampered.verify(&key.verifying_key()));
+
+        // Replayed under a new counter
+        let mut tampered = msg.clone();
+        tampered.sequence += 1;
+        assert!(!tampered.verify(&key.verifying_key()));
+
+        // Tampered node_id
+        let mut tampered = msg.clone();
+        tampered.node_id = U256::from_u64(2);
+        assert!(!tampered.verify(&key.verifying_key()));
+
+        // Unsigned
+        let unsigned = SporeMessage::unsigned(U256::from_u64(1), have.clone(), have.complement());
+        assert!(!unsigned.verify(&key.verifying_key()));
+    }
+
+    #[test]
+    fn test_spore_message_signing_bytes_canonical() {
+        let have = Spore::from_ranges(vec![
+            Range256::new(U256::from_u64(30), U256::from_u64(40)),
+            Range256::new(U256::from_u64(10), U256::from_u64(20)),
+        ]);
+        let mut msg = SporeMessage::unsigned(U256::from_u64(1), have.clone(), Spore::empty());
+        msg.sequence = 9;
+        let bytes = msg.signing_bytes();
+
+        // tag + node_id + sequence + canonical HaveList + canonical WantList
+        assert_eq!(&bytes[..12], b"SPORE-MSG-V2");
+        assert_eq!(&bytes[12..44], &U256::from_u64(1).to_be_bytes());
+        assert_eq!(&bytes[44..52], &9u64.to_be_bytes());
+        assert_eq!(&bytes[52..bytes.len() - 1], &have.to_bytes()[..]);
+        assert_eq!(&bytes[bytes.len() - 1..], &Spore::empty().to_bytes()[..]);
+
+        // Signature does not affect the signed bytes
+        let mut signed = msg.clone();
+        signed.sign(&SigningKey::from_bytes(&[7u8; 32]));
+        assert_eq!(signed.signing_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_spore_encoding_layout() {
+        assert_eq!(Spore::empty().to_bytes(), vec![0]);
+
+        // [0, MAX): start delta 0, then a 32-byte length
+        let full = Spore::full().to_bytes();
+        assert_eq!(full.len(), 1 + 1 + 33);
+        assert_eq!(&full[..2], &[1, 0]);
+
+        // [10, 20) ∪ [300, 301): deltas 10, 10, 280, 1
+        let spore = Spore::from_ranges(vec![
+            Range256::new(U256::from_u64(300), U256::from_u64(301)),
+            Range256::new(U256::from_u64(10), U256::from_u64(20)),
+        ]);
+        assert_eq!(spore.to_bytes(), vec![2, 1, 10, 1, 10, 2, 1, 24, 1, 1]);
+        assert_eq!(Spore::from_bytes(&spore.to_bytes()).unwrap(), spore);
+    }
+
+    #[test]
+    fn test_spore_decode_rejects_invalid() {
+        use DecodeError::*;
+        let cases: &[(&[u8], DecodeError)] = &[
+            (&[], Truncated),
+            (&[1, 1, 10], Truncated),
+            (&[5, 1, 1], Truncated),
+            (&[0, 0], TrailingBytes),
+            (&[0x80, 0x00], NonCanonical),
+            (&[1, 2, 0, 10, 1, 10], NonCanonical),
+            (&[1, 33, 1, 1], Overflow),
+            // Empty range
+            (&[1, 1, 10, 0], NotNormalized),
+            // Adjacent ranges [10, 20) [20, 30)
+            (&[2, 1, 10, 1, 10, 0, 1, 10], NotNormalized),
+        ];
+        for (bytes, err) in cases {
+            assert_eq!(Spore::from_bytes(bytes), Err(*err), "{:?}", bytes);
+        }
+
+        // Boundary past 2^256 - 1
+        let mut overflow = vec![1];
+        overflow.push(32);
+        overflow.extend_from_slice(&[0xff; 32]);
+        overflow.extend_from_slice(&[1, 1]);
+        assert_eq!(Spore::from_bytes(&overflow), Err(Overflow));
+
+        // A range built through its public fields can be inverted
+        let inverted = Range256 { start: U256::from_u64(20), stop: U256::from_u64(10) };
+        assert_eq!(inverted.to_bytes(), Err(InvalidRange));
+    }
+
+    #[test]
+    fn test_spore_serde_rejects_unnormalized_json() {
+        let spore = Spore::from_range(Range256::new(U256::from_u64(10), U256::from_u64(20)));
+        let json = serde_json::to_value(&spore).unwrap();
+        assert!(json.get("ranges").is_some());
+        assert_eq!(serde_json::from_value::<Spore>(json).unwrap(), spore);
+
+        let r = |a, b| Range256::new(U256::from_u64(a), U256::from_u64(b));
+        for ranges in [vec![r(20, 30), r(10, 15)], vec![r(10, 20), r(15, 30)], vec![r(10, 20), r(20, 30)], vec![r(5, 5)]] {
+            let json = serde_json::json!({ "ranges": ranges });
+            assert!(serde_json::from_value::<Spore>(json).is_err());
+        }
+    }
+
+    mod proptests {
+        use super::*;
+        use proptest::prelude::*;
+
+        fn u256() -> impl Strategy<Value = U256> {
+            prop_oneof![
+                any::<u8>().prop_map(|v| U256::from_u64(v as u64)),
+                any::<u64>().prop_map(U256::from_u64),
+                any::<[u64; 4]>().prop_map(|limbs| U256 { limbs }),
+                Just(U256::MAX),
+            ]
+        }
+
+        fn spore() -> impl Strategy<Value = Spore> {
+            prop::collection::vec((u256(), u256()), 0..16).prop_map(|pairs| {
+                Spore::from_ranges(
+                    pairs.into_iter().map(|(a, b)| Range256::new(min(a, b), max(a, b))).collect(),
+                )
+            })
+        }
+
+        proptest! {
+            #[test]
+            fn spore_roundtrip(spore in spore()) {
+                let bytes = spore.to_bytes();
+                prop_assert_eq!(Spore::from_bytes(&bytes).unwrap(), spore.clone());
+                prop_assert_eq!(Spore::from_normalized(spore.ranges().to_vec()).unwrap(), spore);
+            }
+
+            #[test]
+            fn spore_encoding_independent_of_construction(spore in spore()) {
+                // Rebuilding from the same ranges in reverse gives identical bytes
+                let mut ranges = spore.ranges().to_vec();
+                ranges.reverse();
+                prop_assert_eq!(Spore::from_ranges(ranges).to_bytes(), spore.to_bytes());
+            }
+
+            #[test]
+            fn range_roundtrip(a in u256(), b in u256()) {
+                let range = Range256::new(min(a, b), max(a, b));
+                prop_assert_eq!(Range256::from_bytes(&range.to_bytes().unwrap()).unwrap(), range);
+            }
+
+            #[test]
+            fn decode_is_canonical(bytes in prop::collection::vec(any::<u8>(), 0..96)) {
+                // Anything that decodes re-encodes to exactly the same bytes
+                if let Ok(spore) = Spore::from_bytes(&bytes) {
+                    prop_assert_eq!(spore.to_bytes(), bytes);
+                }
+            }
+
+            #[test]
+            fn decode_rejects_zero_delta(spore in spore(), pos in any::<prop::sample::Index>()) {
+                prop_assume!(spore.range_count() > 0);
+                // Rebuild the boundary deltas, zeroing one that must be positive
+                let mut bounds = vec![];
+                for range in spore.ranges() {
+                    bounds.push(range.start);
+                    bounds.push(range.stop);
+                }
+                let zeroed = 1 + pos.index(bounds.len() - 1);
+                let mut bytes = vec![];
+                put_varint(&mut bytes, spore.range_count() as u64);
+                let mut prev = U256::ZERO;
+                for (i, bound) in bounds.iter().enumerate() {
+                    let delta = if i == zeroed { U256::ZERO } else { bound.checked_sub(&prev).unwrap() };
+                    put_u256(&mut bytes, &delta);
+                    prev = *bound;
+                }
+                prop_assert!(Spore::from_bytes(&bytes).is_err());
+            }
+
+            #[test]
+            fn decode_rejects_padded_lengths(spore in spore(), pos in any::<prop::sample::Index>()) {
+                prop_assume!(spore.range_count() > 0);
+                let bytes = spore.to_bytes();
+                // Find the length bytes and pad one with a leading zero digit
+                let mut input = &bytes[..];
+                take_varint(&mut input).unwrap();
+                let mut offsets = vec![];
+                while !input.is_empty() {
+                    offsets.push(bytes.len() - input.len());
+                    take_u256(&mut input).unwrap();
+                }
+                let at = offsets[pos.index(offsets.len())];
+                prop_assume!(bytes[at] < 32);
+                let mut padded = bytes.clone();
+                padded[at] += 1;
+                padded.insert(at + 1, 0);
+                prop_assert!(Spore::from_bytes(&padded).is_err());
+            }
+        }
+    }
 }
@@ -0,0 +1,709 @@
+//! Congestion control for paced streams
+//!
+//! A [`CongestionController`] turns ACK timing and loss feedback into a
+//! congestion window and a pacing rate:
+//! - [`FixedRate`] paces at a configured rate and ignores feedback (the
+//!   original `target_mbps` behaviour)
+//! - [`Ledbat`] is a delay-based background mode (RFC 6817): it backs off as
+//!   soon as queuing delay builds up, so bulk replication yields to
+//!   interactive mesh traffic sharing the bottleneck
+//! - [`Bbr`] is a throughput mode modelled on BBR: it paces at the estimated
+//!   bottleneck bandwidth and keeps about one bandwidth-delay product in flight
+//!
+//! [`Congestion`] wraps a controller with the per-stream bookkeeping (bytes in
+//! flight, RTT estimate, pacing schedule) shared by the continuous and
+//! reliable senders.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::reliable::RttEstimator;
+
+/// Rate fixed-rate senders pace at unless configured otherwise, in Mbps
+pub const DEFAULT_TARGET_MBPS: u32 = 100;
+
+/// Initial congestion window, in packets
+pub const INITIAL_CWND_PACKETS: usize = 10;
+
+/// Smallest congestion window, in packets
+pub const MIN_CWND_PACKETS: usize = 2;
+
+/// Round-trip time assumed before the first sample
+pub const INITIAL_RTT: Duration = Duration::from_millis(100);
+
+/// How far pacing may fall behind before the backlog is forgiven
+///
+/// Timers are coarse, so a sender woken late may catch up by this much in a burst.
+pub const PACING_BURST: Duration = Duration::from_millis(1);
+
+/// Queuing delay LEDBAT aims for
+///
+/// Lower than the RFC's 100 ms ceiling so interactive traffic sharing the
+/// bottleneck sees little added latency.
+pub const LEDBAT_TARGET: Duration = Duration::from_millis(25);
+
+const LEDBAT_GAIN: f64 = 1.0;
+const LEDBAT_ALLOWED_INCREASE: usize = 2;
+const LEDBAT_BASE_WINDOW: Duration = Duration::from_secs(600);
+const LEDBAT_PACING_HEADROOM: f64 = 1.25;
+
+const BBR_HIGH_GAIN: f64 = 2.885;
+const BBR_CWND_GAIN: f64 = 2.0;
+const BBR_PROBE_GAINS: [f64; 8] = [1.25, 0.75, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0];
+const BBR_BW_WINDOW_ROUNDS: u32 = 10;
+const BBR_MAX_SAMPLE_ROUNDS: u32 = 4;
+const BBR_FULL_BW_ROUNDS: u32 = 3;
+const BBR_MIN_RTT_WINDOW: Duration = Duration::from_secs(10);
+const BBR_PROBE_RTT_DURATION: Duration = Duration::from_millis(200);
+const BBR_PROBE_RTT_CWND_PACKETS: usize = 4;
+
+/// Feedback from one acknowledgment
+#[derive(Debug, Clone, Copy)]
+pub struct AckSample {
+    /// Bytes newly acknowledged
+    pub bytes: usize,
+    /// Round-trip sample, if the ACK gave an unambiguous one
+    pub rtt: Option<Duration>,
+    /// Smoothed round-trip time after this sample
+    pub srtt: Option<Duration>,
+    /// Bytes still in flight after this ACK
+    pub in_flight: usize,
+}
+
+/// A congestion control algorithm
+pub trait CongestionController: Send {
+    /// Short name for stats and logs
+    fn name(&self) -> &'static str;
+
+    /// Bytes were acknowledged
+    fn on_ack(&mut self, sample: &AckSample, now: Instant);
+
+    /// Bytes were declared lost
+    fn on_loss(&mut self, bytes: usize, now: Instant);
+
+    /// Congestion window in bytes
+    fn cwnd(&self) -> usize;
+
+    /// Pacing rate in bytes per second
+    fn pacing_rate(&self) -> u64;
+}
+
+/// Which controller a stream uses
+///
+/// Fixed-rate is the default. The adaptive modes size their window from ACK
+/// feedback, so they only suit peers that return it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CongestionMode {
+    /// Pace at `target_mbps` regardless of feedback
+    #[default]
+    Fixed,
+    /// Yield to other traffic (LEDBAT); for background replication
+    Background,
+    /// Fill the available bandwidth (BBR-like)
+    Throughput,
+}
+
+impl CongestionMode {
+    /// Build the controller for this mode
+    pub fn controller(self, target_mbps: u32, mss: usize) -> Box<dyn CongestionController> {
+        match self {
+            Self::Fixed => Box::new(FixedRate::from_mbps(target_mbps)),
+            Self::Background => Box::new(Ledbat::new(mss)),
+            Self::Throughput => Box::new(Bbr::new(mss)),
+        }
+    }
+}
+
+/// Per-stream congestion statistics
+#[derive(Debug, Clone)]
+pub struct CongestionStats {
+    /// Controller name
+    pub controller: &'static str,
+    /// Congestion window in bytes
+    pub cwnd: usize,
+    /// Pacing rate in bytes per second
+    pub pacing_rate: u64,
+    /// Smoothed round-trip time
+    pub srtt: Option<Duration>,
+    /// Lowest round-trip time seen
+    pub min_rtt: Option<Duration>,
+    /// Bytes sent but not yet acknowledged or lost
+    pub bytes_in_flight: usize,
+    /// Total bytes acknowledged
+    pub bytes_acked: u64,
+    /// Packets declared lost
+    pub packets_lost: u64,
+}
+
+/// A controller plus the sender-side state it is fed from
+pub struct Congestion {
+    controller: Box<dyn CongestionController>,
+    rtt: RttEstimator,
+    min_rtt: Option<Duration>,
+    bytes_in_flight: usize,
+    bytes_acked: u64,
+    packets_lost: u64,
+    next_send: Option<Instant>,
+}
+
+impl Congestion {
+    /// Wrap a controller
+    pub fn new(controller: Box<dyn CongestionController>) -> Self {
+        Self {
+            controller,
+            rtt: RttEstimator::new(),
+            min_rtt: None,
+            bytes_in_flight: 0,
+            bytes_acked: 0,
+            packets_lost: 0,
+            next_send: None,
+        }
+    }
+
+    /// Whether the congestion window has room for another packet
+    pub fn can_send(&self) -> bool {
+        self.bytes_in_flight == 0 || self.bytes_in_flight < self.controller.cwnd()
+    }
+
+    /// Earliest time the next packet may go out under pacing
+    pub fn next_send_time(&self) -> Option<Instant> {
+        self.next_send
+    }
+
+    /// Whether pacing allows a packet now
+    pub fn ready(&self, now: Instant) -> bool {
+        self.next_send.is_none_or(|at| at <= now)
+    }
+
+    /// Record a packet sent, counting it in flight
+    pub fn on_sent(&mut self, bytes: usize, now: Instant) {
+        self.bytes_in_flight += bytes;
+        self.pace(bytes, now);
+    }
+
+    /// Advance the pacing schedule without tracking the packet
+    ///
+    /// For senders without ACK feedback.
+    pub fn pace(&mut self, bytes: usize, now: Instant) {
+        let rate = self.controller.pacing_rate().max(1) as u128;
+        let nanos = (bytes as u128 * 1_000_000_000 / rate).min(u64::MAX as u128) as u64;
+        let earliest = now.checked_sub(PACING_BURST).unwrap_or(now);
+        let start = self.next_send.map_or(now, |at| at.max(earliest));
+        self.next_send = Some(start + Duration::from_nanos(nanos));
+    }
+
+    /// Record acknowledged bytes and an optional RTT sample
+    pub fn on_ack(&mut self, bytes: usize, rtt: Option<Duration>, now: Instant) {
+        self.bytes_in_flight = self.bytes_in_flight.saturating_sub(bytes);
+        self.bytes_acked += bytes as u64;
+        if bytes > 0 {
+            self.rtt.reset_backoff();
+        }
+        if let Some(rtt) = rtt {
+            self.rtt.on_sample(rtt);
+            self.min_rtt = Some(self.min_rtt.map_or(rtt, |min| min.min(rtt)));
+        }
+        let sample = AckSample {
+            bytes,
+            rtt,
+            srtt: self.rtt.srtt(),
+            in_flight: self.bytes_in_flight,
+        };
+        self.controller.on_ack(&sample, now);
+    }
+
+    /// Record a lost packet
+    pub fn on_loss(&mut self, bytes: usize, now: Instant) {
+        self.bytes_in_flight = self.bytes_in_flight.saturating_sub(bytes);
+        self.packets_lost += 1;
+        self.controller.on_loss(bytes, now);
+    }
+
+    /// Back off the retransmission timeout after it expired
+    pub fn on_timeout(&mut self) {
+        self.rtt.backoff();
+    }
+
+    /// Round-trip estimate
+    pub fn rtt(&self) -> &RttEstimator {
+        &self.rtt
+    }
+
+    /// Current statistics
+    pub fn stats(&self) -> CongestionStats {
+        CongestionStats {
+            controller: self.controller.name(),
+            cwnd: self.controller.cwnd(),
+            pacing_rate: self.controller.pacing_rate(),
+            srtt: self.rtt.srtt(),
+            min_rtt: self.min_rtt,
+            bytes_in_flight: self.bytes_in_flight,
+            bytes_acked: self.bytes_acked,
+            packets_lost: self.packets_lost,
+        }
+    }
+}
+
+/// Sliding-window minimum or maximum
+#[derive(Debug, Clone)]
+struct WindowedFilter<T> {
+    window: Duration,
+    keep_max: bool,
+    samples: VecDeque<(Instant, T)>,
+}
+
+impl<T: PartialOrd + Copy> WindowedFilter<T> {
+    fn new(window: Duration, keep_max: bool) -> Self {
+        Self {
+            window,
+            keep_max,
+            samples: VecDeque::new(),
+        }
+    }
+
+    fn update(&mut self, value: T, now: Instant) {
+        while let Some(&(_, last)) = self.samples.back() {
+            let dominated = if self.keep_max { last <= value } else { last >= value };
+            if !dominated {
+                break;
+            }
+            self.samples.pop_back();
+        }
+        self.samples.push_back((now, value));
+        while self
+            .samples
+            .front()
+            .is_some_and(|(at, _)| now.saturating_duration_since(*at) > self.window)
+        {
+            self.samples.pop_front();
+        }
+    }
+
+    fn get(&self) -> Option<T> {
+        self.samples.front().map(|(_, value)| *value)
+    }
+}
+
+/// Constant-rate pacing with no congestion window
+#[derive(Debug, Clone)]
+pub struct FixedRate {
+    rate: u64,
+}
+
+impl FixedRate {
+    /// Pace at `mbps` megabits per second
+    pub fn from_mbps(mbps: u32) -> Self {
+        Self {
+            rate: mbps as u64 * 1_000_000 / 8,
+        }
+    }
+}
+
+impl CongestionController for FixedRate {
+    fn name(&self) -> &'static str {
+        "fixed"
+    }
+
+    fn on_ack(&mut self, _sample: &AckSample, _now: Instant) {}
+
+    fn on_loss(&mut self, _bytes: usize, _now: Instant) {}
+
+    fn cwnd(&self) -> usize {
+        usize::MAX
+    }
+
+    fn pacing_rate(&self) -> u64 {
+        self.rate
+    }
+}
+
+/// LEDBAT: low extra delay background transport (RFC 6817)
+///
+/// Grows the window while queuing delay (RTT above the lowest seen) is under
+/// the target and shrinks it in proportion as delay exceeds it, so competing
+/// traffic that builds a queue makes it back off before anything is dropped.
+#[derive(Debug, Clone)]
+pub struct Ledbat {
+    mss: usize,
+    cwnd: f64,
+    target: Duration,
+    base_delay: WindowedFilter<Duration>,
+    srtt: Option<Duration>,
+    last_reduction: Option<Instant>,
+}
+
+impl Ledbat {
+    /// Create a controller with the default target delay
+    pub fn new(mss: usize) -> Self {
+        Self::with_target(mss, LEDBAT_TARGET)
+    }
+
+    /// Create a controller aiming for `target` queuing delay
+    pub fn with_target(mss: usize, target: Duration) -> Self {
+        Self {
+            mss,
+            cwnd: (INITIAL_CWND_PACKETS * mss) as f64,
+            target,
+            base_delay: WindowedFilter::new(LEDBAT_BASE_WINDOW, false),
+            srtt: None,
+            last_reduction: None,
+        }
+    }
+
+    fn min_cwnd(&self) -> f64 {
+        (MIN_CWND_PACKETS * self.mss) as f64
+    }
+}
+
+impl CongestionController for Ledbat {
+    fn name(&self) -> &'static str {
+        "ledbat"
+    }
+
+    fn on_ack(&mut self, sample: &AckSample, now: Instant) {
+        self.srtt = sample.srtt.or(self.srtt);
+        let Some(rtt) = sample.rtt else {
+            return;
+        };
+        self.base_delay.update(rtt, now);
+        let base = self.base_delay.get().unwrap_or(rtt);
+        let queuing = rtt.saturating_sub(base);
+        let off_target = (1.0 - queuing.as_secs_f64() / self.target.as_secs_f64()).max(-1.0);
+        self.cwnd += LEDBAT_GAIN * off_target * sample.bytes as f64 * self.mss as f64 / self.cwnd;
+
+        // Don't let the window run ahead of what the sender actually uses
+        let allowed = sample.in_flight + sample.bytes + LEDBAT_ALLOWED_INCREASE * self.mss;
+        self.cwnd = self.cwnd.min(allowed as f64).max(self.min_cwnd());
+    }
+
+    fn on_loss(&mut self, _bytes: usize, now: Instant) {
+        // Halve at most once per round trip
+        let rtt = self.srtt.unwrap_or(INITIAL_RTT);
+        if self
+            .last_reduction
+            .is_some_and(|at| now.saturating_duration_since(at) < rtt)
+        {
+            return;
+        }
+        self.last_reduction = Some(now);
+        self.cwnd = (self.cwnd / 2.0).max(self.min_cwnd());
+    }
+
+    fn cwnd(&self) -> usize {
+        self.cwnd as usize
+    }
+
+    fn pacing_rate(&self) -> u64 {
+        let rtt = self.srtt.unwrap_or(INITIAL_RTT);
+        (LEDBAT_PACING_HEADROOM * self.cwnd / rtt.as_secs_f64()) as u64
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BbrState {
+    Startup,
+    Drain,
+    ProbeBw { phase: usize },
+    ProbeRtt { until: Instant },
+}
+
+/// BBR-style model-based controller
+///
+/// Estimates the bottleneck bandwidth (windowed max of the delivery rate) and
+/// the propagation delay (windowed min RTT), paces at the bandwidth estimate
+/// times a gain that cycles to probe for more, and caps in-flight data at a
+/// multiple of the bandwidth-delay product. Loss alone is not treated as a
+/// congestion signal.
+#[derive(Debug, Clone)]
+pub struct Bbr {
+    mss: usize,
+    state: BbrState,
+    btl_bw: WindowedFilter<u64>,
+    min_rtt: Option<Duration>,
+    min_rtt_stamp: Option<Instant>,
+    interval_start: Option<Instant>,
+    interval_bytes: u64,
+    full_bw: u64,
+    full_bw_rounds: u32,
+}
+
+impl Bbr {
+    /// Create a controller in startup
+    pub fn new(mss: usize) -> Self {
+        Self {
+            mss,
+            state: BbrState::Startup,
+            btl_bw: WindowedFilter::new(INITIAL_RTT * BBR_BW_WINDOW_ROUNDS, true),
+            min_rtt: None,
+            min_rtt_stamp: None,
+            interval_start: None,
+            interval_bytes: 0,
+            full_bw: 0,
+            full_bw_rounds: 0,
+        }
+    }
+
+    /// Bottleneck bandwidth estimate in bytes per second
+    pub fn bandwidth(&self) -> Option<u64> {
+        self.btl_bw.get()
+    }
+
+    fn bdp(&self) -> Option<f64> {
+        Some(self.btl_bw.get()? as f64 * self.min_rtt?.as_secs_f64())
+    }
+
+    fn gains(&self) -> (f64, f64) {
+        match self.state {
+            BbrState::Startup => (BBR_HIGH_GAIN, BBR_HIGH_GAIN),
+            BbrState::Drain => (1.0 / BBR_HIGH_GAIN, BBR_HIGH_GAIN),
+            BbrState::ProbeBw { phase } => (BBR_PROBE_GAINS[phase], BBR_CWND_GAIN),
+            BbrState::ProbeRtt { .. } => (1.0, 1.0),
+        }
+    }
+
+    /// Advance the state machine once per round trip
+    fn on_round(&mut self, in_flight: usize, now: Instant) {
+        let bw = self.btl_bw.get().unwrap_or(0);
+        let floor = (BBR_PROBE_RTT_CWND_PACKETS * self.mss) as f64;
+        self.state = match self.state {
+            BbrState::Startup => {
+                if bw as f64 >= self.full_bw as f64 * 1.25 {
+                    self.full_bw = bw;
+                    self.full_bw_rounds = 0;
+                } else {
+                    self.full_bw_rounds += 1;
+                }
+                if self.full_bw_rounds >= BBR_FULL_BW_ROUNDS {
+                    BbrState::Drain
+                } else {
+                    BbrState::Startup
+                }
+            }
+            BbrState::Drain if in_flight as f64 <= self.bdp().unwrap_or(0.0).max(floor) => {
+                BbrState::ProbeBw { phase: 2 }
+            }
+            BbrState::Drain => BbrState::Drain,
+            BbrState::ProbeBw { phase } => BbrState::ProbeBw {
+                phase: (phase + 1) % BBR_PROBE_GAINS.len(),
+            },
+            BbrState::ProbeRtt { until } if now >= until => {
+                self.min_rtt_stamp = Some(now);
+                BbrState::ProbeBw { phase: 2 }
+            }
+            state @ BbrState::ProbeRtt { .. } => state,
+        };
+
+        // Drain the queue briefly if the min RTT hasn't been refreshed lately
+        let stale = self
+            .min_rtt_stamp
+            .is_some_and(|at| now.saturating_duration_since(at) > BBR_MIN_RTT_WINDOW);
+        if stale && matches!(self.state, BbrState::ProbeBw { .. }) {
+            self.state = BbrState::ProbeRtt {
+                until: now + BBR_PROBE_RTT_DURATION,
+            };
+        }
+    }
+}
+
+impl CongestionController for Bbr {
+    fn name(&self) -> &'static str {
+        "bbr"
+    }
+
+    fn on_ack(&mut self, sample: &AckSample, now: Instant) {
+        if let Some(rtt) = sample.rtt {
+            let expired = self
+                .min_rtt_stamp
+                .is_some_and(|at| now.saturating_duration_since(at) > BBR_MIN_RTT_WINDOW);
+            if expired || self.min_rtt.is_none_or(|min| rtt <= min) {
+                self.min_rtt = Some(rtt);
+                self.min_rtt_stamp = Some(now);
+            }
+        }
+
+        // Delivery rate measured over roughly one round trip
+        self.interval_bytes += sample.bytes as u64;
+        let start = *self.interval_start.get_or_insert(now);
+        let round = self.min_rtt.unwrap_or(INITIAL_RTT).max(Duration::from_millis(1));
+        let elapsed = now.saturating_duration_since(start);
+        if elapsed > round * BBR_MAX_SAMPLE_ROUNDS {
+            // The sender stalled (idle, or waiting out a timeout): the rate
+            // over this interval says nothing about the bottleneck
+            self.interval_start = Some(now);
+            self.interval_bytes = 0;
+        } else if elapsed >= round {
+            let rate = (self.interval_bytes as u128 * 1_000_000_000 / elapsed.as_nanos()) as u64;
+            self.btl_bw.window = round * BBR_BW_WINDOW_ROUNDS;
+            self.btl_bw.update(rate, now);
+            self.interval_start = Some(now);
+            self.interval_bytes = 0;
+            self.on_round(sample.in_flight, now);
+        }
+    }
+
+    fn on_loss(&mut self, _bytes: usize, _now: Instant) {}
+
+    fn cwnd(&self) -> usize {
+        let floor = BBR_PROBE_RTT_CWND_PACKETS * self.mss;
+        if matches!(self.state, BbrState::ProbeRtt { .. }) {
+            return floor;
+        }
+        let (_, cwnd_gain) = self.gains();
+        match self.bdp() {
+            Some(bdp) => ((cwnd_gain * bdp) as usize).max(floor),
+            None => INITIAL_CWND_PACKETS * self.mss,
+        }
+    }
+
+    fn pacing_rate(&self) -> u64 {
+        let (pacing_gain, _) = self.gains();
+        let bw = match self.btl_bw.get() {
+            Some(bw) => bw as f64,
+            None => {
+                let rtt = self.min_rtt.unwrap_or(INITIAL_RTT).max(Duration::from_millis(1));
+                (INITIAL_CWND_PACKETS * self.mss) as f64 / rtt.as_secs_f64()
+            }
+        };
+        (pacing_gain * bw) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cmp::Reverse;
+    use std::collections::BinaryHeap;
+
+    const MSS: usize = 1200;
+
+    /// Fluid model of one bottleneck link shared by several flows
+    ///
+    /// Returns the bytes each flow delivered and the worst queuing delay seen.
+    fn simulate(flows: &mut [Congestion], bandwidth: u64, base_rtt: Duration, duration: Duration) -> (Vec<u64>, Duration) {
+        let step = Duration::from_micros(100);
+        let max_queue = Duration::from_millis(200);
+        let start = Instant::now();
+        let mut link_free = start;
+        let mut events = BinaryHeap::new();
+        let mut delivered = vec![0; flows.len()];
+        let mut worst_queue = Duration::ZERO;
+
+        let mut now = start;
+        while now < start + duration {
+            while let Some(Reverse((at, flow, sent_at, lost))) = events.peek().copied() {
+                if at > now {
+                    break;
+                }
+                events.pop();
+                let cc: &mut Congestion = &mut flows[flow];
+                if lost {
+                    cc.on_loss(MSS, at);
+                } else {
+                    cc.on_ack(MSS, Some(at - sent_at), at);
+                    delivered[flow] += MSS as u64;
+                }
+            }
+
+            for (flow, cc) in flows.iter_mut().enumerate() {
+                while cc.can_send() && cc.ready(now) {
+                    cc.on_sent(MSS, now);
+                    let queue = link_free.saturating_duration_since(now);
+                    worst_queue = worst_queue.max(queue);
+                    if queue > max_queue {
+                        events.push(Reverse((now + base_rtt, flow, now, true)));
+                        continue;
+                    }
+                    link_free = link_free.max(now) + Duration::from_nanos(MSS as u64 * 1_000_000_000 / bandwidth);
+                    events.push(Reverse((link_free + base_rtt, flow, now, false)));
+                }
+            }
+            now += step;
+        }
+        (delivered, worst_queue)
+    }
+
+    #[test]
+    fn test_fixed_rate_pacing() {
+        let mut cc = Congestion::new(CongestionMode::Fixed.controller(96, MSS));
+        assert_eq!(cc.stats().pacing_rate, 12_000_000);
+
+        let now = Instant::now();
+        assert!(cc.ready(now));
+        cc.pace(MSS, now);
+        cc.pace(MSS, now);
+        // 1200 bytes at 12 MB/s is 100 us per packet
+        assert_eq!(cc.next_send_time(), Some(now + Duration::from_micros(200)));
+        assert!(!cc.ready(now + Duration::from_micros(150)));
+        assert!(cc.can_send());
+    }
+
+    #[test]
+    fn test_ledbat_reacts_to_queuing_delay() {
+        let mut ledbat = Ledbat::new(MSS);
+        let now = Instant::now();
+        let ack = |rtt: u64, in_flight: usize| AckSample {
+            bytes: MSS,
+            rtt: Some(Duration::from_millis(rtt)),
+            srtt: Some(Duration::from_millis(rtt)),
+            in_flight,
+        };
+
+        // No queuing: grows while the sender keeps the window full
+        for _ in 0..100 {
+            let cwnd = ledbat.cwnd();
+            ledbat.on_ack(&ack(20, cwnd), now);
+        }
+        let grown = ledbat.cwnd();
+        assert!(grown > INITIAL_CWND_PACKETS * MSS);
+
+        // Queuing delay well past the target: shrinks to the floor
+        for _ in 0..1_000 {
+            ledbat.on_ack(&ack(100, grown), now);
+        }
+        assert_eq!(ledbat.cwnd(), MIN_CWND_PACKETS * MSS);
+
+        // Loss halves at most once per round trip
+        let mut ledbat = Ledbat::new(MSS);
+        ledbat.on_loss(MSS, now);
+        ledbat.on_loss(MSS, now + Duration::from_millis(1));
+        assert_eq!(ledbat.cwnd(), INITIAL_CWND_PACKETS * MSS / 2);
+    }
+
+    #[test]
+    fn test_bbr_finds_bottleneck_bandwidth() {
+        let bandwidth = 10_000_000;
+        let base_rtt = Duration::from_millis(20);
+        let mut flows = [Congestion::new(Box::new(Bbr::new(MSS)))];
+        let duration = Duration::from_secs(5);
+        let (delivered, _) = simulate(&mut flows, bandwidth, base_rtt, duration);
+
+        let stats = flows[0].stats();
+        let throughput = delivered[0] as f64 / duration.as_secs_f64();
+        assert!(throughput > 0.8 * bandwidth as f64, "throughput {throughput}");
+        assert!(stats.min_rtt.unwrap() >= base_rtt);
+        assert!(stats.srtt.unwrap() < base_rtt * 3, "standing queue: {:?}", stats.srtt);
+    }
+
+    #[test]
+    fn test_background_yields_to_interactive_traffic() {
+        let bandwidth: u64 = 10_000_000;
+        let duration = Duration::from_secs(10);
+        // Interactive traffic at 60% of the link, background replication beside it
+        let mut flows = [
+            Congestion::new(Box::new(FixedRate::from_mbps(48))),
+            Congestion::new(CongestionMode::Background.controller(0, MSS)),
+        ];
+        let (delivered, worst_queue) = simulate(&mut flows, bandwidth, Duration::from_millis(20), duration);
+
+        let secs = duration.as_secs_f64();
+        let interactive = delivered[0] as f64 / secs;
+        let background = delivered[1] as f64 / secs;
+        assert!(interactive > 0.97 * 6_000_000.0, "interactive starved: {interactive}");
+        assert!(background > 1_000_000.0, "background idle: {background}");
+        assert!(background < 4_500_000.0);
+        assert!(worst_queue < Duration::from_millis(100), "queue {worst_queue:?}");
+        assert_eq!(flows[0].stats().packets_lost, 0);
+    }
+}
@@ -0,0 +1,688 @@
+//! Authenticated encryption for UDP streams
+//!
+//! Peers authenticate with their ed25519 node identities, converted to X25519
+//! for Diffie-Hellman, in a Noise-IK-style handshake: the initiator already
+//! knows the responder's identity and both sides prove theirs in one round
+//! trip.
+//!
+//! ```text
+//!   <- s
+//!   ...
+//!   -> e, es, s, ss, {timestamp}
+//!   <- e, ee, se, {}
+//! ```
+//!
+//! [`TgpHandle`](crate::TgpHandle) runs the handshake over
+//! `MsgKind::Handshake` packets (see `TgpHandle::with_identity`). It uses
+//! BLAKE3 as the hash and KDF and ChaCha20-Poly1305 as the AEAD, and leaves a
+//! [`Session`] with one root key per direction. From those, every stream gets
+//! its own key per message kind and epoch, so moving a stream to a new epoch
+//! rekeys it. The sequence number is the nonce, and data and ACKs are
+//! numbered separately, hence the key per kind. Packet bodies are sealed with
+//! the encoded header as associated data, and a sliding window over `SeqNo`
+//! rejects replays.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bytes::{Bytes, BytesMut};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use curve25519_dalek::montgomery::MontgomeryPoint;
+use ed25519_dalek::{SigningKey, VerifyingKey};
+use rand::rngs::OsRng;
+use rand::RngCore;
+
+use crate::types::{Epoch, MsgKind, Packet, PacketHeader, SeqNo, StreamId, HEADER_LEN};
+
+/// Keys and replay windows are kept per stream and message kind
+type Channel = (StreamId, u8);
+
+/// Handshake protocol name, hashed into the transcript
+const PROTOCOL_NAME: &[u8] = b"Noise_IK_25519_ChaChaPoly_BLAKE3";
+
+/// Binds the handshake to this protocol
+const PROLOGUE: &[u8] = b"citadel-transfer/1";
+
+/// KDF context for per-stream keys
+const STREAM_KEY_CONTEXT: &str = "citadel-transfer 2024 stream key v1";
+
+/// AEAD authentication tag size
+pub const TAG_LEN: usize = 16;
+
+/// Size of the initiator's handshake message
+pub const INITIATION_LEN: usize = 32 + (32 + TAG_LEN) + (8 + TAG_LEN);
+
+/// Size of the responder's handshake message
+pub const RESPONSE_LEN: usize = 32 + TAG_LEN;
+
+/// How far behind the highest sequence number a packet may still arrive
+pub const REPLAY_WINDOW: u64 = 1024;
+
+/// Why a handshake failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HandshakeError {
+    /// Message has the wrong length
+    Malformed,
+    /// Authentication failed: wrong keys or tampered message
+    Decrypt,
+    /// The peer's identity is not a valid ed25519 key
+    BadIdentity,
+    /// Diffie-Hellman produced the all-zero output (low-order point)
+    LowOrderPoint,
+    /// An initiation not newer than the last one accepted from this peer
+    Replay,
+}
+
+impl fmt::Display for HandshakeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Malformed => write!(f, "malformed handshake message"),
+            Self::Decrypt => write!(f, "handshake authentication failed"),
+            Self::BadIdentity => write!(f, "invalid peer identity"),
+            Self::LowOrderPoint => write!(f, "low-order public key"),
+            Self::Replay => write!(f, "replayed handshake initiation"),
+        }
+    }
+}
+
+impl std::error::Error for HandshakeError {}
+
+/// Why a sealed packet was rejected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OpenError {
+    /// Body shorter than the authentication tag
+    Malformed,
+    /// Authentication failed: forged, tampered or under another key
+    Decrypt,
+    /// Sequence number already seen or behind the replay window
+    Replay,
+    /// Epoch older than the ones still accepted for the stream
+    StaleEpoch,
+}
+
+impl fmt::Display for OpenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Malformed => write!(f, "sealed body too short"),
+            Self::Decrypt => write!(f, "packet authentication failed"),
+            Self::Replay => write!(f, "replayed packet"),
+            Self::StaleEpoch => write!(f, "packet from a stale epoch"),
+        }
+    }
+}
+
+impl std::error::Error for OpenError {}
+
+/// Noise HKDF with keyed BLAKE3 in place of HMAC
+fn hkdf(ck: &[u8; 32], ikm: &[u8]) -> ([u8; 32], [u8; 32]) {
+    let temp = blake3::keyed_hash(ck, ikm);
+    let out1 = blake3::keyed_hash(temp.as_bytes(), &[1]);
+    let mut input = [0u8; 33];
+    input[..32].copy_from_slice(out1.as_bytes());
+    input[32] = 2;
+    let out2 = blake3::keyed_hash(temp.as_bytes(), &input);
+    (*out1.as_bytes(), *out2.as_bytes())
+}
+
+fn dh(secret: &[u8; 32], public: &MontgomeryPoint) -> Result<[u8; 32], HandshakeError> {
+    let shared = public.mul_clamped(*secret).to_bytes();
+    if shared == [0; 32] {
+        return Err(HandshakeError::LowOrderPoint);
+    }
+    Ok(shared)
+}
+
+fn nonce(counter: u64) -> Nonce {
+    let mut nonce = [0u8; 12];
+    nonce[4..].copy_from_slice(&counter.to_be_bytes());
+    nonce.into()
+}
+
+/// Handshake transcript hash, chaining key and current cipher key
+#[derive(Clone)]
+struct SymmetricState {
+    ck: [u8; 32],
+    h: [u8; 32],
+    k: Option<[u8; 32]>,
+    n: u64,
+}
+
+impl SymmetricState {
+    fn new() -> Self {
+        let h = *blake3::hash(PROTOCOL_NAME).as_bytes();
+        let mut state = Self { ck: h, h, k: None, n: 0 };
+        state.mix_hash(PROLOGUE);
+        state
+    }
+
+    fn mix_hash(&mut self, data: &[u8]) {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&self.h);
+        hasher.update(data);
+        self.h = *hasher.finalize().as_bytes();
+    }
+
+    fn mix_key(&mut self, ikm: &[u8; 32]) {
+        let (ck, k) = hkdf(&self.ck, ikm);
+        self.ck = ck;
+        self.k = Some(k);
+        self.n = 0;
+    }
+
+    fn encrypt_and_hash(&mut self, plaintext: &[u8]) -> Vec<u8> {
+        let ciphertext = match &self.k {
+            Some(k) => ChaCha20Poly1305::new(Key::from_slice(k))
+                .encrypt(&nonce(self.n), Payload { msg: plaintext, aad: &self.h })
+                .expect("handshake payloads are small"),
+            None => plaintext.to_vec(),
+        };
+        self.n += 1;
+        self.mix_hash(&ciphertext);
+        ciphertext
+    }
+
+    fn decrypt_and_hash(&mut self, ciphertext: &[u8]) -> Result<Vec<u8>, HandshakeError> {
+        let plaintext = match &self.k {
+            Some(k) => ChaCha20Poly1305::new(Key::from_slice(k))
+                .decrypt(&nonce(self.n), Payload { msg: ciphertext, aad: &self.h })
+                .map_err(|_| HandshakeError::Decrypt)?,
+            None => ciphertext.to_vec(),
+        };
+        self.n += 1;
+        self.mix_hash(ciphertext);
+        Ok(plaintext)
+    }
+
+    fn split(&self) -> ([u8; 32], [u8; 32]) {
+        hkdf(&self.ck, &[])
+    }
+}
+
+fn ephemeral() -> ([u8; 32], MontgomeryPoint) {
+    let mut secret = [0u8; 32];
+    OsRng.fill_bytes(&mut secret);
+    (secret, MontgomeryPoint::mul_base_clamped(secret))
+}
+
+fn timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default()
+}
+
+/// Initiating side of a handshake, waiting for the response
+pub struct Initiator {
+    state: SymmetricState,
+    ephemeral: [u8; 32],
+    static_secret: [u8; 32],
+    remote: VerifyingKey,
+}
+
+impl Initiator {
+    /// Begin a handshake with the node whose identity is `remote`, returning
+    /// the initiation message to send it
+    pub fn start(local: &SigningKey, remote: &VerifyingKey) -> Result<(Self, Vec<u8>), HandshakeError> {
+        let static_secret = local.to_scalar_bytes();
+        let remote_static = remote.to_montgomery();
+        let mut state = SymmetricState::new();
+        state.mix_hash(remote_static.as_bytes());
+
+        let (ephemeral, ephemeral_pub) = ephemeral();
+        let mut msg = Vec::with_capacity(INITIATION_LEN);
+        msg.extend_from_slice(ephemeral_pub.as_bytes());
+        state.mix_hash(ephemeral_pub.as_bytes());
+        state.mix_key(&dh(&ephemeral, &remote_static)?);
+        msg.extend(state.encrypt_and_hash(local.verifying_key().as_bytes()));
+        state.mix_key(&dh(&static_secret, &remote_static)?);
+        msg.extend(state.encrypt_and_hash(&timestamp().to_be_bytes()));
+
+        let initiator = Self {
+            state,
+            ephemeral,
+            static_secret,
+            remote: *remote,
+        };
+        Ok((initiator, msg))
+    }
+
+    /// Complete the handshake with the responder's reply
+    ///
+    /// The initiator is left as it was, so a forged or corrupt reply doesn't
+    /// stop the genuine one from completing the handshake.
+    pub fn finish(&self, msg: &[u8]) -> Result<Session, HandshakeError> {
+        if msg.len() != RESPONSE_LEN {
+            return Err(HandshakeError::Malformed);
+        }
+        let mut state = self.state.clone();
+        let remote_ephemeral = MontgomeryPoint(msg[..32].try_into().expect("length checked"));
+        state.mix_hash(remote_ephemeral.as_bytes());
+        state.mix_key(&dh(&self.ephemeral, &remote_ephemeral)?);
+        state.mix_key(&dh(&self.static_secret, &remote_ephemeral)?);
+        state.decrypt_and_hash(&msg[32..])?;
+
+        let (send, recv) = state.split();
+        Ok(Session::new(self.remote, send, recv))
+    }
+}
+
+/// Responding side: accepts initiations addressed to the local identity
+pub struct Responder {
+    local: SigningKey,
+    /// Newest initiation timestamp accepted per peer, against replayed initiations
+    latest: HashMap<[u8; 32], u64>,
+}
+
+impl Responder {
+    /// Accept handshakes for `local`
+    pub fn new(local: SigningKey) -> Self {
+        Self {
+            local,
+            latest: HashMap::new(),
+        }
+    }
+
+    /// Process an initiation, returning the session and the reply to send
+    ///
+    /// Any identity that completes the handshake is accepted; check
+    /// [`Session::peer`] against the nodes you expect.
+    pub fn respond(&mut self, msg: &[u8]) -> Result<(Session, Vec<u8>), HandshakeError> {
+        if msg.len() != INITIATION_LEN {
+            return Err(HandshakeError::Malformed);
+        }
+        let static_secret = self.local.to_scalar_bytes();
+        let mut state = SymmetricState::new();
+        state.mix_hash(self.local.verifying_key().to_montgomery().as_bytes());
+
+        let remote_ephemeral = MontgomeryPoint(msg[..32].try_into().expect("length checked"));
+        state.mix_hash(remote_ephemeral.as_bytes());
+        state.mix_key(&dh(&static_secret, &remote_ephemeral)?);
+        let identity: [u8; 32] = state
+            .decrypt_and_hash(&msg[32..80])?
+            .try_into()
+            .map_err(|_| HandshakeError::Malformed)?;
+        let peer = VerifyingKey::from_bytes(&identity).map_err(|_| HandshakeError::BadIdentity)?;
+        state.mix_key(&dh(&static_secret, &peer.to_montgomery())?);
+        let sent_at: [u8; 8] = state
+            .decrypt_and_hash(&msg[80..])?
+            .try_into()
+            .map_err(|_| HandshakeError::Malformed)?;
+        let sent_at = u64::from_be_bytes(sent_at);
+        if self.latest.get(&identity).is_some_and(|latest| sent_at <= *latest) {
+            return Err(HandshakeError::Replay);
+        }
+
+        let (ephemeral, ephemeral_pub) = ephemeral();
+        let mut reply = Vec::with_capacity(RESPONSE_LEN);
+        reply.extend_from_slice(ephemeral_pub.as_bytes());
+        state.mix_hash(ephemeral_pub.as_bytes());
+        state.mix_key(&dh(&ephemeral, &remote_ephemeral)?);
+        state.mix_key(&dh(&ephemeral, &peer.to_montgomery())?);
+        reply.extend(state.encrypt_and_hash(&[]));
+
+        self.latest.insert(identity, sent_at);
+        let (recv, send) = state.split();
+        Ok((Session::new(peer, send, recv), reply))
+    }
+}
+
+/// Sliding window of recently seen sequence numbers
+#[derive(Debug, Clone)]
+pub struct ReplayWindow {
+    highest: Option<SeqNo>,
+    bits: [u64; (REPLAY_WINDOW / 64) as usize],
+}
+
+impl ReplayWindow {
+    /// Create an empty window
+    pub fn new() -> Self {
+        Self {
+            highest: None,
+            bits: [0; (REPLAY_WINDOW / 64) as usize],
+        }
+    }
+
+    fn bit(seq: SeqNo) -> (usize, u64) {
+        let slot = seq % REPLAY_WINDOW;
+        ((slot / 64) as usize, 1 << (slot % 64))
+    }
+
+    /// Whether `seq` is new and inside the window
+    pub fn check(&self, seq: SeqNo) -> bool {
+        match self.highest {
+            None => true,
+            Some(highest) if seq > highest => true,
+            Some(highest) if highest - seq >= REPLAY_WINDOW => false,
+            Some(_) => {
+                let (word, mask) = Self::bit(seq);
+                self.bits[word] & mask == 0
+            }
+        }
+    }
+
+    /// Whether `seq` was marked as seen and is still inside the window
+    pub fn contains(&self, seq: SeqNo) -> bool {
+        match self.highest {
+            Some(highest) if seq <= highest && highest - seq < REPLAY_WINDOW => {
+                let (word, mask) = Self::bit(seq);
+                self.bits[word] & mask != 0
+            }
+            _ => false,
+        }
+    }
+
+    /// Mark `seq` as seen; call only after the packet authenticated
+    pub fn update(&mut self, seq: SeqNo) {
+        match self.highest {
+            Some(highest) if seq <= highest => {}
+            Some(highest) if seq - highest < REPLAY_WINDOW => {
+                for skipped in highest + 1..seq {
+                    let (word, mask) = Self::bit(skipped);
+                    self.bits[word] &= !mask;
+                }
+                self.highest = Some(seq);
+            }
+            _ => {
+                self.bits = [0; (REPLAY_WINDOW / 64) as usize];
+                self.highest = Some(seq);
+            }
+        }
+        let (word, mask) = Self::bit(seq);
+        self.bits[word] |= mask;
+    }
+}
+
+impl Default for ReplayWindow {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Key for one direction of one stream, message kind and epoch
+struct StreamKey {
+    epoch: Epoch,
+    cipher: ChaCha20Poly1305,
+}
+
+impl StreamKey {
+    fn derive(root: &[u8; 32], stream_id: StreamId, kind: MsgKind, epoch: Epoch) -> Self {
+        let mut material = [0u8; 32 + 16 + 1 + 4];
+        material[..32].copy_from_slice(root);
+        material[32..48].copy_from_slice(&stream_id.to_be_bytes());
+        material[48] = kind.to_u8();
+        material[49..].copy_from_slice(&epoch.to_be_bytes());
+        let key = blake3::derive_key(STREAM_KEY_CONTEXT, &material);
+        Self {
+            epoch,
+            cipher: ChaCha20Poly1305::new(Key::from_slice(&key)),
+        }
+    }
+}
+
+struct EpochState {
+    key: StreamKey,
+    window: ReplayWindow,
+}
+
+/// Receive state for a stream; the previous epoch stays open for stragglers
+struct RecvStream {
+    current: EpochState,
+    previous: Option<EpochState>,
+}
+
+/// Header as authenticated: `body_len` is the sealed length
+fn associated_data(hdr: &PacketHeader, sealed_len: usize) -> BytesMut {
+    let mut aad = BytesMut::with_capacity(HEADER_LEN);
+    PacketHeader {
+        body_len: sealed_len as u16,
+        ..hdr.clone()
+    }
+    .encode_into(&mut aad);
+    aad
+}
+
+/// An authenticated session with one peer
+pub struct Session {
+    peer: VerifyingKey,
+    send_root: [u8; 32],
+    recv_root: [u8; 32],
+    send: HashMap<Channel, StreamKey>,
+    recv: HashMap<Channel, RecvStream>,
+}
+
+// Keys stay out of logs
+impl fmt::Debug for Session {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Session")
+            .field("peer", &self.peer)
+            .field("channels", &self.recv.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Session {
+    fn new(peer: VerifyingKey, send_root: [u8; 32], recv_root: [u8; 32]) -> Self {
+        Self {
+            peer,
+            send_root,
+            recv_root,
+            send: HashMap::new(),
+            recv: HashMap::new(),
+        }
+    }
+
+    /// The peer's authenticated identity
+    pub fn peer(&self) -> &VerifyingKey {
+        &self.peer
+    }
+
+    /// Encrypt a packet's body, authenticating its header
+    ///
+    /// The stream is rekeyed whenever the packet's epoch differs from the last
+    /// one sealed. Sequence numbers must not repeat within an epoch, except
+    /// for retransmissions of identical packets.
+    pub fn seal(&mut self, packet: &Packet) -> Packet {
+        let hdr = &packet.hdr;
+        let root = &self.send_root;
+        let key = self
+            .send
+            .entry((hdr.stream_id, hdr.kind.to_u8()))
+            .or_insert_with(|| StreamKey::derive(root, hdr.stream_id, hdr.kind, hdr.epoch));
+        if key.epoch != hdr.epoch {
+            *key = StreamKey::derive(root, hdr.stream_id, hdr.kind, hdr.epoch);
+        }
+
+        let sealed_len = packet.body.len() + TAG_LEN;
+        let aad = associated_data(hdr, sealed_len);
+        let body = key
+            .cipher
+            .encrypt(&nonce(hdr.seq), Payload { msg: &packet.body, aad: &aad })
+            .expect("packet bodies are far below the AEAD limit");
+        let hdr = PacketHeader {
+            body_len: sealed_len as u16,
+            ..hdr.clone()
+        };
+        Packet::new(hdr, Bytes::from(body))
+    }
+
+    /// Authenticate and decrypt a sealed packet
+    ///
+    /// A packet from a newer epoch rekeys the stream once it authenticates;
+    /// the previous epoch is still accepted until the next change. Replays are
+    /// rejected even when valid, but only once they authenticate, so
+    /// [`OpenError::Replay`] means the peer really sent the packet;
+    /// [`TgpHandle`](crate::TgpHandle) answers a replayed data packet it
+    /// delivered with a fresh ACK in case its first one was lost.
+    pub fn open(&mut self, packet: &Packet) -> Result<Packet, OpenError> {
+        let hdr = &packet.hdr;
+        if packet.body.len() < TAG_LEN {
+            return Err(OpenError::Malformed);
+        }
+
+        let channel = (hdr.stream_id, hdr.kind.to_u8());
+        let stream = self.recv.get_mut(&channel);
+        let mut fresh = None;
+        let state = match stream {
+            Some(stream) if stream.current.key.epoch == hdr.epoch => &mut stream.current,
+            Some(stream) if hdr.epoch < stream.current.key.epoch => match &mut stream.previous {
+                Some(previous) if previous.key.epoch == hdr.epoch => previous,
+                _ => return Err(OpenError::StaleEpoch),
+            },
+            // First packet of the stream, or of a newer epoch: commit only once it authenticates
+            _ => fresh.insert(EpochState {
+                key: StreamKey::derive(&self.recv_root, hdr.stream_id, hdr.kind, hdr.epoch),
+                window: ReplayWindow::new(),
+            }),
+        };
+
+        // Authenticate first, so only genuine packets are reported as replays
+        let aad = associated_data(hdr, packet.body.len());
+        let body = state
+            .key
+            .cipher
+            .decrypt(&nonce(hdr.seq), Payload { msg: &packet.body, aad: &aad })
+            .map_err(|_| OpenError::Decrypt)?;
+        if !state.window.check(hdr.seq) {
+            return Err(OpenError::Replay);
+        }
+        state.window.update(hdr.seq);
+
+        if let Some(state) = fresh {
+            let previous = self.recv.remove(&channel).map(|stream| {
+                tracing::debug!("Rekeyed stream {} to epoch {}", hdr.stream_id, hdr.epoch);
+                stream.current
+            });
+            self.recv.insert(channel, RecvStream { current: state, previous });
+        }
+
+        let hdr = PacketHeader {
+            body_len: body.len() as u16,
+            ..hdr.clone()
+        };
+        Ok(Packet::new(hdr, Bytes::from(body)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identity(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    fn sessions() -> (Session, Session) {
+        let (alice, bob) = (identity(1), identity(2));
+        let (initiator, msg1) = Initiator::start(&alice, &bob.verifying_key()).unwrap();
+        let mut responder = Responder::new(bob);
+        let (bob_session, msg2) = responder.respond(&msg1).unwrap();
+        let alice_session = initiator.finish(&msg2).unwrap();
+        (alice_session, bob_session)
+    }
+
+    fn packet(epoch: Epoch, seq: SeqNo) -> Packet {
+        Packet::data(5, epoch, seq, Bytes::from(format!("payload {seq}")))
+    }
+
+    #[test]
+    fn test_handshake_and_seal() {
+        let (mut alice, mut bob) = sessions();
+        assert_eq!(alice.peer(), &identity(2).verifying_key());
+        assert_eq!(bob.peer(), &identity(1).verifying_key());
+
+        let sealed = alice.seal(&packet(0, 1));
+        assert_eq!(sealed.body.len(), packet(0, 1).body.len() + TAG_LEN);
+        assert_ne!(&sealed.body[..9], b"payload 1");
+        assert_eq!(bob.open(&sealed).unwrap().body, packet(0, 1).body);
+
+        // And the other direction, under its own key
+        let reply = bob.seal(&packet(0, 1));
+        assert_ne!(reply.body, sealed.body);
+        assert_eq!(alice.open(&reply).unwrap().body, packet(0, 1).body);
+    }
+
+    #[test]
+    fn test_handshake_rejects_wrong_identity_and_replay() {
+        let (alice, bob, mallory) = (identity(1), identity(2), identity(3));
+
+        // Addressed to bob, but mallory answers
+        let (_, msg1) = Initiator::start(&alice, &bob.verifying_key()).unwrap();
+        assert_eq!(Responder::new(mallory).respond(&msg1).unwrap_err(), HandshakeError::Decrypt);
+
+        let mut responder = Responder::new(bob);
+        responder.respond(&msg1).unwrap();
+        assert_eq!(responder.respond(&msg1).unwrap_err(), HandshakeError::Replay);
+        assert_eq!(responder.respond(&msg1[1..]).unwrap_err(), HandshakeError::Malformed);
+    }
+
+    #[test]
+    fn test_open_rejects_spoofed_and_replayed_packets() {
+        let (mut alice, mut bob) = sessions();
+        let sealed = alice.seal(&packet(0, 7));
+
+        // Header is authenticated: a spoofed stream or sequence number fails
+        let mut spoofed = sealed.clone();
+        spoofed.hdr.stream_id = 6;
+        assert_eq!(bob.open(&spoofed).unwrap_err(), OpenError::Decrypt);
+        let mut spoofed = sealed.clone();
+        spoofed.hdr.seq = 8;
+        assert_eq!(bob.open(&spoofed).unwrap_err(), OpenError::Decrypt);
+        let mut tampered = sealed.clone();
+        let mut body = tampered.body.to_vec();
+        body[0] ^= 1;
+        tampered.body = body.into();
+        assert_eq!(bob.open(&tampered).unwrap_err(), OpenError::Decrypt);
+
+        // Same sequence number under another kind is a separate key and window
+        let mut ack = packet(0, 7);
+        ack.hdr.kind = MsgKind::Ack;
+        bob.open(&alice.seal(&ack)).unwrap();
+
+        // Injected with a key from another session
+        let (mut eve, _) = sessions();
+        assert_eq!(bob.open(&eve.seal(&packet(0, 9))).unwrap_err(), OpenError::Decrypt);
+
+        bob.open(&sealed).unwrap();
+        assert_eq!(bob.open(&sealed).unwrap_err(), OpenError::Replay);
+        // A forged copy of a seen sequence number is not a replay
+        let mut forged = sealed.clone();
+        forged.body = vec![0; forged.body.len()].into();
+        assert_eq!(bob.open(&forged).unwrap_err(), OpenError::Decrypt);
+        // Out of order within the window is fine
+        bob.open(&alice.seal(&packet(0, 3))).unwrap();
+    }
+
+    #[test]
+    fn test_rekey_on_epoch_change() {
+        let (mut alice, mut bob) = sessions();
+        let old = alice.seal(&packet(1, 10));
+        let new = alice.seal(&packet(2, 10));
+        assert_ne!(old.body, new.body);
+
+        bob.open(&old).unwrap();
+        // Sequence numbers restart with the new epoch's key and window
+        bob.open(&new).unwrap();
+        // Stragglers from the previous epoch are still accepted...
+        bob.open(&alice.seal(&packet(1, 11))).unwrap();
+        // ...but not from before it
+        bob.open(&alice.seal(&packet(3, 0))).unwrap();
+        assert_eq!(bob.open(&alice.seal(&packet(1, 12))).unwrap_err(), OpenError::StaleEpoch);
+    }
+
+    #[test]
+    fn test_replay_window() {
+        let mut window = ReplayWindow::new();
+        for seq in [5, 3, 4, 2000] {
+            assert!(window.check(seq) && !window.contains(seq));
+            window.update(seq);
+            assert!(!window.check(seq) && window.contains(seq));
+        }
+        // Too old to tell: not new, but not known to have been seen either
+        assert!(!window.check(5) && !window.contains(5));
+        assert!(!window.check(2000 - REPLAY_WINDOW));
+        assert!(window.check(2000 - REPLAY_WINDOW + 1));
+        assert!(window.check(1999));
+    }
+}
@@ -0,0 +1,621 @@
+//! Forward error correction for lossy links
+//!
+//! The sender groups consecutive data packets into blocks and follows each
+//! block with Reed-Solomon repair packets (`MsgKind::Repair`). The code is
+//! systematic: data packets go out unchanged, and any `k` of a block's `k`
+//! data and `r` repair packets are enough to rebuild the rest, so the
+//! receiver recovers losses without a NACK or a round trip.
+//!
+//! Each data packet is one shard: its flags, length and body, zero-padded to
+//! the longest packet in the block. A repair packet carries one parity shard
+//! behind a small header naming its block:
+//!
+//! ```text
+//! 0        8       9        10      11       12
+//! | first  | data  | repair | index | rsvd   | shard ... |
+//! ```
+//!
+//! Repair packets are numbered separately from data, like ACKs, so sealing
+//! them never reuses a nonce. The number of repair packets per block follows
+//! the loss the receiver reports in its ACK frames: the packets it NACKs
+//! plus those it had to rebuild ([`AckFrame::recovered`]).
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use reed_solomon_erasure::galois_8::ReedSolomon;
+
+use crate::reliable::AckFrame;
+use crate::types::{Epoch, MsgKind, Packet, PacketHeader, SeqNo, StreamId};
+
+/// Size of the block header in front of a repair shard
+pub const REPAIR_HEADER_LEN: usize = 12;
+
+/// Per-shard framing: flags and body length
+const SHARD_PREFIX_LEN: usize = 3;
+
+/// Repair packets are this much larger than the largest data packet in their block
+pub const REPAIR_OVERHEAD: usize = REPAIR_HEADER_LEN + SHARD_PREFIX_LEN;
+
+/// GF(2^8) Reed-Solomon supports at most this many shards per block
+pub const MAX_SHARDS: usize = 256;
+
+/// Send a partial block's repair packets once the stream has been idle this long
+pub const FLUSH_INTERVAL: Duration = Duration::from_millis(20);
+
+/// How far behind the newest data packet the decoder keeps packets for
+/// recovery, and how far ahead of it a repair packet may open a block
+const HISTORY: u64 = 4096;
+
+/// Most blocks the decoder keeps waiting for shards at once
+const MAX_PENDING_BLOCKS: usize = 256;
+
+/// Weight of the newest loss sample in the smoothed loss rate
+const LOSS_GAIN: f64 = 0.25;
+
+/// FEC settings for one stream
+#[derive(Debug, Clone)]
+pub struct FecConfig {
+    /// Data packets per block
+    pub block_size: usize,
+    /// Repair packets per data packet with no loss observed
+    pub min_redundancy: f64,
+    /// Upper bound on repair packets per data packet
+    pub max_redundancy: f64,
+}
+
+impl Default for FecConfig {
+    fn default() -> Self {
+        Self {
+            block_size: 32,
+            min_redundancy: 0.05,
+            max_redundancy: 0.5,
+        }
+    }
+}
+
+/// Which block a repair packet belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct BlockInfo {
+    first: SeqNo,
+    data: usize,
+    repair: usize,
+}
+
+impl BlockInfo {
+    fn contains(&self, seq: SeqNo) -> bool {
+        (self.first..self.first + self.data as u64).contains(&seq)
+    }
+}
+
+fn shard(packet: &Packet, len: usize) -> Vec<u8> {
+    let mut shard = Vec::with_capacity(len);
+    shard.put_u8(packet.hdr.flags);
+    shard.put_u16(packet.body.len() as u16);
+    shard.extend_from_slice(&packet.body);
+    shard.resize(len, 0);
+    shard
+}
+
+/// Rebuild a data packet from its shard, or `None` if the framing is invalid
+fn unshard(stream_id: StreamId, epoch: Epoch, seq: SeqNo, shard: &[u8]) -> Option<Packet> {
+    let mut buf = shard;
+    let flags = buf.get_u8();
+    let len = buf.get_u16() as usize;
+    let body = buf.get(..len)?;
+    let hdr = PacketHeader {
+        flags,
+        ..PacketHeader::new_data(stream_id, epoch, seq, len as u16)
+    };
+    Some(Packet::new(hdr, Bytes::copy_from_slice(body)))
+}
+
+/// Sending side: emits repair packets for each block of data packets
+pub struct FecEncoder {
+    config: FecConfig,
+    block: Vec<Packet>,
+    repair_seq: SeqNo,
+    loss: f64,
+    repairs_sent: u64,
+}
+
+impl FecEncoder {
+    /// Create an encoder; block sizes are clamped to leave room for at least
+    /// one repair shard
+    pub fn new(mut config: FecConfig) -> Self {
+        config.block_size = config.block_size.clamp(1, MAX_SHARDS - 1);
+        Self {
+            config,
+            block: Vec::new(),
+            repair_seq: 0,
+            loss: 0.0,
+            repairs_sent: 0,
+        }
+    }
+
+    /// Add an outgoing packet, returning repair packets to send after it
+    ///
+    /// Only data packets are protected. A block closes when it is full, or
+    /// early when the packet does not follow on from it (another stream or
+    /// epoch, or a gap in sequence numbers).
+    pub fn protect(&mut self, packet: &Packet) -> Vec<Packet> {
+        if !packet.is_data() {
+            return Vec::new();
+        }
+        let mut repairs = match self.block.last() {
+            Some(last)
+                if last.hdr.stream_id != packet.hdr.stream_id
+                    || last.hdr.epoch != packet.hdr.epoch
+                    || last.hdr.seq + 1 != packet.hdr.seq =>
+            {
+                self.flush()
+            }
+            _ => Vec::new(),
+        };
+        self.block.push(packet.clone());
+        if self.block.len() == self.config.block_size {
+            repairs.extend(self.flush());
+        }
+        repairs
+    }
+
+    /// Close the current block early, returning its repair packets
+    pub fn flush(&mut self) -> Vec<Packet> {
+        let block = std::mem::take(&mut self.block);
+        let Some(first) = block.first() else {
+            return Vec::new();
+        };
+        let info = BlockInfo {
+            first: first.hdr.seq,
+            data: block.len(),
+            repair: self.repair_count(block.len()),
+        };
+        let (stream_id, epoch) = (first.hdr.stream_id, first.hdr.epoch);
+        if info.repair == 0 {
+            return Vec::new();
+        }
+
+        let len = SHARD_PREFIX_LEN + block.iter().map(|p| p.body.len()).max().unwrap_or(0);
+        let mut shards: Vec<Vec<u8>> = block.iter().map(|p| shard(p, len)).collect();
+        shards.resize(info.data + info.repair, vec![0; len]);
+        ReedSolomon::new(info.data, info.repair)
+            .and_then(|rs| rs.encode(&mut shards))
+            .expect("shard counts and lengths are validated");
+
+        shards
+            .drain(info.data..)
+            .enumerate()
+            .map(|(index, shard)| {
+                let mut body = BytesMut::with_capacity(REPAIR_HEADER_LEN + len);
+                body.put_u64(info.first);
+                body.put_u8(info.data as u8);
+                body.put_u8(info.repair as u8);
+                body.put_u8(index as u8);
+                body.put_u8(0);
+                body.extend_from_slice(&shard);
+
+                let hdr = PacketHeader {
+                    kind: MsgKind::Repair,
+                    ..PacketHeader::new_data(stream_id, epoch, self.repair_seq, body.len() as u16)
+                };
+                self.repair_seq += 1;
+                self.repairs_sent += 1;
+                Packet::new(hdr, body.freeze())
+            })
+            .collect()
+    }
+
+    /// Update the loss estimate from an ACK frame
+    ///
+    /// Loss counts the packets the receiver NACKs plus those it had to
+    /// recover, so working FEC does not talk itself out of its redundancy.
+    pub fn on_feedback(&mut self, frame: &AckFrame) {
+        let acked: u64 = frame.ranges.iter().map(|r| r.end.saturating_sub(r.start)).sum();
+        let lost = frame.nacks.len() as u64 + frame.recovered.min(acked);
+        let expected = acked + frame.nacks.len() as u64;
+        if expected > 0 {
+            let sample = lost as f64 / expected as f64;
+            self.loss += LOSS_GAIN * (sample - self.loss);
+        }
+    }
+
+    /// Smoothed loss rate the redundancy is sized for
+    pub fn loss(&self) -> f64 {
+        self.loss
+    }
+
+    /// Current repair packets per data packet for a full block
+    pub fn redundancy(&self) -> f64 {
+        self.repair_count(self.config.block_size) as f64 / self.config.block_size as f64
+    }
+
+    /// Repair packets emitted so far
+    pub fn repairs_sent(&self) -> u64 {
+        self.repairs_sent
+    }
+
+    /// Repair packets for a block of `data` packets: the expected losses plus
+    /// two standard deviations, within the configured bounds
+    fn repair_count(&self, data: usize) -> usize {
+        let n = data as f64;
+        let expected = n * self.loss;
+        let margin = 2.0 * (expected * (1.0 - self.loss)).sqrt();
+        let wanted = (expected + margin)
+            .max(n * self.config.min_redundancy)
+            .min(n * self.config.max_redundancy)
+            .ceil() as usize;
+        wanted.min(MAX_SHARDS - data)
+    }
+}
+
+/// A block waiting for enough shards to recover its missing packets
+struct PendingBlock {
+    info: BlockInfo,
+    stream_id: StreamId,
+    epoch: Epoch,
+    repairs: Vec<Option<Bytes>>,
+}
+
+/// Receiving side: rebuilds lost data packets from repair packets
+///
+/// Use one decoder per stream, fed with every data and repair packet received.
+pub struct FecDecoder {
+    data: BTreeMap<SeqNo, Packet>,
+    blocks: BTreeMap<SeqNo, PendingBlock>,
+    horizon: SeqNo,
+    /// One past the newest data packet seen
+    newest: SeqNo,
+    packets_recovered: u64,
+}
+
+impl FecDecoder {
+    /// Create a decoder
+    pub fn new() -> Self {
+        Self {
+            data: BTreeMap::new(),
+            blocks: BTreeMap::new(),
+            horizon: 0,
+            newest: 0,
+            packets_recovered: 0,
+        }
+    }
+
+    /// Accept a data or repair packet, returning any data packets it let the
+    /// decoder recover
+    ///
+    /// Recovered packets are returned once; packets that arrive after being
+    /// recovered are not reported again. Malformed repair packets are dropped,
+    /// as are those for blocks more than the history window ahead of the
+    /// newest data. At most [`MAX_PENDING_BLOCKS`] incomplete blocks are
+    /// kept; past that, the furthest ahead is dropped.
+    pub fn on_packet(&mut self, packet: &Packet) -> Vec<Packet> {
+        match packet.hdr.kind {
+            MsgKind::Data => self.on_data(packet),
+            MsgKind::Repair => self.on_repair(packet),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Data packets rebuilt from repair packets so far
+    pub fn packets_recovered(&self) -> u64 {
+        self.packets_recovered
+    }
+
+    fn on_data(&mut self, packet: &Packet) -> Vec<Packet> {
+        let seq = packet.hdr.seq;
+        if seq < self.horizon || self.data.contains_key(&seq) {
+            return Vec::new();
+        }
+        self.data.insert(seq, packet.clone());
+        self.newest = self.newest.max(seq + 1);
+        self.prune(seq);
+
+        let block = self.blocks.range(..=seq).next_back().map(|(&first, block)| (first, block.info));
+        match block {
+            Some((first, info)) if info.contains(seq) => self.try_recover(first),
+            _ => Vec::new(),
+        }
+    }
+
+    fn on_repair(&mut self, packet: &Packet) -> Vec<Packet> {
+        let mut buf = &packet.body[..];
+        if buf.len() <= REPAIR_HEADER_LEN + SHARD_PREFIX_LEN {
+            tracing::debug!("Dropping truncated repair packet {}", packet.hdr.seq);
+            return Vec::new();
+        }
+        let info = BlockInfo {
+            first: buf.get_u64(),
+            data: buf.get_u8() as usize,
+            repair: buf.get_u8() as usize,
+        };
+        let index = buf.get_u8() as usize;
+        buf.advance(1);
+        if info.data == 0 || index >= info.repair || info.data + info.repair > MAX_SHARDS {
+            tracing::debug!("Dropping malformed repair packet {}", packet.hdr.seq);
+            return Vec::new();
+        }
+        let complete = (info.first..info.first + info.data as u64).all(|s| self.data.contains_key(&s));
+        let too_far = info.first >= self.newest.max(self.horizon).saturating_add(HISTORY);
+        if info.first < self.horizon || too_far || complete {
+            return Vec::new();
+        }
+        if !self.blocks.contains_key(&info.first) && self.blocks.len() >= MAX_PENDING_BLOCKS {
+            match self.blocks.last_key_value() {
+                Some((&last, _)) if last > info.first => {
+                    self.blocks.remove(&last);
+                }
+                _ => return Vec::new(),
+            }
+        }
+
+        let block = self.blocks.entry(info.first).or_insert_with(|| PendingBlock {
+            info,
+            stream_id: packet.hdr.stream_id,
+            epoch: packet.hdr.epoch,
+            repairs: vec![None; info.repair],
+        });
+        let shard_len = block.repairs.iter().flatten().next().map(Bytes::len);
+        if block.info != info || shard_len.is_some_and(|len| len != buf.len()) {
+            tracing::debug!("Dropping repair packet {} inconsistent with its block", packet.hdr.seq);
+            return Vec::new();
+        }
+        block.repairs[index] = Some(packet.body.slice(REPAIR_HEADER_LEN..));
+        self.try_recover(info.first)
+    }
+
+    /// Rebuild a block's missing packets once enough shards have arrived
+    fn try_recover(&mut self, first: SeqNo) -> Vec<Packet> {
+        let Some(block) = self.blocks.get(&first) else {
+            return Vec::new();
+        };
+        let info = block.info;
+        let seqs = info.first..info.first + info.data as u64;
+        let have_data = seqs.clone().filter(|s| self.data.contains_key(s)).count();
+        let have_repair = block.repairs.iter().flatten().count();
+        if have_data == info.data {
+            self.blocks.remove(&first);
+            return Vec::new();
+        }
+        if have_data + have_repair < info.data {
+            return Vec::new();
+        }
+
+        let block = self.blocks.remove(&first).expect("block checked above");
+        let len = block.repairs.iter().flatten().next().map_or(0, Bytes::len);
+        let mut shards: Vec<Option<Vec<u8>>> = seqs
+            .clone()
+            .map(|s| self.data.get(&s).map(|p| shard(p, len)))
+            .chain(block.repairs.iter().map(|r| r.as_ref().map(|r| r.to_vec())))
+            .collect();
+        // A data packet longer than the shards means the repair packets lie
+        let fits = self.data.range(seqs.clone()).all(|(_, p)| SHARD_PREFIX_LEN + p.body.len() <= len);
+        let rebuilt = fits
+            && ReedSolomon::new(info.data, info.repair)
+                .and_then(|rs| rs.reconstruct_data(&mut shards))
+                .is_ok();
+        if !rebuilt {
+            tracing::debug!("Failed to recover block at {}", first);
+            return Vec::new();
+        }
+
+        let mut recovered = Vec::new();
+        for (seq, shard) in seqs.zip(shards) {
+            if self.data.contains_key(&seq) {
+                continue;
+            }
+            let Some(packet) = shard.and_then(|shard| unshard(block.stream_id, block.epoch, seq, &shard)) else {
+                continue;
+            };
+            self.data.insert(seq, packet.clone());
+            recovered.push(packet);
+        }
+        self.packets_recovered += recovered.len() as u64;
+        recovered
+    }
+
+    /// Forget packets and blocks too far behind `newest` to matter
+    fn prune(&mut self, newest: SeqNo) {
+        let horizon = newest.saturating_sub(HISTORY);
+        if horizon <= self.horizon {
+            return;
+        }
+        self.horizon = horizon;
+        self.data = self.data.split_off(&horizon);
+        self.blocks = self.blocks.split_off(&horizon);
+    }
+}
+
+impl Default for FecDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packets(count: u64) -> Vec<Packet> {
+        (0..count)
+            .map(|seq| {
+                let mut packet = Packet::data(7, 3, seq, Bytes::from(vec![seq as u8; 100 + seq as usize * 13]));
+                packet.hdr.flags = seq as u8;
+                packet
+            })
+            .collect()
+    }
+
+    fn with_redundancy(block_size: usize, redundancy: f64) -> FecEncoder {
+        FecEncoder::new(FecConfig {
+            block_size,
+            min_redundancy: redundancy,
+            max_redundancy: redundancy,
+        })
+    }
+
+    /// Feedback for `received` packets in a row, `recovered` of them rebuilt,
+    /// followed by `lost` NACKed ones
+    fn feedback(received: u64, lost: u64, recovered: u64) -> AckFrame {
+        AckFrame {
+            cumulative: 0,
+            ranges: vec![0..received, received + lost..received + lost + 1],
+            nacks: (received..received + lost).collect(),
+            recovered,
+        }
+    }
+
+    #[test]
+    fn test_recovers_lost_packets() {
+        let source = packets(8);
+        let mut encoder = with_redundancy(8, 0.5);
+        let mut repairs = Vec::new();
+        for packet in &source {
+            repairs.extend(encoder.protect(packet));
+        }
+        assert_eq!(repairs.len(), 4);
+        assert!(repairs.iter().all(|p| p.hdr.kind == MsgKind::Repair && p.hdr.stream_id == 7));
+        assert_eq!(repairs.iter().map(|p| p.hdr.seq).collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+
+        // Lose four data packets and one repair packet
+        let mut decoder = FecDecoder::new();
+        for packet in source.iter().filter(|p| ![1, 2, 5, 7].contains(&p.seq())) {
+            assert!(decoder.on_packet(packet).is_empty());
+        }
+        assert!(decoder.on_packet(&repairs[0]).is_empty());
+        assert!(decoder.on_packet(&repairs[2]).is_empty());
+        assert!(decoder.on_packet(&repairs[3]).is_empty());
+        let recovered = decoder.on_packet(&repairs[1]);
+
+        assert_eq!(recovered.iter().map(|p| p.seq()).collect::<Vec<_>>(), vec![1, 2, 5, 7]);
+        for packet in &recovered {
+            let original = &source[packet.seq() as usize];
+            assert_eq!(packet.body, original.body);
+            assert_eq!(packet.hdr.flags, original.hdr.flags);
+            assert_eq!((packet.hdr.kind, packet.hdr.epoch), (MsgKind::Data, 3));
+            assert_eq!(packet.hdr.body_len as usize, original.body.len());
+        }
+        assert_eq!(decoder.packets_recovered(), 4);
+
+        // Late arrivals and leftover repairs recover nothing more
+        assert!(decoder.on_packet(&source[1]).is_empty());
+        assert!(decoder.on_packet(&repairs[0]).is_empty());
+    }
+
+    #[test]
+    fn test_partial_block_and_repair_first() {
+        let source = packets(5);
+        let mut encoder = with_redundancy(16, 0.25);
+        for packet in &source {
+            assert!(encoder.protect(packet).is_empty());
+        }
+        let repairs = encoder.flush();
+        assert_eq!(repairs.len(), 2);
+        assert!(encoder.flush().is_empty());
+
+        // Repairs arrive before the data; recovery waits for enough shards
+        let mut decoder = FecDecoder::new();
+        for repair in &repairs {
+            assert!(decoder.on_packet(repair).is_empty());
+        }
+        assert!(decoder.on_packet(&source[0]).is_empty());
+        assert!(decoder.on_packet(&source[4]).is_empty());
+        let recovered = decoder.on_packet(&source[2]);
+        assert_eq!(recovered.iter().map(|p| p.seq()).collect::<Vec<_>>(), vec![1, 3]);
+        assert_eq!(recovered[1].body, source[3].body);
+
+        // A gap in sequence numbers closes the block early
+        let mut encoder = with_redundancy(16, 0.25);
+        encoder.protect(&source[0]);
+        let repairs = encoder.protect(&source[2]);
+        assert_eq!(repairs.len(), 1);
+        assert_eq!(&repairs[0].body[..9], &[0, 0, 0, 0, 0, 0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn test_too_many_losses() {
+        let source = packets(8);
+        let mut encoder = with_redundancy(8, 0.25);
+        let repairs: Vec<Packet> = source.iter().flat_map(|p| encoder.protect(p)).collect();
+        assert_eq!(repairs.len(), 2);
+
+        let mut decoder = FecDecoder::new();
+        for packet in source.iter().skip(3) {
+            decoder.on_packet(packet);
+        }
+        for repair in &repairs {
+            assert!(decoder.on_packet(repair).is_empty());
+        }
+
+        // Truncated or inconsistent repair packets are ignored
+        let mut bad = repairs[0].clone();
+        bad.body = bad.body.slice(..REPAIR_HEADER_LEN);
+        assert!(decoder.on_packet(&bad).is_empty());
+        let mut bad = repairs[1].clone();
+        bad.body = bad.body.slice(..bad.body.len() - 1);
+        assert!(decoder.on_packet(&bad).is_empty());
+
+        // One more data packet is enough
+        let recovered = decoder.on_packet(&source[0]);
+        assert_eq!(recovered.iter().map(|p| p.seq()).collect::<Vec<_>>(), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_redundancy_adapts_to_loss() {
+        let mut encoder = FecEncoder::new(FecConfig::default());
+        assert_eq!(encoder.redundancy(), 2.0 / 32.0);
+
+        // 10% of packets lost, half of them recovered
+        for _ in 0..20 {
+            encoder.on_feedback(&feedback(949, 50, 50));
+        }
+        assert!((encoder.loss() - 0.1).abs() < 0.01, "loss {}", encoder.loss());
+        let high = encoder.redundancy();
+        assert!(high > 0.2 && high <= 0.5, "redundancy {high}");
+
+        // Heavy loss is capped
+        for _ in 0..20 {
+            encoder.on_feedback(&feedback(9, 90, 0));
+        }
+        assert_eq!(encoder.redundancy(), 0.5);
+
+        // A clean link decays back to the floor
+        for _ in 0..40 {
+            encoder.on_feedback(&feedback(999, 0, 0));
+        }
+        assert_eq!(encoder.redundancy(), 2.0 / 32.0);
+    }
+
+    #[test]
+    fn test_far_ahead_repairs_are_bounded() {
+        let source = packets(4);
+        let mut encoder = with_redundancy(4, 0.5);
+        let repairs: Vec<Packet> = source.iter().flat_map(|p| encoder.protect(p)).collect();
+        let at = |first: u64| {
+            let mut repair = repairs[0].clone();
+            let mut body = repair.body.to_vec();
+            body[..8].copy_from_slice(&first.to_be_bytes());
+            repair.body = Bytes::from(body);
+            repair
+        };
+
+        let mut decoder = FecDecoder::new();
+        decoder.on_packet(&source[0]);
+        assert!(decoder.on_packet(&at(u64::MAX - 10)).is_empty());
+        assert!(decoder.on_packet(&at(HISTORY + 1)).is_empty());
+        assert!(decoder.blocks.is_empty());
+
+        // Within the window, pending blocks are capped, keeping the nearest
+        for first in (0..MAX_PENDING_BLOCKS as u64 + 10).map(|i| 100 + i * 8).rev() {
+            decoder.on_packet(&at(first));
+        }
+        assert_eq!(decoder.blocks.len(), MAX_PENDING_BLOCKS);
+        assert_eq!(decoder.blocks.keys().next(), Some(&100));
+
+        // The real block still recovers
+        decoder.on_packet(&source[1]);
+        decoder.on_packet(&repairs[0]);
+        let recovered = decoder.on_packet(&repairs[1]);
+        assert_eq!(recovered.iter().map(|p| p.seq()).collect::<Vec<_>>(), vec![2, 3]);
+    }
+}
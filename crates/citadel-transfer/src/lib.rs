@@ -3,6 +3,10 @@
 //! This crate provides:
 //! - High-speed UDP transport with configurable buffers
 //! - TGP-style continuous streaming for bulk data transfer
+//! - Reliable, in-order streams with selective ACKs and retransmission
+//! - Pluggable congestion control: LEDBAT background and BBR-like throughput modes
+//! - Authenticated encryption keyed by ed25519 node identities
+//! - Reed-Solomon forward error correction with loss-adaptive redundancy
 //! - Compatible API for applications migrating from palace-consensus
 //!
 //! # Design Philosophy
@@ -10,7 +14,11 @@
 //! Unlike TCP which requests retransmissions, TGP-style streaming floods data
 //! continuously at a target rate. Packet loss is compensated by redundant
 //! transmissions, achieving linear degradation (50% loss → 50% throughput)
-//! rather than TCP's exponential backoff.
+//! rather than TCP's exponential backoff. The [`fec`] module adds repair
+//! packets so receivers can rebuild lost data without asking for it again.
+//!
+//! Where data must arrive complete and in order, the [`reliable`] module adds
+//! a retransmit buffer driven by selective ACKs/NACKs and RTT-based timeouts.
 //!
 //! # Example
 //!
@@ -27,11 +35,23 @@
 //! let transport = TransportHandle::new(config).await?;
 //! ```
 
+pub mod congestion;
+pub mod crypto;
+pub mod fec;
+pub mod reliable;
 pub mod streaming;
 pub mod transport;
 pub mod types;
 
 // Re-export main types at crate root
+pub use congestion::{
+    Bbr, CongestionController, CongestionMode, CongestionStats, FixedRate, Ledbat, DEFAULT_TARGET_MBPS,
+};
+pub use crypto::{HandshakeError, Initiator, OpenError, Responder, Session};
+pub use fec::{FecConfig, FecDecoder, FecEncoder};
+pub use reliable::{AckFrame, ReliableReceiver, ReliableSender, RttEstimator, SenderStats};
 pub use streaming::{ContinuousStreamer, PacketReceiver, TgpConfig, TgpHandle};
 pub use transport::{TransportConfig, TransportHandle};
-pub use types::{Epoch, MsgKind, NodeId, Packet, PacketHeader, SeqNo, StreamId, DEFAULT_PAYLOAD_MTU};
+pub use types::{
+    DecodeError, EncodeError, Epoch, MsgKind, NodeId, Packet, PacketHeader, SeqNo, StreamId, DEFAULT_PAYLOAD_MTU, HEADER_LEN,
+};
@@ -0,0 +1,808 @@
+//! Reliable, in-order delivery over lossy links
+//!
+//! The continuous streamer never looks back: lost packets stay lost. Reliable
+//! mode is for data that must arrive whole and in order:
+//! - The sender keeps every unacknowledged packet in a retransmit buffer,
+//!   bounded by a window
+//! - The receiver reorders packets, delivers them in sequence and answers
+//!   with `MsgKind::Ack` packets carrying an [`AckFrame`]: the cumulative
+//!   sequence number, selective ranges received beyond it, and NACKs for gaps
+//! - NACKed packets are retransmitted at once; a retransmission timeout
+//!   derived from the smoothed RTT (RFC 6298) catches lost tails and lost ACKs
+//! - A [`CongestionController`] paces sends and bounds the bytes in flight
+//!
+//! [`ReliableSender`] and [`ReliableReceiver`] are plain state machines that
+//! take the current time as an argument; [`run_sender`] and [`run_receiver`]
+//! drive them over channels.
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::time::Duration;
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+use crate::congestion::{Congestion, CongestionController, CongestionMode, CongestionStats, DEFAULT_TARGET_MBPS};
+use crate::streaming::ReceiverStats;
+use crate::types::{Epoch, MsgKind, Packet, PacketHeader, SeqNo, StreamId, DEFAULT_PAYLOAD_MTU};
+
+/// Default maximum number of unacknowledged packets
+pub const DEFAULT_WINDOW: usize = 1024;
+
+/// Most selective ranges carried in one ACK
+pub const MAX_ACK_RANGES: usize = 32;
+
+/// Most NACKed sequence numbers carried in one ACK
+pub const MAX_NACKS: usize = 64;
+
+/// Retransmission timeout before the first RTT sample
+pub const INITIAL_RTO: Duration = Duration::from_millis(250);
+
+/// Lower bound for the retransmission timeout
+pub const MIN_RTO: Duration = Duration::from_millis(20);
+
+/// Upper bound for the retransmission timeout (after backoff)
+pub const MAX_RTO: Duration = Duration::from_secs(5);
+
+/// Acknowledgment carried in the body of a `MsgKind::Ack` packet
+///
+/// Wire format (little-endian):
+/// `cumulative: u64 | range count: u16 | (start: u64, end: u64)* | nack count: u16 | nack: u64* | recovered: u64`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AckFrame {
+    /// Every sequence number below this has been received
+    pub cumulative: SeqNo,
+    /// Ranges received beyond `cumulative` (end exclusive), ascending
+    pub ranges: Vec<Range<SeqNo>>,
+    /// Missing sequence numbers the receiver asks to have resent
+    pub nacks: Vec<SeqNo>,
+    /// How many of the packets in `ranges` FEC rebuilt rather than received
+    pub recovered: u64,
+}
+
+impl AckFrame {
+    /// Encode for a packet body
+    pub fn encode(&self) -> Bytes {
+        let mut buf = BytesMut::with_capacity(20 + self.ranges.len() * 16 + self.nacks.len() * 8);
+        buf.put_u64_le(self.cumulative);
+        buf.put_u16_le(self.ranges.len() as u16);
+        for range in &self.ranges {
+            buf.put_u64_le(range.start);
+            buf.put_u64_le(range.end);
+        }
+        buf.put_u16_le(self.nacks.len() as u16);
+        for seq in &self.nacks {
+            buf.put_u64_le(*seq);
+        }
+        buf.put_u64_le(self.recovered);
+        buf.freeze()
+    }
+
+    /// Decode a packet body, or `None` if it is malformed
+    pub fn decode(mut data: &[u8]) -> Option<Self> {
+        if data.remaining() < 10 {
+            return None;
+        }
+        let cumulative = data.get_u64_le();
+        let range_count = data.get_u16_le() as usize;
+        if data.remaining() < range_count * 16 + 2 {
+            return None;
+        }
+        let ranges = (0..range_count)
+            .map(|_| data.get_u64_le()..data.get_u64_le())
+            .collect();
+        let nack_count = data.get_u16_le() as usize;
+        if data.remaining() != nack_count * 8 + 8 {
+            return None;
+        }
+        let nacks = (0..nack_count).map(|_| data.get_u64_le()).collect();
+        Some(Self {
+            cumulative,
+            ranges,
+            nacks,
+            recovered: data.get_u64_le(),
+        })
+    }
+
+    /// Wrap in a `MsgKind::Ack` packet for the sender
+    ///
+    /// ACKs are numbered in their own sequence, separate from the data they
+    /// acknowledge, so sealed ACKs never reuse a nonce.
+    pub fn to_packet(&self, stream_id: StreamId, epoch: Epoch, seq: SeqNo) -> Packet {
+        let body = self.encode();
+        let hdr = PacketHeader {
+            stream_id,
+            epoch,
+            seq,
+            kind: MsgKind::Ack,
+            flags: 0,
+            body_len: body.len() as u16,
+        };
+        Packet::new(hdr, body)
+    }
+
+    /// Whether `seq` is acknowledged by this frame
+    pub fn covers(&self, seq: SeqNo) -> bool {
+        seq < self.cumulative || self.ranges.iter().any(|r| r.contains(&seq))
+    }
+}
+
+/// Smoothed RTT and retransmission timeout (RFC 6298)
+#[derive(Debug, Clone)]
+pub struct RttEstimator {
+    srtt: Option<Duration>,
+    rttvar: Duration,
+    base_rto: Duration,
+    rto: Duration,
+}
+
+impl RttEstimator {
+    /// Create an estimator with no samples
+    pub fn new() -> Self {
+        Self {
+            srtt: None,
+            rttvar: Duration::ZERO,
+            base_rto: INITIAL_RTO,
+            rto: INITIAL_RTO,
+        }
+    }
+
+    /// Add a round-trip sample
+    pub fn on_sample(&mut self, rtt: Duration) {
+        match self.srtt {
+            None => {
+                self.srtt = Some(rtt);
+                self.rttvar = rtt / 2;
+            }
+            Some(srtt) => {
+                let delta = srtt.abs_diff(rtt);
+                self.rttvar = (self.rttvar * 3 + delta) / 4;
+                self.srtt = Some((srtt * 7 + rtt) / 8);
+            }
+        }
+        let srtt = self.srtt.unwrap_or(rtt);
+        self.base_rto = (srtt + (self.rttvar * 4).max(Duration::from_millis(1))).clamp(MIN_RTO, MAX_RTO);
+        self.rto = self.base_rto;
+    }
+
+    /// Double the timeout after it expired
+    pub fn backoff(&mut self) {
+        self.rto = (self.rto * 2).min(MAX_RTO);
+    }
+
+    /// Drop any backoff once new data is acknowledged, even without a
+    /// usable sample
+    pub fn reset_backoff(&mut self) {
+        self.rto = self.base_rto;
+    }
+
+    /// Smoothed round-trip time, if any sample was taken
+    pub fn srtt(&self) -> Option<Duration> {
+        self.srtt
+    }
+
+    /// Current retransmission timeout
+    pub fn rto(&self) -> Duration {
+        self.rto
+    }
+}
+
+impl Default for RttEstimator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A packet awaiting acknowledgment
+#[derive(Debug, Clone)]
+struct InFlight {
+    packet: Packet,
+    sent_at: Instant,
+    transmissions: u32,
+}
+
+/// Sender statistics
+#[derive(Debug, Clone, Default)]
+pub struct SenderStats {
+    /// Distinct packets sent
+    pub packets_sent: u64,
+    /// Retransmissions (NACK or timeout)
+    pub retransmits: u64,
+    /// Packets acknowledged
+    pub packets_acked: u64,
+}
+
+/// Sending half of a reliable stream
+pub struct ReliableSender {
+    stream_id: StreamId,
+    epoch: Epoch,
+    next_seq: SeqNo,
+    window: usize,
+    in_flight: BTreeMap<SeqNo, InFlight>,
+    congestion: Congestion,
+    stats: SenderStats,
+}
+
+impl ReliableSender {
+    /// Create a sender allowing `window` unacknowledged packets, paced at
+    /// [`DEFAULT_TARGET_MBPS`]
+    pub fn new(stream_id: StreamId, epoch: Epoch, window: usize) -> Self {
+        let controller = CongestionMode::Fixed.controller(DEFAULT_TARGET_MBPS, DEFAULT_PAYLOAD_MTU);
+        Self::with_controller(stream_id, epoch, window, controller)
+    }
+
+    /// Create a sender with the given congestion controller
+    pub fn with_controller(
+        stream_id: StreamId,
+        epoch: Epoch,
+        window: usize,
+        controller: Box<dyn CongestionController>,
+    ) -> Self {
+        Self {
+            stream_id,
+            epoch,
+            next_seq: 0,
+            window: window.max(1),
+            in_flight: BTreeMap::new(),
+            congestion: Congestion::new(controller),
+            stats: SenderStats::default(),
+        }
+    }
+
+    /// Whether the window and congestion window have room for another packet
+    pub fn can_send(&self) -> bool {
+        self.in_flight.len() < self.window && self.congestion.can_send()
+    }
+
+    /// Earliest time pacing allows the next packet
+    pub fn next_send_time(&self) -> Option<Instant> {
+        self.congestion.next_send_time()
+    }
+
+    /// Whether every packet sent has been acknowledged
+    pub fn is_idle(&self) -> bool {
+        self.in_flight.is_empty()
+    }
+
+    /// Number of unacknowledged packets
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    /// Wrap `body` in the next data packet and keep it for retransmission
+    pub fn send(&mut self, body: Bytes, now: Instant) -> Packet {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        let packet = Packet::data(self.stream_id, self.epoch, seq, body);
+        self.congestion.on_sent(packet.body.len(), now);
+        self.in_flight.insert(
+            seq,
+            InFlight {
+                packet: packet.clone(),
+                sent_at: now,
+                transmissions: 1,
+            },
+        );
+        self.stats.packets_sent += 1;
+        packet
+    }
+
+    /// Apply an acknowledgment, returning the NACKed packets to resend
+    pub fn on_ack(&mut self, frame: &AckFrame, now: Instant) -> Vec<Packet> {
+        let acked: Vec<SeqNo> = self
+            .in_flight
+            .keys()
+            .copied()
+            .take_while(|seq| *seq < frame.cumulative)
+            .chain(
+                frame
+                    .ranges
+                    .iter()
+                    .flat_map(|r| self.in_flight.range(r.clone()).map(|(seq, _)| *seq)),
+            )
+            .collect();
+
+        // Karn's algorithm: only packets sent once give an unambiguous sample
+        let mut sample = None;
+        let mut acked_bytes = 0;
+        for seq in acked {
+            if let Some(entry) = self.in_flight.remove(&seq) {
+                self.stats.packets_acked += 1;
+                acked_bytes += entry.packet.body.len();
+                if entry.transmissions == 1 {
+                    sample = Some(now.saturating_duration_since(entry.sent_at));
+                }
+            }
+        }
+        if acked_bytes > 0 || sample.is_some() {
+            self.congestion.on_ack(acked_bytes, sample, now);
+        }
+
+        // Resend each NACKed packet at most once per round trip, however
+        // many ACKs repeat the NACK
+        let rtt = self.congestion.rtt();
+        let holdoff = rtt.srtt().unwrap_or(rtt.rto());
+        let mut resend = Vec::new();
+        for seq in &frame.nacks {
+            if let Some(entry) = self.in_flight.get_mut(seq) {
+                if now.saturating_duration_since(entry.sent_at) >= holdoff {
+                    resend.push(Self::retransmit(entry, now, &mut self.congestion, &mut self.stats));
+                }
+            }
+        }
+        resend
+    }
+
+    /// Resend packets whose retransmission timeout has expired
+    pub fn poll_timeout(&mut self, now: Instant) -> Vec<Packet> {
+        let rto = self.congestion.rtt().rto();
+        let mut resend = Vec::new();
+        for entry in self.in_flight.values_mut() {
+            if now.saturating_duration_since(entry.sent_at) >= rto {
+                resend.push(Self::retransmit(entry, now, &mut self.congestion, &mut self.stats));
+            }
+        }
+        if !resend.is_empty() {
+            self.congestion.on_timeout();
+        }
+        resend
+    }
+
+    /// When the oldest unacknowledged packet times out
+    pub fn next_timeout(&self) -> Option<Instant> {
+        let rto = self.congestion.rtt().rto();
+        self.in_flight
+            .values()
+            .map(|entry| entry.sent_at + rto)
+            .min()
+    }
+
+    fn retransmit(
+        entry: &mut InFlight,
+        now: Instant,
+        congestion: &mut Congestion,
+        stats: &mut SenderStats,
+    ) -> Packet {
+        // The original counts as lost; the copy goes back in flight
+        let bytes = entry.packet.body.len();
+        congestion.on_loss(bytes, now);
+        congestion.on_sent(bytes, now);
+        entry.sent_at = now;
+        entry.transmissions += 1;
+        stats.retransmits += 1;
+        entry.packet.clone()
+    }
+
+    /// Round-trip estimate
+    pub fn rtt(&self) -> &RttEstimator {
+        self.congestion.rtt()
+    }
+
+    /// Congestion window, pacing rate and RTT
+    pub fn congestion_stats(&self) -> CongestionStats {
+        self.congestion.stats()
+    }
+
+    /// Send statistics
+    pub fn stats(&self) -> SenderStats {
+        self.stats.clone()
+    }
+}
+
+/// Receiving half of a reliable stream
+pub struct ReliableReceiver {
+    stream_id: StreamId,
+    epoch: Epoch,
+    next_expected: SeqNo,
+    window: usize,
+    buffer: BTreeMap<SeqNo, Bytes>,
+    acks_sent: SeqNo,
+    stats: ReceiverStats,
+}
+
+impl ReliableReceiver {
+    /// Create a receiver buffering up to `window` packets ahead of the next
+    /// one to deliver
+    pub fn new(stream_id: StreamId, epoch: Epoch, window: usize) -> Self {
+        Self {
+            stream_id,
+            epoch,
+            next_expected: 0,
+            window: window.max(1),
+            buffer: BTreeMap::new(),
+            acks_sent: 0,
+            stats: ReceiverStats {
+                packets_received: 0,
+                bytes_received: 0,
+                out_of_order: 0,
+                packets_lost: 0,
+                packets_recovered: 0,
+            },
+        }
+    }
+
+    /// Accept a data packet, returning the payloads now deliverable in order
+    ///
+    /// Duplicates and packets beyond the window are dropped (the sender will
+    /// resend the latter).
+    pub fn on_packet(&mut self, packet: &Packet) -> Vec<Bytes> {
+        let seq = packet.seq();
+        if packet.stream_id() != self.stream_id || !packet.is_data() {
+            return Vec::new();
+        }
+        if seq < self.next_expected
+            || seq >= self.next_expected + self.window as u64
+            || self.buffer.contains_key(&seq)
+        {
+            return Vec::new();
+        }
+        self.stats.packets_received += 1;
+        self.stats.bytes_received += packet.body.len() as u64;
+        if seq != self.next_expected {
+            self.stats.out_of_order += 1;
+        }
+        self.buffer.insert(seq, packet.body.clone());
+
+        let mut ready = Vec::new();
+        while let Some(body) = self.buffer.remove(&self.next_expected) {
+            ready.push(body);
+            self.next_expected += 1;
+        }
+        ready
+    }
+
+    /// Next sequence number to be delivered
+    pub fn next_expected(&self) -> SeqNo {
+        self.next_expected
+    }
+
+    /// Current acknowledgment: buffered ranges, with the gaps between them NACKed
+    pub fn ack_frame(&self) -> AckFrame {
+        let mut frame = AckFrame {
+            cumulative: self.next_expected,
+            ..Default::default()
+        };
+        let mut expected = self.next_expected;
+        for &seq in self.buffer.keys() {
+            match frame.ranges.last_mut() {
+                Some(range) if range.end == seq => range.end += 1,
+                _ => {
+                    if frame.ranges.len() == MAX_ACK_RANGES {
+                        break;
+                    }
+                    frame.ranges.push(seq..seq + 1);
+                }
+            }
+            let room = MAX_NACKS - frame.nacks.len();
+            frame.nacks.extend((expected..seq).take(room));
+            expected = seq + 1;
+        }
+        frame
+    }
+
+    /// Current acknowledgment as a packet for the sender
+    pub fn ack_packet(&mut self) -> Packet {
+        let seq = self.acks_sent;
+        self.acks_sent += 1;
+        self.ack_frame().to_packet(self.stream_id, self.epoch, seq)
+    }
+
+    /// Receive statistics; lost packets are the gaps still waiting to be resent
+    pub fn stats(&self) -> ReceiverStats {
+        let span = self.buffer.keys().next_back().map_or(0, |&last| last + 1 - self.next_expected);
+        ReceiverStats {
+            packets_lost: span - self.buffer.len() as u64,
+            ..self.stats.clone()
+        }
+    }
+}
+
+/// Send everything from `data_rx` reliably
+///
+/// Data packets and retransmissions go to `packet_tx`; ACK packets from the
+/// peer arrive on `ack_rx`. Returns once the input is closed and every packet
+/// has been acknowledged, or when either channel to the peer closes.
+pub async fn run_sender(
+    mut sender: ReliableSender,
+    mut data_rx: mpsc::Receiver<Bytes>,
+    packet_tx: mpsc::Sender<Packet>,
+    mut ack_rx: mpsc::Receiver<Packet>,
+) -> SenderStats {
+    let mut input_open = true;
+    loop {
+        if !input_open && sender.is_idle() {
+            break;
+        }
+        let now = Instant::now();
+        let mut timeout = sender.next_timeout().unwrap_or(now + MAX_RTO);
+        let mut paced = true;
+        if input_open && sender.can_send() {
+            if let Some(at) = sender.next_send_time().filter(|at| *at > now) {
+                timeout = timeout.min(at);
+                paced = false;
+            }
+        }
+
+        let outgoing = tokio::select! {
+            data = data_rx.recv(), if input_open && sender.can_send() && paced => match data {
+                Some(body) => vec![sender.send(body, Instant::now())],
+                None => {
+                    input_open = false;
+                    Vec::new()
+                }
+            },
+            ack = ack_rx.recv() => match ack {
+                Some(packet) if packet.hdr.kind == MsgKind::Ack => match AckFrame::decode(&packet.body) {
+                    Some(frame) => sender.on_ack(&frame, Instant::now()),
+                    None => {
+                        tracing::debug!("Dropping malformed ACK on stream {}", packet.stream_id());
+                        Vec::new()
+                    }
+                },
+                Some(_) => Vec::new(),
+                None => break,
+            },
+            _ = tokio::time::sleep_until(timeout) => sender.poll_timeout(Instant::now()),
+        };
+
+        for packet in outgoing {
+            if packet_tx.send(packet).await.is_err() {
+                return sender.stats();
+            }
+        }
+    }
+    sender.stats()
+}
+
+/// Receive a reliable stream from `packet_rx`, delivering payloads in order
+/// to `data_tx` and acknowledging every packet on `ack_tx`
+///
+/// Returns the receive statistics once the packet channel closes or the
+/// consumer goes away.
+pub async fn run_receiver(
+    mut receiver: ReliableReceiver,
+    mut packet_rx: mpsc::Receiver<Packet>,
+    ack_tx: mpsc::Sender<Packet>,
+    data_tx: mpsc::Sender<Bytes>,
+) -> ReceiverStats {
+    while let Some(packet) = packet_rx.recv().await {
+        for body in receiver.on_packet(&packet) {
+            if data_tx.send(body).await.is_err() {
+                return receiver.stats();
+            }
+        }
+        // A lost ACK is covered by the next one or the sender's timeout
+        if ack_tx.send(receiver.ack_packet()).await.is_err() {
+            break;
+        }
+    }
+    receiver.stats()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic xorshift, so lossy runs are reproducible
+    struct Lossy(u64);
+
+    impl Lossy {
+        fn lose(&mut self, loss_percent: u64) -> bool {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 % 100 < loss_percent
+        }
+    }
+
+    fn payload(i: usize) -> Bytes {
+        Bytes::from(format!("chunk-{i}").into_bytes())
+    }
+
+    #[test]
+    fn test_ack_frame_roundtrip() {
+        let frame = AckFrame {
+            cumulative: 7,
+            ranges: vec![9..12, 15..16],
+            nacks: vec![7, 8, 12],
+            recovered: 2,
+        };
+        let decoded = AckFrame::decode(&frame.encode()).unwrap();
+        assert_eq!(decoded, frame);
+        assert!(decoded.covers(3) && decoded.covers(10) && !decoded.covers(8));
+
+        let encoded = frame.encode();
+        assert!(AckFrame::decode(&encoded[..encoded.len() - 1]).is_none());
+        assert!(AckFrame::decode(&[]).is_none());
+    }
+
+    #[test]
+    fn test_receiver_reorders_and_nacks_gaps() {
+        let mut receiver = ReliableReceiver::new(1, 0, 64);
+        let packet = |seq: u64| Packet::data(1, 0, seq, payload(seq as usize));
+
+        assert_eq!(receiver.on_packet(&packet(0)), vec![payload(0)]);
+        assert!(receiver.on_packet(&packet(2)).is_empty());
+        assert!(receiver.on_packet(&packet(5)).is_empty());
+
+        let frame = receiver.ack_frame();
+        assert_eq!(frame.cumulative, 1);
+        assert_eq!(frame.ranges, vec![2..3, 5..6]);
+        assert_eq!(frame.nacks, vec![1, 3, 4]);
+
+        assert_eq!(receiver.on_packet(&packet(1)), vec![payload(1), payload(2)]);
+        assert!(receiver.on_packet(&packet(1)).is_empty(), "duplicate");
+        assert_eq!(receiver.stats().out_of_order, 2);
+        assert_eq!(receiver.next_expected(), 3);
+    }
+
+    #[test]
+    fn test_rtt_estimation() {
+        let mut rtt = RttEstimator::new();
+        assert_eq!(rtt.rto(), INITIAL_RTO);
+        rtt.on_sample(Duration::from_millis(100));
+        assert_eq!(rtt.srtt(), Some(Duration::from_millis(100)));
+        assert_eq!(rtt.rto(), Duration::from_millis(300));
+        for _ in 0..20 {
+            rtt.on_sample(Duration::from_millis(40));
+        }
+        assert!(rtt.srtt().unwrap() < Duration::from_millis(45));
+        let rto = rtt.rto();
+        rtt.backoff();
+        assert_eq!(rtt.rto(), 2 * rto);
+        rtt.reset_backoff();
+        assert_eq!(rtt.rto(), rto);
+    }
+
+    #[test]
+    fn test_sender_retransmits_on_nack_and_timeout() {
+        let start = Instant::now();
+        let mut sender = ReliableSender::new(1, 0, 8);
+        for i in 0..4 {
+            sender.send(payload(i), start);
+        }
+
+        // 0, 2 and 3 arrive; 1 is NACKed once a round trip has passed
+        let frame = AckFrame {
+            cumulative: 1,
+            ranges: std::iter::once(2..4).collect(),
+            nacks: vec![1],
+            recovered: 0,
+        };
+        let resent = sender.on_ack(&frame, start + Duration::from_millis(50));
+        assert_eq!(resent.iter().map(Packet::seq).collect::<Vec<_>>(), vec![1]);
+        assert_eq!(sender.rtt().srtt(), Some(Duration::from_millis(50)));
+        // The same NACK straight away doesn't resend again
+        assert!(sender
+            .on_ack(&frame, start + Duration::from_millis(51))
+            .is_empty());
+
+        let timeout = sender.next_timeout().unwrap();
+        let resent = sender.poll_timeout(timeout);
+        assert_eq!(resent.iter().map(Packet::seq).collect::<Vec<_>>(), vec![1]);
+        assert_eq!(sender.stats().retransmits, 2);
+
+        sender.on_ack(
+            &AckFrame {
+                cumulative: 4,
+                ..Default::default()
+            },
+            timeout,
+        );
+        assert!(sender.is_idle());
+        assert_eq!(sender.stats().packets_acked, 4);
+    }
+
+    #[test]
+    fn test_bulk_transfer_survives_loss_simulated() {
+        // Lock-step simulation: 30% of data and ACK packets are lost
+        let total = 5_000;
+        let mut sender = ReliableSender::new(7, 1, 256);
+        let mut receiver = ReliableReceiver::new(7, 1, 256);
+        let mut link = Lossy(0x9e37_79b9_7f4a_7c15);
+        let mut now = Instant::now();
+        let mut delivered = Vec::new();
+        let mut next = 0;
+        let mut last_delivery = now;
+
+        while delivered.len() < total {
+            now += Duration::from_millis(1);
+            let before = delivered.len();
+            let mut outgoing = sender.poll_timeout(now);
+            while next < total && sender.can_send() && outgoing.len() < 32 {
+                outgoing.push(sender.send(payload(next), now));
+                next += 1;
+            }
+            for packet in outgoing {
+                if link.lose(30) {
+                    continue;
+                }
+                delivered.extend(receiver.on_packet(&packet));
+                let ack = receiver.ack_packet();
+                if !link.lose(30) {
+                    // Deliver ACKs a few milliseconds later
+                    let frame = AckFrame::decode(&ack.body).unwrap();
+                    for resent in sender.on_ack(&frame, now + Duration::from_millis(5)) {
+                        if !link.lose(30) {
+                            delivered.extend(receiver.on_packet(&resent));
+                        }
+                    }
+                }
+            }
+            if delivered.len() > before {
+                last_delivery = now;
+            }
+            // Retransmission keeps data moving despite the loss
+            assert!(now.duration_since(last_delivery) < MAX_RTO * 10, "transfer stalled");
+        }
+
+        assert_eq!(delivered, (0..total).map(payload).collect::<Vec<_>>());
+        assert!(sender.stats().retransmits > 0);
+    }
+
+    #[tokio::test]
+    async fn test_bulk_transfer_over_lossy_channels() {
+        let total = 2_000;
+        let (data_tx, data_rx) = mpsc::channel(64);
+        let (packet_tx, mut packet_rx) = mpsc::channel::<Packet>(1024);
+        let (lossy_packet_tx, lossy_packet_rx) = mpsc::channel(1024);
+        let (ack_tx, mut ack_rx) = mpsc::channel::<Packet>(1024);
+        let (lossy_ack_tx, lossy_ack_rx) = mpsc::channel(1024);
+        let (out_tx, mut out_rx) = mpsc::channel(64);
+
+        // In-process link dropping 20% of packets in each direction
+        tokio::spawn(async move {
+            let mut link = Lossy(42);
+            while let Some(packet) = packet_rx.recv().await {
+                if !link.lose(20) && lossy_packet_tx.send(packet).await.is_err() {
+                    break;
+                }
+            }
+        });
+        tokio::spawn(async move {
+            let mut link = Lossy(7);
+            while let Some(packet) = ack_rx.recv().await {
+                if !link.lose(20) && lossy_ack_tx.send(packet).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let sender = tokio::spawn(run_sender(
+            ReliableSender::new(3, 0, 128),
+            data_rx,
+            packet_tx,
+            lossy_ack_rx,
+        ));
+        tokio::spawn(run_receiver(
+            ReliableReceiver::new(3, 0, 128),
+            lossy_packet_rx,
+            ack_tx,
+            out_tx,
+        ));
+        tokio::spawn(async move {
+            for i in 0..total {
+                data_tx.send(payload(i)).await.unwrap();
+            }
+        });
+
+        let received = tokio::time::timeout(Duration::from_secs(30), async {
+            let mut received = Vec::new();
+            while received.len() < total {
+                received.push(out_rx.recv().await.unwrap());
+            }
+            received
+        })
+        .await
+        .expect("transfer timed out");
+
+        assert_eq!(received, (0..total).map(payload).collect::<Vec<_>>());
+        let stats = tokio::time::timeout(Duration::from_secs(30), sender)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stats.packets_acked, total as u64);
+        assert!(stats.retransmits > 0);
+    }
+}
@@ -4,14 +4,22 @@
 //! - Configurable send/receive buffer sizes
 //! - Batched packet processing
 //! - Handler registration for incoming packets
+//!
+//! Packets travel one per datagram in the fixed wire format documented on
+//! [`PacketHeader`](crate::types::PacketHeader). Received datagrams are read
+//! into a shared buffer and parsed without copying the body.
 
 use std::net::SocketAddr;
 use std::sync::Arc;
 
+use bytes::BytesMut;
 use socket2::{Domain, Protocol, Socket, Type};
 use tokio::net::UdpSocket;
 
-use crate::types::Packet;
+use crate::types::{Packet, MAX_DATAGRAM};
+
+/// Receive buffer allocation; many small datagrams share one before it is replaced
+const RECV_CHUNK: usize = 4 * MAX_DATAGRAM;
 
 /// Transport configuration
 #[derive(Debug, Clone)]
@@ -109,16 +117,58 @@ impl TransportHandle {
     }
 
     /// Send a packet to the given address
+    ///
+    /// Fails with [`EncodeError::BodyTooLarge`](crate::EncodeError::BodyTooLarge)
+    /// if the body doesn't fit in one datagram.
     pub async fn send(&self, addr: SocketAddr, pkt: Packet) -> anyhow::Result<()> {
-        // Simple framing: length-prefix the body
-        let mut buf = Vec::with_capacity(2 + pkt.body.len());
-        buf.extend_from_slice(&(pkt.body.len() as u16).to_le_bytes());
-        buf.extend_from_slice(&pkt.body);
-
+        let buf = pkt.encode()?;
         self.inner.socket.send_to(&buf, addr).await?;
         Ok(())
     }
 
+    /// Receive and decode one packet
+    ///
+    /// The datagram is read into `buf` and split off, so the returned body
+    /// shares its allocation. Reuse the same `buf` across calls.
+    pub async fn recv(&self, buf: &mut BytesMut) -> anyhow::Result<(Packet, SocketAddr)> {
+        if buf.capacity() - buf.len() < MAX_DATAGRAM {
+            *buf = BytesMut::with_capacity(RECV_CHUNK);
+        }
+        buf.clear();
+        let (_, addr) = self.inner.socket.recv_buf_from(buf).await?;
+        let packet = Packet::decode_bytes(buf.split().freeze())?;
+        Ok((packet, addr))
+    }
+
+    /// Deliver incoming packets to the registered handler until the socket fails
+    ///
+    /// Malformed datagrams are dropped.
+    pub fn start_receiving(&self) -> tokio::task::JoinHandle<()> {
+        let handle = Self {
+            inner: self.inner.clone(),
+        };
+        tokio::spawn(async move {
+            let mut buf = BytesMut::with_capacity(RECV_CHUNK);
+            loop {
+                match handle.recv(&mut buf).await {
+                    Ok((packet, addr)) => {
+                        let handler = handle.inner.handlers.handler.lock().unwrap();
+                        if let Some(handler) = handler.as_ref() {
+                            handler(addr, packet);
+                        }
+                    }
+                    Err(e) if e.is::<crate::types::DecodeError>() => {
+                        tracing::debug!("Dropping malformed datagram: {}", e);
+                    }
+                    Err(e) => {
+                        tracing::error!("UDP receive failed: {}", e);
+                        break;
+                    }
+                }
+            }
+        })
+    }
+
     /// Send raw bytes to the given address (no framing)
     pub async fn send_raw(&self, addr: SocketAddr, data: &[u8]) -> anyhow::Result<()> {
         self.inner.socket.send_to(data, addr).await?;
@@ -190,4 +240,32 @@ mod tests {
         assert_eq!(&buf[..len], b"hello");
         assert_eq!(from_addr, t1.local_addr().unwrap());
     }
+
+    #[tokio::test]
+    async fn test_packet_send_recv() {
+        let cfg = || TransportConfig {
+            bind: "127.0.0.1:0".parse().unwrap(),
+            ..Default::default()
+        };
+        let t1 = TransportHandle::new(cfg()).await.unwrap();
+        let t2 = TransportHandle::new(cfg()).await.unwrap();
+        let addr2 = t2.local_addr().unwrap();
+
+        t2.send_raw(addr2, b"not a packet").await.unwrap();
+        let oversized = Packet::data(9, 1, 0, bytes::Bytes::from(vec![0u8; crate::types::MAX_BODY_LEN + 1]));
+        assert!(t1.send(addr2, oversized).await.is_err());
+        for seq in 0..3 {
+            let packet = Packet::data(9, 1, seq, bytes::Bytes::from(vec![seq as u8; 100]));
+            t1.send(addr2, packet).await.unwrap();
+        }
+
+        let mut buf = BytesMut::new();
+        assert!(t2.recv(&mut buf).await.is_err());
+        for seq in 0..3 {
+            let (packet, from) = t2.recv(&mut buf).await.unwrap();
+            assert_eq!(from, t1.local_addr().unwrap());
+            assert_eq!((packet.stream_id(), packet.seq()), (9, seq));
+            assert_eq!(packet.body, vec![seq as u8; 100]);
+        }
+    }
 }
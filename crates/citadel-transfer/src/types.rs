@@ -1,5 +1,8 @@
 //! Core types for Citadel Transfer protocol
 
+use std::fmt;
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
 use serde::{Deserialize, Serialize};
 
 /// Unique identifier for a logical stream of data
@@ -17,6 +20,21 @@ pub type NodeId = u64;
 /// Default MTU for payload data (optimal for most networks)
 pub const DEFAULT_PAYLOAD_MTU: usize = 1200;
 
+/// Size of an encoded [`PacketHeader`]
+pub const HEADER_LEN: usize = 48;
+
+/// Largest UDP payload over IPv4
+pub const MAX_DATAGRAM: usize = 65_507;
+
+/// Largest body that fits in one datagram after the header
+pub const MAX_BODY_LEN: usize = MAX_DATAGRAM - HEADER_LEN;
+
+/// Identifies Citadel Transfer datagrams on the wire
+pub const WIRE_MAGIC: [u8; 4] = *b"CTDL";
+
+/// Wire format version written by this implementation
+pub const WIRE_VERSION: u8 = 1;
+
 /// Message type classification
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MsgKind {
@@ -30,18 +48,55 @@ pub enum MsgKind {
     Bft,
     /// TGP commit message (bilateral coordination complete)
     TgpCommit,
+    /// Session handshake (see [`crate::crypto`])
+    Handshake,
+    /// FEC repair symbol (see [`crate::fec`])
+    Repair,
+}
+
+impl MsgKind {
+    /// Wire code
+    pub fn to_u8(self) -> u8 {
+        match self {
+            Self::Data => 0,
+            Self::Ack => 1,
+            Self::Control => 2,
+            Self::Bft => 3,
+            Self::TgpCommit => 4,
+            Self::Handshake => 5,
+            Self::Repair => 6,
+        }
+    }
+
+    /// Parse a wire code
+    pub fn from_u8(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(Self::Data),
+            1 => Some(Self::Ack),
+            2 => Some(Self::Control),
+            3 => Some(Self::Bft),
+            4 => Some(Self::TgpCommit),
+            5 => Some(Self::Handshake),
+            6 => Some(Self::Repair),
+            _ => None,
+        }
+    }
 }
 
 /// Packet header containing routing and sequencing information
 ///
-/// Total size: 48 bytes
-/// - stream_id: 16 bytes (u128)
-/// - epoch: 4 bytes (u32)
-/// - seq: 8 bytes (u64)
-/// - kind: 1 byte (enum)
-/// - flags: 1 byte
-/// - body_len: 2 bytes (u16)
-/// - padding: 16 bytes (alignment)
+/// Total size: 48 bytes, integers big-endian
+/// - stream_id: 16 bytes (u128), offset 0
+/// - epoch: 4 bytes (u32), offset 16
+/// - seq: 8 bytes (u64), offset 20
+/// - kind: 1 byte ([`MsgKind::to_u8`]), offset 28
+/// - flags: 1 byte, offset 29
+/// - body_len: 2 bytes (u16), offset 30
+/// - magic: 4 bytes ([`WIRE_MAGIC`]), offset 32
+/// - version: 1 byte ([`WIRE_VERSION`]), offset 36
+/// - reserved: 11 bytes, offset 37 (zero when sent, ignored when received)
+///
+/// The body follows immediately; a datagram carries exactly one packet.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PacketHeader {
     /// Stream this packet belongs to
@@ -82,8 +137,109 @@ impl PacketHeader {
             body_len: 0,
         }
     }
+
+    /// Append the 48-byte wire encoding to `buf`
+    pub fn encode_into(&self, buf: &mut BytesMut) {
+        buf.reserve(HEADER_LEN);
+        buf.put_u128(self.stream_id);
+        buf.put_u32(self.epoch);
+        buf.put_u64(self.seq);
+        buf.put_u8(self.kind.to_u8());
+        buf.put_u8(self.flags);
+        buf.put_u16(self.body_len);
+        buf.put_slice(&WIRE_MAGIC);
+        buf.put_u8(WIRE_VERSION);
+        buf.put_bytes(0, HEADER_LEN - 37);
+    }
+
+    /// Decode a header from the start of `data`
+    ///
+    /// Only the header is checked; see [`Packet::decode`] for the body.
+    pub fn decode(data: &[u8]) -> Result<Self, DecodeError> {
+        if data.len() < HEADER_LEN {
+            return Err(DecodeError::Truncated {
+                needed: HEADER_LEN,
+                got: data.len(),
+            });
+        }
+        if data[32..36] != WIRE_MAGIC {
+            return Err(DecodeError::BadMagic);
+        }
+        if data[36] != WIRE_VERSION {
+            return Err(DecodeError::UnsupportedVersion(data[36]));
+        }
+
+        let mut buf = &data[..HEADER_LEN];
+        let stream_id = buf.get_u128();
+        let epoch = buf.get_u32();
+        let seq = buf.get_u64();
+        let code = buf.get_u8();
+        let kind = MsgKind::from_u8(code).ok_or(DecodeError::UnknownKind(code))?;
+        let flags = buf.get_u8();
+        let body_len = buf.get_u16();
+        if body_len as usize > MAX_BODY_LEN {
+            return Err(DecodeError::BodyTooLarge(body_len as usize));
+        }
+        Ok(Self {
+            stream_id,
+            epoch,
+            seq,
+            kind,
+            flags,
+            body_len,
+        })
+    }
+}
+
+/// Why a datagram could not be decoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// Fewer bytes than the header or `body_len` requires
+    Truncated { needed: usize, got: usize },
+    /// Bytes left over after the body
+    TrailingBytes(usize),
+    /// `body_len` exceeds [`MAX_BODY_LEN`]
+    BodyTooLarge(usize),
+    /// Not a Citadel Transfer datagram
+    BadMagic,
+    /// Wire version this implementation doesn't speak
+    UnsupportedVersion(u8),
+    /// Unknown message kind code
+    UnknownKind(u8),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Truncated { needed, got } => write!(f, "truncated packet: need {needed} bytes, got {got}"),
+            Self::TrailingBytes(n) => write!(f, "{n} trailing bytes after packet body"),
+            Self::BodyTooLarge(n) => write!(f, "body length {n} exceeds maximum of {MAX_BODY_LEN}"),
+            Self::BadMagic => write!(f, "bad magic"),
+            Self::UnsupportedVersion(v) => write!(f, "unsupported wire version {v}"),
+            Self::UnknownKind(k) => write!(f, "unknown message kind {k}"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Why a packet could not be encoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncodeError {
+    /// The body is longer than [`MAX_BODY_LEN`]
+    BodyTooLarge(usize),
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BodyTooLarge(n) => write!(f, "body length {n} exceeds maximum of {MAX_BODY_LEN}"),
+        }
+    }
 }
 
+impl std::error::Error for EncodeError {}
+
 /// Complete packet with header and body
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Packet {
@@ -119,6 +275,63 @@ impl Packet {
     pub fn is_data(&self) -> bool {
         self.hdr.kind == MsgKind::Data
     }
+
+    /// Append the wire encoding (header, then body) to `buf`
+    ///
+    /// `body_len` is written from the body itself. Bodies longer than
+    /// [`MAX_BODY_LEN`] don't fit in a datagram and are refused, leaving
+    /// `buf` untouched.
+    pub fn encode_into(&self, buf: &mut BytesMut) -> Result<(), EncodeError> {
+        if self.body.len() > MAX_BODY_LEN {
+            return Err(EncodeError::BodyTooLarge(self.body.len()));
+        }
+        buf.reserve(HEADER_LEN + self.body.len());
+        let hdr = PacketHeader {
+            body_len: self.body.len() as u16,
+            ..self.hdr.clone()
+        };
+        hdr.encode_into(buf);
+        buf.put_slice(&self.body);
+        Ok(())
+    }
+
+    /// Encode into a new buffer
+    pub fn encode(&self) -> Result<Bytes, EncodeError> {
+        let mut buf = BytesMut::with_capacity(HEADER_LEN + self.body.len());
+        self.encode_into(&mut buf)?;
+        Ok(buf.freeze())
+    }
+
+    /// Decode one datagram, copying the body
+    pub fn decode(data: &[u8]) -> Result<Self, DecodeError> {
+        let hdr = PacketHeader::decode(data)?;
+        let body = Self::body_range(&hdr, data.len())?;
+        Ok(Self {
+            body: Bytes::copy_from_slice(&data[body]),
+            hdr,
+        })
+    }
+
+    /// Decode one datagram without copying: the body is a slice of `data`
+    pub fn decode_bytes(data: Bytes) -> Result<Self, DecodeError> {
+        let hdr = PacketHeader::decode(&data)?;
+        let body = Self::body_range(&hdr, data.len())?;
+        Ok(Self {
+            body: data.slice(body),
+            hdr,
+        })
+    }
+
+    fn body_range(hdr: &PacketHeader, len: usize) -> Result<std::ops::Range<usize>, DecodeError> {
+        let end = HEADER_LEN + hdr.body_len as usize;
+        if len < end {
+            return Err(DecodeError::Truncated { needed: end, got: len });
+        }
+        if len > end {
+            return Err(DecodeError::TrailingBytes(len - end));
+        }
+        Ok(HEADER_LEN..end)
+    }
 }
 
 #[cfg(test)]
@@ -127,7 +340,7 @@ mod tests {
 
     #[test]
     fn test_packet_creation() {
-        let body = bytes::Bytes::from_static(b"hello world");
+        let body = Bytes::from_static(b"hello world");
         let packet = Packet::data(12345, 1, 0, body.clone());
 
         assert_eq!(packet.stream_id(), 12345);
@@ -136,6 +349,78 @@ mod tests {
         assert_eq!(packet.body, body);
     }
 
+    #[test]
+    fn test_wire_roundtrip() {
+        let mut packet = Packet::data(0x0102_0304_0506_0708_090a_0b0c_0d0e_0f10, 7, 42, Bytes::from_static(b"payload"));
+        packet.hdr.kind = MsgKind::Ack;
+        packet.hdr.flags = 0x81;
+
+        let wire = packet.encode().unwrap();
+        assert_eq!(wire.len(), HEADER_LEN + 7);
+        // Fixed layout other implementations rely on
+        assert_eq!(&wire[..16], &packet.hdr.stream_id.to_be_bytes());
+        assert_eq!(&wire[16..20], &7u32.to_be_bytes());
+        assert_eq!(&wire[20..28], &42u64.to_be_bytes());
+        assert_eq!(wire[28], 1);
+        assert_eq!(wire[29], 0x81);
+        assert_eq!(&wire[30..32], &7u16.to_be_bytes());
+        assert_eq!(&wire[32..36], b"CTDL");
+        assert_eq!(wire[36], WIRE_VERSION);
+        assert!(wire[37..48].iter().all(|b| *b == 0));
+
+        let decoded = Packet::decode_bytes(wire.clone()).unwrap();
+        assert_eq!(decoded.hdr.stream_id, packet.hdr.stream_id);
+        assert_eq!((decoded.hdr.epoch, decoded.hdr.seq), (7, 42));
+        assert_eq!((decoded.hdr.kind, decoded.hdr.flags), (MsgKind::Ack, 0x81));
+        assert_eq!(decoded.body, packet.body);
+        // Zero-copy: the body points into the datagram
+        assert_eq!(decoded.body.as_ptr(), wire[HEADER_LEN..].as_ptr());
+        assert_eq!(Packet::decode(&wire).unwrap().body, packet.body);
+    }
+
+    #[test]
+    fn test_wire_rejects_malformed() {
+        let wire = Packet::data(1, 0, 0, Bytes::from_static(b"abc")).encode().unwrap();
+
+        assert_eq!(
+            Packet::decode(&wire[..20]).unwrap_err(),
+            DecodeError::Truncated { needed: HEADER_LEN, got: 20 }
+        );
+        assert_eq!(
+            Packet::decode(&wire[..HEADER_LEN + 1]).unwrap_err(),
+            DecodeError::Truncated { needed: HEADER_LEN + 3, got: HEADER_LEN + 1 }
+        );
+        let mut long = wire.to_vec();
+        long.push(0);
+        assert_eq!(Packet::decode(&long).unwrap_err(), DecodeError::TrailingBytes(1));
+
+        let mut bad = wire.to_vec();
+        bad[30..32].copy_from_slice(&u16::MAX.to_be_bytes());
+        assert_eq!(Packet::decode(&bad).unwrap_err(), DecodeError::BodyTooLarge(u16::MAX as usize));
+        let mut bad = wire.to_vec();
+        bad[32] = b'X';
+        assert_eq!(Packet::decode(&bad).unwrap_err(), DecodeError::BadMagic);
+        let mut bad = wire.to_vec();
+        bad[36] = 9;
+        assert_eq!(Packet::decode(&bad).unwrap_err(), DecodeError::UnsupportedVersion(9));
+        let mut bad = wire.to_vec();
+        bad[28] = 200;
+        assert_eq!(Packet::decode(&bad).unwrap_err(), DecodeError::UnknownKind(200));
+    }
+
+    #[test]
+    fn test_encode_rejects_oversized_body() {
+        let fits = Packet::data(1, 0, 0, Bytes::from(vec![0u8; MAX_BODY_LEN]));
+        assert_eq!(fits.encode().unwrap().len(), MAX_DATAGRAM);
+
+        // body_len is a u16, so this would otherwise wrap to a short length
+        let oversized = Packet::data(1, 0, 0, Bytes::from(vec![0u8; u16::MAX as usize + 10]));
+        assert_eq!(oversized.encode().unwrap_err(), EncodeError::BodyTooLarge(u16::MAX as usize + 10));
+        let mut buf = BytesMut::new();
+        assert!(oversized.encode_into(&mut buf).is_err());
+        assert!(buf.is_empty());
+    }
+
     #[test]
     fn test_header_creation() {
         let hdr = PacketHeader::new_data(999, 2, 42, 100);